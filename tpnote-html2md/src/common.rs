@@ -14,3 +14,19 @@ pub fn get_tag_attr(tag: &Handle, attr_name: &str) -> Option<String> {
         _ => None,
     }
 }
+
+/// Returns the tag's name or the empty string.
+pub fn element_name(tag: &Handle) -> String {
+    match tag.data {
+        NodeData::Element { ref name, .. } => name.local.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Concatenates the text of `tag` and its descendants.
+pub fn text_content(tag: &Handle) -> String {
+    match tag.data {
+        NodeData::Text { ref contents } => contents.borrow().to_string(),
+        _ => tag.children.borrow().iter().map(text_content).collect(),
+    }
+}
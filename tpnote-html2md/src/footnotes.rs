@@ -0,0 +1,304 @@
+use crate::common::{element_name, get_tag_attr, text_content};
+use crate::markup5ever_rcdom;
+
+use super::StructuredPrinter;
+use super::TagHandler;
+use super::TagHandlerFactory;
+
+use markup5ever_rcdom::{Handle, NodeData};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Returns the id of the footnote body a footnote reference points to.
+///
+/// A footnote reference is a `<sup>` enclosing nothing but a link to a
+/// fragment, e.g. `<sup><a href="#fn1">1</a></sup>`, or a link to a
+/// fragment enclosing nothing but a `<sup>`, e.g.
+/// `<a href="#fn1"><sup>1</sup></a>`.
+pub fn footnote_target(tag: &Handle) -> Option<String> {
+    let NodeData::Element { ref name, .. } = tag.data else {
+        return None;
+    };
+    let link = match name.local.as_ref() {
+        "sup" => only_child_element(tag).filter(|child| element_name(child) == "a")?,
+        "a" => {
+            only_child_element(tag).filter(|child| element_name(child) == "sup")?;
+            tag.clone()
+        }
+        _ => return None,
+    };
+    get_tag_attr(&link, "href")?
+        .strip_prefix('#')
+        .filter(|target| !target.is_empty())
+        .map(|target| target.to_string())
+}
+
+/// Registers all footnote references found in `tag` and its descendants in
+/// document order, so footnote bodies are recognized even when they precede
+/// their references. A link to an element that is no footnote body, see
+/// `is_footnote_body()`, or that encloses the link, is no footnote reference.
+/// Called before walking the DOM tree.
+pub fn collect_footnotes(tag: &Handle, printer: &mut StructuredPrinter) {
+    let mut references = Vec::new();
+    let mut ids = HashMap::new();
+    find_references(tag, &mut references, &mut ids);
+    for (reference, target) in references {
+        let is_reference = ids
+            .get(&target)
+            .is_none_or(|body| is_footnote_body(body) && !is_ancestor(body, &reference));
+        if is_reference {
+            register_reference(&reference, &target, printer);
+        }
+    }
+}
+
+/// Collects the footnote reference candidates in `tag` and its descendants
+/// together with their targets, and the elements with an id.
+fn find_references(
+    tag: &Handle,
+    references: &mut Vec<(Handle, String)>,
+    ids: &mut HashMap<String, Handle>,
+) {
+    if let Some(target) = footnote_target(tag) {
+        references.push((tag.clone(), target));
+        return;
+    }
+    if let Some(id) = get_tag_attr(tag, "id") {
+        ids.entry(id).or_insert_with(|| tag.clone());
+    }
+    for child in tag.children.borrow().iter() {
+        find_references(child, references, ids);
+    }
+}
+
+/// Tells whether `tag` can hold a footnote body: it has the role
+/// `doc-endnote` or `doc-footnote`, or the class `footnote-definition`
+/// (pulldown-cmark), it is inside a section listing footnotes, i.e. an
+/// element with the class `footnotes` or the role `doc-endnotes`, or it is
+/// an `<li>` or `<aside>` in a list of references, e.g. Wikipedia's
+/// `<ol class="references">`.
+fn is_footnote_body(tag: &Handle) -> bool {
+    let role = get_tag_attr(tag, "role").unwrap_or_default();
+    if role == "doc-endnote" || role == "doc-footnote" || has_class(tag, "footnote-definition") {
+        return true;
+    }
+    let is_item = matches!(element_name(tag).as_str(), "li" | "aside");
+    let mut node = parent(tag);
+    while let Some(n) = node {
+        if has_class(&n, "footnotes")
+            || get_tag_attr(&n, "role").is_some_and(|role| role == "doc-endnotes")
+            || (is_item && has_class(&n, "references"))
+        {
+            return true;
+        }
+        node = parent(&n);
+    }
+    false
+}
+
+/// Tells whether `ancestor` encloses `tag`.
+fn is_ancestor(ancestor: &Handle, tag: &Handle) -> bool {
+    let mut node = parent(tag);
+    while let Some(n) = node {
+        if Rc::ptr_eq(&n, ancestor) {
+            return true;
+        }
+        node = parent(&n);
+    }
+    false
+}
+
+/// Appends the collected footnote bodies to the document. Continuation lines
+/// are indented, so that they belong to the footnote.
+pub fn append_footnotes(printer: &mut StructuredPrinter) {
+    for (label, body) in std::mem::take(&mut printer.footnotes) {
+        printer.append_str(&format!(
+            "\n\n[^{}]: {}",
+            label,
+            body.replace('\n', "\n    ")
+        ));
+    }
+}
+
+/// Returns the label of the footnote reference `tag`, if it is one.
+pub fn footnote_label(tag: &Handle, printer: &StructuredPrinter) -> Option<String> {
+    footnote_target(tag).and_then(|target| printer.footnote_labels.get(&target).cloned())
+}
+
+/// Chooses the handler for footnote related tags, if `tag` is one. A
+/// `footnote` handler in `custom` replaces `FootnoteReferenceHandler`.
+pub fn footnote_handler(
+    tag: &Handle,
+    printer: &StructuredPrinter,
+    custom: &HashMap<String, Box<dyn TagHandlerFactory>>,
+) -> Option<Box<dyn TagHandler>> {
+    // Only references registered by `collect_footnotes()` count.
+    if footnote_label(tag, printer).is_some() {
+        return Some(match custom.get("footnote") {
+            Some(factory) => factory.instantiate(),
+            None => Box::new(FootnoteReferenceHandler),
+        });
+    }
+    if get_tag_attr(tag, "id").is_some_and(|id| printer.footnote_labels.contains_key(&id))
+        && is_footnote_body(tag)
+    {
+        return Some(Box::new(FootnoteHandler::default()));
+    }
+    let has_class = |c: &str| has_class(tag, c);
+    let role = get_tag_attr(tag, "role").unwrap_or_default();
+    let is_backlink = element_name(tag) == "a"
+        && (role == "doc-backlink"
+            || has_class("footnote-back")
+            || has_class("footnote-backref")
+            || has_class("data-footnote-backref")
+            || get_tag_attr(tag, "href")
+                .and_then(|href| href.strip_prefix('#').map(|id| id.to_string()))
+                .is_some_and(|id| printer.footnote_backrefs.contains(&id)));
+    if is_backlink || has_class("footnote-definition-label") {
+        return Some(Box::new(FootnoteMarkHandler));
+    }
+    if has_class("footnotes") || role == "doc-endnotes" {
+        return Some(Box::new(FootnotesSectionHandler::default()));
+    }
+    None
+}
+
+/// Writes a footnote reference `[^label]`.
+#[derive(Default)]
+pub struct FootnoteReferenceHandler;
+
+impl TagHandler for FootnoteReferenceHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        if let Some(label) = footnote_label(tag, printer) {
+            printer.append_str(&format!("[^{}]", label));
+        }
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+
+    fn skip_descendants(&self) -> bool {
+        true
+    }
+}
+
+/// Moves a footnote body, i.e. an element a footnote reference points to,
+/// out of the text. `append_footnotes()` adds it at the end.
+#[derive(Default)]
+pub struct FootnoteHandler {
+    start_pos: usize,
+    label: String,
+}
+
+impl TagHandler for FootnoteHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        self.start_pos = printer.data.len();
+        self.label = get_tag_attr(tag, "id")
+            .and_then(|id| printer.footnote_labels.get(&id).cloned())
+            .unwrap_or_default();
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let body = printer.data.split_off(self.start_pos);
+        printer
+            .footnotes
+            .push((self.label.clone(), body.trim().to_string()));
+    }
+}
+
+/// Drops footnote backlinks and the labels preceding footnote bodies.
+/// Markdown generates them itself.
+#[derive(Default)]
+pub struct FootnoteMarkHandler;
+
+impl TagHandler for FootnoteMarkHandler {
+    fn handle(&mut self, _tag: &Handle, _printer: &mut StructuredPrinter) {}
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+
+    fn skip_descendants(&self) -> bool {
+        true
+    }
+}
+
+/// Drops the remains of a section listing footnotes, e.g. the separator
+/// line, once its footnote bodies have been collected.
+#[derive(Default)]
+pub struct FootnotesSectionHandler {
+    start_pos: usize,
+    footnote_count: usize,
+}
+
+impl TagHandler for FootnotesSectionHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        self.start_pos = printer.data.len();
+        self.footnote_count = printer.footnotes.len();
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        if printer.footnotes.len() > self.footnote_count {
+            printer.data.truncate(self.start_pos);
+        }
+    }
+}
+
+/// Registers the footnote reference `tag` pointing to `target` and returns
+/// its label. The label is the reference's text, e.g. `1` for `[1]`, or its
+/// number, when the text is not a valid label.
+fn register_reference(tag: &Handle, target: &str, printer: &mut StructuredPrinter) -> String {
+    // Backlinks point to the reference or to its link.
+    let mut node = Some(tag.clone());
+    while let Some(n) = node {
+        if let Some(id) = get_tag_attr(&n, "id") {
+            printer.footnote_backrefs.insert(id);
+        }
+        node = only_child_element(&n);
+    }
+
+    if let Some(label) = printer.footnote_labels.get(target) {
+        return label.clone();
+    }
+    let text = text_content(tag);
+    let text = text.trim().trim_start_matches('[').trim_end_matches(']');
+    let label = if !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        && !printer.footnote_labels.values().any(|l| l == text)
+    {
+        text.to_string()
+    } else {
+        (printer.footnote_labels.len() + 1).to_string()
+    };
+    printer
+        .footnote_labels
+        .insert(target.to_string(), label.clone());
+    label
+}
+
+/// Returns the only child element of `tag`, if there is no other child
+/// except white space.
+fn only_child_element(tag: &Handle) -> Option<Handle> {
+    let mut element = None;
+    for child in tag.children.borrow().iter() {
+        match child.data {
+            NodeData::Element { .. } if element.is_none() => element = Some(child.clone()),
+            NodeData::Text { ref contents } if contents.borrow().trim().is_empty() => {}
+            NodeData::Comment { .. } => {}
+            _ => return None,
+        }
+    }
+    element
+}
+
+/// Tells whether `tag` has the class `class`.
+fn has_class(tag: &Handle, class: &str) -> bool {
+    get_tag_attr(tag, "class").is_some_and(|c| c.split_whitespace().any(|c| c == class))
+}
+
+/// Returns the parent of `tag`, if any.
+fn parent(tag: &Handle) -> Option<Handle> {
+    let weak = tag.parent.take();
+    let parent = weak.as_ref().and_then(|weak| weak.upgrade());
+    tag.parent.set(weak);
+    parent
+}
@@ -1,5 +1,6 @@
 use std::boxed::Box;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::ffi::CStr;
 use std::ffi::CString;
 use std::os::raw::c_char;
@@ -18,11 +19,13 @@ pub mod codes;
 pub mod common;
 pub mod containers;
 pub mod dummy;
+pub mod footnotes;
 pub mod headers;
 pub mod iframes;
 pub mod images;
 pub mod lists;
 pub mod markup5ever_rcdom;
+pub mod math;
 pub mod paragraphs;
 pub mod quotes;
 pub mod styles;
@@ -34,11 +37,17 @@ use crate::containers::ContainerHandler;
 use crate::dummy::DummyHandler;
 use crate::dummy::HtmlCherryPickHandler;
 use crate::dummy::IdentityHandler;
+use crate::footnotes::{append_footnotes, collect_footnotes, footnote_handler};
 use crate::headers::HeaderHandler;
 use crate::iframes::IframeHandler;
 use crate::images::ImgHandler;
+use crate::lists::CheckboxHandler;
+use crate::lists::DefinitionDescriptionHandler;
+use crate::lists::DefinitionListHandler;
+use crate::lists::DefinitionTermHandler;
 use crate::lists::ListHandler;
 use crate::lists::ListItemHandler;
+use crate::math::{MathHandler, math_latex};
 use crate::paragraphs::ParagraphHandler;
 use crate::quotes::QuoteHandler;
 use crate::styles::StyleHandler;
@@ -57,7 +66,7 @@ static START_OF_LINE_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new("(^|
 static MARKDOWN_STARTONLY_KEYCHARS: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^(\s*)([=>+\-#])").unwrap()); // for Markdown escaping
 static MARKDOWN_MIDDLE_KEYCHARS: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"[<>*\\_~]").unwrap()); // for Markdown escaping
+    LazyLock::new(|| Regex::new(r"[<>*\\_~`]").unwrap()); // for Markdown escaping
 
 /// Custom variant of main function. Allows to pass custom tag<->tag factory pairs
 /// in order to register custom tag handler for tags you want.
@@ -75,7 +84,9 @@ pub fn parse_html_custom(
         .read_from(&mut html.as_bytes())
         .unwrap();
     let mut result = StructuredPrinter::default();
+    collect_footnotes(&dom.document, &mut result);
     walk(&dom.document, &mut result, custom);
+    append_footnotes(&mut result);
 
     clean_markdown(&result.data)
 }
@@ -145,6 +156,17 @@ pub fn walk(
             if inside_pre {
                 // don't add any html tags inside the pre section
                 handler = Box::new(DummyHandler);
+            } else if let Some(footnote_handler) = footnote_handler(input, result, custom) {
+                // footnote references and bodies, recognized by their structure,
+                // a custom `footnote` handler writes the references
+                handler = footnote_handler;
+            } else if math_latex(input).is_some() {
+                // formulas and their KaTeX, MathJax or Wikipedia wrappers,
+                // a custom `math` handler applies to all of them
+                handler = match custom.get("math") {
+                    Some(factory) => factory.instantiate(),
+                    None => Box::new(MathHandler),
+                };
             } else if custom.contains_key(&tag_name) {
                 // have user-supplied factory, instantiate a handler for this tag
                 let factory = custom.get(&tag_name).unwrap();
@@ -169,10 +191,14 @@ pub fn walk(
                     // lists
                     "ol" | "ul" | "menu" => Box::new(ListHandler),
                     "li" => Box::new(ListItemHandler::default()),
+                    "dl" => Box::new(DefinitionListHandler),
+                    "dt" => Box::new(DefinitionTermHandler),
+                    "dd" => Box::new(DefinitionDescriptionHandler::default()),
+                    "input" => Box::new(CheckboxHandler),
                     // as-is
                     "sub" | "sup" => Box::new(IdentityHandler),
                     // tables, handled fully internally as markdown can't have nested content in tables
-                    // supports only single tables as of now
+                    // tables with nested tables or other block content are kept as HTML
                     "table" => Box::new(TableHandler),
                     "iframe" => Box::new(IframeHandler),
                     // other
@@ -270,6 +296,15 @@ pub struct StructuredPrinter {
 
     /// resulting markdown document
     pub data: String,
+
+    /// Labels of footnote references, indexed by the id of the footnote body they point to
+    pub footnote_labels: HashMap<String, String>,
+
+    /// Ids of footnote references, footnote backlinks point to them
+    pub footnote_backrefs: HashSet<String>,
+
+    /// Collected footnote bodies `(label, markdown)`, appended to the document at the end
+    pub footnotes: Vec<(String, String)>,
 }
 
 impl StructuredPrinter {
@@ -1,3 +1,4 @@
+use crate::common::get_tag_attr;
 use crate::markup5ever_rcdom;
 
 use super::StructuredPrinter;
@@ -92,6 +93,13 @@ impl TagHandler for ListItemHandler {
             }
         }
 
+        // a task list marker is followed by exactly one space
+        for marker in ["[ ]  ", "[x]  "] {
+            if printer.data[index..].starts_with(marker) {
+                printer.data.remove(index + marker.len() - 1);
+            }
+        }
+
         // non-nested indentation (padding). Markdown requires that all
         // paragraphs in the list item except first should be indented with at
         // least 1 space
@@ -104,3 +112,116 @@ impl TagHandler for ListItemHandler {
         }
     }
 }
+
+#[derive(Default)]
+pub struct DefinitionListHandler;
+
+impl TagHandler for DefinitionListHandler {
+    /// we're entering "dl" tag, terms and definitions are handled separately
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        printer.insert_newline();
+        printer.insert_newline();
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        printer.insert_newline();
+        printer.insert_newline();
+    }
+}
+
+#[derive(Default)]
+pub struct DefinitionTermHandler;
+
+impl TagHandler for DefinitionTermHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        if !printer.data.ends_with('\n') {
+            printer.insert_newline();
+        }
+
+        // a new term after a definition starts a new entry: separate with an empty line
+        let current_depth = printer.parent_chain.len();
+        if printer.siblings[&current_depth]
+            .last()
+            .is_some_and(|tag| tag == "dd")
+        {
+            printer.insert_newline();
+        }
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+}
+
+#[derive(Default)]
+pub struct DefinitionDescriptionHandler {
+    start_pos: usize,
+}
+
+impl TagHandler for DefinitionDescriptionHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        if !printer.data.ends_with('\n') {
+            printer.insert_newline();
+        }
+        printer.append_str(": ");
+        self.start_pos = printer.data.len();
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        // same as for list items: no leading newlines, following paragraphs indented
+        let index = self.start_pos;
+        while index < printer.data.len() {
+            if printer.data.as_bytes().get(index) == Some(&b'\n')
+                || printer.data.as_bytes().get(index) == Some(&b' ')
+            {
+                printer.data.remove(index);
+            } else {
+                break;
+            }
+        }
+
+        let mut index = printer.data.len();
+        while index > self.start_pos {
+            if printer.data.as_bytes().get(index) == Some(&b'\n') {
+                printer.insert_str(index + 1, "  ");
+            }
+            index -= 1;
+        }
+    }
+}
+
+/// Writes a task list item marker `[ ]` or `[x]` for a checkbox at the
+/// beginning of a list item. Other form elements are dropped.
+#[derive(Default)]
+pub struct CheckboxHandler;
+
+impl TagHandler for CheckboxHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        if get_tag_attr(tag, "type").is_none_or(|t| !t.eq_ignore_ascii_case("checkbox")) {
+            return;
+        }
+        // the list item marker must be the only thing on the line so far
+        let line = printer
+            .data
+            .trim_end()
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default();
+        let line = line.trim_start();
+        let is_item_start = line == "*"
+            || line
+                .strip_suffix('.')
+                .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+        if !is_item_start || !printer.parent_chain.iter().any(|tag| tag == "li") {
+            return;
+        }
+        if !printer.data.ends_with(' ') {
+            printer.append_str(" ");
+        }
+        if get_tag_attr(tag, "checked").is_some() {
+            printer.append_str("[x] ");
+        } else {
+            printer.append_str("[ ] ");
+        }
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+}
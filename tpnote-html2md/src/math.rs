@@ -0,0 +1,340 @@
+use crate::common::{element_name, get_tag_attr, text_content};
+use crate::markup5ever_rcdom;
+
+use super::StructuredPrinter;
+use super::TagHandler;
+
+use markup5ever_rcdom::{Handle, NodeData};
+
+/// Functions written upright in LaTeX, e.g. `\sin`.
+const FUNCTIONS: [&str; 24] = [
+    "arccos", "arcsin", "arctan", "arg", "cos", "cosh", "cot", "coth", "deg", "det", "dim", "exp",
+    "gcd", "inf", "ker", "lim", "ln", "log", "max", "min", "sin", "sinh", "sup", "tan",
+];
+
+/// Unicode characters of MathML tokens and their LaTeX commands.
+const SYMBOLS: [(char, &str); 80] = [
+    ('α', "\\alpha"),
+    ('β', "\\beta"),
+    ('γ', "\\gamma"),
+    ('δ', "\\delta"),
+    ('ε', "\\varepsilon"),
+    ('ϵ', "\\epsilon"),
+    ('ζ', "\\zeta"),
+    ('η', "\\eta"),
+    ('θ', "\\theta"),
+    ('ϑ', "\\vartheta"),
+    ('ι', "\\iota"),
+    ('κ', "\\kappa"),
+    ('λ', "\\lambda"),
+    ('μ', "\\mu"),
+    ('ν', "\\nu"),
+    ('ξ', "\\xi"),
+    ('π', "\\pi"),
+    ('ρ', "\\rho"),
+    ('σ', "\\sigma"),
+    ('ς', "\\varsigma"),
+    ('τ', "\\tau"),
+    ('υ', "\\upsilon"),
+    ('φ', "\\varphi"),
+    ('ϕ', "\\phi"),
+    ('χ', "\\chi"),
+    ('ψ', "\\psi"),
+    ('ω', "\\omega"),
+    ('Γ', "\\Gamma"),
+    ('Δ', "\\Delta"),
+    ('Θ', "\\Theta"),
+    ('Λ', "\\Lambda"),
+    ('Ξ', "\\Xi"),
+    ('Π', "\\Pi"),
+    ('Σ', "\\Sigma"),
+    ('Υ', "\\Upsilon"),
+    ('Φ', "\\Phi"),
+    ('Ψ', "\\Psi"),
+    ('Ω', "\\Omega"),
+    ('∞', "\\infty"),
+    ('∂', "\\partial"),
+    ('∇', "\\nabla"),
+    ('∑', "\\sum"),
+    ('∏', "\\prod"),
+    ('∫', "\\int"),
+    ('∬', "\\iint"),
+    ('∮', "\\oint"),
+    ('±', "\\pm"),
+    ('∓', "\\mp"),
+    ('×', "\\times"),
+    ('÷', "\\div"),
+    ('·', "\\cdot"),
+    ('⋅', "\\cdot"),
+    ('∘', "\\circ"),
+    ('≤', "\\leq"),
+    ('≥', "\\geq"),
+    ('≠', "\\neq"),
+    ('≈', "\\approx"),
+    ('≡', "\\equiv"),
+    ('∼', "\\sim"),
+    ('∝', "\\propto"),
+    ('→', "\\to"),
+    ('←', "\\leftarrow"),
+    ('↔', "\\leftrightarrow"),
+    ('⇒', "\\Rightarrow"),
+    ('⇐', "\\Leftarrow"),
+    ('⇔', "\\Leftrightarrow"),
+    ('↦', "\\mapsto"),
+    ('∈', "\\in"),
+    ('∉', "\\notin"),
+    ('⊂', "\\subset"),
+    ('⊆', "\\subseteq"),
+    ('∪', "\\cup"),
+    ('∩', "\\cap"),
+    ('∅', "\\emptyset"),
+    ('∀', "\\forall"),
+    ('∃', "\\exists"),
+    ('¬', "\\neg"),
+    ('∧', "\\land"),
+    ('∨', "\\lor"),
+    ('…', "\\ldots"),
+];
+
+/// Returns the LaTeX source of the formula `tag` and whether it is displayed
+/// as a block.
+///
+/// Recognized are MathML `<math>` elements and the wrappers KaTeX, MathJax
+/// and Wikipedia put around them. The LaTeX source is taken from the
+/// `<annotation encoding="application/x-tex">` element or the `alttext`
+/// attribute. Without them, it is derived from the MathML markup.
+pub fn math_latex(tag: &Handle) -> Option<(String, bool)> {
+    let name = element_name(tag);
+    let class = get_tag_attr(tag, "class").unwrap_or_default();
+    let has_class = |c: &str| class.split_whitespace().any(|x| x == c);
+    let wrapper_display = if name == "math" {
+        false
+    } else if has_class("katex-display") || has_class("mwe-math-element-block") {
+        true
+    } else if has_class("katex") || has_class("mwe-math-element") {
+        false
+    } else if name == "mjx-container" {
+        get_tag_attr(tag, "display").is_some_and(|d| d == "true")
+    } else {
+        return None;
+    };
+
+    let math = if name == "math" {
+        Some(tag.clone())
+    } else {
+        find_descendant(tag, &|node| element_name(node) == "math")
+    };
+    let display = wrapper_display
+        || math.as_ref().is_some_and(|math| {
+            get_tag_attr(math, "display").is_some_and(|d| d == "block")
+                || get_tag_attr(math, "mode").is_some_and(|m| m == "display")
+        })
+        || find_descendant(tag, &|node| {
+            get_tag_attr(node, "class")
+                .is_some_and(|c| c.contains("mwe-math-fallback-image-display"))
+        })
+        .is_some();
+
+    let annotation = find_descendant(tag, &|node| {
+        element_name(node) == "annotation"
+            && get_tag_attr(node, "encoding")
+                .is_some_and(|e| e == "application/x-tex" || e == "TeX")
+    })
+    .map(|annotation| text_content(&annotation));
+    let latex = annotation
+        .or_else(|| math.as_ref().and_then(|math| get_tag_attr(math, "alttext")))
+        .or_else(|| math.as_ref().map(mathml_to_latex))
+        // Wikipedia's fallback image.
+        .or_else(|| {
+            find_descendant(tag, &|node| element_name(node) == "img")
+                .and_then(|img| get_tag_attr(&img, "alt"))
+        })?;
+
+    let latex = latex.split_whitespace().collect::<Vec<_>>().join(" ");
+    let latex = ["{\\displaystyle ", "{\\textstyle "]
+        .iter()
+        .find_map(|style| latex.strip_prefix(style)?.strip_suffix('}'))
+        .unwrap_or(&latex)
+        .trim()
+        .to_string();
+    (!latex.is_empty()).then_some((latex, display))
+}
+
+/// Writes formulas as LaTeX: `$...$` inline and `$$...$$` as block.
+#[derive(Default)]
+pub struct MathHandler;
+
+impl TagHandler for MathHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        let Some((latex, display)) = math_latex(tag) else {
+            return;
+        };
+        if display {
+            printer.append_str(&format!("\n\n$${}$$\n\n", latex));
+        } else {
+            printer.append_str(&format!("${}$", latex));
+        }
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+
+    fn skip_descendants(&self) -> bool {
+        true
+    }
+}
+
+/// Derives LaTeX from the MathML element `tag` and its descendants.
+pub fn mathml_to_latex(tag: &Handle) -> String {
+    let children = || -> Vec<Handle> {
+        tag.children
+            .borrow()
+            .iter()
+            .filter(|child| matches!(child.data, NodeData::Element { .. }))
+            .cloned()
+            .collect()
+    };
+    let arg = |i: usize| children().get(i).map(mathml_to_latex).unwrap_or_default();
+    match element_name(tag).as_str() {
+        "mi" => {
+            let text = text_content(tag);
+            let text = text.trim();
+            let variant = get_tag_attr(tag, "mathvariant").unwrap_or_default();
+            if text.chars().count() > 1 {
+                if FUNCTIONS.contains(&text) {
+                    format!("\\{}", text)
+                } else {
+                    format!("\\mathrm{{{}}}", text)
+                }
+            } else {
+                let symbol = symbols(text);
+                match variant.as_str() {
+                    "normal" if text.chars().all(|c| c.is_ascii_alphabetic()) => {
+                        format!("\\mathrm{{{}}}", symbol)
+                    }
+                    "bold" => format!("\\mathbf{{{}}}", symbol),
+                    "double-struck" => format!("\\mathbb{{{}}}", symbol),
+                    "fraktur" => format!("\\mathfrak{{{}}}", symbol),
+                    "script" => format!("\\mathcal{{{}}}", symbol),
+                    _ => symbol,
+                }
+            }
+        }
+        "mn" => text_content(tag).trim().to_string(),
+        "mo" => match text_content(tag).trim() {
+            "{" => "\\{".to_string(),
+            "}" => "\\}".to_string(),
+            "−" => "-".to_string(),
+            text => symbols(text),
+        },
+        "mtext" => {
+            let text = text_content(tag);
+            if text.trim().is_empty() {
+                " ".to_string()
+            } else {
+                format!("\\text{{{}}}", text.trim())
+            }
+        }
+        "mspace" => " ".to_string(),
+        "msup" => format!("{}^{}", group(&arg(0)), group(&arg(1))),
+        "msub" => format!("{}_{}", group(&arg(0)), group(&arg(1))),
+        "msubsup" | "munderover" => {
+            format!("{}_{}^{}", group(&arg(0)), group(&arg(1)), group(&arg(2)))
+        }
+        "munder" => format!("{}_{}", group(&arg(0)), group(&arg(1))),
+        "mover" => {
+            let accent = match arg(1).as_str() {
+                "^" | "\\hat" | "ˆ" => Some("\\hat"),
+                "~" | "˜" => Some("\\tilde"),
+                "_" | "¯" | "‾" => Some("\\overline"),
+                "\\to" | "⃗" => Some("\\vec"),
+                "." | "˙" => Some("\\dot"),
+                _ => None,
+            };
+            match accent {
+                Some(accent) => format!("{}{{{}}}", accent, arg(0)),
+                None => format!("\\overset{{{}}}{{{}}}", arg(1), arg(0)),
+            }
+        }
+        "mfrac" => format!("\\frac{{{}}}{{{}}}", arg(0), arg(1)),
+        "msqrt" => format!("\\sqrt{{{}}}", concat(&children())),
+        "mroot" => format!("\\sqrt[{}]{{{}}}", arg(1), arg(0)),
+        "mtable" => {
+            let rows = children()
+                .iter()
+                .map(|row| {
+                    row.children
+                        .borrow()
+                        .iter()
+                        .filter(|cell| matches!(cell.data, NodeData::Element { .. }))
+                        .map(mathml_to_latex)
+                        .collect::<Vec<_>>()
+                        .join(" & ")
+                })
+                .collect::<Vec<_>>();
+            format!("\\begin{{matrix}} {} \\end{{matrix}}", rows.join(" \\\\ "))
+        }
+        // Only the first child of `<semantics>` is the presentation markup.
+        "semantics" => arg(0),
+        "annotation" | "annotation-xml" | "mphantom" => String::new(),
+        _ => concat(&children()),
+    }
+}
+
+/// Concatenates the LaTeX of `nodes`. A space separates a command or a one
+/// character script, e.g. `^n`, from a following letter.
+fn concat(nodes: &[Handle]) -> String {
+    let mut latex = String::new();
+    for node in nodes {
+        let next = mathml_to_latex(node);
+        let command_end = latex.rsplit_once('\\').is_some_and(|(_, cmd)| {
+            !cmd.is_empty() && cmd.chars().all(|c| c.is_ascii_alphabetic())
+        });
+        let mut last = latex.chars().rev();
+        let script_end = last.next().is_some_and(|c| c.is_alphanumeric())
+            && last.next().is_some_and(|c| c == '^' || c == '_');
+        if (command_end || script_end) && next.starts_with(|c: char| c.is_alphanumeric()) {
+            latex.push(' ');
+        }
+        latex.push_str(&next);
+    }
+    latex
+}
+
+/// Replaces the Unicode characters of `text` with LaTeX commands.
+fn symbols(text: &str) -> String {
+    let mut latex = String::new();
+    for c in text.chars() {
+        match SYMBOLS.iter().find(|(symbol, _)| *symbol == c) {
+            Some((_, command)) => latex.push_str(command),
+            // Invisible function application and times.
+            None if ('\u{2061}'..='\u{2063}').contains(&c) => {}
+            None => latex.push(c),
+        }
+    }
+    latex
+}
+
+/// Encloses `latex` in braces, unless it is a single character or command.
+fn group(latex: &str) -> String {
+    let is_command = latex
+        .strip_prefix('\\')
+        .is_some_and(|cmd| !cmd.is_empty() && cmd.chars().all(|c| c.is_ascii_alphabetic()));
+    if latex.chars().count() == 1 || is_command {
+        latex.to_string()
+    } else {
+        format!("{{{}}}", latex)
+    }
+}
+
+/// Returns the first descendant of `tag` matching `predicate`.
+fn find_descendant(tag: &Handle, predicate: &dyn Fn(&Handle) -> bool) -> Option<Handle> {
+    for child in tag.children.borrow().iter() {
+        if predicate(child) {
+            return Some(child.clone());
+        }
+        if let Some(found) = find_descendant(child, predicate) {
+            return Some(found);
+        }
+    }
+    None
+}
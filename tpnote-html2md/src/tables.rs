@@ -1,3 +1,5 @@
+use crate::common::get_tag_attr;
+use crate::dummy::IdentityHandler;
 use crate::markup5ever_rcdom;
 
 use super::StructuredPrinter;
@@ -8,58 +10,63 @@ use std::{cmp, collections::HashMap};
 
 use markup5ever_rcdom::{Handle, NodeData};
 
+/// Tags that can not be expressed inside of a GFM table cell. Tables
+/// containing them are kept as HTML.
+const BLOCK_TAGS: [&str; 6] = ["table", "pre", "ul", "ol", "dl", "blockquote"];
+
+/// Converts tables into GFM tables.
+///
+/// Cells spanning several columns are padded with empty cells, cells spanning
+/// several rows are repeated in each row. A caption precedes the table as
+/// paragraph. Tables with block content, e.g. nested tables or lists, are
+/// emitted as HTML block.
 #[derive(Default)]
 pub struct TableHandler;
 
 impl TagHandler for TableHandler {
     fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
         let mut table_markup = String::new();
-        let any_matcher = |cell: &Handle| {
-            let name = tag_name(cell);
-            name == "td" || name == "th"
-        };
 
-        // detect cell width, counts
-        let column_count: usize;
-        let mut column_widths: Vec<usize>;
-        let rows = find_children(tag, "tr");
-        {
-            // detect row count
-            let most_big_row = rows.iter().max_by(|left, right| {
-                collect_children(left, any_matcher)
-                    .len()
-                    .cmp(&collect_children(right, any_matcher).len())
-            });
-            if most_big_row.is_none() {
-                // we don't have rows with content at all
-                return;
-            }
-            // have rows with content, set column count
-            column_count = collect_children(most_big_row.unwrap(), any_matcher).len();
-            column_widths = vec![3; column_count];
+        if has_block_content(tag) {
+            printer.insert_newline();
+            printer.insert_newline();
+            printer.append_str(&to_html_block(tag));
+            printer.insert_newline();
+            printer.insert_newline();
+            return;
+        }
 
-            // detect max column width
-            for row in &rows {
-                let cells = collect_children(row, any_matcher);
-                #[allow(clippy::needless_range_loop)]
-                for index in 0..column_count {
-                    // from regular rows
-                    if let Some(cell) = cells.get(index) {
-                        let text = to_text(cell);
-                        column_widths[index] = cmp::max(column_widths[index], text.chars().count());
-                    }
-                }
+        // lay out the cells on a grid, resolving spans
+        let grid = layout(&table_rows(tag));
+        let texts: Vec<Vec<String>> = grid
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.as_ref().map(to_cell_text).unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+
+        // detect cell width, counts
+        let column_count = texts.iter().map(|row| row.len()).max().unwrap_or_default();
+        if column_count == 0 {
+            // we don't have rows with content at all
+            return;
+        }
+        let mut column_widths: Vec<usize> = vec![3; column_count];
+        for row in &texts {
+            for (index, text) in row.iter().enumerate() {
+                column_widths[index] = cmp::max(column_widths[index], text.chars().count());
             }
         }
 
         // header row must always be present
-        for (idx, row) in rows.iter().enumerate() {
+        for (idx, row) in texts.iter().enumerate() {
             table_markup.push('|');
-            let cells = collect_children(row, any_matcher);
             #[allow(clippy::needless_range_loop)]
             for index in 0..column_count {
                 // we need to fill all cells in a column, even if some rows don't have enough
-                let padded_cell_text = pad_cell_text(&cells.get(index), column_widths[index]);
+                let padded_cell_text = pad_cell_text(row.get(index), column_widths[index]);
                 table_markup.push_str(&padded_cell_text);
                 table_markup.push('|');
             }
@@ -81,20 +88,9 @@ impl TagHandler for TableHandler {
 
                     // try to detect alignment
                     let mut alignment = String::new();
-                    if let Some(header_cell) = cells.get(index) {
+                    if let Some(Some(header_cell)) = grid[0].get(index) {
                         // we have a header, try to extract alignment from it
-                        alignment = match header_cell.data {
-                            NodeData::Element { ref attrs, .. } => {
-                                let attrs = attrs.borrow();
-                                let align_attr = attrs
-                                    .iter()
-                                    .find(|attr| attr.name.local.to_string() == "align");
-                                align_attr
-                                    .map(|attr| attr.value.to_string())
-                                    .unwrap_or_default()
-                            }
-                            _ => String::new(),
-                        };
+                        alignment = get_tag_attr(header_cell, "align").unwrap_or_default();
                     }
 
                     // push lines according to alignment, fallback to default behaviour
@@ -122,6 +118,14 @@ impl TagHandler for TableHandler {
 
         printer.insert_newline();
         printer.insert_newline();
+        if let Some(caption) = own_descendants(tag, "caption").first() {
+            let caption = to_text(caption);
+            if !caption.is_empty() {
+                printer.append_str(&caption);
+                printer.insert_newline();
+                printer.insert_newline();
+            }
+        }
         printer.append_str(&table_markup);
     }
 
@@ -132,16 +136,108 @@ impl TagHandler for TableHandler {
     }
 }
 
+/// Places the cells of `rows` on a grid. A cell spanning several columns is
+/// followed by empty (`None`) cells, a cell spanning several rows is repeated
+/// in the same column of the following rows.
+fn layout(rows: &[Handle]) -> Vec<Vec<Option<Handle>>> {
+    let mut grid = Vec::with_capacity(rows.len());
+    // per column: the cell repeated in the following rows and how many times
+    let mut spanning: Vec<Option<(Option<Handle>, usize)>> = Vec::new();
+    let any_matcher = |cell: &Handle| {
+        let name = tag_name(cell);
+        name == "td" || name == "th"
+    };
+
+    for row in rows {
+        let mut cells: Vec<Option<Handle>> = Vec::new();
+        for cell in collect_children(row, any_matcher) {
+            fill_spanned(&mut cells, &mut spanning, false);
+            let span = |attr: &str| {
+                get_tag_attr(&cell, attr)
+                    .and_then(|n| n.trim().parse::<usize>().ok())
+                    .unwrap_or(1)
+                    .clamp(1, 1000)
+            };
+            let (colspan, rowspan) = (span("colspan"), span("rowspan"));
+            for i in 0..colspan {
+                let content = (i == 0).then(|| cell.clone());
+                if rowspan > 1 {
+                    let column = cells.len();
+                    if spanning.len() <= column {
+                        spanning.resize(column + 1, None);
+                    }
+                    spanning[column] = Some((content.clone(), rowspan - 1));
+                }
+                cells.push(content);
+            }
+        }
+        fill_spanned(&mut cells, &mut spanning, true);
+
+        grid.push(cells);
+    }
+
+    grid
+}
+
+/// Appends the cells spanning from the rows above into the next columns of
+/// `cells`. Stops at the first column without such a cell, unless `all` is
+/// set: then the gaps are filled with empty cells.
+fn fill_spanned(
+    cells: &mut Vec<Option<Handle>>,
+    spanning: &mut [Option<(Option<Handle>, usize)>],
+    all: bool,
+) {
+    while cells.len() < spanning.len() {
+        let column = cells.len();
+        let Some((cell, remaining)) = spanning[column].take() else {
+            if all {
+                cells.push(None);
+                continue;
+            }
+            break;
+        };
+        if remaining > 1 {
+            spanning[column] = Some((cell.clone(), remaining - 1));
+        }
+        cells.push(cell);
+    }
+}
+
+/// Finds the rows of this table, without the rows of nested tables.
+fn table_rows(tag: &Handle) -> Vec<Handle> {
+    own_descendants(tag, "tr")
+}
+
+/// Checks whether one of the cells contains content that a GFM table cell
+/// can not hold, e.g. a nested table.
+fn has_block_content(tag: &Handle) -> bool {
+    tag.children
+        .borrow()
+        .iter()
+        .any(|child| BLOCK_TAGS.contains(&tag_name(child).as_str()) || has_block_content(child))
+}
+
+/// Serializes the table as HTML block. Markdown ends an HTML block at the
+/// first empty line, so these are removed.
+fn to_html_block(tag: &Handle) -> String {
+    let mut printer = StructuredPrinter::default();
+    IdentityHandler.handle(tag, &mut printer);
+    printer
+        .data
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// Pads cell text from right and left so it looks centered inside the table cell
 /// ### Arguments
-/// `tag` - optional reference to currently processed handle, text is extracted from here
+/// `text` - optional text of the currently processed cell
 ///
 /// `column_width` - precomputed column width to compute padding length from
-fn pad_cell_text(tag: &Option<&Handle>, column_width: usize) -> String {
+fn pad_cell_text(text: Option<&String>, column_width: usize) -> String {
     let mut result = String::new();
-    if let Some(cell) = tag {
-        // have header at specified position
-        let text = to_text(cell);
+    if let Some(text) = text {
         // compute difference between width and text length
         let len_diff = column_width - text.chars().count();
         if len_diff > 0 {
@@ -151,16 +247,16 @@ fn pad_cell_text(tag: &Option<&Handle>, column_width: usize) -> String {
                 let pad_len = len_diff / 2;
                 let remainder = len_diff % 2;
                 result.push_str(&" ".repeat(pad_len));
-                result.push_str(&text);
+                result.push_str(text);
                 result.push_str(&" ".repeat(pad_len + remainder));
             } else {
                 // it's just one space, add at the end
-                result.push_str(&text);
+                result.push_str(text);
                 result.push(' ');
             }
         } else {
             // shouldn't pad, text fills whole cell
-            result.push_str(&text);
+            result.push_str(text);
         }
     } else {
         // no text in this cell, fill cell with spaces
@@ -181,17 +277,21 @@ fn tag_name(tag: &Handle) -> String {
 }
 
 /// Find descendants of this tag with tag name `name`
-/// This includes both direct children and descendants
-fn find_children(tag: &Handle, name: &str) -> Vec<Handle> {
+/// This includes both direct children and descendants, but not the content
+/// of nested tables
+fn own_descendants(tag: &Handle, name: &str) -> Vec<Handle> {
     let mut result: Vec<Handle> = vec![];
     let children = tag.children.borrow();
     for child in children.iter() {
-        if tag_name(child) == name {
+        let child_name = tag_name(child);
+        if child_name == name {
             result.push(child.clone());
         }
 
-        let mut descendants = find_children(child, name);
-        result.append(&mut descendants);
+        if child_name != "table" {
+            let mut descendants = own_descendants(child, name);
+            result.append(&mut descendants);
+        }
     }
 
     result
@@ -224,3 +324,8 @@ fn to_text(tag: &Handle) -> String {
     let result = clean_markdown(&printer.data);
     result.replace("\n", "<br/>")
 }
+
+/// Convert a table cell to text, escaping pipes, which would end the cell.
+fn to_cell_text(tag: &Handle) -> String {
+    to_text(tag).replace('|', "\\|")
+}
@@ -0,0 +1,83 @@
+extern crate html2md;
+
+use html2md::parse_html;
+
+#[test]
+fn test_footnotes_pulldown_cmark() {
+    let md = parse_html(
+        r##"
+        <p>Text<sup class="footnote-reference"><a href="#1">1</a></sup>
+        and more<sup class="footnote-reference"><a href="#note">2</a></sup>.</p>
+        <div class="footnote-definition" id="1"><sup class="footnote-definition-label">1</sup>
+        <p>First note.</p>
+        </div>
+        <div class="footnote-definition" id="note"><sup class="footnote-definition-label">2</sup>
+        <p>Second note.</p><p>Second paragraph.</p>
+        </div>
+        <p>After</p>
+    "##,
+    );
+    assert_eq!(
+        md,
+        "\
+Text[^1] and more[^2].
+
+After
+
+[^1]: First note.
+
+[^2]: Second note.
+
+    Second paragraph."
+    )
+}
+
+#[test]
+fn test_footnotes_pandoc() {
+    let md = parse_html(
+        r##"
+        <p>Pandoc<a href="#fn1" class="footnote-ref" id="fnref1" role="doc-noteref"><sup>1</sup></a> text.</p>
+        <section id="footnotes" class="footnotes footnotes-end-of-document" role="doc-endnotes">
+        <hr />
+        <ol>
+        <li id="fn1"><p>The <em>note</em>.<a href="#fnref1" class="footnote-back" role="doc-backlink">↩︎</a></p></li>
+        </ol>
+        </section>
+    "##,
+    );
+    assert_eq!(md, "Pandoc[^1] text.\n\n[^1]: The *note*.")
+}
+
+#[test]
+fn test_footnotes_wikipedia() {
+    // The footnote body precedes the reference and the label is not a number.
+    let md = parse_html(
+        r##"
+        <ol class="references">
+        <li id="cite_note-a"><span><a href="#cite_ref-a">^</a></span> <span>A source.</span></li>
+        </ol>
+        <p>Wiki<sup id="cite_ref-a" class="reference"><a href="#cite_note-a">[a]</a></sup>
+        and again<sup class="reference"><a href="#cite_note-a">[a]</a></sup>.</p>
+    "##,
+    );
+    assert_eq!(md, "Wiki[^a] and again[^a].\n\n[^a]: A source.")
+}
+
+#[test]
+fn test_footnotes_unresolved() {
+    // A superscript link without footnote body is still a reference.
+    let md = parse_html(r##"<p>See<sup><a href="#missing">*</a></sup>, <sup>2</sup></p>"##);
+    assert_eq!(md, "See[^1], <sup>2</sup>")
+}
+
+#[test]
+fn test_footnotes_no_body() {
+    // Links to elements enclosing them or outside of footnote sections are
+    // no footnote references.
+    let md = parse_html(
+        r##"<div id="top"><h1>Title</h1><p>Text</p><a href="#top"><sup>↑</sup></a></div>"##,
+    );
+    assert_eq!(md, "# Title\n\nText\n\n[<sup>↑</sup>](#top)");
+    let md = parse_html(r##"<h2 id="intro">Intro<sup><a href="#intro">[1]</a></sup></h2>"##);
+    assert_eq!(md, "## Intro<sup><a href=\"#intro\">[1]</a></sup>");
+}
@@ -272,3 +272,61 @@ Phrases to describe me:
 * Learning to count to six B)"
     )
 }
+
+#[test]
+fn test_list_tasks() {
+    let md = parse_html(
+        r#"
+        <ul>
+            <li><input type="checkbox" disabled checked> Write the parser</li>
+            <li><input disabled="" type="checkbox"/>
+            Write the tests</li>
+            <li><p><input type="checkbox"> Ship it</p></li>
+        </ul>
+        <ol><li><input type="checkbox" checked>First</li></ol>
+        <p>Not a task <input type="checkbox"></p>
+    "#,
+    );
+    assert_eq!(
+        md,
+        "\
+* [x] Write the parser
+* [ ] Write the tests
+* [ ] Ship it
+
+1. [x] First
+
+Not a task"
+    )
+}
+
+#[test]
+fn test_definition_list() {
+    let md = parse_html(
+        r#"
+        <dl>
+            <dt>Apple</dt>
+            <dd>A <em>red</em> fruit</dd>
+            <dt>Orange</dt>
+            <dd><p>An orange fruit</p><p>Juicy</p></dd>
+            <dd>A color</dd>
+        </dl>
+        <p>After</p>
+    "#,
+    );
+    assert_eq!(
+        md,
+        "\
+Apple
+: A *red* fruit
+
+Orange
+: An orange fruit
+
+  Juicy
+
+: A color
+
+After"
+    )
+}
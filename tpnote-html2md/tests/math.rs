@@ -0,0 +1,37 @@
+extern crate html2md;
+
+use html2md::parse_html;
+
+#[test]
+fn test_math_katex() {
+    let md = parse_html(
+        r#"<p>Energy <span class="katex"><span class="katex-mathml"><math xmlns="http://www.w3.org/1998/Math/MathML"><semantics><mrow><mi>E</mi><mo>=</mo><mi>m</mi><msup><mi>c</mi><mn>2</mn></msup></mrow><annotation encoding="application/x-tex">E=mc^2</annotation></semantics></math></span><span class="katex-html" aria-hidden="true"><span class="base"><span class="mord mathnormal">E</span><span class="mrel">=</span></span></span></span> is conserved:</p>
+        <span class="katex-display"><span class="katex"><span class="katex-mathml"><math xmlns="http://www.w3.org/1998/Math/MathML" display="block"><semantics><mrow><mi>x</mi></mrow><annotation encoding="application/x-tex">\sum_{i=1}^n
+        x_i</annotation></semantics></math></span><span class="katex-html" aria-hidden="true">∑x</span></span></span>"#,
+    );
+    assert_eq!(md, "Energy $E=mc^2$ is conserved:\n\n$$\\sum_{i=1}^n x_i$$")
+}
+
+#[test]
+fn test_math_wikipedia() {
+    let md = parse_html(
+        r#"<p>The formula <span class="mwe-math-element"><span class="mwe-math-mathml-inline mwe-math-mathml-a11y" style="display: none;"><math xmlns="http://www.w3.org/1998/Math/MathML" alttext="{\displaystyle a^{2}+b^{2}=c^{2}}"><semantics><mrow class="MJX-TeXAtom-ORD"><mstyle displaystyle="true" scriptlevel="0"><msup><mi>a</mi><mrow class="MJX-TeXAtom-ORD"><mn>2</mn></mrow></msup></mstyle></mrow><annotation encoding="application/x-tex">{\displaystyle a^{2}+b^{2}=c^{2}}</annotation></semantics></math></span><img src="https://wikimedia.org/api/rest_v1/media/math/render/svg/abc" class="mwe-math-fallback-image-inline mw-invert skin-invert" aria-hidden="true" alt="{\displaystyle a^{2}+b^{2}=c^{2}}"></span> holds.</p>"#,
+    );
+    assert_eq!(md, "The formula $a^{2}+b^{2}=c^{2}$ holds.")
+}
+
+#[test]
+fn test_math_mathml() {
+    // Without annotation, e.g. rendered by `latex2mathml`.
+    let md = parse_html(
+        r#"<p><math xmlns="http://www.w3.org/1998/Math/MathML" display="inline"><mfrac><mrow><mi>a</mi><mo>+</mo><mn>1</mn></mrow><msqrt><mi>b</mi></msqrt></mfrac><mo>≤</mo><munderover><mo>∑</mo><mrow><mi>i</mi><mo>=</mo><mn>1</mn></mrow><mi>n</mi></munderover><msub><mi>x</mi><mi>i</mi></msub><mo>·</mo><mi>α</mi><mi>x</mi></math></p>
+        <math xmlns="http://www.w3.org/1998/Math/MathML" display="block"><msubsup><mo>∫</mo><mn>0</mn><mi mathvariant="normal">∞</mi></msubsup><msup><mi>e</mi><mrow><mo>-</mo><mi>x</mi></mrow></msup><mi mathvariant="normal">d</mi><mi>x</mi><mo>+</mo><mover><mi>x</mi><mo accent="true">^</mo></mover><mo>+</mo><mroot><mi>x</mi><mn>3</mn></mroot><mo>+</mo><mi>sin</mi><mi mathvariant="double-struck">R</mi></math>
+        <p><math alttext="x &lt; y"><mi>x</mi><mo>&lt;</mo><mi>y</mi></math></p>"#,
+    );
+    assert_eq!(
+        md,
+        "$\\frac{a+1}{\\sqrt{b}}\\leq\\sum_{i=1}^n x_i\\cdot\\alpha x$\n\n\
+         $$\\int_0^\\infty e^{-x}\\mathrm{d}x+\\hat{x}+\\sqrt[3]{x}+\\sin\\mathbb{R}$$\n\n\
+         $x < y$"
+    )
+}
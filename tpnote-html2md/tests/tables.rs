@@ -218,3 +218,56 @@ fn test_tables_wild_example() {
 | One ring to bring them all  | And three for the girls  |      Roll on, Titanic, roll       |   |   |   |
 |And in the darkness bind them|  And four for the boys   |      Into the mists of time       |   |   |   |");
 }
+
+#[test]
+fn test_tables_spans() {
+    // Spreadsheet clipboard: no `<thead>`, merged cells.
+    let md = parse_html(
+        r#"<table>
+  <caption>Sales | 2024</caption>
+  <tr><th rowspan="2">Region</th><th colspan="2">Half year</th></tr>
+  <tr><th>H1</th><th>H2</th></tr>
+  <tr><td>North</td><td>1|2</td><td rowspan="2">7</td></tr>
+  <tr><td>South</td><td>3</td></tr>
+</table>"#,
+    );
+
+    assert_eq!(
+        md,
+        "\
+Sales | 2024
+
+|Region|Half year|   |
+|------|---------|---|
+|Region|   H1    |H2 |
+|North |  1\\|2   | 7 |
+|South |    3    | 7 |"
+    );
+}
+
+#[test]
+fn test_tables_nested() {
+    // A nested table can not be expressed in GFM.
+    let md = parse_html(
+        r#"<p>Before</p>
+<table>
+  <tr><th>Outer</th></tr>
+
+  <tr><td><table><tr><td>Inner</td></tr></table></td></tr>
+</table>
+<p>After</p>"#,
+    );
+
+    assert_eq!(
+        md,
+        "\
+Before
+
+<table>
+  <tbody><tr><th>Outer</th></tr>
+  <tr><td><table><tbody><tr><td>Inner</td></tr></tbody></table></td></tr>
+</tbody></table>
+
+After"
+    );
+}
@@ -63,6 +63,12 @@ fn test_escaping() {
     )
 }
 
+#[test]
+fn test_escaping_backticks() {
+    let md = parse_html(r#"<p>Not `code`, but <code>code</code></p>"#);
+    assert_eq!(md, "Not \\`code\\`, but `code`")
+}
+
 #[test]
 fn test_escaping_mid_hyphens() {
     let md = parse_html(r#"<h1>This is a header with-hyphen!</h1>"#);
@@ -0,0 +1,113 @@
+//! Measures the Markdown rendition of a code heavy note with the shared
+//! syntax registry `HIGHLIGHT_REGISTRY` and compares it with the former
+//! approach, which reloaded the syntax definitions for every code block.
+//!
+//! Run with:
+//!
+//! ```sh
+//! cargo bench -p tpnote-lib --bench highlight
+//! ```
+
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, html};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use syntect::html::{ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+use tpnote_lib::highlight::HIGHLIGHT_REGISTRY;
+use tpnote_lib::highlight::SyntaxPreprocessor;
+
+/// Number of fenced code blocks in the benchmark note.
+const CODE_BLOCKS: usize = 40;
+/// Number of renditions per measurement.
+const ITERATIONS: u32 = 5;
+
+/// Returns a Markdown note with `CODE_BLOCKS` fenced code blocks.
+fn code_heavy_note() -> String {
+    let mut note = String::from("# Code heavy note\n\n");
+    for i in 0..CODE_BLOCKS {
+        let (lang, code) = match i % 4 {
+            0 => ("rust", "fn main() {\n    println!(\"Hello, world!\");\n}\n"),
+            1 => ("python", "def main():\n    print('Hello, world!')\n"),
+            2 => ("bash", "for f in *.md; do\n    echo \"$f\"\ndone\n"),
+            _ => ("json", "{\"hello\": [\"world\", 1, true]}\n"),
+        };
+        note.push_str(&format!("Paragraph {i}.\n\n```{lang}\n{code}```\n\n"));
+    }
+    note
+}
+
+/// Renders `note` with Tp-Note's `SyntaxPreprocessor`.
+fn render_shared_registry(note: &str) -> String {
+    let parser = SyntaxPreprocessor::new(Parser::new_ext(note, Options::all()));
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, parser);
+    html_output
+}
+
+/// Renders `note` loading the syntax definitions for every code block, as
+/// Tp-Note did before `HIGHLIGHT_REGISTRY` was introduced.
+fn render_reload_per_block(note: &str) -> String {
+    let mut events = Parser::new_ext(note, Options::all());
+    let mut out = Vec::new();
+    while let Some(event) = events.next() {
+        let Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) = event else {
+            out.push(event);
+            continue;
+        };
+        let mut code = String::new();
+        for event in events.by_ref() {
+            match event {
+                Event::Text(t) => code.push_str(&t),
+                _ => break,
+            }
+        }
+        let ss = SyntaxSet::load_defaults_newlines();
+        let sr = ss
+            .find_syntax_by_token(&lang)
+            .unwrap_or_else(|| ss.find_syntax_plain_text());
+        let mut html_generator =
+            ClassedHTMLGenerator::new_with_class_style(sr, &ss, ClassStyle::Spaced);
+        for line in LinesWithEndings::from(&code) {
+            html_generator
+                .parse_html_for_line_which_includes_newline(line)
+                .unwrap_or_default();
+        }
+        out.push(Event::Html(
+            format!("<pre><code>{}</code></pre>", html_generator.finalize()).into(),
+        ));
+    }
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, out.into_iter());
+    html_output
+}
+
+/// Returns the mean duration of `ITERATIONS` calls of `f`.
+fn measure(f: impl Fn() -> String) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let note = code_heavy_note();
+
+    // The first access initializes the registry: measure it separately.
+    let start = Instant::now();
+    black_box(&*HIGHLIGHT_REGISTRY);
+    let init = start.elapsed();
+
+    let shared = measure(|| render_shared_registry(&note));
+    let reload = measure(|| render_reload_per_block(&note));
+
+    println!("Rendition of a note with {CODE_BLOCKS} code blocks, mean of {ITERATIONS} runs:");
+    println!("  registry initialization (once):  {init:>12.2?}");
+    println!("  shared registry:                 {shared:>12.2?}");
+    println!("  syntax reload per code block:    {reload:>12.2?}");
+    println!(
+        "  speedup:                         {:>11.1}x",
+        reload.as_secs_f64() / shared.as_secs_f64()
+    );
+}
@@ -0,0 +1,1227 @@
+//! A renderer for the subset of AsciiDoc commonly found in notes.
+//!
+//! The block parser recognizes section titles, paragraphs, (nested) ordered,
+//! unordered, check and description lists, tables, admonitions, listing,
+//! literal, source, example, sidebar, quote and passthrough blocks, block
+//! images and thematic breaks. Inline markup comprises strong, emphasis,
+//! monospace, cross references (`<<id>>`, `<<id,text>>`, `xref:target[text]`),
+//! `link:` macros, bare URLs and inline images. Source blocks are highlighted
+//! with the configured `HighlightRegistry`.
+//!
+//! Section titles get Asciidoctor compatible ids, e.g. `== First Section`
+//! becomes `<h2 id="_first_section">`. Attribute entries, comments and
+//! preprocessor directives are skipped.
+use crate::highlight::HighlightRegistry;
+use crate::html::unique_id;
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Labels of the admonition styles, as they are written in the AsciiDoc
+/// source (`NOTE: ...` or `[NOTE]`).
+const ADMONITIONS: [&str; 5] = ["NOTE", "TIP", "IMPORTANT", "WARNING", "CAUTION"];
+
+/// Delimiters of blocks whose content is not parsed.
+const VERBATIM_DELIMITERS: [&str; 3] = ["----", "....", "++++"];
+
+/// Delimiters of blocks containing other blocks.
+const COMPOUND_DELIMITERS: [&str; 3] = ["====", "****", "____"];
+
+/// Block attributes, e.g. `[source,rust]` or `[[id]]`, and the block title
+/// `.Title` preceding a block.
+#[derive(Debug, Default)]
+struct BlockMeta {
+    style: Option<String>,
+    /// Second positional attribute, e.g. the language of a source block.
+    second: Option<String>,
+    id: Option<String>,
+    title: Option<String>,
+    /// Set by `%header` or `options="header"`.
+    header: bool,
+    /// Number of columns set by `cols=...`.
+    cols: Option<usize>,
+}
+
+impl BlockMeta {
+    fn is_empty(&self) -> bool {
+        self.style.is_none() && self.id.is_none() && self.title.is_none()
+    }
+
+    /// Parses the block attribute line `[...]` and adds the result to `self`.
+    fn parse_attributes(&mut self, line: &str) {
+        let line = line.trim();
+        if let Some(id) = line.strip_prefix("[[").and_then(|l| l.strip_suffix("]]")) {
+            self.id = Some(id.split(',').next().unwrap_or_default().to_string());
+            return;
+        }
+        let inner = &line[1..line.len() - 1];
+        for (i, attr) in split_attributes(inner).into_iter().enumerate() {
+            if let Some((key, val)) = attr.split_once('=') {
+                let val = val.trim_matches('"');
+                match key.trim() {
+                    "id" => self.id = Some(val.to_string()),
+                    "options" | "opts" if val.split(',').any(|o| o.trim() == "header") => {
+                        self.header = true
+                    }
+                    "cols" if val.trim().parse::<usize>().is_ok() => {
+                        self.cols = val.trim().parse().ok()
+                    }
+                    "cols" => {
+                        self.cols = Some(
+                            val.split(',')
+                                .map(|c| {
+                                    c.split_once('*')
+                                        .and_then(|(n, _)| n.trim().parse::<usize>().ok())
+                                        .unwrap_or(1)
+                                })
+                                .sum(),
+                        )
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            // Shorthands: `style#id.role%option`.
+            let mut rest = attr;
+            let mut style = "";
+            let end = rest.find(['#', '.', '%']).unwrap_or(rest.len());
+            if i == 0 {
+                style = &rest[..end];
+            }
+            rest = &rest[end..];
+            while !rest.is_empty() {
+                let end = rest[1..]
+                    .find(['#', '.', '%'])
+                    .map_or(rest.len(), |e| e + 1);
+                let (shorthand, value) = rest[..end].split_at(1);
+                match shorthand {
+                    "#" => self.id = Some(value.to_string()),
+                    "%" if value == "header" => self.header = true,
+                    _ => {}
+                }
+                rest = &rest[end..];
+            }
+            match i {
+                0 if !style.is_empty() => self.style = Some(style.to_string()),
+                1 => self.second = Some(attr.to_string()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Splits a block attribute list at commas outside of double quotes.
+fn split_attributes(inner: &str) -> Vec<&str> {
+    let mut attrs = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                attrs.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    attrs.push(inner[start..].trim());
+    attrs
+}
+
+/// The HTML writer for AsciiDoc input.
+struct Renderer<'a> {
+    lines: Vec<&'a str>,
+    pos: usize,
+    html: String,
+    /// Section ids mapped to their section titles.
+    titles: HashMap<String, String>,
+    /// Ids already in use.
+    ids: HashSet<String>,
+    registry: Arc<HighlightRegistry>,
+}
+
+/// Renders AsciiDoc `input` to HTML.
+///
+/// ```rust
+/// use tpnote_lib::asciidoc::asciidoc_to_html;
+///
+/// let input = "== Intro\n\nSee <<_intro>> and *bold* text.";
+/// let expected = "<h2 id=\"_intro\">Intro</h2>\n\
+///     <p>See <a href=\"#_intro\">Intro</a> and <strong>bold</strong> text.</p>\n";
+/// assert_eq!(asciidoc_to_html(input), expected);
+/// ```
+pub fn asciidoc_to_html(input: &str) -> String {
+    let mut renderer = Renderer {
+        lines: input.lines().collect(),
+        pos: 0,
+        html: String::with_capacity(input.len() * 3 / 2),
+        titles: HashMap::new(),
+        ids: HashSet::new(),
+        registry: HighlightRegistry::configured(),
+    };
+    renderer.collect_section_ids();
+    renderer.blocks(None);
+    renderer.html
+}
+
+impl<'a> Renderer<'a> {
+    /// First pass: register the ids of all section titles, so that cross
+    /// references without link text can be rendered with the section title.
+    fn collect_section_ids(&mut self) {
+        let mut ids = HashSet::new();
+        let mut verbatim = None;
+        let mut explicit_id = None;
+        for line in &self.lines {
+            let trimmed = line.trim_end();
+            if let Some(delimiter) = verbatim {
+                if trimmed == delimiter {
+                    verbatim = None;
+                }
+                continue;
+            }
+            if VERBATIM_DELIMITERS.contains(&trimmed) {
+                verbatim = Some(trimmed);
+            } else if trimmed.starts_with('[') && trimmed.ends_with(']') && trimmed.len() > 2 {
+                let mut meta = BlockMeta::default();
+                meta.parse_attributes(trimmed);
+                explicit_id = meta.id.or(explicit_id);
+                continue;
+            } else if let Some((_, title)) = section_title(trimmed) {
+                let id = match explicit_id.take() {
+                    Some(id) => id,
+                    None => unique_id(&mut ids, &section_id(title)),
+                };
+                self.titles.insert(id, title.to_string());
+            }
+            explicit_id = None;
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.lines.get(self.pos).copied()
+    }
+
+    /// Renders blocks until the line `until` or the end of input is reached.
+    /// The closing delimiter line is consumed.
+    fn blocks(&mut self, until: Option<&str>) {
+        let mut meta = BlockMeta::default();
+        let mut comment_block = false;
+        while let Some(line) = self.peek() {
+            let trimmed = line.trim_end();
+            self.pos += 1;
+            if comment_block {
+                comment_block = trimmed != "////";
+                continue;
+            }
+            if Some(trimmed) == until {
+                return;
+            }
+            if trimmed.is_empty() {
+                continue;
+            }
+            if trimmed == "////" {
+                comment_block = true;
+                continue;
+            }
+            if (trimmed.starts_with("//") && !trimmed.starts_with("///"))
+                || is_attribute_entry(trimmed)
+                || is_directive(trimmed)
+                || trimmed == "<<<"
+            {
+                continue;
+            }
+            if trimmed.starts_with('[') && trimmed.ends_with(']') && trimmed.len() > 2 {
+                meta.parse_attributes(trimmed);
+                continue;
+            }
+            if let Some(title) = trimmed.strip_prefix('.')
+                && !title.is_empty()
+                && !title.starts_with(['.', ' '])
+                && !is_list_item(trimmed)
+            {
+                meta.title = Some(title.to_string());
+                continue;
+            }
+            self.block(trimmed, std::mem::take(&mut meta));
+        }
+    }
+
+    /// Renders the block starting with the (already consumed) `line`.
+    fn block(&mut self, line: &'a str, meta: BlockMeta) {
+        if let Some((level, title)) = section_title(line) {
+            let id = match meta.id {
+                Some(id) => id,
+                None => unique_id(&mut self.ids, &section_id(title)),
+            };
+            self.html.push_str(&format!(
+                "<h{level} id=\"{}\">{}</h{level}>\n",
+                encode_double_quoted_attribute(&id),
+                self.inline(title)
+            ));
+            return;
+        }
+
+        if line == "'''" || line == "---" || line == "***" {
+            self.html.push_str("<hr>\n");
+            return;
+        }
+
+        if line == "|===" {
+            self.table(meta);
+            return;
+        }
+
+        if VERBATIM_DELIMITERS.contains(&line) {
+            let content = self.verbatim(line);
+            self.verbatim_block(line, &content, meta);
+            return;
+        }
+
+        if COMPOUND_DELIMITERS.contains(&line) {
+            self.compound_block(line, meta);
+            return;
+        }
+
+        if let Some(target) = line.strip_prefix("image::")
+            && let Some((target, attrs)) = macro_target(target)
+            && target.len() + attrs.len() + 9 == line.len()
+        {
+            self.open_block("imageblock", &meta);
+            self.html.push_str(&image_html(target, attrs));
+            self.html.push_str("\n</div>\n");
+            return;
+        }
+
+        if let Some((label, text)) = ADMONITIONS
+            .iter()
+            .find_map(|a| line.strip_prefix(a)?.strip_prefix(": ").map(|t| (*a, t)))
+        {
+            let paragraph = self.paragraph_lines(text);
+            self.open_admonition(label, &meta);
+            self.html
+                .push_str(&format!("<p>{}</p>\n</div>\n", self.inline(&paragraph)));
+            return;
+        }
+
+        if is_list_item(line) {
+            self.pos -= 1;
+            let mut ancestors = Vec::new();
+            self.list(&mut ancestors);
+            return;
+        }
+
+        if let Some((term, definition)) = description_term(line) {
+            self.pos -= 1;
+            self.description_list(term, definition);
+            return;
+        }
+
+        // Literal paragraph.
+        if line.starts_with([' ', '\t']) {
+            let mut text = line.to_string();
+            while let Some(next) = self.peek()
+                && !next.trim().is_empty()
+            {
+                text.push('\n');
+                text.push_str(next);
+                self.pos += 1;
+            }
+            let text = unindent(&text);
+            self.open_block("literalblock", &meta);
+            self.html
+                .push_str(&format!("<pre>{}</pre>\n</div>\n", encode_text(&text)));
+            return;
+        }
+
+        let paragraph = self.paragraph_lines(line);
+        match meta.style.as_deref() {
+            Some(style) if ADMONITIONS.contains(&style) => {
+                self.open_admonition(style, &meta);
+                self.html
+                    .push_str(&format!("<p>{}</p>\n</div>\n", self.inline(&paragraph)));
+            }
+            Some("source") | Some("listing") => {
+                self.verbatim_block("----", &paragraph, meta);
+            }
+            Some("literal") => self.verbatim_block("....", &paragraph, meta),
+            Some("quote") | Some("verse") => {
+                self.open_quote(&meta);
+                self.html
+                    .push_str(&format!("<p>{}</p>\n", self.inline(&paragraph)));
+                self.close_quote(&meta);
+            }
+            _ if !meta.is_empty() => {
+                self.open_block("paragraph", &meta);
+                self.html
+                    .push_str(&format!("<p>{}</p>\n</div>\n", self.inline(&paragraph)));
+            }
+            _ => self
+                .html
+                .push_str(&format!("<p>{}</p>\n", self.inline(&paragraph))),
+        }
+    }
+
+    /// Collects the lines of a paragraph starting with `first`.
+    fn paragraph_lines(&mut self, first: &str) -> String {
+        let mut text = first.to_string();
+        while let Some(next) = self.peek() {
+            let next = next.trim_end();
+            if next.is_empty()
+                || (next.starts_with('[') && next.ends_with(']'))
+                || VERBATIM_DELIMITERS.contains(&next)
+                || COMPOUND_DELIMITERS.contains(&next)
+                || next == "|==="
+                || next.starts_with("//")
+            {
+                break;
+            }
+            text.push('\n');
+            text.push_str(next);
+            self.pos += 1;
+        }
+        text
+    }
+
+    /// Returns the content of a delimited block whose content is not parsed.
+    fn verbatim(&mut self, delimiter: &str) -> String {
+        let mut content = String::new();
+        while let Some(line) = self.peek() {
+            self.pos += 1;
+            if line.trim_end() == delimiter {
+                break;
+            }
+            content.push_str(line);
+            content.push('\n');
+        }
+        content
+    }
+
+    /// Renders a listing (`----`), literal (`....`) or passthrough (`++++`)
+    /// block.
+    fn verbatim_block(&mut self, delimiter: &str, content: &str, meta: BlockMeta) {
+        if delimiter == "++++" {
+            self.html.push_str(content);
+            return;
+        }
+        let source_lang = match meta.style.as_deref() {
+            Some("source") => Some(meta.second.as_deref().unwrap_or_default()),
+            _ => None,
+        };
+        if delimiter == "...." || meta.style.as_deref() == Some("literal") {
+            self.open_block("literalblock", &meta);
+            self.html
+                .push_str(&format!("<pre>{}</pre>\n</div>\n", encode_text(content)));
+        } else if let Some(lang) = source_lang.filter(|l| !l.is_empty()) {
+            self.open_block("listingblock", &meta);
+            let code = if content.ends_with('\n') {
+                content.to_string()
+            } else {
+                format!("{content}\n")
+            };
+            self.html
+                .push_str(&self.registry.highlight_code(lang, &code));
+            self.html.push_str("\n</div>\n");
+        } else {
+            self.open_block("listingblock", &meta);
+            self.html.push_str(&format!(
+                "<pre><code>{}</code></pre>\n</div>\n",
+                encode_text(content)
+            ));
+        }
+    }
+
+    /// Renders an example (`====`), sidebar (`****`) or quote (`____`) block.
+    fn compound_block(&mut self, delimiter: &'a str, meta: BlockMeta) {
+        match (delimiter, meta.style.as_deref()) {
+            (_, Some(style)) if ADMONITIONS.contains(&style) => {
+                self.open_admonition(style, &meta);
+                self.blocks(Some(delimiter));
+                self.html.push_str("</div>\n");
+            }
+            ("____", _) | (_, Some("quote")) => {
+                self.open_quote(&meta);
+                self.blocks(Some(delimiter));
+                self.close_quote(&meta);
+            }
+            _ => {
+                let class = if delimiter == "****" {
+                    "sidebarblock"
+                } else {
+                    "exampleblock"
+                };
+                self.open_block(class, &meta);
+                self.blocks(Some(delimiter));
+                self.html.push_str("</div>\n");
+            }
+        }
+    }
+
+    /// Writes `<div class="class" id="...">` and the block title.
+    fn open_block(&mut self, class: &str, meta: &BlockMeta) {
+        self.html.push_str(&format!("<div class=\"{class}\""));
+        if let Some(id) = &meta.id {
+            self.html
+                .push_str(&format!(" id=\"{}\"", encode_double_quoted_attribute(id)));
+        }
+        self.html.push_str(">\n");
+        if let Some(title) = &meta.title {
+            let title = self.inline(title);
+            self.html
+                .push_str(&format!("<div class=\"title\">{title}</div>\n"));
+        }
+    }
+
+    /// Writes the opening of an admonition block with the style `label`,
+    /// e.g. `NOTE`.
+    fn open_admonition(&mut self, label: &str, meta: &BlockMeta) {
+        let class = label.to_lowercase();
+        self.html
+            .push_str(&format!("<div class=\"admonition {class}\""));
+        if let Some(id) = &meta.id {
+            self.html
+                .push_str(&format!(" id=\"{}\"", encode_double_quoted_attribute(id)));
+        }
+        let title = match &meta.title {
+            Some(title) => self.inline(title),
+            None => {
+                let mut title = class;
+                title[..1].make_ascii_uppercase();
+                title
+            }
+        };
+        self.html
+            .push_str(&format!(">\n<p class=\"admonition-title\">{title}</p>\n"));
+    }
+
+    fn open_quote(&mut self, meta: &BlockMeta) {
+        self.open_block("quoteblock", meta);
+        self.html.push_str("<blockquote>\n");
+    }
+
+    /// Closes a quote block and writes its attribution, if any.
+    fn close_quote(&mut self, meta: &BlockMeta) {
+        self.html.push_str("</blockquote>\n");
+        if let Some(author) = meta.second.as_deref().filter(|a| !a.is_empty()) {
+            let author = self.inline(author.trim_matches('"'));
+            self.html.push_str(&format!(
+                "<div class=\"attribution\">&#8212; {author}</div>\n"
+            ));
+        }
+        self.html.push_str("</div>\n");
+    }
+
+    /// Renders an (ordered or unordered) list and its nested lists.
+    /// `ancestors` holds the markers of the enclosing lists.
+    fn list(&mut self, ancestors: &mut Vec<String>) {
+        let Some((marker, _)) = self.peek().and_then(|l| list_item(l.trim())) else {
+            return;
+        };
+        let marker = marker.to_string();
+        let ordered = marker.starts_with('.') || marker.ends_with('.');
+        let tag = if ordered { "ol" } else { "ul" };
+        self.html.push_str(&format!("<{tag}>\n"));
+        let mut item_open = false;
+        loop {
+            // Skip blank lines between items.
+            let mut lookahead = self.pos;
+            while self
+                .lines
+                .get(lookahead)
+                .is_some_and(|l| l.trim().is_empty())
+            {
+                lookahead += 1;
+            }
+            let Some((m, text)) = self.lines.get(lookahead).and_then(|l| list_item(l.trim()))
+            else {
+                break;
+            };
+            // A blank line followed by a different marker starts a new list.
+            if m != marker && (lookahead > self.pos || ancestors.contains(&m.to_string())) {
+                break;
+            }
+            self.pos = lookahead;
+            if m != marker {
+                // A nested list.
+                if !item_open {
+                    self.html.push_str("<li>");
+                    item_open = true;
+                }
+                self.html.push('\n');
+                ancestors.push(marker.clone());
+                self.list(ancestors);
+                ancestors.pop();
+                continue;
+            }
+            self.pos += 1;
+            if item_open {
+                self.html.push_str("</li>\n");
+            }
+            let text = self.list_item_lines(text);
+            self.html.push_str("<li>");
+            self.html.push_str(&self.check_box_inline(&text));
+            item_open = true;
+            self.list_continuation();
+        }
+        if item_open {
+            self.html.push_str("</li>\n");
+        }
+        self.html.push_str(&format!("</{tag}>\n"));
+    }
+
+    /// Renders list item text and replaces a leading `[ ]` or `[x]` with a
+    /// check box.
+    fn check_box_inline(&self, text: &str) -> String {
+        for (prefix, checked) in [("[ ] ", ""), ("[x] ", " checked"), ("[*] ", " checked")] {
+            if let Some(text) = text.strip_prefix(prefix) {
+                return format!(
+                    "<input type=\"checkbox\" disabled{checked}> {}",
+                    self.inline(text)
+                );
+            }
+        }
+        self.inline(text)
+    }
+
+    /// Collects the continuation lines of a list item.
+    fn list_item_lines(&mut self, first: &str) -> String {
+        let mut text = first.to_string();
+        while let Some(next) = self.peek() {
+            let next = next.trim();
+            if next.is_empty()
+                || next == "+"
+                || is_list_item(next)
+                || description_term(next).is_some()
+                || (next.starts_with('[') && next.ends_with(']'))
+                || VERBATIM_DELIMITERS.contains(&next)
+                || COMPOUND_DELIMITERS.contains(&next)
+                || next == "|==="
+            {
+                break;
+            }
+            text.push('\n');
+            text.push_str(next);
+            self.pos += 1;
+        }
+        text
+    }
+
+    /// Renders blocks attached to a list item with `+`.
+    fn list_continuation(&mut self) {
+        while self.peek().map(str::trim_end) == Some("+") {
+            self.pos += 1;
+            let mut meta = BlockMeta::default();
+            while let Some(line) = self.peek() {
+                let line = line.trim_end();
+                if line.starts_with('[') && line.ends_with(']') && line.len() > 2 {
+                    meta.parse_attributes(line);
+                    self.pos += 1;
+                } else if let Some(title) = line.strip_prefix('.')
+                    && !title.is_empty()
+                    && !title.starts_with(['.', ' '])
+                {
+                    meta.title = Some(title.to_string());
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            }
+            let Some(line) = self.peek() else {
+                return;
+            };
+            self.pos += 1;
+            let line = line.trim_end();
+            if is_list_item(line) || line.is_empty() {
+                self.pos -= 1;
+                return;
+            }
+            self.html.push('\n');
+            self.block(line, meta);
+        }
+    }
+
+    /// Renders a description list, `term:: definition`.
+    fn description_list(&mut self, mut term: &'a str, mut definition: &'a str) {
+        self.html.push_str("<dl>\n");
+        loop {
+            self.pos += 1;
+            self.html
+                .push_str(&format!("<dt>{}</dt>\n", self.inline(term)));
+            if definition.is_empty() {
+                // The definition may follow on the next line.
+                if let Some(next) = self.peek()
+                    && !next.trim().is_empty()
+                    && description_term(next.trim()).is_none()
+                    && !is_list_item(next.trim())
+                {
+                    definition = next.trim();
+                    self.pos += 1;
+                }
+            }
+            let text = self.list_item_lines(definition);
+            self.html.push_str("<dd>");
+            if !text.is_empty() {
+                self.html.push_str(&self.inline(&text));
+            }
+            if self.peek().is_some_and(|l| is_list_item(l.trim())) {
+                self.html.push('\n');
+                let mut ancestors = Vec::new();
+                self.list(&mut ancestors);
+            }
+            self.list_continuation();
+            self.html.push_str("</dd>\n");
+
+            let mut lookahead = self.pos;
+            while self
+                .lines
+                .get(lookahead)
+                .is_some_and(|l| l.trim().is_empty())
+            {
+                lookahead += 1;
+            }
+            match self
+                .lines
+                .get(lookahead)
+                .and_then(|l| description_term(l.trim_end()))
+            {
+                Some((t, d)) => {
+                    self.pos = lookahead;
+                    term = t;
+                    definition = d;
+                }
+                None => break,
+            }
+        }
+        self.html.push_str("</dl>\n");
+    }
+
+    /// Renders a table delimited by `|===`.
+    fn table(&mut self, meta: BlockMeta) {
+        let mut cells: Vec<String> = Vec::new();
+        let mut first_row_len = None;
+        let mut header = meta.header;
+        let mut line_no = 0;
+        while let Some(line) = self.peek() {
+            self.pos += 1;
+            let line = line.trim();
+            if line == "|===" {
+                break;
+            }
+            if line.is_empty() {
+                // An implicit header row is followed by a blank line.
+                if line_no == 1 && first_row_len.is_some() && meta.cols.is_none() {
+                    header = true;
+                }
+                if first_row_len.is_none() && !cells.is_empty() {
+                    first_row_len = Some(cells.len());
+                }
+                line_no += 1;
+                continue;
+            }
+            line_no += 1;
+            if let Some(rest) = line.strip_prefix('|') {
+                let row = split_cells(rest);
+                if first_row_len.is_none() && line_no == 1 {
+                    first_row_len = Some(row.len());
+                }
+                cells.extend(row);
+            } else if let Some(last) = cells.last_mut() {
+                // Continuation of the previous cell.
+                last.push('\n');
+                last.push_str(line);
+            }
+        }
+        let cols = meta.cols.or(first_row_len).unwrap_or(1).max(1);
+
+        self.open_block("tableblock", &meta);
+        self.html.push_str("<table>\n");
+        let mut rows = cells.chunks(cols);
+        if header && let Some(row) = rows.next() {
+            self.html.push_str("<thead>\n<tr>");
+            for cell in row {
+                self.html
+                    .push_str(&format!("<th>{}</th>", self.inline(cell)));
+            }
+            self.html.push_str("</tr>\n</thead>\n");
+        }
+        self.html.push_str("<tbody>\n");
+        for row in rows {
+            self.html.push_str("<tr>");
+            for cell in row {
+                self.html
+                    .push_str(&format!("<td>{}</td>", self.inline(cell)));
+            }
+            self.html.push_str("</tr>\n");
+        }
+        self.html.push_str("</tbody>\n</table>\n</div>\n");
+    }
+
+    /// Renders the inline markup of `text`.
+    fn inline(&self, text: &str) -> String {
+        let mut html = String::with_capacity(text.len() * 3 / 2);
+        let mut rest = text;
+        // The character preceding `rest`.
+        let mut prev: Option<char> = None;
+        while let Some(c) = rest.chars().next() {
+            let at_boundary = prev.is_none_or(|p| !p.is_alphanumeric());
+            if let Some((consumed, out)) = self.inline_element(rest, at_boundary) {
+                html.push_str(&out);
+                prev = rest[..consumed].chars().next_back();
+                rest = &rest[consumed..];
+                continue;
+            }
+            match c {
+                // Hard line break ` +` at the end of a line.
+                ' ' if rest.starts_with(" +\n") || rest == " +" => {
+                    html.push_str("<br>");
+                    rest = &rest[2..];
+                    prev = Some('+');
+                    continue;
+                }
+                '\n' => html.push('\n'),
+                _ => html.push_str(&encode_text(&rest[..c.len_utf8()])),
+            }
+            prev = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        html
+    }
+
+    /// Tries to render an inline element at the start of `s`. Returns the
+    /// number of consumed bytes and the HTML rendition.
+    fn inline_element(&self, s: &str, at_boundary: bool) -> Option<(usize, String)> {
+        // Cross reference `<<id>>` or `<<id,text>>`.
+        if let Some(inner) = s.strip_prefix("<<")
+            && let Some(end) = inner.find(">>")
+            && !inner[..end].contains(['\n', '<'])
+            && end > 0
+        {
+            let (target, text) = match inner[..end].split_once(',') {
+                Some((t, text)) => (t.trim(), Some(text.trim())),
+                None => (inner[..end].trim(), None),
+            };
+            return Some((end + 4, self.xref(target, text)));
+        }
+
+        if !at_boundary {
+            return None;
+        }
+
+        // Passthrough `+text+`, `++text++` and `+++html+++`.
+        if s.starts_with("+++")
+            && let Some(end) = s[3..].find("+++")
+        {
+            return Some((end + 6, s[3..3 + end].to_string()));
+        }
+        if let Some(inner) = s.strip_prefix("++")
+            && let Some(end) = inner.find("++")
+            && end > 0
+        {
+            return Some((end + 4, encode_text(&inner[..end]).into_owned()));
+        }
+        if let Some(inner) = s.strip_prefix('+')
+            && inner.starts_with(|c: char| !c.is_whitespace())
+            && let Some(end) = constrained_end(inner, '+')
+        {
+            return Some((end + 2, encode_text(&inner[..end]).into_owned()));
+        }
+
+        // Macros.
+        if let Some(target) = s.strip_prefix("xref:")
+            && let Some((target, text)) = macro_target(target)
+        {
+            let text = (!text.is_empty()).then_some(text);
+            return Some((5 + target.len() + text.map_or(0, str::len) + 2, {
+                self.xref(target, text)
+            }));
+        }
+        if let Some(target) = s.strip_prefix("link:")
+            && let Some((target, text)) = macro_target(target)
+        {
+            return Some((5 + target.len() + text.len() + 2, self.link(target, text)));
+        }
+        if let Some(target) = s.strip_prefix("image:")
+            && !target.starts_with(':')
+            && let Some((target, attrs)) = macro_target(target)
+        {
+            return Some((
+                6 + target.len() + attrs.len() + 2,
+                image_html(target, attrs),
+            ));
+        }
+        if ["https://", "http://", "ftp://", "mailto:"]
+            .iter()
+            .any(|scheme| s.starts_with(scheme))
+        {
+            let end = s
+                .find(|c: char| c.is_whitespace() || c == '[' || c == '<' || c == '"')
+                .unwrap_or(s.len());
+            if s[end..].starts_with('[')
+                && let Some((target, text)) = macro_target(s)
+            {
+                return Some((target.len() + text.len() + 2, self.link(target, text)));
+            }
+            let url = s[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')']);
+            return Some((url.len(), self.link(url, "")));
+        }
+
+        // Quoted text: unconstrained pairs first.
+        for (delimiter, tag) in [("**", "strong"), ("__", "em"), ("``", "code")] {
+            if let Some(inner) = s.strip_prefix(delimiter)
+                && let Some(end) = inner.find(delimiter)
+                && end > 0
+            {
+                return Some((end + 4, self.quoted(tag, &inner[..end])));
+            }
+        }
+        for (delimiter, tag) in [('*', "strong"), ('_', "em"), ('`', "code")] {
+            if let Some(inner) = s.strip_prefix(delimiter)
+                && inner.starts_with(|c: char| !c.is_whitespace())
+                && let Some(end) = constrained_end(inner, delimiter)
+            {
+                return Some((end + 2, self.quoted(tag, &inner[..end])));
+            }
+        }
+        None
+    }
+
+    fn quoted(&self, tag: &str, inner: &str) -> String {
+        let inner = if tag == "code" {
+            encode_text(inner).into_owned()
+        } else {
+            self.inline(inner)
+        };
+        format!("<{tag}>{inner}</{tag}>")
+    }
+
+    /// Renders a cross reference. References to other documents keep their
+    /// destination, e.g. `other.adoc#section`, so that they can be resolved
+    /// as local links later.
+    fn xref(&self, target: &str, text: Option<&str>) -> String {
+        let (href, default_text) = match target.split_once('#') {
+            Some((doc, _)) if !doc.is_empty() => (target.to_string(), target),
+            Some((_, id)) => (format!("#{id}"), id),
+            None if target.contains('.') && !self.titles.contains_key(target) => {
+                (target.to_string(), target)
+            }
+            None => (format!("#{target}"), target),
+        };
+        let text = match text {
+            Some(text) => self.inline(text),
+            None => match self.titles.get(default_text) {
+                Some(title) => self.inline(title),
+                None => format!("[{}]", encode_text(default_text)),
+            },
+        };
+        format!(
+            "<a href=\"{}\">{text}</a>",
+            encode_double_quoted_attribute(&href)
+        )
+    }
+
+    fn link(&self, target: &str, text: &str) -> String {
+        let text = text.trim().trim_end_matches('^');
+        let text = if text.is_empty() {
+            encode_text(target.strip_prefix("mailto:").unwrap_or(target)).into_owned()
+        } else {
+            self.inline(text.trim_matches('"'))
+        };
+        format!(
+            "<a href=\"{}\">{text}</a>",
+            encode_double_quoted_attribute(target)
+        )
+    }
+}
+
+/// Returns the level and the title of the section title `line`, e.g.
+/// `== Title` is level 2.
+fn section_title(line: &str) -> Option<(usize, &str)> {
+    let marker = if line.starts_with('=') { '=' } else { '#' };
+    let level = line.chars().take_while(|c| *c == marker).count();
+    let title = line[level..].strip_prefix(' ')?.trim();
+    (1..=6)
+        .contains(&level)
+        .then_some((level, title))
+        .filter(|(_, t)| !t.is_empty())
+}
+
+/// Derives an Asciidoctor compatible id from a section `title`.
+fn section_id(title: &str) -> String {
+    let mut id = String::from("_");
+    for c in title.chars() {
+        if c.is_alphanumeric() {
+            id.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '.') && !id.ends_with('_') {
+            id.push('_');
+        }
+    }
+    if id.len() > 1 && id.ends_with('_') {
+        id.pop();
+    }
+    id
+}
+
+/// Attribute entries like `:toc:` or `:author: Me`.
+fn is_attribute_entry(line: &str) -> bool {
+    line.strip_prefix(':')
+        .and_then(|l| l.split_once(':'))
+        .is_some_and(|(name, _)| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '!')
+        })
+}
+
+/// Preprocessor directives like `include::file[]` or `ifdef::attr[]`.
+fn is_directive(line: &str) -> bool {
+    ["include::", "ifdef::", "ifndef::", "ifeval::", "endif::"]
+        .iter()
+        .any(|d| line.starts_with(d))
+        && line.ends_with(']')
+}
+
+/// Returns the marker and the text of the list item `line`.
+fn list_item(line: &str) -> Option<(&str, &str)> {
+    let marker_len = match line.chars().next()? {
+        '*' => line.chars().take_while(|c| *c == '*').count(),
+        '.' => line.chars().take_while(|c| *c == '.').count(),
+        '-' => 1,
+        c if c.is_ascii_digit() => {
+            let digits = line.chars().take_while(char::is_ascii_digit).count();
+            line[digits..].starts_with('.').then_some(digits + 1)?
+        }
+        _ => return None,
+    };
+    let text = line[marker_len..].strip_prefix([' ', '\t'])?.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let marker = &line[..marker_len];
+    // Numbered items like `1.` and `2.` belong to the same list.
+    let marker = if marker.ends_with('.') && !marker.starts_with('.') {
+        "1."
+    } else {
+        marker
+    };
+    Some((marker, text))
+}
+
+fn is_list_item(line: &str) -> bool {
+    list_item(line).is_some()
+}
+
+/// Returns the term and the (possibly empty) definition of the description
+/// list item `term:: definition`.
+fn description_term(line: &str) -> Option<(&str, &str)> {
+    let (term, definition) = line.split_once("::")?;
+    if term.trim().is_empty()
+        || term.starts_with([' ', '\t'])
+        || term.contains(['[', '<'])
+        || is_directive(line)
+        || !(definition.is_empty() || definition.starts_with([' ', '\t']))
+    {
+        return None;
+    }
+    Some((term.trim(), definition.trim()))
+}
+
+/// Splits `target[text]`, the text between brackets may be empty.
+fn macro_target(s: &str) -> Option<(&str, &str)> {
+    let open = s.find('[')?;
+    let target = &s[..open];
+    if target.is_empty() || target.contains(char::is_whitespace) {
+        return None;
+    }
+    let close = s[open..].find(']')? + open;
+    Some((target, &s[open + 1..close]))
+}
+
+/// Returns the length of the text enclosed by the constrained `delimiter`,
+/// or `None` if there is no valid closing delimiter.
+fn constrained_end(inner: &str, delimiter: char) -> Option<usize> {
+    let mut prev = None;
+    for (i, c) in inner.char_indices() {
+        if c == '\n' && prev == Some('\n') {
+            return None;
+        }
+        if c == delimiter
+            && i > 0
+            && prev.is_some_and(|p: char| !p.is_whitespace())
+            && inner[i + 1..]
+                .chars()
+                .next()
+                .is_none_or(|n| !n.is_alphanumeric() && n != delimiter)
+        {
+            return Some(i);
+        }
+        prev = Some(c);
+    }
+    None
+}
+
+/// Renders an image macro with the attributes `alt,width,height`.
+fn image_html(target: &str, attrs: &str) -> String {
+    let attrs = split_attributes(attrs);
+    let alt = attrs
+        .first()
+        .filter(|a| !a.is_empty() && !a.contains('='))
+        .map(|a| a.trim_matches('"').to_string())
+        .unwrap_or_else(|| {
+            // Asciidoctor derives the alt text from the file name.
+            let stem = target.rsplit('/').next().unwrap_or(target);
+            let stem = stem.rsplit_once('.').map_or(stem, |(s, _)| s);
+            stem.replace(['-', '_'], " ")
+        });
+    let mut html = format!(
+        "<img src=\"{}\" alt=\"{}\"",
+        encode_double_quoted_attribute(target),
+        encode_double_quoted_attribute(&alt)
+    );
+    for (i, name) in [(1, "width"), (2, "height")] {
+        if let Some(val) = attrs.get(i).filter(|v| !v.is_empty() && !v.contains('=')) {
+            html.push_str(&format!(
+                " {name}=\"{}\"",
+                encode_double_quoted_attribute(val)
+            ));
+        }
+    }
+    html.push('>');
+    html
+}
+
+/// Splits a table row `a | b | c` (without the leading `|`) into cells.
+fn split_cells(row: &str) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+/// Removes the common leading white space of all lines in `text`.
+fn unindent(text: &str) -> String {
+    let indent = text
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    text.lines()
+        .map(|l| l.get(indent..).unwrap_or(l.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::asciidoc_to_html;
+
+    #[test]
+    fn test_asciidoc_sections_and_paragraphs() {
+        let input = "= Document Title\n:toc:\n// A comment.\n\n\
+            == First Section\n\nSome _emphasized_ and `mono` text.\nSecond line +\n\
+            third line.\n\n[[custom]]\n=== Sub-section\n\n== First Section\n";
+        let expected = "<h1 id=\"_document_title\">Document Title</h1>\n\
+            <h2 id=\"_first_section\">First Section</h2>\n\
+            <p>Some <em>emphasized</em> and <code>mono</code> text.\nSecond line<br>\n\
+            third line.</p>\n\
+            <h3 id=\"custom\">Sub-section</h3>\n\
+            <h2 id=\"_first_section-1\">First Section</h2>\n";
+        assert_eq!(asciidoc_to_html(input), expected);
+
+        // Escaping and constrained quotes.
+        let input = "a < b & snake_case_name * 2 * 3";
+        let expected = "<p>a &lt; b &amp; snake_case_name * 2 * 3</p>\n";
+        assert_eq!(asciidoc_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_asciidoc_lists() {
+        let input = "* one\n* two\n** nested\n*** deeper\n* three\n\n\
+            . first\n. second\n\n- [x] done\n- [ ] open\n";
+        let expected = "<ul>\n<li>one</li>\n<li>two\n<ul>\n<li>nested\n\
+            <ul>\n<li>deeper</li>\n</ul>\n</li>\n</ul>\n</li>\n<li>three</li>\n</ul>\n\
+            <ol>\n<li>first</li>\n<li>second</li>\n</ol>\n\
+            <ul>\n<li><input type=\"checkbox\" disabled checked> done</li>\n\
+            <li><input type=\"checkbox\" disabled> open</li>\n</ul>\n";
+        assert_eq!(asciidoc_to_html(input), expected);
+
+        // List continuation and description lists.
+        let input = "* item\n+\n----\ncode\n----\n\nCPU:: The brain.\nRAM::\nMemory.\n";
+        let expected = "<ul>\n<li>item\n<div class=\"listingblock\">\n\
+            <pre><code>code\n</code></pre>\n</div>\n</li>\n</ul>\n\
+            <dl>\n<dt>CPU</dt>\n<dd>The brain.</dd>\n\
+            <dt>RAM</dt>\n<dd>Memory.</dd>\n</dl>\n";
+        assert_eq!(asciidoc_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_asciidoc_tables() {
+        let input = ".Prices\n|===\n|Name |Price\n\n|Apple |1\n|Pear |2\n|===\n";
+        let expected = "<div class=\"tableblock\">\n<div class=\"title\">Prices</div>\n\
+            <table>\n<thead>\n<tr><th>Name</th><th>Price</th></tr>\n</thead>\n\
+            <tbody>\n<tr><td>Apple</td><td>1</td></tr>\n\
+            <tr><td>Pear</td><td>2</td></tr>\n</tbody>\n</table>\n</div>\n";
+        assert_eq!(asciidoc_to_html(input), expected);
+
+        // One cell per line, columns given by `cols`.
+        let input = "[cols=\"1,1\"]\n|===\n|a\n|b\n|c\n|d\n|===";
+        let expected = "<div class=\"tableblock\">\n<table>\n<tbody>\n\
+            <tr><td>a</td><td>b</td></tr>\n<tr><td>c</td><td>d</td></tr>\n\
+            </tbody>\n</table>\n</div>\n";
+        assert_eq!(asciidoc_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_asciidoc_admonitions() {
+        let input = "NOTE: Read *this*.\n\n[WARNING]\n.Careful\n====\nHot.\n====\n";
+        let expected = "<div class=\"admonition note\">\n\
+            <p class=\"admonition-title\">Note</p>\n<p>Read <strong>this</strong>.</p>\n</div>\n\
+            <div class=\"admonition warning\">\n<p class=\"admonition-title\">Careful</p>\n\
+            <p>Hot.</p>\n</div>\n";
+        assert_eq!(asciidoc_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_asciidoc_source_blocks() {
+        let input = "[source,rust]\n----\nfn main() {}\n----\n";
+        let result = asciidoc_to_html(input);
+        assert!(result.starts_with(
+            "<div class=\"listingblock\">\n<pre><code class=\"language-rust\">\
+            <span class=\"source rust\">"
+        ));
+        assert!(result.ends_with("</code></pre>\n</div>\n"));
+
+        let input = "....\n<b>literal</b>\n....\n";
+        let expected = "<div class=\"literalblock\">\n\
+            <pre>&lt;b&gt;literal&lt;/b&gt;\n</pre>\n</div>\n";
+        assert_eq!(asciidoc_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_asciidoc_links() {
+        let input = "== Setup\n\nSee <<_setup>>, <<_setup,here>>, \
+            xref:other.adoc#intro[Intro], <<missing>>,\n\
+            link:docs/manual.adoc[the manual], https://example.com[Example], \
+            https://example.com. image:icon.png[Icon]";
+        let expected = "<h2 id=\"_setup\">Setup</h2>\n\
+            <p>See <a href=\"#_setup\">Setup</a>, <a href=\"#_setup\">here</a>, \
+            <a href=\"other.adoc#intro\">Intro</a>, <a href=\"#missing\">[missing]</a>,\n\
+            <a href=\"docs/manual.adoc\">the manual</a>, \
+            <a href=\"https://example.com\">Example</a>, \
+            <a href=\"https://example.com\">https://example.com</a>. \
+            <img src=\"icon.png\" alt=\"Icon\"></p>\n";
+        assert_eq!(asciidoc_to_html(input), expected);
+    }
+}
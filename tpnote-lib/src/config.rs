@@ -19,8 +19,9 @@
 
 use crate::config_value::CfgVal;
 use crate::error::LibCfgError;
+use crate::front_matter::FrontMatterFormat;
 #[cfg(feature = "renderer")]
-use crate::highlight::get_highlighting_css;
+use crate::highlight::HighlightRegistry;
 #[cfg(feature = "lang-detection")]
 use crate::lingua::IsoCode639_1;
 use crate::markup_language::InputConverter;
@@ -30,10 +31,10 @@ use sanitize_filename_reader_friendly::TRIM_LINE_CHARS;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Write;
+#[cfg(feature = "renderer")]
+use std::path::Path;
 use std::str::FromStr;
 use std::sync::LazyLock;
-#[cfg(feature = "renderer")]
-use syntect::highlighting::ThemeSet;
 use toml::Value;
 
 /// Default library configuration as TOML.
@@ -236,6 +237,33 @@ pub const TMPL_HTML_VAR_DOC_ERROR: &str = "doc_error";
 #[cfg(feature = "viewer")]
 pub const TMPL_HTML_VAR_DOC_TEXT: &str = "doc_text";
 
+/// HTML template variable containing the sorted list of Tp-Note files linking
+/// to the current document. The paths are absolute with `TMPL_VAR_ROOT_PATH`
+/// as base, e.g. `/dir/my note.md`. Used by the viewer and the exporter to
+/// render a "Linked from" section.
+pub const TMPL_HTML_VAR_DOC_BACKLINKS: &str = "doc_backlinks";
+
+/// HTML template variable containing the table of contents of the current
+/// document: a list of maps with the keys `level`, `text` and `anchor` (cf.
+/// `html::TocEntry`). Used by the viewer and the exporter to render a table
+/// of contents sidebar.
+pub const TMPL_HTML_VAR_DOC_TOC: &str = "doc_toc";
+
+/// HTML template variable containing the HTML rendition of the current
+/// document's body, rendered when its table of contents was collected: a map
+/// with the keys `extension` and `html`. The `markup_to_html` filter takes
+/// the rendition over instead of rendering the body a second time.
+pub const TMPL_HTML_VAR_DOC_BODY_HTML: &str = "doc_body_html";
+
+/// HTML template variable used in the exporter's index pages containing the
+/// sorted list of subdirectory names of the current directory.
+pub const TMPL_HTML_VAR_INDEX_DIRS: &str = "index_dirs";
+
+/// HTML template variable used in the exporter's index pages containing the
+/// sorted list of Tp-Note file names in the current directory, e.g.
+/// `01-my note.md`. The exported file has the additional extension `.html`.
+pub const TMPL_HTML_VAR_INDEX_NOTES: &str = "index_notes";
+
 /// Global variable containing the filename and template related configuration
 /// data. This can be changed by the consumer of this library. Once the
 /// initialization done, this should remain static.
@@ -466,19 +494,20 @@ impl LibCfg {
         // `tmpl_html.exporter_highlighting_theme`.
         #[cfg(feature = "renderer")]
         {
-            let hl_theme_set = ThemeSet::load_defaults();
+            let hl_registry = HighlightRegistry::get(Path::new(&self.tmpl_html.highlighting_dir))?;
+            let hl_theme_set = hl_registry.theme_set();
             let hl_theme_name = &self.tmpl_html.viewer_highlighting_theme;
             if !hl_theme_name.is_empty() && !hl_theme_set.themes.contains_key(hl_theme_name) {
                 return Err(LibCfgError::HighlightingThemeName {
                     var: "viewer_highlighting_theme".to_string(),
                     value: hl_theme_name.to_owned(),
-                    available: hl_theme_set.themes.into_keys().fold(
-                        String::new(),
-                        |mut output, k| {
+                    available: hl_theme_set
+                        .themes
+                        .keys()
+                        .fold(String::new(), |mut output, k| {
                             let _ = write!(output, "{k}, ");
                             output
-                        },
-                    ),
+                        }),
                 });
             };
             let hl_theme_name = &self.tmpl_html.exporter_highlighting_theme;
@@ -486,13 +515,13 @@ impl LibCfg {
                 return Err(LibCfgError::HighlightingThemeName {
                     var: "exporter_highlighting_theme".to_string(),
                     value: hl_theme_name.to_owned(),
-                    available: hl_theme_set.themes.into_keys().fold(
-                        String::new(),
-                        |mut output, k| {
+                    available: hl_theme_set
+                        .themes
+                        .keys()
+                        .fold(String::new(), |mut output, k| {
                             let _ = write!(output, "{k}, ");
                             output
-                        },
-                    ),
+                        }),
                 });
             };
         }
@@ -553,12 +582,15 @@ impl TryFrom<LibCfgIntermediate> for LibCfg {
         let raw = raw; // Freeze.
 
         let mut tmpl_html = raw.tmpl_html;
+        // Load the themes of `LibCfgRaw.tmpl_html.highlighting_dir`.
+        #[cfg(feature = "renderer")]
+        let hl_registry = HighlightRegistry::get(Path::new(&tmpl_html.highlighting_dir))?;
         // Now calculate `LibCfgRaw.tmpl_html.viewer_highlighting_css`:
         #[cfg(feature = "renderer")]
         let css = if !tmpl_html.viewer_highlighting_css.is_empty() {
             tmpl_html.viewer_highlighting_css
         } else {
-            get_highlighting_css(&tmpl_html.viewer_highlighting_theme)
+            hl_registry.highlighting_css(&tmpl_html.viewer_highlighting_theme)
         };
         #[cfg(not(feature = "renderer"))]
         let css = String::new();
@@ -570,7 +602,7 @@ impl TryFrom<LibCfgIntermediate> for LibCfg {
         let css = if !tmpl_html.exporter_highlighting_css.is_empty() {
             tmpl_html.exporter_highlighting_css
         } else {
-            get_highlighting_css(&tmpl_html.exporter_highlighting_theme)
+            hl_registry.highlighting_css(&tmpl_html.exporter_highlighting_theme)
         };
         #[cfg(not(feature = "renderer"))]
         let css = String::new();
@@ -632,6 +664,11 @@ pub struct Filename {
     pub copy_counter: CopyCounter,
     pub extension_default: String,
     pub extensions: Vec<(String, InputConverter, MarkupLanguage)>,
+    /// Commands rendering file extensions marked `MarkupLanguage::External`:
+    /// `Vec<(extension, [program, args..])>`.
+    pub external_renderers: Vec<(String, Vec<String>)>,
+    /// Seconds an external renderer may run before it is killed.
+    pub external_renderer_timeout: u64,
 }
 
 /// Configuration for sort-tag.
@@ -664,6 +701,7 @@ pub struct CopyCounter {
 pub struct Tmpl {
     pub fm_var: FmVar,
     pub filter: Filter,
+    pub fm_format: FrontMatterFormat,
     pub from_dir_content: String,
     pub from_dir_filename: String,
     pub from_text_file_content: String,
@@ -792,9 +830,16 @@ pub struct TmplHtml {
     pub viewer_highlighting_theme: String,
     pub viewer_highlighting_css: String,
     pub exporter: String,
+    pub exporter_index: String,
     pub exporter_doc_css: String,
     pub exporter_highlighting_theme: String,
     pub exporter_highlighting_css: String,
+    pub highlighting_dir: String,
+    /// Commands rendering fenced code blocks into inline SVG:
+    /// `Vec<(fence language, [program, args..])>`.
+    pub diagram_renderers: Vec<(String, Vec<String>)>,
+    /// Seconds a diagram renderer may run before it is killed.
+    pub diagram_renderer_timeout: u64,
 }
 
 /// Defines the way the HTML exporter rewrites local links.
@@ -12,6 +12,9 @@ use substring::Substring;
 
 use crate::config::TMPL_VAR_DOC;
 use crate::error::InputStreamError;
+use crate::front_matter::FrontMatterFormat;
+use crate::front_matter::{ORG_PROPERTY_DRAWER, org_keyword};
+use crate::markup_language::MarkupLanguage;
 use crate::text_reader::read_as_string_with_crlf_suppression;
 
 /// As all text before the header marker `"---"` is ignored, this
@@ -20,6 +23,52 @@ use crate::text_reader::read_as_string_with_crlf_suppression;
 /// must start within the first `BEFORE_HEADER_MAX_IGNORED_CHARS`.
 const BEFORE_HEADER_MAX_IGNORED_CHARS: usize = 1024;
 
+/// Start and end marker of a TOML front matter. Unlike the YAML
+/// marker `---`, it is only recognized at the very beginning of the content.
+const TOML_HEADER_TAG: &str = "+++";
+
+/// Returns the length of the Org-mode front matter at the beginning of
+/// `content`: consecutive keyword lines `#+KEY: value` and at most one
+/// property drawer `:PROPERTIES:` ... `:END:`. The front matter ends with
+/// the first other line. Returns 0 if there is none.
+fn org_header_len(content: &str) -> usize {
+    let mut len = 0;
+    let mut drawer_seen = false;
+    let mut lines = content.split_inclusive('\n');
+    while let Some(line) = lines.next() {
+        if org_keyword(line.trim_end()).is_some() {
+            len += line.len();
+        } else if !drawer_seen && line.trim().eq_ignore_ascii_case(ORG_PROPERTY_DRAWER.0) {
+            // An unterminated drawer does not belong to the front matter.
+            let mut drawer_len = line.len();
+            if !lines.any(|l| {
+                drawer_len += l.len();
+                l.trim().eq_ignore_ascii_case(ORG_PROPERTY_DRAWER.1)
+            }) {
+                break;
+            }
+            len += drawer_len;
+            drawer_seen = true;
+        } else {
+            break;
+        }
+    }
+    len
+}
+
+/// Reports the syntax of the front matter at the beginning of `content`,
+/// see `Content::header_format()`. Org-mode front matter is only recognized
+/// with `org_mode`.
+fn front_matter_format(content: &str, org_mode: bool) -> FrontMatterFormat {
+    if content.starts_with(TOML_HEADER_TAG) {
+        FrontMatterFormat::Toml
+    } else if org_mode && org_header_len(content) > 0 {
+        FrontMatterFormat::Org
+    } else {
+        FrontMatterFormat::Yaml
+    }
+}
+
 /// This trait represents Tp-Note content.
 /// The content is devided into header and body.
 /// The header is the YAML meta data describing the body.
@@ -107,6 +156,7 @@ const BEFORE_HEADER_MAX_IGNORED_CHARS: usize = 1024;
 pub trait Content: AsRef<str> + Debug + Eq + PartialEq + Default {
     /// Reads the file at `path` and stores the content
     /// `Content`. Possible `\r\n` are replaced by `\n`.
+    /// Files with an Org-mode extension are read with `from_org_string()`.
     /// This trait has a default implementation, the empty content.
     ///
     /// ```rust
@@ -130,10 +180,12 @@ pub trait Content: AsRef<str> + Debug + Eq + PartialEq + Default {
     where
         Self: Sized,
     {
-        Ok(Self::from_string(
-            read_as_string_with_crlf_suppression(File::open(path)?)?,
-            TMPL_VAR_DOC.to_string(),
-        ))
+        let input = read_as_string_with_crlf_suppression(File::open(path)?)?;
+        if MarkupLanguage::from(path) == MarkupLanguage::OrgMode {
+            Ok(Self::from_org_string(input, TMPL_VAR_DOC.to_string()))
+        } else {
+            Ok(Self::from_string(input, TMPL_VAR_DOC.to_string()))
+        }
     }
 
     /// Constructor that parses a Tp-Note document.
@@ -169,12 +221,72 @@ pub trait Content: AsRef<str> + Debug + Eq + PartialEq + Default {
     /// referenced in various templates.
     fn from_string(input: String, name: String) -> Self;
 
-    /// Returns a reference to the inner part in between `---`.
+    /// Constructor for Org-mode notes. Like `from_string()`, but Org-mode
+    /// keyword lines `#+KEY: value` and a property drawer at the beginning
+    /// of `input` are the front matter, see `split_org()`. The default
+    /// implementation ignores them and calls `from_string()`.
+    ///
+    /// ```rust
+    /// use tpnote_lib::content::Content;
+    /// use tpnote_lib::content::ContentString;
+    ///
+    /// let input = "#+TITLE: My note\n\nMy body";
+    /// let c = ContentString::from_org_string(input.to_string(), "doc".to_string());
+    /// assert_eq!(c.header(), "#+TITLE: My note");
+    /// assert_eq!(c.body(), "\nMy body");
+    ///
+    /// // Other notes have no Org-mode front matter.
+    /// let c = ContentString::from_string(input.to_string(), "doc".to_string());
+    /// assert_eq!(c.header(), "");
+    /// assert_eq!(c.body(), input);
+    /// ```
+    fn from_org_string(input: String, name: String) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_string(input, name)
+    }
+
+    /// Returns a reference to the inner part in between `---`
+    /// (or `+++`, or the Org-mode keywords, see `header_format()`).
     fn header(&self) -> &str;
 
-    /// Returns the body below the second `---`.
+    /// Returns the body below the second `---` (or `+++`).
     fn body(&self) -> &str;
 
+    /// Reports the syntax of the `header()`: a front matter delimited by
+    /// `+++` is TOML, Org-mode keyword lines `#+KEY: value` or a leading
+    /// property drawer read with `from_org_string()` are `Org`, all others
+    /// are YAML.
+    ///
+    /// ```rust
+    /// use tpnote_lib::content::Content;
+    /// use tpnote_lib::content::ContentString;
+    /// use tpnote_lib::front_matter::FrontMatterFormat;
+    ///
+    /// let c = ContentString::from_string(
+    ///     "+++\ntitle = \"My note\"\n+++\nMy body".to_string(),
+    ///     "doc".to_string());
+    /// assert_eq!(c.header(), "title = \"My note\"");
+    /// assert_eq!(c.body(), "My body");
+    /// assert_eq!(c.header_format(), FrontMatterFormat::Toml);
+    ///
+    /// let c = ContentString::from_string(
+    ///     "---\ntitle: My note\n---\nMy body".to_string(),
+    ///     "doc".to_string());
+    /// assert_eq!(c.header_format(), FrontMatterFormat::Yaml);
+    ///
+    /// let c = ContentString::from_org_string(
+    ///     "#+TITLE: My note\n\nMy body".to_string(),
+    ///     "doc".to_string());
+    /// assert_eq!(c.header(), "#+TITLE: My note");
+    /// assert_eq!(c.body(), "\nMy body");
+    /// assert_eq!(c.header_format(), FrontMatterFormat::Org);
+    /// ```
+    fn header_format(&self) -> FrontMatterFormat {
+        front_matter_format(self.as_str(), false)
+    }
+
     /// Returns the associated name exactly as it was given to the constructor.
     fn name(&self) -> &str;
 
@@ -349,6 +461,9 @@ pub trait Content: AsRef<str> + Debug + Eq + PartialEq + Default {
     /// 1. skip all text (BEFORE_HEADER_MAX_IGNORED_CHARS) until you find `"\n\n---"`
     /// 2. followed by header bytes,
     /// 3. same as above ...
+    ///
+    /// A TOML front matter is delimited by `+++` instead. It must start
+    /// at the first byte (after a possible BOM) and ends with a line `+++`.
     fn split(content: &str) -> (&str, &str) {
         // Bring in scope `HtmlString`.
         use crate::html::HtmlStr;
@@ -367,6 +482,32 @@ pub trait Content: AsRef<str> + Debug + Eq + PartialEq + Default {
             return ("", content);
         }
 
+        if let Some(toml) = content.strip_prefix(TOML_HEADER_TAG) {
+            // The first character after the start marker must be a whitespace.
+            if !toml.starts_with(char::is_whitespace) {
+                return ("", content);
+            }
+            const TOML_HEADER_END_TAG: &str = "\n+++";
+            // The end marker must be followed by the end of the line.
+            let Some(fm_end) = toml
+                .match_indices(TOML_HEADER_END_TAG)
+                .map(|(i, _)| i)
+                .find(|i| {
+                    toml[i + TOML_HEADER_END_TAG.len()..]
+                        .split('\n')
+                        .next()
+                        .is_some_and(|rest| rest.trim_matches([' ', '\t', '\r']).is_empty())
+                })
+            else {
+                return ("", content);
+            };
+            let mut body = &toml[fm_end + TOML_HEADER_END_TAG.len()..];
+            // Skip spaces and tabs followed by one optional newline.
+            body = body.trim_start_matches([' ', '\t']);
+            body = body.strip_prefix('\n').unwrap_or(body);
+            return (toml[..fm_end].trim(), body);
+        }
+
         const HEADER_START_TAG: &str = "---";
         let fm_start = if content.starts_with(HEADER_START_TAG) {
             // Found at first byte.
@@ -437,6 +578,20 @@ pub trait Content: AsRef<str> + Debug + Eq + PartialEq + Default {
 
         (content[fm_start..fm_end].trim(), &content[body_start..])
     }
+
+    /// Like `split()`, but for Org-mode notes: an Org-mode front matter
+    /// consists of the keyword lines `#+KEY: value` and/or one property
+    /// drawer at the first byte (after a possible BOM). It ends with the
+    /// first line that is neither, which starts the body.
+    fn split_org(content: &str) -> (&str, &str) {
+        let trimmed = content.trim_start_matches('\u{feff}');
+        let org_len = org_header_len(trimmed);
+        if org_len > 0 {
+            (trimmed[..org_len].trim(), &trimmed[org_len..])
+        } else {
+            Self::split(content)
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -450,6 +605,8 @@ pub struct ContentRef<'a> {
     pub body: &'a str,
     /// A name associated with this content. Used in templates.
     pub name: String,
+    /// The syntax of `header`, see `Content::header_format()`.
+    pub header_format: FrontMatterFormat,
 }
 
 self_cell!(
@@ -473,10 +630,11 @@ self_cell!(
 /// Add `header()` and `body()` implementation.
 impl Content for ContentString {
     fn from_string(input: String, name: String) -> Self {
-        ContentString::new(input, |owner: &String| {
-            let (header, body) = ContentString::split(owner);
-            ContentRef { header, body, name }
-        })
+        ContentString::from_string_with(input, name, false)
+    }
+
+    fn from_org_string(input: String, name: String) -> Self {
+        ContentString::from_string_with(input, name, true)
     }
 
     /// Cheap access to the note's header.
@@ -493,6 +651,31 @@ impl Content for ContentString {
     fn name(&self) -> &str {
         &self.borrow_dependent().name
     }
+
+    /// Cheap access to the syntax of the note's header.
+    fn header_format(&self) -> FrontMatterFormat {
+        self.borrow_dependent().header_format
+    }
+}
+
+impl ContentString {
+    /// Helper function for `from_string()` and `from_org_string()`.
+    fn from_string_with(input: String, name: String, org_mode: bool) -> Self {
+        ContentString::new(input, |owner: &String| {
+            let (header, body) = if org_mode {
+                ContentString::split_org(owner)
+            } else {
+                ContentString::split(owner)
+            };
+            let header_format = front_matter_format(owner.trim_start_matches('\u{feff}'), org_mode);
+            ContentRef {
+                header,
+                body,
+                name,
+                header_format,
+            }
+        })
+    }
 }
 
 /// Default is the empty string.
@@ -511,6 +694,7 @@ impl AsRef<str> for ContentString {
 }
 
 /// Concatenates the header and the body and prints the content.
+/// The header is delimited according to its `header_format`.
 /// This function is expensive as it involves copying the
 /// whole content.
 impl fmt::Display for ContentRef<'_> {
@@ -518,7 +702,19 @@ impl fmt::Display for ContentRef<'_> {
         let s = if self.header.is_empty() {
             self.body.to_string()
         } else {
-            format!("\u{feff}---\n{}\n---\n{}", &self.header, &self.body)
+            match self.header_format {
+                FrontMatterFormat::Yaml => {
+                    format!("\u{feff}---\n{}\n---\n{}", &self.header, &self.body)
+                }
+                FrontMatterFormat::Toml => format!(
+                    "\u{feff}{}\n{}\n{}\n{}",
+                    TOML_HEADER_TAG, &self.header, TOML_HEADER_TAG, &self.body
+                ),
+                // Org-mode keywords are not delimited.
+                FrontMatterFormat::Org => {
+                    format!("\u{feff}{}\n{}", &self.header, &self.body)
+                }
+            }
         };
         write!(f, "{}", s)
     }
@@ -671,6 +867,69 @@ mod tests {
         let expected = ("my header", "my body\n");
         let result = ContentString::split(input_stream);
         assert_eq!(result, expected);
+
+        // TOML front matter.
+        let input_stream = "\u{feff}+++\ntitle = \"my title\"\n+++ \nmy body\n";
+        let expected = ("title = \"my title\"", "my body\n");
+        let result = ContentString::split(input_stream);
+        assert_eq!(result, expected);
+
+        // TOML start marker is not followed by whitespace.
+        let input_stream = "+++first\n+++\nsecond";
+        let expected = ("", "+++first\n+++\nsecond");
+        let result = ContentString::split(input_stream);
+        assert_eq!(result, expected);
+
+        // Lines starting with `+++` do not end the TOML front matter.
+        let input_stream = "+++\ntitle = \"\"\"\n+++foo\n++++\n\"\"\"\n+++\nmy body\n";
+        let expected = ("title = \"\"\"\n+++foo\n++++\n\"\"\"", "my body\n");
+        let result = ContentString::split(input_stream);
+        assert_eq!(result, expected);
+
+        // TOML end marker is missing.
+        let input_stream = "+++\ntitle = \"my title\"\nmy body\n";
+        let expected = ("", "+++\ntitle = \"my title\"\nmy body\n");
+        let result = ContentString::split(input_stream);
+        assert_eq!(result, expected);
+
+        // TOML front matter is only recognized at the beginning.
+        let input_stream = "my prelude\n\n+++\nmy header\n+++\nmy body\n";
+        let expected = ("", "my prelude\n\n+++\nmy header\n+++\nmy body\n");
+        let result = ContentString::split(input_stream);
+        assert_eq!(result, expected);
+
+        // Org-mode front matter.
+        let input_stream = "\u{feff}#+TITLE: my title\n#+DATE:\n\nmy body\n";
+        let expected = ("#+TITLE: my title\n#+DATE:", "\nmy body\n");
+        let result = ContentString::split_org(input_stream);
+        assert_eq!(result, expected);
+
+        // Org-mode keywords followed by a property drawer.
+        let input_stream = "#+TITLE: my title\n:PROPERTIES:\n:ID: 1\n:END:\n* my body\n";
+        let expected = (
+            "#+TITLE: my title\n:PROPERTIES:\n:ID: 1\n:END:",
+            "* my body\n",
+        );
+        let result = ContentString::split_org(input_stream);
+        assert_eq!(result, expected);
+
+        // Org-mode property drawer without end marker.
+        let input_stream = ":PROPERTIES:\n:ID: 1\nmy body\n";
+        let expected = ("", ":PROPERTIES:\n:ID: 1\nmy body\n");
+        let result = ContentString::split_org(input_stream);
+        assert_eq!(result, expected);
+
+        // A source block is not a keyword line.
+        let input_stream = "#+BEGIN_SRC rust\nfn main() {}\n#+END_SRC\n";
+        let expected = ("", "#+BEGIN_SRC rust\nfn main() {}\n#+END_SRC\n");
+        let result = ContentString::split_org(input_stream);
+        assert_eq!(result, expected);
+
+        // Without `split_org()`, keyword lines are part of the body.
+        let input_stream = "#+TITLE: my title\n\nmy body\n";
+        let expected = ("", "#+TITLE: my title\n\nmy body\n");
+        let result = ContentString::split(input_stream);
+        assert_eq!(result, expected);
     }
 
     #[test]
@@ -693,5 +952,16 @@ mod tests {
             "does not matter".to_string(),
         );
         assert_eq!(input.to_string(), expected);
+
+        // TOML front matter.
+        let expected = "\u{feff}+++\nfirst = 1\n+++\n\nsecond\nthird\n".to_string();
+        let input = ContentString::from_string(expected.clone(), "does not matter".to_string());
+        assert_eq!(input.to_string(), expected);
+
+        // Org-mode front matter.
+        let expected =
+            "\u{feff}#+TITLE: first\n:PROPERTIES:\n:LANG: en\n:END:\n\nsecond\n".to_string();
+        let input = ContentString::from_org_string(expected.clone(), "does not matter".to_string());
+        assert_eq!(input.to_string(), expected);
     }
 }
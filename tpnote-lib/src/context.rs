@@ -2,12 +2,17 @@
 use crate::config::Assertion;
 use crate::config::FILENAME_ROOT_PATH_MARKER;
 use crate::config::LIB_CFG;
+use crate::config::TMPL_HTML_VAR_DOC_BACKLINKS;
+use crate::config::TMPL_HTML_VAR_DOC_BODY_HTML;
 #[cfg(feature = "viewer")]
 use crate::config::TMPL_HTML_VAR_DOC_ERROR;
 #[cfg(feature = "viewer")]
 use crate::config::TMPL_HTML_VAR_DOC_TEXT;
+use crate::config::TMPL_HTML_VAR_DOC_TOC;
 use crate::config::TMPL_HTML_VAR_EXPORTER_DOC_CSS;
 use crate::config::TMPL_HTML_VAR_EXPORTER_HIGHLIGHTING_CSS;
+use crate::config::TMPL_HTML_VAR_INDEX_DIRS;
+use crate::config::TMPL_HTML_VAR_INDEX_NOTES;
 use crate::config::TMPL_HTML_VAR_VIEWER_DOC_CSS_PATH;
 use crate::config::TMPL_HTML_VAR_VIEWER_DOC_CSS_PATH_VALUE;
 use crate::config::TMPL_HTML_VAR_VIEWER_DOC_JS;
@@ -37,11 +42,18 @@ use crate::filename::NotePath;
 use crate::filename::NotePathStr;
 use crate::filter::name;
 use crate::front_matter::FrontMatter;
+use crate::html::backlinks;
+#[cfg(feature = "renderer")]
+use crate::html::toc;
+use crate::markup_language::MarkupLanguage;
 use crate::settings::SETTINGS;
 use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::fs::File;
 use std::marker::PhantomData;
 use std::ops::Deref;
+use std::path::MAIN_SEPARATOR_STR;
 use std::path::Path;
 use std::path::PathBuf;
 use std::time::SystemTime;
@@ -80,6 +92,10 @@ pub(crate) struct ReadyForHtmlTemplate;
 /// See description in the `ContextState` implementor list.
 pub(crate) struct ReadyForHtmlErrorTemplate;
 
+#[derive(Debug, PartialEq, Clone)]
+/// See description in the `ContextState` implementor list.
+pub(crate) struct ReadyForHtmlIndexTemplate;
+
 /// The `Context` object is in an invalid state. Either it was not initialized
 /// or its data does not correspond any more to the `Content` it represents.
 ///
@@ -174,6 +190,8 @@ impl ContextState for ReadyForContentTemplate {}
 /// In addition to the `ReadyForFilenameTemplate` state this state has the
 /// following variables set:
 ///
+/// * `TMPL_HTML_VAR_DOC_BACKLINKS`
+/// * `TMPL_HTML_VAR_DOC_TOC`
 /// * `TMPL_HTML_VAR_EXPORTER_DOC_CSS`
 /// * `TMPL_HTML_VAR_EXPORTER_HIGHLIGHTING_CSS`
 /// * `TMPL_HTML_VAR_EXPORTER_HIGHLIGHTING_CSS`
@@ -213,6 +231,24 @@ impl ContextState for ReadyForHtmlTemplate {}
 #[cfg(feature = "viewer")]
 impl ContextState for ReadyForHtmlErrorTemplate {}
 
+/// The `Context` has all data for the exporter's directory index template.
+/// `self.path` points to the directory.
+///
+/// * `TMPL_HTML_VAR_EXPORTER_DOC_CSS`
+/// * `TMPL_HTML_VAR_INDEX_DIRS` from `index_dirs`
+/// * `TMPL_HTML_VAR_INDEX_NOTES` from `index_notes`
+///
+/// Once this state is achieved, `Context` is constant and write protected until
+/// the next state transition.
+///
+/// |  State order   |                                       |
+/// |----------------|---------------------------------------|
+/// | Previous state | `HasSettings`                         |
+/// | Current state  | `ReadyForHtmlIndexTemplate`           |
+/// | Next state     | none                                  |
+///
+impl ContextState for ReadyForHtmlIndexTemplate {}
+
 /// Recursively checks that all leaf values of a `tera::Value` tree satisfy `f`.
 /// Arrays and maps are traversed; other values are passed to `f`.
 fn tera_all_leaves(val: &tera::Value, f: &dyn Fn(&tera::Value) -> bool) -> bool {
@@ -524,7 +560,7 @@ impl<S: ContextState> Context<S> {
             // Can we find a front matter in the input stream? If yes, the
             // unmodified input stream is our new note content.
             if !clip.header().is_empty() {
-                let input_fm = FrontMatter::try_from(clip.header());
+                let input_fm = FrontMatter::try_from_content(clip);
                 match input_fm {
                     Ok(ref fm) => {
                         log::trace!(
@@ -674,6 +710,14 @@ impl Context<HasSettings> {
         }
     }
 
+    /// Inserts `backlinks`, the notes linking to this document, as
+    /// `TMPL_HTML_VAR_DOC_BACKLINKS`. Use this when the backlinks are known
+    /// already: `insert_raw_content_and_css()` does not search them again.
+    pub(crate) fn insert_backlinks(mut self, backlinks: &BTreeSet<PathBuf>) -> Self {
+        self.ct.insert(TMPL_HTML_VAR_DOC_BACKLINKS, backlinks);
+        self
+    }
+
     /// Inserts clipboard data, standard input data and/or existing note file
     /// content into the context. The data may contain some copied text with
     /// or without a YAML header. The latter usually carries front matter
@@ -732,6 +776,35 @@ impl Context<HasSettings> {
             _marker: PhantomData,
         }
     }
+
+    /// This adds the following variables to `self`:
+    ///
+    /// * `TMPL_HTML_VAR_EXPORTER_DOC_CSS`
+    /// * `TMPL_HTML_VAR_INDEX_DIRS` from `index_dirs`
+    /// * `TMPL_HTML_VAR_INDEX_NOTES` from `index_notes`
+    ///
+    pub(crate) fn insert_dir_index(
+        mut self,
+        index_dirs: &[String],
+        index_notes: &[String],
+    ) -> Context<ReadyForHtmlIndexTemplate> {
+        //
+        self.ct.insert(
+            TMPL_HTML_VAR_EXPORTER_DOC_CSS,
+            &(LIB_CFG.read_recursive().tmpl_html.exporter_doc_css),
+        );
+        self.ct.insert(TMPL_HTML_VAR_INDEX_DIRS, index_dirs);
+        self.ct.insert(TMPL_HTML_VAR_INDEX_NOTES, index_notes);
+
+        Context {
+            ct: self.ct,
+            path: self.path,
+            dir_path: self.dir_path,
+            root_path: self.root_path,
+            doc_file_date: self.doc_file_date,
+            _marker: PhantomData,
+        }
+    }
 }
 
 impl Context<HasExistingContent> {
@@ -969,6 +1042,8 @@ impl Context<ReadyForFilenameTemplate> {
     /// * `TMPL_HTML_VAR_VIEWER_DOC_CSS_PATH_VALUE`
     /// * `TMPL_HTML_VAR_VIEWER_HIGHLIGHTING_CSS_PATH`
     /// * `TMPL_HTML_VAR_VIEWER_HIGHLIGHTING_CSS_PATH_VALUE`
+    /// * `TMPL_HTML_VAR_DOC_BACKLINKS`
+    /// * `TMPL_HTML_VAR_DOC_TOC`
     ///
     pub(crate) fn insert_raw_content_and_css(
         mut self,
@@ -1012,6 +1087,43 @@ impl Context<ReadyForFilenameTemplate> {
             TMPL_HTML_VAR_VIEWER_HIGHLIGHTING_CSS_PATH,
             TMPL_HTML_VAR_VIEWER_HIGHLIGHTING_CSS_PATH_VALUE,
         );
+
+        // Insert the notes linking to this document, unless already known.
+        if !self.ct.contains_key(TMPL_HTML_VAR_DOC_BACKLINKS) {
+            let doc_path = self
+                .path
+                .strip_prefix(&self.root_path)
+                .unwrap_or(&self.path);
+            let doc_path = Path::new(MAIN_SEPARATOR_STR).join(doc_path);
+            let backlinks = backlinks(&self.root_path, &self.dir_path, &doc_path);
+            self.ct.insert(TMPL_HTML_VAR_DOC_BACKLINKS, &backlinks);
+        }
+
+        // Insert the table of contents. It is collected from the body's
+        // rendition, which the `markup_to_html` filter then takes over
+        // instead of rendering a second time. A failing renderer is reported
+        // by the template later. Without the `renderer` feature, the table
+        // of contents is empty.
+        let extension = self
+            .path
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+        let markup_language = MarkupLanguage::from(extension).or(MarkupLanguage::Unkown);
+        let rendition = markup_language.render_for_catch_unwind(extension, content.body());
+        #[cfg(feature = "renderer")]
+        let doc_toc = rendition.as_deref().map(toc).unwrap_or_default();
+        #[cfg(not(feature = "renderer"))]
+        let doc_toc: Vec<crate::html::TocEntry> = Vec::new();
+        self.ct.insert(TMPL_HTML_VAR_DOC_TOC, &doc_toc);
+        if let Ok(html) = rendition {
+            self.ct.insert(
+                TMPL_HTML_VAR_DOC_BODY_HTML,
+                &BTreeMap::from([("extension", extension), ("html", html.as_str())]),
+            );
+        }
+
         Context {
             ct: self.ct,
             path: self.path,
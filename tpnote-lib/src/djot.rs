@@ -0,0 +1,1639 @@
+//! A renderer for the Djot markup language.
+//!
+//! Supported are headings, paragraphs, block quotes, (nested) bullet,
+//! ordered, task and definition lists, pipe tables with captions, code blocks
+//! (highlighted with the configured `HighlightRegistry`), raw HTML blocks,
+//! divs, thematic breaks, block attributes `{#id .class}`, reference links
+//! and footnotes. Inline, Djot's emphasis `_..._`, strong `*...*`,
+//! highlight `{=...=}`, insert `{+...+}`, delete `{-...-}`, superscript
+//! `^...^`, subscript `~...~`, verbatim, math `` $`...` ``, links, images,
+//! autolinks, spans `[...]{.class}` and smart punctuation are rendered.
+//! Attributes `{...}` directly following an inline element apply to it.
+//! Comments `{% ... %}` are dropped.
+//!
+//! Every heading gets an id derived from its text. This makes headings
+//! available as implicit link references: `[My heading][]`.
+//!
+//! The output follows the reference implementation `djot.js`, except:
+//! headings carry their id themselves instead of being wrapped in a
+//! `<section>`, heading ids are derived with `html::slugify()`, tables have
+//! `<thead>` and `<tbody>`, task list items show a checkbox, code blocks are
+//! highlighted and math is rendered to MathML.
+use crate::highlight::HighlightRegistry;
+use crate::html::slug;
+use crate::html::unique_id;
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Inline containers: their delimiter, whether the delimiter must be
+/// braced `{_ ... _}` and their HTML tag.
+const CONTAINERS: [(char, bool, &str); 7] = [
+    ('_', false, "em"),
+    ('*', false, "strong"),
+    ('^', false, "sup"),
+    ('~', false, "sub"),
+    ('=', true, "mark"),
+    ('+', true, "ins"),
+    ('-', true, "del"),
+];
+
+/// The HTML writer for Djot input.
+struct Renderer {
+    /// Link reference definitions `[label]: url`.
+    references: HashMap<String, String>,
+    /// Footnote definitions `[^label]: text` with their unindented lines.
+    footnote_defs: HashMap<String, Vec<String>>,
+    /// Labels of the referenced footnotes in order of their first reference.
+    footnotes: Vec<String>,
+    /// Heading texts mapped to their ids, the implicit link references.
+    headings: HashMap<String, String>,
+    /// Ids already in use.
+    ids: HashSet<String>,
+    /// Set during the first pass, which only registers references.
+    collecting: bool,
+    registry: Arc<HighlightRegistry>,
+}
+
+/// Renders Djot `input` to HTML.
+///
+/// ```rust
+/// use tpnote_lib::djot::djot_to_html;
+///
+/// let input = "# Intro\n\nSee [here][Intro] and *strong* _emphasis_.";
+/// let expected = "<h1 id=\"intro\">Intro</h1>\n\
+///     <p>See <a href=\"#intro\">here</a> and <strong>strong</strong> \
+///     <em>emphasis</em>.</p>\n";
+/// assert_eq!(djot_to_html(input), expected);
+/// ```
+pub fn djot_to_html(input: &str) -> String {
+    let mut renderer = Renderer {
+        references: HashMap::new(),
+        footnote_defs: HashMap::new(),
+        footnotes: Vec::new(),
+        headings: HashMap::new(),
+        ids: HashSet::new(),
+        collecting: true,
+        registry: HighlightRegistry::configured(),
+    };
+    let lines: Vec<&str> = input.lines().collect();
+    // The first pass registers link references, footnotes and headings,
+    // which may be referenced before their definition.
+    renderer.blocks(&lines, false);
+    renderer.collecting = false;
+    renderer.ids.clear();
+    renderer.footnotes.clear();
+
+    let mut html = renderer.blocks(&lines, false);
+    html.push_str(&renderer.endnotes());
+    html
+}
+
+impl Renderer {
+    /// Renders the block level elements of `lines`. When `tight`, paragraphs
+    /// are written without `<p>`.
+    fn blocks(&mut self, lines: &[&str], tight: bool) -> String {
+        let mut html = String::new();
+        let mut attrs = Attributes::default();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            let trimmed = line.trim();
+
+            if trimmed.is_empty() {
+                i += 1;
+                continue;
+            }
+
+            // Block attributes apply to the next block.
+            if let Some((a, n)) = Attributes::parse(trimmed)
+                && n == trimmed.len()
+            {
+                attrs.merge(a);
+                i += 1;
+                continue;
+            }
+            let mut block_attrs = std::mem::take(&mut attrs);
+
+            if let Some((level, text)) = heading(trimmed) {
+                let mut title = text.to_string();
+                i += 1;
+                while i < lines.len() && !lines[i].trim().is_empty() {
+                    title.push('\n');
+                    title.push_str(heading_continuation(lines[i].trim(), level));
+                    i += 1;
+                }
+                html.push_str(&self.heading(level, &title, block_attrs));
+                continue;
+            }
+
+            if let Some((fence, info)) = code_fence(trimmed) {
+                let indent = indentation(line);
+                let start = i + 1;
+                i = start;
+                while i < lines.len()
+                    && !code_fence(lines[i].trim()).is_some_and(|(n, i)| n >= fence && i.is_empty())
+                {
+                    i += 1;
+                }
+                let code: String = lines[start..i.min(lines.len())]
+                    .iter()
+                    .map(|l| format!("{}\n", strip_indent(l, indent)))
+                    .collect();
+                i += 1;
+                html.push_str(&with_attributes(self.code_block(info, &code), &block_attrs));
+                continue;
+            }
+
+            if let Some((colons, class)) = div_fence(trimmed) {
+                let start = i + 1;
+                i = div_end(lines, start, colons);
+                if !class.is_empty() {
+                    block_attrs.classes.insert(0, class.to_string());
+                }
+                let content = self.blocks(&lines[start..i.min(lines.len())], false);
+                i += 1;
+                html.push_str(&with_attributes(
+                    format!("<div>\n{content}</div>\n"),
+                    &block_attrs,
+                ));
+                continue;
+            }
+
+            if is_thematic_break(trimmed) {
+                html.push_str(&with_attributes("<hr>\n".to_string(), &block_attrs));
+                i += 1;
+                continue;
+            }
+
+            if let Some(first) = quote_content(line) {
+                let mut content = vec![first];
+                i += 1;
+                while i < lines.len() {
+                    match quote_content(lines[i]) {
+                        Some(c) => content.push(c),
+                        // Lazy continuation of a paragraph.
+                        None if !lines[i].trim().is_empty()
+                            && content.last().is_some_and(|l| !l.trim().is_empty()) =>
+                        {
+                            content.push(lines[i])
+                        }
+                        None => break,
+                    }
+                    i += 1;
+                }
+                html.push_str(&with_attributes(
+                    format!(
+                        "<blockquote>\n{}</blockquote>\n",
+                        self.blocks(&content, false)
+                    ),
+                    &block_attrs,
+                ));
+                continue;
+            }
+
+            if let Some((label, dest)) = reference_definition(trimmed) {
+                let indent = indentation(line);
+                let mut url = dest.to_string();
+                i += 1;
+                // The destination may continue on indented lines.
+                while i < lines.len()
+                    && !lines[i].trim().is_empty()
+                    && indentation(lines[i]) > indent
+                {
+                    url.push_str(lines[i].trim());
+                    i += 1;
+                }
+                self.references.entry(label).or_insert(url);
+                continue;
+            }
+
+            if let Some((label, text)) = footnote_definition(trimmed) {
+                let end = container_end(lines, i + 1, indentation(line));
+                if self.collecting {
+                    let mut body = vec![text.to_string()];
+                    body.extend(unindent(&lines[i + 1..end]).iter().map(|l| l.to_string()));
+                    self.footnote_defs.entry(label).or_insert(body);
+                }
+                i = end;
+                continue;
+            }
+
+            if is_table_row(trimmed) {
+                let start = i;
+                while i < lines.len() && is_table_row(lines[i].trim()) {
+                    i += 1;
+                }
+                let rows = &lines[start..i];
+                // A caption `^ caption` may follow the table.
+                let mut j = i;
+                while j < lines.len() && lines[j].trim().is_empty() {
+                    j += 1;
+                }
+                let mut caption = None;
+                if let Some(text) = lines.get(j).and_then(|l| l.trim().strip_prefix("^ ")) {
+                    let mut text = text.to_string();
+                    i = j + 1;
+                    while i < lines.len() && !lines[i].trim().is_empty() {
+                        text.push('\n');
+                        text.push_str(lines[i].trim());
+                        i += 1;
+                    }
+                    caption = Some(text);
+                }
+                html.push_str(&with_attributes(
+                    self.table(rows, caption.as_deref()),
+                    &block_attrs,
+                ));
+                continue;
+            }
+
+            if list_marker(line).is_some() {
+                let (list, end) = self.list(lines, i);
+                html.push_str(&with_attributes(list, &block_attrs));
+                i = end;
+                continue;
+            }
+
+            // Paragraph. Only a blank line ends it.
+            let start = i;
+            while i < lines.len() && !lines[i].trim().is_empty() {
+                i += 1;
+            }
+            let text = self.inline_lines(&lines[start..i]);
+            if tight {
+                html.push_str(&text);
+                if i < lines.len() {
+                    html.push('\n');
+                }
+            } else {
+                html.push_str(&with_attributes(format!("<p>{text}</p>\n"), &block_attrs));
+            }
+        }
+        html
+    }
+
+    /// Renders a heading and registers its id.
+    fn heading(&mut self, level: usize, title: &str, mut attrs: Attributes) -> String {
+        let id = match attrs.id.take() {
+            Some(id) => {
+                self.ids.insert(id.clone());
+                id
+            }
+            None => unique_id(&mut self.ids, &slug(title)),
+        };
+        let text = self.inline(title);
+        // Implicit references match the heading text without markup.
+        self.headings
+            .entry(normalize_label(&plain_text(&text)))
+            .or_insert_with(|| id.clone());
+        let level = level.min(6);
+        with_attributes(
+            format!(
+                "<h{level} id=\"{}\">{text}</h{level}>\n",
+                encode_double_quoted_attribute(&id),
+            ),
+            &attrs,
+        )
+    }
+
+    /// Renders a code block. The `info` string `=html` marks raw HTML, other
+    /// raw formats are dropped.
+    fn code_block(&self, info: &str, code: &str) -> String {
+        if let Some(format) = info.strip_prefix('=') {
+            return if format == "html" {
+                code.to_string()
+            } else {
+                String::new()
+            };
+        }
+        if self.collecting {
+            return String::new();
+        }
+        let mut html = self.registry.highlight_code(info, code);
+        html.push('\n');
+        html
+    }
+
+    /// Renders a pipe table. A separator row `|---|:--:|` turns the row above
+    /// into a header row and sets the alignment of the columns.
+    fn table(&mut self, rows: &[&str], caption: Option<&str>) -> String {
+        let is_separator = |cells: &[&str]| {
+            cells.iter().all(|c| {
+                let c = c.trim().trim_start_matches(':').trim_end_matches(':');
+                !c.is_empty() && c.chars().all(|c| c == '-')
+            })
+        };
+        let rows: Vec<Vec<&str>> = rows.iter().map(|r| table_cells(r)).collect();
+        let separator = rows.iter().position(|r| is_separator(r));
+        let header_rows = match separator {
+            Some(n) if n > 0 => 1,
+            _ => 0,
+        };
+        let alignments: Vec<&str> = separator
+            .map(|n| {
+                rows[n]
+                    .iter()
+                    .map(|c| {
+                        let c = c.trim();
+                        match (c.starts_with(':'), c.ends_with(':')) {
+                            (true, true) => " style=\"text-align: center;\"",
+                            (true, false) => " style=\"text-align: left;\"",
+                            (false, true) => " style=\"text-align: right;\"",
+                            (false, false) => "",
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut html = String::from("<table>\n");
+        if let Some(caption) = caption {
+            html.push_str(&format!("<caption>{}</caption>\n", self.inline(caption)));
+        }
+        let rows: Vec<&Vec<&str>> = rows.iter().filter(|r| !is_separator(r)).collect();
+        for (n, row) in rows.iter().enumerate() {
+            if n == 0 && header_rows > 0 {
+                html.push_str("<thead>\n");
+            }
+            if n == header_rows {
+                html.push_str("<tbody>\n");
+            }
+            let tag = if n < header_rows { "th" } else { "td" };
+            html.push_str("<tr>");
+            for (col, cell) in row.iter().enumerate() {
+                let align = alignments.get(col).copied().unwrap_or_default();
+                html.push_str(&format!(
+                    "<{tag}{align}>{}</{tag}>",
+                    self.inline(cell.trim())
+                ));
+            }
+            html.push_str("</tr>\n");
+            if n + 1 == header_rows {
+                html.push_str("</thead>\n");
+            }
+        }
+        if rows.len() > header_rows {
+            html.push_str("</tbody>\n");
+        }
+        html.push_str("</table>\n");
+        html
+    }
+
+    /// Renders the list starting at `lines[start]`. Returns the HTML and the
+    /// index of the line after the list.
+    fn list(&mut self, lines: &[&str], start: usize) -> (String, usize) {
+        let Some(first) = list_marker(lines[start]) else {
+            return (String::new(), start + 1);
+        };
+
+        // Split into items.
+        let mut items: Vec<Vec<&str>> = Vec::new();
+        let mut loose = false;
+        let mut i = start;
+        while i < lines.len() {
+            let Some(marker) = list_marker(lines[i]).filter(|m| first.continues(m)) else {
+                break;
+            };
+            let end = container_end(lines, i + 1, marker.indent);
+            // Continuation lines are unindented relative to the item text.
+            let content_indent = marker.indent + marker.len + 1;
+            let mut content = vec![marker.text];
+            content.extend(
+                lines[i + 1..end]
+                    .iter()
+                    .map(|l| strip_indent(l, content_indent)),
+            );
+            // A blank line between two blocks of an item makes the list
+            // loose, unless a sublist follows.
+            loose |= content.windows(2).any(|w| {
+                w[0].trim().is_empty() && !w[1].trim().is_empty() && list_marker(w[1]).is_none()
+            });
+            items.push(content);
+
+            // Blank lines between items make the list loose.
+            let mut next = end;
+            while next < lines.len() && lines[next].trim().is_empty() {
+                next += 1;
+            }
+            i = end;
+            if next > end
+                && next < lines.len()
+                && list_marker(lines[next]).is_some_and(|m| first.continues(&m))
+            {
+                loose = true;
+                i = next;
+            }
+        }
+
+        let (tag, attributes) = match first.kind {
+            ListKind::Bullet(_) => ("ul", String::new()),
+            ListKind::Definition => ("dl", String::new()),
+            ListKind::Ordered(numbering, _) => {
+                let mut attributes = String::new();
+                if first.start != 1 {
+                    attributes.push_str(&format!(" start=\"{}\"", first.start));
+                }
+                if let Some(t) = numbering.html_type() {
+                    attributes.push_str(&format!(" type=\"{t}\""));
+                }
+                ("ol", attributes)
+            }
+        };
+        let mut html = format!("<{tag}{attributes}>\n");
+        for mut content in items {
+            if first.kind == ListKind::Definition {
+                let term_end = content
+                    .iter()
+                    .position(|l| l.trim().is_empty())
+                    .unwrap_or(content.len());
+                let term = self.inline_lines(&content[..term_end]);
+                let definition = self.blocks(&content[term_end..], !loose);
+                html.push_str(&format!("<dt>{term}</dt>\n<dd>"));
+                if loose {
+                    html.push('\n');
+                }
+                html.push_str(&definition);
+                html.push_str("</dd>\n");
+                continue;
+            }
+            html.push_str("<li>");
+            if matches!(first.kind, ListKind::Bullet(_)) {
+                let (checkbox, text) = checkbox(content[0]);
+                content[0] = text;
+                if let Some(checked) = checkbox {
+                    html.push_str(&format!("<input type=\"checkbox\" disabled{checked}> "));
+                }
+            }
+            if loose {
+                html.push('\n');
+            }
+            html.push_str(&self.blocks(&content, !loose));
+            html.push_str("</li>\n");
+        }
+        html.push_str(&format!("</{tag}>\n"));
+        (html, i)
+    }
+
+    /// Renders the endnotes of all referenced footnotes.
+    fn endnotes(&mut self) -> String {
+        if self.footnotes.is_empty() {
+            return String::new();
+        }
+        let mut html = String::from("<section role=\"doc-endnotes\">\n<hr>\n<ol>\n");
+        let mut n = 0;
+        // Footnotes may reference further footnotes.
+        while n < self.footnotes.len() {
+            let label = self.footnotes[n].clone();
+            n += 1;
+            let body = self.footnote_defs.get(&label).cloned().unwrap_or_default();
+            let lines: Vec<&str> = body.iter().map(String::as_str).collect();
+            let mut content = self.blocks(&lines, false);
+            let backlink = format!("<a href=\"#fnref{n}\" role=\"doc-backlink\">↩\u{fe0e}</a>");
+            match content.strip_suffix("</p>\n") {
+                Some(paragraph) => content = format!("{paragraph}{backlink}</p>\n"),
+                None => content.push_str(&format!("<p>{backlink}</p>\n")),
+            }
+            html.push_str(&format!("<li id=\"fn{n}\">\n{content}</li>\n"));
+        }
+        html.push_str("</ol>\n</section>\n");
+        html
+    }
+
+    /// Renders the inline markup of the joined `lines`.
+    fn inline_lines(&mut self, lines: &[&str]) -> String {
+        let text = lines
+            .iter()
+            .map(|l| l.trim())
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.inline(&text)
+    }
+
+    /// Renders the inline markup of `text`.
+    fn inline(&mut self, text: &str) -> String {
+        let mut html = String::with_capacity(text.len() * 3 / 2);
+        let mut rest = text;
+        let mut prev: Option<char> = None;
+        while let Some(c) = rest.chars().next() {
+            if let Some((mut consumed, mut out)) = self.inline_element(rest, prev) {
+                // Attributes directly following an element apply to it.
+                if out.starts_with('<')
+                    && !out.starts_with("<br>")
+                    && let Some((attrs, n)) = Attributes::parse(&rest[consumed..])
+                {
+                    out = with_attributes(out, &attrs);
+                    consumed += n;
+                }
+                html.push_str(&out);
+                prev = rest[..consumed].chars().next_back();
+                rest = &rest[consumed..];
+                continue;
+            }
+            html.push_str(&encode_text(&rest[..c.len_utf8()]));
+            prev = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        html
+    }
+
+    /// Tries to render an inline element at the start of `s`. `prev` is the
+    /// preceding character. Returns the number of consumed bytes and the
+    /// HTML rendition.
+    fn inline_element(&mut self, s: &str, prev: Option<char>) -> Option<(usize, String)> {
+        let c = s.chars().next()?;
+        match c {
+            '\\' => match s[1..].chars().next()? {
+                '\n' => Some((2, "<br>\n".to_string())),
+                ' ' => Some((2, "&nbsp;".to_string())),
+                n if n.is_ascii_punctuation() => Some((2, encode_text(&s[1..2]).into_owned())),
+                _ => None,
+            },
+
+            '`' => {
+                let (consumed, content) = verbatim(s);
+                // Raw inline `` `<b>`{=html} ``.
+                if let Some(format) = s[consumed..].strip_prefix("{=")
+                    && let Some(end) = format.find('}')
+                {
+                    let raw = if &format[..end] == "html" {
+                        content.to_string()
+                    } else {
+                        String::new()
+                    };
+                    return Some((consumed + end + 3, raw));
+                }
+                Some((consumed, format!("<code>{}</code>", encode_text(content))))
+            }
+
+            '$' => {
+                let (style, n) = if s.starts_with("$$`") {
+                    (latex2mathml::DisplayStyle::Block, 2)
+                } else if s.starts_with("$`") {
+                    (latex2mathml::DisplayStyle::Inline, 1)
+                } else {
+                    return None;
+                };
+                let (consumed, content) = verbatim(&s[n..]);
+                Some((
+                    n + consumed,
+                    latex2mathml::latex_to_mathml(content, style).unwrap_or_else(|e| e.to_string()),
+                ))
+            }
+
+            '<' => {
+                let end = s.find('>')?;
+                let target = &s[1..end];
+                if target.is_empty() || target.contains(|c: char| c.is_whitespace() || c == '<') {
+                    return None;
+                }
+                let href = if is_url(target) {
+                    target.to_string()
+                } else if target.contains('@') {
+                    format!("mailto:{target}")
+                } else {
+                    return None;
+                };
+                Some((
+                    end + 1,
+                    format!(
+                        "<a href=\"{}\">{}</a>",
+                        encode_double_quoted_attribute(&href),
+                        encode_text(target)
+                    ),
+                ))
+            }
+
+            '!' if s[1..].starts_with('[') => self.bracketed(s, true),
+
+            '[' => self.bracketed(s, false),
+
+            '{' => {
+                if let Some(comment) = s.strip_prefix("{%") {
+                    let end = comment.find("%}")?;
+                    return Some((end + 4, String::new()));
+                }
+                let marker = s[1..].chars().next()?;
+                if let Some((_, _, tag)) = CONTAINERS.iter().find(|(m, _, _)| *m == marker) {
+                    return self
+                        .container(&s[1..], marker, tag, true)
+                        .map(|(n, html)| (n + 1, html));
+                }
+                // Attributes not following a span are dropped.
+                Attributes::parse(s).map(|(_, n)| (n, String::new()))
+            }
+
+            c if CONTAINERS.iter().any(|(m, braced, _)| *m == c && !braced) => {
+                let (_, _, tag) = CONTAINERS.iter().find(|(m, _, _)| *m == c)?;
+                self.container(s, c, tag, false)
+            }
+
+            // Smart punctuation.
+            '.' if s.starts_with("...") => Some((3, "…".to_string())),
+
+            '-' if s.starts_with("--") => {
+                let n = s.chars().take_while(|c| *c == '-').count();
+                let (em, en) = match (n % 3, n % 2) {
+                    (0, _) => (n / 3, 0),
+                    (_, 0) => (0, n / 2),
+                    (2, _) => (n / 3, 1),
+                    _ => ((n - 4) / 3, 2),
+                };
+                Some((n, format!("{}{}", "—".repeat(em), "–".repeat(en))))
+            }
+
+            '"' | '\'' => {
+                let opening = prev.is_none_or(|p| p.is_whitespace() || "([{-–—\"'".contains(p));
+                let quote = match (c, opening) {
+                    ('"', true) => "“",
+                    ('"', false) => "”",
+                    (_, true) => "‘",
+                    (_, false) => "’",
+                };
+                Some((1, quote.to_string()))
+            }
+
+            _ => None,
+        }
+    }
+
+    /// Renders the inline container at the start of `s`, which starts with
+    /// its delimiter `marker`. A `braced` container ends with `marker}`.
+    fn container(
+        &mut self,
+        s: &str,
+        marker: char,
+        tag: &str,
+        braced: bool,
+    ) -> Option<(usize, String)> {
+        let inner = &s[1..];
+        if !braced && inner.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let end = closing_delimiter(inner, marker, braced)?;
+        let consumed = 1 + end + 1 + usize::from(braced);
+        Some((
+            consumed,
+            format!("<{tag}>{}</{tag}>", self.inline(&inner[..end])),
+        ))
+    }
+
+    /// Renders a link, image, span or footnote reference starting with `[`,
+    /// or with `![` for an `image`.
+    fn bracketed(&mut self, s: &str, image: bool) -> Option<(usize, String)> {
+        let offset = usize::from(image);
+        let b = &s[offset..];
+        let close = closing_bracket(b)?;
+        let text = &b[1..close];
+        let after = &b[close + 1..];
+        let consumed = offset + close + 1;
+
+        // Footnote reference `[^label]`.
+        if !image && let Some(label) = text.strip_prefix('^') {
+            return Some((consumed, self.footnote_reference(label.trim())));
+        }
+
+        // Inline link `[text](url)`.
+        if let Some(dest) = after.strip_prefix('(') {
+            let end = closing_paren(dest)?;
+            // Line breaks in destinations are ignored.
+            let url: String = dest[..end].lines().map(str::trim).collect();
+            return Some((consumed + end + 2, self.link(text, &url, image)));
+        }
+
+        // Reference link `[text][label]` or `[text][]`.
+        if after.starts_with('[') {
+            let end = closing_bracket(after)?;
+            let label = match &after[1..end] {
+                "" => text,
+                label => label,
+            };
+            let key = normalize_label(label);
+            let url = self
+                .references
+                .get(&key)
+                .cloned()
+                .or_else(|| self.headings.get(&key).map(|id| format!("#{id}")));
+            let html = match url {
+                Some(url) => self.link(text, &url, image),
+                None if image => String::new(),
+                None => format!("<a>{}</a>", self.inline(text)),
+            };
+            return Some((consumed + end + 1, html));
+        }
+
+        // Span `[text]{.class}`.
+        if !image
+            && after.starts_with('{')
+            && let Some((attrs, n)) = Attributes::parse(after)
+        {
+            return Some((
+                consumed + n,
+                format!("<span{}>{}</span>", attrs.html(), self.inline(text)),
+            ));
+        }
+
+        None
+    }
+
+    /// Renders a link or an image.
+    fn link(&mut self, text: &str, url: &str, image: bool) -> String {
+        let url = encode_double_quoted_attribute(url);
+        let text = self.inline(text);
+        if image {
+            format!(
+                "<img alt=\"{}\" src=\"{url}\">",
+                plain_text(&text).replace('"', "&quot;")
+            )
+        } else {
+            format!("<a href=\"{url}\">{text}</a>")
+        }
+    }
+
+    /// Registers a footnote reference and returns its mark.
+    fn footnote_reference(&mut self, label: &str) -> String {
+        let n = match self.footnotes.iter().position(|l| l == label) {
+            Some(n) => n + 1,
+            None => {
+                self.footnotes.push(label.to_string());
+                self.footnotes.len()
+            }
+        };
+        format!("<a id=\"fnref{n}\" href=\"#fn{n}\" role=\"doc-noteref\"><sup>{n}</sup></a>")
+    }
+}
+
+/// Block and span attributes `{#id .class key="value"}`.
+#[derive(Debug, Default, PartialEq)]
+struct Attributes {
+    id: Option<String>,
+    classes: Vec<String>,
+    pairs: Vec<(String, String)>,
+}
+
+impl Attributes {
+    /// Parses the attributes at the start of `s`. Returns them with the
+    /// number of consumed bytes.
+    fn parse(s: &str) -> Option<(Self, usize)> {
+        let mut attrs = Self::default();
+        let mut rest = s.strip_prefix('{')?;
+        loop {
+            rest = rest.trim_start();
+            if let Some(r) = rest.strip_prefix('}') {
+                return Some((attrs, s.len() - r.len()));
+            }
+            if let Some(r) = rest.strip_prefix('%') {
+                // Comment.
+                rest = &r[r.find('%')? + 1..];
+            } else if let Some(r) = rest.strip_prefix('#') {
+                let (id, r) = split_name(r)?;
+                attrs.id = Some(id.to_string());
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix('.') {
+                let (class, r) = split_name(r)?;
+                attrs.classes.push(class.to_string());
+                rest = r;
+            } else {
+                let (key, r) = split_name(rest)?;
+                let r = r.strip_prefix('=')?;
+                let (value, r) = match r.strip_prefix('"') {
+                    Some(r) => {
+                        let mut value = String::new();
+                        let mut chars = r.char_indices();
+                        loop {
+                            match chars.next()? {
+                                (_, '\\') => value.push(chars.next()?.1),
+                                (i, '"') => break (value, &r[i + 1..]),
+                                (_, c) => value.push(c),
+                            }
+                        }
+                    }
+                    None => {
+                        let (value, r) = split_name(r)?;
+                        (value.to_string(), r)
+                    }
+                };
+                if key == "class" {
+                    attrs.classes.push(value);
+                } else {
+                    attrs.pairs.push((key.to_string(), value));
+                }
+                rest = r;
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.id.is_none() && self.classes.is_empty() && self.pairs.is_empty()
+    }
+
+    /// Adds `other`. Its id replaces ours.
+    fn merge(&mut self, other: Self) {
+        if other.id.is_some() {
+            self.id = other.id;
+        }
+        self.classes.extend(other.classes);
+        self.pairs.extend(other.pairs);
+    }
+
+    /// Renders the attributes, each preceded by a space.
+    fn html(&self) -> String {
+        let mut html = String::new();
+        if let Some(id) = &self.id {
+            html.push_str(&format!(" id=\"{}\"", encode_double_quoted_attribute(id)));
+        }
+        if !self.classes.is_empty() {
+            html.push_str(&format!(
+                " class=\"{}\"",
+                encode_double_quoted_attribute(&self.classes.join(" "))
+            ));
+        }
+        for (key, value) in &self.pairs {
+            html.push_str(&format!(
+                " {key}=\"{}\"",
+                encode_double_quoted_attribute(value)
+            ));
+        }
+        html
+    }
+}
+
+/// Adds `attrs` to the first tag of `html`.
+fn with_attributes(html: String, attrs: &Attributes) -> String {
+    match html.find('>') {
+        Some(n) if !attrs.is_empty() => format!("{}{}{}", &html[..n], attrs.html(), &html[n..]),
+        _ => html,
+    }
+}
+
+/// Splits an attribute name or a bare value from `s`.
+fn split_name(s: &str) -> Option<(&str, &str)> {
+    let end = s
+        .find(|c: char| !(c.is_alphanumeric() || "_-:".contains(c)))
+        .unwrap_or(s.len());
+    (end > 0).then(|| s.split_at(end))
+}
+
+/// The kind of a list, determined by its first marker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ListKind {
+    /// `-`, `+` or `*`.
+    Bullet(char),
+    Ordered(Numbering, Delimiter),
+    /// `: term`.
+    Definition,
+}
+
+/// The numbering style of an ordered list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Numbering {
+    Decimal,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+impl Numbering {
+    /// The HTML `type` attribute of `<ol>`.
+    fn html_type(self) -> Option<&'static str> {
+        match self {
+            Self::Decimal => None,
+            Self::LowerAlpha => Some("a"),
+            Self::UpperAlpha => Some("A"),
+            Self::LowerRoman => Some("i"),
+            Self::UpperRoman => Some("I"),
+        }
+    }
+}
+
+/// The delimiter of an ordered list marker: `1.`, `1)` or `(1)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Delimiter {
+    Period,
+    Paren,
+    Parens,
+}
+
+/// A list item marker.
+struct ListMarker<'a> {
+    indent: usize,
+    kind: ListKind,
+    /// The length of the marker.
+    len: usize,
+    /// The number of an ordered list item.
+    start: usize,
+    /// A single letter that might be alphabetical or roman, e.g. `v.`.
+    ambiguous: bool,
+    text: &'a str,
+}
+
+impl ListMarker<'_> {
+    /// Is `other` an item of the list starting with `self`?
+    fn continues(&self, other: &ListMarker) -> bool {
+        if other.indent != self.indent {
+            return false;
+        }
+        match (self.kind, other.kind) {
+            (ListKind::Ordered(a, d1), ListKind::Ordered(b, d2)) => {
+                d1 == d2
+                    && (a == b
+                        || other.ambiguous
+                            && matches!(
+                                (a, b),
+                                (Numbering::LowerRoman, Numbering::LowerAlpha)
+                                    | (Numbering::UpperRoman, Numbering::UpperAlpha)
+                            ))
+            }
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// Returns the list item marker of `line`.
+fn list_marker(line: &str) -> Option<ListMarker<'_>> {
+    let indent = indentation(line);
+    let rest = &line[indent..];
+    let (kind, len, start, ambiguous) = match rest.chars().next()? {
+        c @ ('-' | '+' | '*') => (ListKind::Bullet(c), 1, 1, false),
+        ':' => (ListKind::Definition, 1, 1, false),
+        '(' => {
+            let (numbering, n, start, ambiguous) = enumerator(&rest[1..])?;
+            if !rest[1 + n..].starts_with(')') {
+                return None;
+            }
+            (
+                ListKind::Ordered(numbering, Delimiter::Parens),
+                n + 2,
+                start,
+                ambiguous,
+            )
+        }
+        _ => {
+            let (numbering, n, start, ambiguous) = enumerator(rest)?;
+            let delimiter = match rest[n..].chars().next()? {
+                '.' => Delimiter::Period,
+                ')' => Delimiter::Paren,
+                _ => return None,
+            };
+            (
+                ListKind::Ordered(numbering, delimiter),
+                n + 1,
+                start,
+                ambiguous,
+            )
+        }
+    };
+    let after = &rest[len..];
+    let text = if after.is_empty() {
+        after
+    } else {
+        after.strip_prefix([' ', '\t'])?.trim_start()
+    };
+    Some(ListMarker {
+        indent,
+        kind,
+        len,
+        start,
+        ambiguous,
+        text,
+    })
+}
+
+/// Parses the enumerator of an ordered list marker: `1`, `a`, `A`, `iv`
+/// or `IV`. Returns the numbering style, the length, the number and whether
+/// it is ambiguous. A single `i` or `I` is roman, other single letters are
+/// alphabetical.
+fn enumerator(s: &str) -> Option<(Numbering, usize, usize, bool)> {
+    let n = s.chars().take_while(char::is_ascii_alphanumeric).count();
+    let word = &s[..n];
+    if n == 0 {
+        return None;
+    }
+    if word.chars().all(|c| c.is_ascii_digit()) {
+        return Some((Numbering::Decimal, n, word.parse().ok()?, false));
+    }
+    let lower = word.chars().all(|c| c.is_ascii_lowercase());
+    if !lower && !word.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    if n == 1 && !word.eq_ignore_ascii_case("i") {
+        let c = word.chars().next()?.to_ascii_lowercase();
+        let numbering = if lower {
+            Numbering::LowerAlpha
+        } else {
+            Numbering::UpperAlpha
+        };
+        return Some((
+            numbering,
+            1,
+            (c as u8 - b'a') as usize + 1,
+            "vxlcdm".contains(c),
+        ));
+    }
+    let numbering = if lower {
+        Numbering::LowerRoman
+    } else {
+        Numbering::UpperRoman
+    };
+    Some((numbering, n, roman_value(word)?, false))
+}
+
+/// Returns the value of the roman numeral `s`.
+fn roman_value(s: &str) -> Option<usize> {
+    let digit = |c: char| match c.to_ascii_lowercase() {
+        'i' => Some(1),
+        'v' => Some(5),
+        'x' => Some(10),
+        'l' => Some(50),
+        'c' => Some(100),
+        'd' => Some(500),
+        'm' => Some(1000),
+        _ => None,
+    };
+    let digits = s.chars().map(digit).collect::<Option<Vec<usize>>>()?;
+    let mut value = 0;
+    for (n, d) in digits.iter().enumerate() {
+        if digits.get(n + 1).is_some_and(|next| next > d) {
+            value -= d;
+        } else {
+            value += d;
+        }
+    }
+    Some(value)
+}
+
+/// Splits a leading check box `[ ]`, `[x]` or `[X]` from `text`.
+fn checkbox(text: &str) -> (Option<&'static str>, &str) {
+    for (prefix, checked) in [("[ ]", ""), ("[x]", " checked"), ("[X]", " checked")] {
+        if let Some(rest) = text.strip_prefix(prefix)
+            && (rest.is_empty() || rest.starts_with(' '))
+        {
+            return (Some(checked), rest.trim_start());
+        }
+    }
+    (None, text)
+}
+
+/// Returns the level and the text of the heading `line`.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    (level > 0 && (rest.is_empty() || rest.starts_with([' ', '\t']))).then(|| (level, rest.trim()))
+}
+
+/// Continuation lines of a heading may repeat its `#` markers.
+fn heading_continuation(line: &str, level: usize) -> &str {
+    match heading(line) {
+        Some((l, text)) if l == level => text,
+        _ => line,
+    }
+}
+
+/// Returns the number of backticks and the info string of the code fence
+/// `line`.
+fn code_fence(line: &str) -> Option<(usize, &str)> {
+    let n = line.chars().take_while(|c| *c == '`').count();
+    let info = line[n..].trim();
+    (n >= 3 && !info.contains('`')).then_some((n, info))
+}
+
+/// Returns the number of colons and the class of the div fence `line`.
+fn div_fence(line: &str) -> Option<(usize, &str)> {
+    let n = line.chars().take_while(|c| *c == ':').count();
+    let class = line[n..].trim();
+    (n >= 3 && !class.contains(char::is_whitespace)).then_some((n, class))
+}
+
+/// Returns the index of the fence closing the div, whose content starts at
+/// `lines[start]`. Nested divs and code blocks are skipped.
+fn div_end(lines: &[&str], start: usize, colons: usize) -> usize {
+    let mut nested: Vec<usize> = Vec::new();
+    let mut fence: Option<usize> = None;
+    let mut i = start;
+    while i < lines.len() {
+        let line = lines[i].trim();
+        if let Some(n) = fence {
+            if code_fence(line).is_some_and(|(m, info)| m >= n && info.is_empty()) {
+                fence = None;
+            }
+        } else if let Some((n, _)) = code_fence(line) {
+            fence = Some(n);
+        } else if let Some((n, class)) = div_fence(line) {
+            if !class.is_empty() {
+                nested.push(n);
+            } else if let Some(top) = nested.last() {
+                if n >= *top {
+                    nested.pop();
+                }
+            } else if n >= colons {
+                break;
+            }
+        }
+        i += 1;
+    }
+    i
+}
+
+/// A line of three or more `*` or `-`, possibly separated by spaces.
+fn is_thematic_break(line: &str) -> bool {
+    line.chars().filter(|c| !c.is_whitespace()).count() >= 3
+        && line
+            .chars()
+            .all(|c| c.is_whitespace() || c == '*' || c == '-')
+}
+
+/// Returns the content of the block quote line `> text`.
+fn quote_content(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    line.strip_prefix("> ")
+        .or_else(|| (line.trim_end() == ">").then_some(""))
+}
+
+/// Parses the link reference definition `[label]: url`.
+fn reference_definition(line: &str) -> Option<(String, &str)> {
+    if line.starts_with("[^") {
+        return None;
+    }
+    let (label, rest) = line.strip_prefix('[')?.split_once("]:")?;
+    (rest.is_empty() || rest.starts_with(char::is_whitespace))
+        .then(|| (normalize_label(label), rest.trim()))
+}
+
+/// Parses the first line of the footnote definition `[^label]: text`.
+fn footnote_definition(line: &str) -> Option<(String, &str)> {
+    let (label, rest) = line.strip_prefix("[^")?.split_once("]:")?;
+    (rest.is_empty() || rest.starts_with(char::is_whitespace))
+        .then(|| (label.trim().to_string(), rest.trim_start()))
+}
+
+fn is_table_row(line: &str) -> bool {
+    line.len() >= 2 && line.starts_with('|') && line.ends_with('|') && !line.ends_with("\\|")
+}
+
+/// Splits a table row into its cells. Escaped `\|` and `|` in verbatim
+/// spans do not separate cells.
+fn table_cells(row: &str) -> Vec<&str> {
+    let row = row.trim();
+    let inner = &row[1..row.len() - 1];
+    let mut cells = Vec::new();
+    let mut cell_start = 0;
+    let mut i = 0;
+    while i < inner.len() {
+        match inner.as_bytes()[i] {
+            b'\\' => i += 1,
+            b'`' => {
+                i += verbatim(&inner[i..]).0;
+                continue;
+            }
+            b'|' => {
+                cells.push(&inner[cell_start..i]);
+                cell_start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    cells.push(&inner[cell_start..]);
+    cells
+}
+
+/// Returns the index of the line after the container (list item or
+/// footnote), whose first line is `lines[start - 1]`. Its continuation lines
+/// are indented more than `indent`. A line directly following a paragraph
+/// line continues the paragraph lazily.
+fn container_end(lines: &[&str], start: usize, indent: usize) -> usize {
+    let mut i = start;
+    let mut lazy = true;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            lazy = false;
+        } else if indentation(line) > indent {
+            lazy = true;
+        } else if !lazy
+            || list_marker(line).is_some()
+            || heading(trimmed).is_some()
+            || code_fence(trimmed).is_some()
+            || div_fence(trimmed).is_some()
+            || quote_content(line).is_some()
+            || is_thematic_break(trimmed)
+            || footnote_definition(trimmed).is_some()
+        {
+            break;
+        }
+        i += 1;
+    }
+    // Trailing blank lines do not belong to the container.
+    while i > start && lines[i - 1].trim().is_empty() {
+        i -= 1;
+    }
+    i
+}
+
+/// Splits the verbatim span at the start of `s`. Returns the number of
+/// consumed bytes and the content. Without closing backticks, the span
+/// extends to the end of `s`.
+fn verbatim(s: &str) -> (usize, &str) {
+    let n = s.chars().take_while(|c| *c == '`').count();
+    let rest = &s[n..];
+    let mut i = 0;
+    let (content, consumed) = loop {
+        match rest[i..].find('`') {
+            Some(pos) => {
+                let start = i + pos;
+                let m = rest[start..].chars().take_while(|c| *c == '`').count();
+                if m == n {
+                    break (&rest[..start], n + start + m);
+                }
+                i = start + m;
+            }
+            None => break (rest, s.len()),
+        }
+    };
+    // A single space next to a backtick in the content is removed.
+    let content = match content.strip_prefix(' ') {
+        Some(c) if c.starts_with('`') => c,
+        _ => content,
+    };
+    let content = match content.strip_suffix(' ') {
+        Some(c) if c.ends_with('`') => c,
+        _ => content,
+    };
+    (consumed, content)
+}
+
+/// Returns the index of the `]` matching the `[` at the start of `s`.
+fn closing_bracket(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < s.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'`' => {
+                i += verbatim(&s[i..]).0;
+                continue;
+            }
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Returns the index of the `)` closing the link destination `s`.
+fn closing_paren(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < s.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'(' => depth += 1,
+            b')' if depth == 0 => return Some(i),
+            b')' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Returns the index of the delimiter `marker` closing the inline container
+/// with the content `s`. Nested containers with the same delimiter are
+/// skipped.
+fn closing_delimiter(s: &str, marker: char, braced: bool) -> Option<usize> {
+    let mut depth = 0;
+    let mut prev: Option<char> = None;
+    let mut i = 0;
+    while let Some(c) = s[i..].chars().next() {
+        match c {
+            '\\' => {
+                i += 1 + s[i + 1..].chars().next().map_or(0, char::len_utf8);
+                prev = Some('\\');
+                continue;
+            }
+            '`' => {
+                i += verbatim(&s[i..]).0;
+                prev = Some('`');
+                continue;
+            }
+            c if c == marker && i > 0 => {
+                let next = s[i + 1..].chars().next();
+                if braced {
+                    if next == Some('}') {
+                        return Some(i);
+                    }
+                } else if prev.is_some_and(|p| !p.is_whitespace()) {
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                    depth -= 1;
+                } else if next.is_some_and(|n| !n.is_whitespace()) {
+                    depth += 1;
+                }
+            }
+            _ => {}
+        }
+        prev = Some(c);
+        i += c.len_utf8();
+    }
+    None
+}
+
+/// Does `s` start with a URL scheme like `https:`?
+fn is_url(s: &str) -> bool {
+    s.split_once(':').is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    })
+}
+
+/// Removes the tags from `html`.
+fn plain_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Link labels match regardless of their white space.
+fn normalize_label(label: &str) -> String {
+    label.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Removes up to `n` bytes of leading white space from `line`.
+fn strip_indent(line: &str, n: usize) -> &str {
+    let strip = line
+        .char_indices()
+        .take_while(|(i, c)| *i < n && c.is_whitespace())
+        .map(|(_, c)| c.len_utf8())
+        .sum::<usize>();
+    &line[strip..]
+}
+
+/// Removes the common leading white space of `lines`.
+fn unindent<'a>(lines: &[&'a str]) -> Vec<&'a str> {
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| indentation(l))
+        .min()
+        .unwrap_or(0);
+    lines.iter().map(|l| strip_indent(l, indent)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::djot_to_html;
+
+    #[test]
+    fn test_djot_headings() {
+        let input = "# My *first* heading\n\n{#custom .wide}\n## Second\n\
+            continued\n\n# My first heading\n\nSee [My first heading][] and [two][Second continued].";
+        let expected = "<h1 id=\"my-first-heading\">My <strong>first</strong> heading</h1>\n\
+            <h2 id=\"custom\" class=\"wide\">Second\ncontinued</h2>\n\
+            <h1 id=\"my-first-heading-1\">My first heading</h1>\n\
+            <p>See <a href=\"#my-first-heading\">My first heading</a> and \
+            <a href=\"#custom\">two</a>.</p>\n";
+        assert_eq!(djot_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_djot_inline() {
+        let input = "_em_ *strong* {=mark=} {+ins+} {-del-} x^2^ H~2~O `a < b`\n\
+            {_ spaced _} 2 * 3 * 4 \\*not\\* 'quoted' \"text\" -- --- ...\\\n\
+            $`x^2` [span]{.red} {% comment %}`<b>`{=html}";
+        let expected = "<p><em>em</em> <strong>strong</strong> <mark>mark</mark> \
+            <ins>ins</ins> <del>del</del> x<sup>2</sup> H<sub>2</sub>O <code>a &lt; b</code>\n\
+            <em> spaced </em> 2 * 3 * 4 *not* ‘quoted’ “text” – — …<br>\n";
+        let result = djot_to_html(input);
+        assert!(result.starts_with(expected), "{result}");
+        assert!(result.ends_with(" <span class=\"red\">span</span> <b></p>\n"));
+    }
+
+    #[test]
+    fn test_djot_links() {
+        let input = "[inline](https://example.com) [ref][] ![logo](img/logo.png) \
+            <https://example.com> <me@example.com> [note](other.dj)\n\n\
+            [ref]: https://example.com/\n  long";
+        let expected = "<p><a href=\"https://example.com\">inline</a> \
+            <a href=\"https://example.com/long\">ref</a> \
+            <img alt=\"logo\" src=\"img/logo.png\"> \
+            <a href=\"https://example.com\">https://example.com</a> \
+            <a href=\"mailto:me@example.com\">me@example.com</a> \
+            <a href=\"other.dj\">note</a></p>\n";
+        assert_eq!(djot_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_djot_lists() {
+        let input = "- one\n  continued\n\n  1. nested\n  2. [x] done\n- [ ] two\n\n\
+            b) first\nc) second\n\n: Term\n\n  Definition\n\nAfter.";
+        let expected = "<ul>\n<li>one\ncontinued\n<ol>\n<li>nested</li>\n\
+            <li>[x] done</li>\n</ol>\n</li>\n\
+            <li><input type=\"checkbox\" disabled> two</li>\n</ul>\n\
+            <ol start=\"2\" type=\"a\">\n<li>first</li>\n<li>second</li>\n</ol>\n\
+            <dl>\n<dt>Term</dt>\n<dd>\n<p>Definition</p>\n</dd>\n</dl>\n\
+            <p>After.</p>\n";
+        assert_eq!(djot_to_html(input), expected);
+
+        let input = "* loose\n\n* list";
+        let expected = "<ul>\n<li>\n<p>loose</p>\n</li>\n<li>\n<p>list</p>\n</li>\n</ul>\n";
+        assert_eq!(djot_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_djot_tables() {
+        let input = "| Name  | Price |\n|-------|------:|\n| Apple | 1     |\n\
+            | Pear  | *2*   |\n\n^ Prices";
+        let expected = "<table>\n<caption>Prices</caption>\n\
+            <thead>\n<tr><th>Name</th><th style=\"text-align: right;\">Price</th></tr>\n\
+            </thead>\n<tbody>\n<tr><td>Apple</td><td style=\"text-align: right;\">1</td></tr>\n\
+            <tr><td>Pear</td><td style=\"text-align: right;\"><strong>2</strong></td></tr>\n\
+            </tbody>\n</table>\n";
+        assert_eq!(djot_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_djot_blocks() {
+        let input = "```rust\nfn main() {}\n```\n\n``` =html\n<b>raw</b>\n```\n\n\
+            > Quoted _text_.\n>\n> More.\n\n::: warning\nHot.\n:::\n\n* * *\n\n\
+            Note.[^1]\n\n[^1]: The *note*.";
+        let result = djot_to_html(input);
+        assert!(result.starts_with("<pre><code class=\"language-rust\">"));
+        assert!(result.ends_with(
+            "</code></pre>\n<b>raw</b>\n\
+            <blockquote>\n<p>Quoted <em>text</em>.</p>\n<p>More.</p>\n</blockquote>\n\
+            <div class=\"warning\">\n<p>Hot.</p>\n</div>\n<hr>\n\
+            <p>Note.<a id=\"fnref1\" href=\"#fn1\" role=\"doc-noteref\"><sup>1</sup></a></p>\n\
+            <section role=\"doc-endnotes\">\n<hr>\n<ol>\n<li id=\"fn1\">\n\
+            <p>The <strong>note</strong>.<a href=\"#fnref1\" role=\"doc-backlink\">↩\u{fe0e}</a></p>\n\
+            </li>\n</ol>\n</section>\n"
+        ));
+    }
+
+    /// Examples from the Djot syntax reference and test suite together with
+    /// the HTML rendition of the reference implementation `djot.js`.
+    /// Line breaks next to tags are ignored. Not compared are the
+    /// deviations documented in the module description: sections around
+    /// headings, tables, task lists, code blocks and math.
+    #[test]
+    fn test_djot_spec() {
+        let normalize = |html: &str| html.trim().replace(">\n", ">").replace("\n<", "<");
+        let cases = [
+            // Emphasis and strong.
+            ("_emphasized text_", "<p><em>emphasized text</em></p>"),
+            (
+                "*strong emphasis*",
+                "<p><strong>strong emphasis</strong></p>",
+            ),
+            ("_ not emphasized_", "<p>_ not emphasized_</p>"),
+            ("_not emphasized _", "<p>_not emphasized _</p>"),
+            ("{_ emphasized _}", "<p><em> emphasized </em></p>"),
+            ("*foo*bar", "<p><strong>foo</strong>bar</p>"),
+            ("_foo *bar_ baz*", "<p><em>foo *bar</em> baz*</p>"),
+            ("*_nested_*", "<p><strong><em>nested</em></strong></p>"),
+            // Other inline containers.
+            ("H~2~O and x^2^", "<p>H<sub>2</sub>O and x<sup>2</sup></p>"),
+            ("{=highlighted=}", "<p><mark>highlighted</mark></p>"),
+            (
+                "{+insert+} {-delete-}",
+                "<p><ins>insert</ins> <del>delete</del></p>",
+            ),
+            ("[span]{.red}", "<p><span class=\"red\">span</span></p>"),
+            (
+                "an _emphasized_{#foo .bar} word",
+                "<p>an <em id=\"foo\" class=\"bar\">emphasized</em> word</p>",
+            ),
+            (
+                "`code`{key=\"a b\"} [link](url){.ext}",
+                "<p><code key=\"a b\">code</code> <a href=\"url\" class=\"ext\">link</a></p>",
+            ),
+            ("*not closed", "<p>*not closed</p>"),
+            ("[not a link]", "<p>[not a link]</p>"),
+            // Verbatim.
+            (
+                "``Verbatim with a backtick` character``",
+                "<p><code>Verbatim with a backtick` character</code></p>",
+            ),
+            ("`` `foo` ``", "<p><code>`foo`</code></p>"),
+            ("`_not emphasis_`", "<p><code>_not emphasis_</code></p>"),
+            ("`foo bar", "<p><code>foo bar</code></p>"),
+            ("`<a>`{=html}", "<p><a></p>"),
+            // Escapes and line breaks.
+            ("\\*not strong\\*", "<p>*not strong*</p>"),
+            ("\\a", "<p>\\a</p>"),
+            ("line one\\\nline two", "<p>line one<br>\nline two</p>"),
+            ("a\\ b", "<p>a&nbsp;b</p>"),
+            ("soft\nbreak", "<p>soft\nbreak</p>"),
+            // Smart punctuation.
+            (
+                "\"Hello,\" said the spider.",
+                "<p>“Hello,” said the spider.</p>",
+            ),
+            ("It's 'quoted'", "<p>It’s ‘quoted’</p>"),
+            ("a -- b --- c ...", "<p>a – b — c …</p>"),
+            // Links and images.
+            (
+                "[My link text](http://example.com)",
+                "<p><a href=\"http://example.com\">My link text</a></p>",
+            ),
+            (
+                "[foo][bar]\n\n[bar]: /url",
+                "<p><a href=\"/url\">foo</a></p>",
+            ),
+            ("[foo][]\n\n[foo]: /url", "<p><a href=\"/url\">foo</a></p>"),
+            (
+                "[link](http://example.com?n=123\n456)",
+                "<p><a href=\"http://example.com?n=123456\">link</a></p>",
+            ),
+            (
+                "![picture of a cat](cat.jpg)",
+                "<p><img alt=\"picture of a cat\" src=\"cat.jpg\"></p>",
+            ),
+            (
+                "<https://pandoc.org/lua-filters>",
+                "<p><a href=\"https://pandoc.org/lua-filters\">\
+                https://pandoc.org/lua-filters</a></p>",
+            ),
+            (
+                "<me@example.com>",
+                "<p><a href=\"mailto:me@example.com\">me@example.com</a></p>",
+            ),
+            // Blocks.
+            (
+                "para\nwith lines\n\nnext",
+                "<p>para\nwith lines</p><p>next</p>",
+            ),
+            (
+                "> Basic block quote.",
+                "<blockquote><p>Basic block quote.</p></blockquote>",
+            ),
+            (
+                "> lazy\ncontinuation",
+                "<blockquote><p>lazy\ncontinuation</p></blockquote>",
+            ),
+            ("- one\n- two", "<ul><li>one</li><li>two</li></ul>"),
+            (
+                "+ one\n\n+ two",
+                "<ul><li><p>one</p></li><li><p>two</p></li></ul>",
+            ),
+            ("1. one\n2. two", "<ol><li>one</li><li>two</li></ol>"),
+            ("1) one", "<ol><li>one</li></ol>"),
+            ("- a\n* b", "<ul><li>a</li></ul><ul><li>b</li></ul>"),
+            (
+                "3. three\n4. four",
+                "<ol start=\"3\"><li>three</li><li>four</li></ol>",
+            ),
+            (
+                "a) one\nb) two",
+                "<ol type=\"a\"><li>one</li><li>two</li></ol>",
+            ),
+            (
+                "(i) one\n(ii) two",
+                "<ol type=\"i\"><li>one</li><li>two</li></ol>",
+            ),
+            (
+                ": orange\n\n  A fruit.",
+                "<dl><dt>orange</dt><dd><p>A fruit.</p></dd></dl>",
+            ),
+            ("* * *", "<hr>"),
+            (
+                "::: warning\nHot.\n:::",
+                "<div class=\"warning\"><p>Hot.</p></div>",
+            ),
+            ("``` =html\n<b>x</b>\n```", "<b>x</b>"),
+            ("{.foo}\nparagraph", "<p class=\"foo\">paragraph</p>"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                normalize(&djot_to_html(input)),
+                normalize(expected),
+                "input: {input:?}"
+            );
+        }
+    }
+}
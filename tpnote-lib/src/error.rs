@@ -1,5 +1,7 @@
 //! Custom error types.
 
+use serde::Serialize;
+use serde::Serializer;
 use std::io;
 use std::path::PathBuf;
 use thiserror::Error;
@@ -197,17 +199,48 @@ pub enum LibCfgError {
         available: String,
     },
 
+    /// Remedy: correct the path or the syntax definition and theme files in
+    /// this directory.
+    #[error(
+        "Configuration file error in section `[tmp_html]` in line:\n\
+        \thighlighting_dir = \"{path}\"\n\
+        Can not load syntax definitions or themes: {error}"
+    )]
+    HighlightingDir { path: String, error: String },
+
     #[error(transparent)]
     Deserialize(#[from] toml::de::Error),
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Error, Serialize)]
+#[serde(tag = "code", content = "fields")]
 /// Error type returned form methods in or related to the `note` module.
+/// For machine-readable output, it serializes to a structured record: the
+/// variant name becomes the error `code` and the variant's fields become
+/// `fields`. Wrapped errors from other crates serialize as their message.
+///
+/// ```rust
+/// use tpnote_lib::error::NoteError;
+///
+/// let e = NoteError::FrontMatterFieldMissing {
+///     field_name: "title".to_string(),
+/// };
+/// let json = serde_json::to_string(&e).unwrap();
+/// assert_eq!(
+///     json,
+///     r#"{"code":"FrontMatterFieldMissing","fields":{"field_name":"title"}}"#
+/// );
+/// ```
 pub enum NoteError {
     /// Remedy: make sure, that a file starting with `path` exists.
     #[error("<NONE FOUND: {path}...>")]
     CanNotExpandShorthandLink { path: String },
 
+    /// Remedy: make sure, that a note with the title or sort-tag `link`
+    /// exists under the root path.
+    #[error("<NONE FOUND: [[{link}]]>")]
+    CanNotResolveWikiLink { link: String },
+
     /// Remedy: Choose another scheme.
     #[error(
         "Invalid header variable value: no scheme `{scheme_val}` found.\n\
@@ -396,9 +429,24 @@ pub enum NoteError {
     )]
     InvalidFrontMatterYaml {
         front_matter: String,
+        #[serde(serialize_with = "serialize_display")]
         source_error: serde_yaml::Error,
     },
 
+    /// Remedy: check TOML syntax in the note's front matter.
+    #[error(
+        "Can not parse TOML front matter:\n\
+         \n\
+         {front_matter}\
+         \n\
+         {source_error}"
+    )]
+    InvalidFrontMatterToml {
+        front_matter: String,
+        #[serde(serialize_with = "serialize_display")]
+        source_error: Box<toml::de::Error>,
+    },
+
     /// Remedy: check YAML syntax in the input stream's front matter.
     #[error(
         "Invalid YAML field(s) in the {tmpl_var} input\n\
@@ -428,9 +476,17 @@ pub enum NoteError {
     #[error("<INVALID: {path}>")]
     InvalidLocalPath { path: String },
 
+    /// Remedy: create the missing file or correct the link path.
+    #[error("<NOT FOUND: {path}>")]
+    LocalLinkTargetNotFound { path: String },
+
     /// Remedy: check the file permission of the note file.
     #[error("Can not read file:\n\t {path:?}\n{source}")]
-    Read { path: PathBuf, source: io::Error },
+    Read {
+        path: PathBuf,
+        #[serde(serialize_with = "serialize_display")]
+        source: io::Error,
+    },
 
     /// Remedy: check ReStructuredText syntax.
     #[error("Can not parse reStructuredText input:\n{msg}")]
@@ -454,6 +510,7 @@ pub enum NoteError {
     )]
     Tera {
         #[from]
+        #[serde(serialize_with = "serialize_display")]
         source: tera::Error,
     },
 
@@ -472,21 +529,43 @@ pub enum NoteError {
     MarkupError { msg: String },
 
     #[error(transparent)]
-    File(#[from] FileError),
+    File(
+        #[from]
+        #[serde(serialize_with = "serialize_display")]
+        FileError,
+    ),
 
     #[error(transparent)]
-    Io(#[from] std::io::Error),
+    Io(
+        #[from]
+        #[serde(serialize_with = "serialize_display")]
+        std::io::Error,
+    ),
 
     #[error(transparent)]
-    ParseLanguageCode(#[from] LibCfgError),
+    ParseLanguageCode(
+        #[from]
+        #[serde(serialize_with = "serialize_display")]
+        LibCfgError,
+    ),
 
     #[error(transparent)]
     Utf8Conversion {
         #[from]
+        #[serde(serialize_with = "serialize_display")]
         source: core::str::Utf8Error,
     },
 }
 
+/// Helper serializing error types from other crates, which do not implement
+/// `Serialize`, as their error message.
+fn serialize_display<T: std::fmt::Display, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
 /// Macro to construct a `NoteError` from a `Tera::Error`.
 /// Filter errors (markup rendering failures) produce `NoteError::MarkupError`
 /// directly; all other Tera errors produce `NoteError::TeraTemplate`.
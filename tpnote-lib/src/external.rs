@@ -0,0 +1,246 @@
+//! Runs external commands that convert markup into HTML.
+//!
+//! The input is piped into the command's standard input and the standard
+//! output is taken as result. A command that does not terminate within the
+//! given timeout is killed. Under Unix, the command runs in its own process
+//! group: its subprocesses are killed too, also when they keep the pipes
+//! open after the command terminated.
+use crate::error::NoteError;
+use std::io::{Read, Write};
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Interval between two checks whether the child process has terminated.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Executes `command`, where `command[0]` is the program and the remaining
+/// items are its arguments. `input` is written to the command's standard
+/// input. Returns the command's standard output, when the command terminates
+/// successfully within `timeout`. Otherwise a `NoteError::RenderError`
+/// is returned, containing the command's standard error output, if any.
+pub(crate) fn run_external(
+    command: &[String],
+    timeout: Duration,
+    input: &str,
+) -> Result<String, NoteError> {
+    let Some((program, args)) = command.split_first() else {
+        return Err(NoteError::RenderError {
+            renderer: String::new(),
+            msg: "the external renderer command is empty".to_string(),
+        });
+    };
+    let error = |msg: String| NoteError::RenderError {
+        renderer: program.to_string(),
+        msg,
+    };
+
+    let mut command_builder = Command::new(program);
+    command_builder
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    command_builder.process_group(0);
+    let mut child = command_builder
+        .spawn()
+        .map_err(|e| error(format!("can not execute `{}`: {}", command.join(" "), e)))?;
+
+    // Feed and drain the pipes in separate threads, otherwise the child
+    // might block when one of the pipe buffers is full.
+    let stdin = child.stdin.take().map(|mut stdin| {
+        let input = input.to_string();
+        // A command may terminate without reading all its input, the
+        // resulting broken pipe is not an error.
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        })
+    });
+    let stdout = child.stdout.take().map(|mut stdout| {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stdout.read_to_end(&mut buf);
+            buf
+        })
+    });
+    let stderr = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            buf
+        })
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Ok(status),
+            Ok(None) if Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+            Ok(None) => {
+                kill(&mut child);
+                break Err(error(format!(
+                    "`{}` did not terminate within {} ms",
+                    command.join(" "),
+                    timeout.as_millis()
+                )));
+            }
+            Err(e) => {
+                kill(&mut child);
+                break Err(error(e.to_string()));
+            }
+        }
+    };
+
+    // A subprocess may keep the pipes open after the command terminated.
+    // Wait for it until the deadline, then kill it.
+    while stdin.as_ref().is_some_and(|h| !h.is_finished())
+        || stdout.as_ref().is_some_and(|h| !h.is_finished())
+        || stderr.as_ref().is_some_and(|h| !h.is_finished())
+    {
+        if Instant::now() >= deadline {
+            kill(&mut child);
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    // The pipes are closed now, the threads terminate.
+    if let Some(handle) = stdin {
+        let _ = handle.join();
+    }
+    let stdout = stdout
+        .and_then(|handle| handle.join().ok())
+        .unwrap_or_default();
+    let stderr = stderr
+        .and_then(|handle| handle.join().ok())
+        .unwrap_or_default();
+    let status = status?;
+
+    if !status.success() {
+        return Err(error(format!(
+            "`{}` exited with {}:\n{}",
+            command.join(" "),
+            status,
+            String::from_utf8_lossy(&stderr).trim_end()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Kills `child` and waits for it. Under Unix, all processes in the child's
+/// process group are killed too: they might keep its pipes open.
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: `kill()` has no memory safety preconditions. `pid` is
+        // the id of our child's process group, which we did not reap yet.
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::run_external;
+    use crate::error::NoteError;
+    use std::env::temp_dir;
+    use std::fs;
+    use std::process::Command;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_run_external() {
+        let timeout = Duration::from_secs(10);
+
+        // The input is echoed.
+        let command = vec!["cat".to_string()];
+        let result = run_external(&command, timeout, "<p>Hello</p>").unwrap();
+        assert_eq!(result, "<p>Hello</p>");
+
+        // Non-zero exit status.
+        let command = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "echo oops >&2; exit 3".to_string(),
+        ];
+        let result = run_external(&command, timeout, "");
+        assert!(matches!(
+            result,
+            Err(NoteError::RenderError { renderer, msg })
+                if renderer == "sh" && msg.ends_with("oops")
+        ));
+
+        // Unknown program.
+        let command = vec!["tpnote-no-such-program".to_string()];
+        let result = run_external(&command, timeout, "");
+        assert!(matches!(result, Err(NoteError::RenderError { .. })));
+
+        // Empty command.
+        let result = run_external(&[], timeout, "");
+        assert!(matches!(result, Err(NoteError::RenderError { .. })));
+    }
+
+    #[test]
+    fn test_run_external_timeout() {
+        let command = vec!["sleep".to_string(), "5".to_string()];
+        let result = run_external(&command, Duration::from_millis(100), "");
+        assert!(matches!(
+            result,
+            Err(NoteError::RenderError { msg, .. }) if msg.contains("did not terminate")
+        ));
+
+        // A subprocess inheriting the pipes is killed too.
+        let pid_file = temp_dir().join("tpnote_test_run_external.pid");
+        let _ = fs::remove_file(&pid_file);
+        let command = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            format!("sleep 30 & echo $! > '{}'; wait", pid_file.display()),
+        ];
+        let start = Instant::now();
+        let result = run_external(&command, Duration::from_millis(500), "");
+        assert!(matches!(
+            result,
+            Err(NoteError::RenderError { msg, .. }) if msg.contains("did not terminate")
+        ));
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        // `ps` prints nothing for a terminated process, `Z` for a zombie.
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let is_running = || {
+            let out = Command::new("ps")
+                .args(["-o", "stat=", "-p", pid.trim()])
+                .output()
+                .unwrap();
+            let stat = String::from_utf8_lossy(&out.stdout);
+            !stat.trim().is_empty() && !stat.trim_start().starts_with('Z')
+        };
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while is_running() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+        assert!(!is_running());
+        let _ = fs::remove_file(&pid_file);
+
+        // A subprocess keeping the pipes open after the command terminated
+        // is killed at the deadline. The command's output is kept.
+        let command = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "echo out; sleep 30 &".to_string(),
+        ];
+        let start = Instant::now();
+        let result = run_external(&command, Duration::from_millis(500), "");
+        assert_eq!(result.unwrap(), "out\n");
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -253,6 +253,13 @@ pub trait NotePath {
     /// A method that searches the directory in `self` for a Tp-Note
     /// file with the sort-tag `sort_tag`. It returns the filename.
     fn find_file_with_sort_tag(&self, sort_tag: &str) -> Option<PathBuf>;
+
+    /// Walks the directory `self` and all its subdirectories and collects
+    /// the paths of all Tp-Note files (cf. `has_tpnote_ext()`). Dot
+    /// directories and symbolic links to directories are not entered. The
+    /// result is sorted. Only a failure to read `self` is reported as error,
+    /// unreadable subdirectories are skipped with a warning.
+    fn find_tpnote_files_recursively(&self) -> Result<Vec<PathBuf>, std::io::Error>;
 }
 
 impl NotePath for Path {
@@ -453,6 +460,48 @@ impl NotePath for Path {
         }
         found
     }
+
+    fn find_tpnote_files_recursively(&self) -> Result<Vec<PathBuf>, std::io::Error> {
+        let mut found = Vec::new();
+        // Directories still to be visited.
+        let mut dirs = vec![self.to_path_buf()];
+        let mut is_top_dir = true;
+
+        while let Some(dir) = dirs.pop() {
+            let files = match dir.read_dir() {
+                Ok(files) => files,
+                Err(e) if is_top_dir => return Err(e),
+                Err(e) => {
+                    log::warn!("Skipping directory {:?}: {}", dir, e);
+                    continue;
+                }
+            };
+            is_top_dir = false;
+
+            for file in files.flatten() {
+                // `DirEntry::file_type()` does not follow symbolic links.
+                let ft = match file.file_type() {
+                    Ok(ft) => ft,
+                    _ => continue,
+                };
+                let path = file.path();
+                if ft.is_dir() {
+                    if !file
+                        .file_name()
+                        .to_str()
+                        .unwrap_or_default()
+                        .starts_with(FILENAME_DOTFILE_MARKER)
+                    {
+                        dirs.push(path);
+                    }
+                } else if ft.is_file() && path.has_tpnote_ext() {
+                    found.push(path);
+                }
+            }
+        }
+        found.sort();
+        Ok(found)
+    }
 }
 
 /// Some private helper functions related to note filenames.
@@ -963,6 +1012,40 @@ mod tests {
         assert!(!path.has_tpnote_ext());
     }
 
+    #[test]
+    fn test_find_tpnote_files_recursively() {
+        use crate::filename::NotePath;
+        use std::env::temp_dir;
+        use std::fs;
+
+        // Prepare test: a small note tree.
+        let notedir = temp_dir().join("find_tpnote_files_recursively");
+        let _ = fs::remove_dir_all(&notedir);
+        fs::create_dir_all(notedir.join("sub/subsub")).unwrap();
+        fs::create_dir_all(notedir.join(".hidden")).unwrap();
+        fs::write(notedir.join("01-note.md"), "").unwrap();
+        fs::write(notedir.join("image.png"), "").unwrap();
+        fs::write(notedir.join("sub/02-note.rst"), "").unwrap();
+        fs::write(notedir.join("sub/subsub/03-note.md"), "").unwrap();
+        fs::write(notedir.join(".hidden/04-note.md"), "").unwrap();
+
+        let result = notedir.find_tpnote_files_recursively().unwrap();
+        let expected = vec![
+            notedir.join("01-note.md"),
+            notedir.join("sub/02-note.rst"),
+            notedir.join("sub/subsub/03-note.md"),
+        ];
+        assert_eq!(result, expected);
+
+        // A missing top directory is an error.
+        assert!(
+            notedir
+                .join("does-not-exist")
+                .find_tpnote_files_recursively()
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_has_wellformed_filename() {
         use crate::filename::NotePath;
@@ -6,6 +6,9 @@ use crate::error::NoteError;
 use crate::config::FILENAME_DOTFILE_MARKER;
 use crate::config::LIB_CFG;
 use crate::config::Scheme;
+use crate::config::TMPL_HTML_VAR_DOC_BODY_HTML;
+use crate::config::TMPL_VAR_BODY;
+use crate::config::TMPL_VAR_DOC;
 use crate::config::TMPL_VAR_FM_;
 use crate::filename::NotePath;
 use crate::filename::NotePathBuf;
@@ -17,6 +20,7 @@ use crate::markup_language::MarkupLanguage;
 use crate::settings::SETTINGS;
 use parse_hyperlinks::iterator::MarkupLink;
 use parse_hyperlinks::parser::Link;
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
 use sanitize_filename_reader_friendly::sanitize;
 use std::borrow::Cow;
 use std::path::Path;
@@ -36,6 +40,21 @@ const TRUNC_LEN_MAX: usize = 200;
 #[cfg(test)]
 pub const TRUNC_LEN_MAX: usize = 10;
 
+/// Characters percent encoded by the `url_encode_filter()`. Slashes are
+/// kept, the input is a path.
+const URL_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'\'')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
 /// Tera function returning the current time as Unix epoch seconds (u64).
 /// Replaces tera v1's built-in `now()`.
 fn now_function(_kwargs: Kwargs, _state: &State) -> TeraResult<Value> {
@@ -106,6 +125,7 @@ pub static TERA: LazyLock<Tera> = LazyLock::new(|| {
     tera.register_filter("to_yaml", to_yaml_filter);
     tera.register_filter("trim_file_sort_tag", trim_file_sort_tag_filter);
     tera.register_filter("trunc", trunc_filter);
+    tera.register_filter("url_encode", url_encode_filter);
     tera.register_filter("date", date_filter);
     tera.register_function("now", now_function);
     tera
@@ -359,58 +379,63 @@ fn html_to_markup_filter(
 fn markup_to_html_filter(
     value: &Value,
     kwargs: Kwargs,
-    _state: &State,
+    state: &State,
 ) -> TeraResult<Value> {
     let input = value
         .as_str()
         .ok_or_else(|| tera::Error::message("Filter 'markup_to_html': value must be a string"))?;
 
-    let markup_language = if let Some(ext) = kwargs.get::<String>("extension")? {
-        let ml = MarkupLanguage::from(ext.as_str());
-        if ml.is_some() { ml } else { MarkupLanguage::Unkown }
-    } else {
-        MarkupLanguage::Unkown
-    };
+    let ext = kwargs.get::<String>("extension")?.unwrap_or_default();
+    let markup_language = MarkupLanguage::from(ext.as_str()).or(MarkupLanguage::Unkown);
+
+    // The note's body was rendered already when its table of contents was
+    // collected, see `Context::insert_raw_content_and_css()`.
+    if let Some(html_output) = body_rendition(state, &ext, input)? {
+        return Ok(Value::from(html_output));
+    }
 
     // Render the markup language. When the renderer feature is enabled,
-    // catch panics (e.g. unsupported markup elements) and Err() returns,
-    // mapping each to its own NoteError variant before propagating as a
-    // Tera error.  Without the renderer feature neither panics nor Err()
-    // returns can occur, so a plain call suffices.
+    // panics (e.g. unsupported markup elements) are reported as
+    // `NoteError::RenderPanic`, other errors are wrapped into a
+    // `NoteError::RenderError`.
+    let html_output = markup_language.render_for_catch_unwind(&ext, input);
     #[cfg(feature = "renderer")]
-    let html_output = {
-        let renderer = format!("{:?}", markup_language);
-        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            markup_language.render(input)
-        })) {
-            Ok(Ok(html)) => html,
-            Ok(Err(e)) => {
-                return Err(tera::Error::message(format!(
-                    "markup_to_html: {}",
-                    NoteError::RenderError { renderer, msg: e.to_string() }
-                )))
-            }
-            Err(payload) => {
-                let msg = payload
-                    .downcast_ref::<&str>()
-                    .map(|s| s.to_string())
-                    .or_else(|| payload.downcast_ref::<String>().cloned())
-                    .unwrap_or_else(|| "unknown".to_string());
-                return Err(tera::Error::message(format!(
-                    "markup_to_html: {}",
-                    NoteError::RenderPanic { renderer, msg }
-                )))
-            }
-        }
-    };
-    #[cfg(not(feature = "renderer"))]
-    let html_output = markup_language
-        .render(input)
-        .map_err(|e| tera::Error::message(e.to_string()))?;
+    let html_output = html_output.map_err(|e| match e {
+        // External renderers report their own `RenderError`.
+        NoteError::RenderError { .. } | NoteError::RenderPanic { .. } => e,
+        e => NoteError::RenderError {
+            renderer: format!("{:?}", markup_language),
+            msg: e.to_string(),
+        },
+    });
+    let html_output =
+        html_output.map_err(|e| tera::Error::message(format!("markup_to_html: {}", e)))?;
 
     Ok(Value::from(html_output))
 }
 
+/// Helper function for `markup_to_html_filter()`: returns the rendition
+/// `TMPL_HTML_VAR_DOC_BODY_HTML`, if `input` is the note's body and the
+/// rendition was made for the same `extension`.
+fn body_rendition(state: &State, extension: &str, input: &str) -> TeraResult<Option<String>> {
+    let Some(rendition) = state.get::<Value>(TMPL_HTML_VAR_DOC_BODY_HTML)? else {
+        return Ok(None);
+    };
+    let body = state.get::<Value>(TMPL_VAR_DOC)?;
+    let is_body = body
+        .as_ref()
+        .and_then(|doc| doc.get_from_path(TMPL_VAR_BODY))
+        .and_then(Value::as_str)
+        == Some(input);
+    let is_same_extension =
+        rendition.get_from_path("extension").and_then(Value::as_str) == Some(extension);
+    Ok(rendition
+        .get_from_path("html")
+        .and_then(Value::as_str)
+        .filter(|_| is_body && is_same_extension)
+        .map(str::to_string))
+}
+
 /// Adds a new filter to Tera templates:
 /// `sanit` or `sanit()` sanitizes a string so that it can be used to
 /// assemble filenames or paths. In addition, `sanit(alpha=true)` prepends
@@ -568,6 +593,25 @@ fn trunc_filter(
     Ok(Value::from(short.to_owned()))
 }
 
+/// A Tera filter that percent encodes the input path, so that it can be
+/// inserted into a link destination, e.g. `my note#1.md` becomes
+/// `my%20note%231.md`. Slashes are not encoded.
+/// The input type must be `Value::String` and the output type is
+/// `Value::String()`
+fn url_encode_filter(
+    value: &Value,
+    _kwargs: Kwargs,
+    _state: &State,
+) -> TeraResult<Value> {
+    let input = value
+        .as_str()
+        .ok_or_else(|| tera::Error::message("Filter 'url_encode': value must be a string"))?;
+
+    Ok(Value::from(
+        utf8_percent_encode(input, URL_ENCODE_SET).to_string(),
+    ))
+}
+
 /// A Tera filter that returns the first line or the first sentence of the input
 /// stream.
 /// The input type must be `Value::String` and the output type is
@@ -1586,7 +1630,7 @@ mod tests {
         let input = Value::from("# Title\nHello World");
 
         #[cfg(feature = "renderer")]
-        let expected = "<h1>Title</h1>\n<p>Hello World</p>\n".to_string();
+        let expected = "<h1 id=\"title\">Title</h1>\n<p>Hello World</p>\n".to_string();
         #[cfg(not(feature = "renderer"))]
         let expected = "".to_string();
 
@@ -1618,6 +1662,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_markup_to_html_filter_body_rendition() {
+        let mut ctx = tera::Context::new();
+        ctx.insert(TMPL_VAR_DOC, &BTreeMap::from([(TMPL_VAR_BODY, "# Title")]));
+        ctx.insert(
+            TMPL_HTML_VAR_DOC_BODY_HTML,
+            &BTreeMap::from([("extension", "md"), ("html", "<p>rendered</p>")]),
+        );
+        let st = State::new(&ctx);
+
+        // The note's body is not rendered again.
+        let input = Value::from("# Title");
+        let kwargs = || Kwargs::from([("extension", Value::from("md"))]);
+        assert_eq!(
+            markup_to_html_filter(&input, kwargs(), &st).unwrap(),
+            Value::from("<p>rendered</p>")
+        );
+
+        // Other input, or another markup language, is rendered.
+        let input = Value::from("other");
+        assert_ne!(
+            markup_to_html_filter(&input, kwargs(), &st).unwrap(),
+            Value::from("<p>rendered</p>")
+        );
+        let input = Value::from("# Title");
+        assert_ne!(
+            markup_to_html_filter(
+                &input,
+                Kwargs::from([("extension", Value::from("txtnote"))]),
+                &st
+            )
+            .unwrap(),
+            Value::from("<p>rendered</p>")
+        );
+    }
+
     /// RST renderer panics on unsupported elements (e.g. unresolved substitution
     /// references); verify that `markup_to_html_filter` catches the panic and
     /// returns a `RenderPanic` error instead of unwinding the caller.
@@ -2097,6 +2177,16 @@ Some more text."#;
         assert_eq!("Jens Getr", output.as_str().unwrap());
     }
 
+    #[test]
+    fn test_url_encode_filter() {
+        let ctx = tera::Context::new();
+        let st = State::new(&ctx);
+
+        let input = "dir/my note#1?%.md";
+        let output = url_encode_filter(&Value::from(input), Kwargs::default(), &st).unwrap();
+        assert_eq!("dir/my%20note%231%3F%25.md", output.as_str().unwrap());
+    }
+
     #[test]
     fn test_first_html_heading() {
         // Test case: No heading in the HTML
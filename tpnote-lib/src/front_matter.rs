@@ -2,30 +2,226 @@
 //! In this documentation, the terms "YAML header", "header" and "front matter"
 //! are used as synonyms for the note's meta data block at the beginning
 //! of the text file. Technically this is a wrapper around a `serde_json::Map`.
+//! The front matter is usually written in YAML. Alternatively, TOML front
+//! matter delimited by `+++` is understood, as used by Hugo or Zola, and
+//! the keyword lines `#+KEY: value` and the property drawer of Org-mode files.
+use crate::content::Content;
 use crate::error::FRONT_MATTER_ERROR_MAX_LINES;
 use crate::error::NoteError;
+use serde::Deserialize;
+use serde::Serialize;
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::str;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize)]
 /// Represents the front matter of the note. This is a newtype
-/// for `serde_json::Map<String, serde_json::Value>`.
+/// for `serde_json::Map<String, serde_json::Value>`. It serializes
+/// as the wrapped map.
 pub struct FrontMatter(pub serde_json::Map<String, serde_json::Value>);
 
+/// The syntax the note's front matter is written in.
+/// In the configuration file, only `yaml` and `toml` are accepted, see
+/// `tmpl.fm_format`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrontMatterFormat {
+    /// YAML front matter delimited by `---`.
+    #[default]
+    Yaml,
+    /// TOML front matter delimited by `+++`.
+    Toml,
+    /// Org-mode keyword lines `#+KEY: value` and/or a property drawer
+    /// `:PROPERTIES:` ... `:END:` at the beginning of the content.
+    #[serde(skip_deserializing)]
+    Org,
+}
+
+/// Start and end line of an Org-mode property drawer.
+pub(crate) const ORG_PROPERTY_DRAWER: (&str, &str) = (":PROPERTIES:", ":END:");
+
+/// Returns the lowercase key and the trimmed value of the Org-mode keyword
+/// line `#+KEY: value`. Block delimiters like `#+BEGIN_SRC:` are not
+/// keywords.
+pub(crate) fn org_keyword(line: &str) -> Option<(String, &str)> {
+    let (key, value) = line.strip_prefix("#+")?.split_once(':')?;
+    let key = key.to_lowercase();
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        || key.starts_with("begin_")
+        || key.starts_with("end_")
+        || !(value.is_empty() || value.starts_with([' ', '\t']))
+    {
+        return None;
+    }
+    Some((key, value.trim()))
+}
+
+/// Returns the lowercase key and the trimmed value of the Org-mode property
+/// line `:KEY: value`.
+fn org_property(line: &str) -> Option<(String, &str)> {
+    let (key, value) = line.strip_prefix(':')?.split_once(':')?;
+    // `:KEY+:` appends to an inherited value.
+    let key = key.trim_end_matches('+');
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((key.to_lowercase(), value.trim()))
+}
+
+/// TOML datetimes deserialize into a map with this single key.
+const TOML_DATETIME_KEY: &str = "$__toml_private_datetime";
+
+impl FrontMatter {
+    /// Deserializes the front matter of `content`. Depending on
+    /// `content.header_format()`, the header is parsed as YAML, TOML or
+    /// Org-mode keywords.
+    /// An empty header leads to an empty map; no error.
+    ///
+    /// ```rust
+    /// use tpnote_lib::content::Content;
+    /// use tpnote_lib::content::ContentString;
+    /// use tpnote_lib::front_matter::FrontMatter;
+    /// use serde_json::json;
+    ///
+    /// let raw = "+++\ntitle = \"My day\"\ndate = 2020-04-21\n+++\nBody";
+    /// let content = ContentString::from_string(raw.to_string(), "doc".to_string());
+    /// let fm = FrontMatter::try_from_content(&content).unwrap();
+    /// assert_eq!(fm.get("title"), Some(&json!("My day")));
+    /// assert_eq!(fm.get("date"), Some(&json!("2020-04-21")));
+    ///
+    /// let raw = "#+TITLE: My day\n:PROPERTIES:\n:LANG: en\n:END:\nBody";
+    /// let content = ContentString::from_org_string(raw.to_string(), "doc".to_string());
+    /// let fm = FrontMatter::try_from_content(&content).unwrap();
+    /// assert_eq!(fm.get("title"), Some(&json!("My day")));
+    /// assert_eq!(fm.get("lang"), Some(&json!("en")));
+    /// ```
+    pub fn try_from_content(content: &impl Content) -> Result<FrontMatter, NoteError> {
+        match content.header_format() {
+            FrontMatterFormat::Yaml => FrontMatter::try_from(content.header()),
+            FrontMatterFormat::Toml => FrontMatter::try_from_toml(content.header()),
+            FrontMatterFormat::Org => Ok(FrontMatter::from_org(content.header())),
+        }
+    }
+
+    /// Helper function collecting the keyword lines and the properties of an
+    /// Org-mode front matter. All values are strings. Keys occurring more
+    /// than once collect their values in an array. Other lines are ignored.
+    fn from_org(header: &str) -> FrontMatter {
+        let mut map = serde_json::Map::new();
+        let mut in_drawer = false;
+        for line in header.lines().map(str::trim) {
+            let entry = if line.eq_ignore_ascii_case(ORG_PROPERTY_DRAWER.0) {
+                in_drawer = true;
+                None
+            } else if line.eq_ignore_ascii_case(ORG_PROPERTY_DRAWER.1) {
+                in_drawer = false;
+                None
+            } else if in_drawer {
+                org_property(line)
+            } else {
+                org_keyword(line)
+            };
+            let Some((key, value)) = entry else {
+                continue;
+            };
+            let value = serde_json::Value::String(value.to_string());
+            match map.get_mut(&key) {
+                Some(serde_json::Value::Array(values)) => values.push(value),
+                Some(first) => *first = serde_json::Value::Array(vec![first.take(), value]),
+                None => {
+                    map.insert(key, value);
+                }
+            }
+        }
+        FrontMatter(map)
+    }
+
+    /// Helper function deserializing a TOML front matter. TOML datetimes
+    /// become strings, just like YAML dates do.
+    fn try_from_toml(header: &str) -> Result<FrontMatter, NoteError> {
+        let map: serde_json::Map<String, serde_json::Value> =
+            toml::from_str(header).map_err(|e| NoteError::InvalidFrontMatterToml {
+                front_matter: numbered_lines(header),
+                source_error: Box::new(e),
+            })?;
+        let mut map = serde_json::Value::Object(map);
+        replace_toml_datetimes(&mut map);
+        let serde_json::Value::Object(map) = map else {
+            unreachable!()
+        };
+
+        Ok(FrontMatter(map))
+    }
+}
+
+/// Helper function replacing all TOML datetimes in `val` by their string
+/// representation.
+fn replace_toml_datetimes(val: &mut serde_json::Value) {
+    match val {
+        serde_json::Value::Object(map) => {
+            if map.len() == 1
+                && let Some(serde_json::Value::String(s)) = map.get(TOML_DATETIME_KEY)
+            {
+                *val = serde_json::Value::String(s.to_owned());
+            } else {
+                map.values_mut().for_each(replace_toml_datetimes);
+            }
+        }
+        serde_json::Value::Array(a) => a.iter_mut().for_each(replace_toml_datetimes),
+        _ => {}
+    }
+}
+
+/// Helper function converting the YAML front matter `header` into TOML.
+/// Unlike `FrontMatter`, this preserves the order of the keys. As TOML
+/// has no null value, null values are omitted. Returns `None` if `header`
+/// has no TOML representation, e.g. because of non-string keys.
+pub(crate) fn yaml_to_toml(header: &str) -> Option<String> {
+    /// Helper function removing all null values.
+    fn remove_nulls(val: &mut serde_yaml::Value) {
+        match val {
+            serde_yaml::Value::Mapping(map) => {
+                map.retain(|_, v| !v.is_null());
+                map.values_mut().for_each(remove_nulls);
+            }
+            serde_yaml::Value::Sequence(a) => {
+                a.retain(|v| !v.is_null());
+                a.iter_mut().for_each(remove_nulls);
+            }
+            _ => {}
+        }
+    }
+
+    let mut val: serde_yaml::Value = serde_yaml::from_str(header).ok()?;
+    if !val.is_mapping() {
+        return None;
+    }
+    remove_nulls(&mut val);
+    toml::to_string(&val).ok()
+}
+
+/// Helper function prepending line numbers to the erroneous front matter.
+fn numbered_lines(header: &str) -> String {
+    header
+        .lines()
+        .enumerate()
+        .map(|(n, s)| format!("{:03}: {}\n", n + 1, s))
+        .take(FRONT_MATTER_ERROR_MAX_LINES)
+        .collect::<String>()
+}
+
 impl TryFrom<&str> for FrontMatter {
     type Error = NoteError;
-    /// Helper function deserializing the front-matter of the note file.
+    /// Helper function deserializing the YAML front-matter of the note file.
     /// An empty header leads to an empty map; no error.
+    /// For TOML front matter, see `FrontMatter::try_from_content()`.
     fn try_from(header: &str) -> Result<FrontMatter, NoteError> {
         let map: serde_json::Map<String, serde_json::Value> = serde_yaml::from_str(header)
             .map_err(|e| NoteError::InvalidFrontMatterYaml {
-                front_matter: header
-                    .lines()
-                    .enumerate()
-                    .map(|(n, s)| format!("{:03}: {}\n", n + 1, s))
-                    .take(FRONT_MATTER_ERROR_MAX_LINES)
-                    .collect::<String>(),
+                front_matter: numbered_lines(header),
                 source_error: e,
             })?;
         let fm = FrontMatter(map);
@@ -149,6 +345,7 @@ mod tests {
     fn test_try_from_content() {
         use crate::content::Content;
         use crate::content::ContentString;
+        use crate::error::NoteError;
         use serde_json::json;
 
         // Create existing note.
@@ -160,6 +357,59 @@ mod tests {
         let front_matter = FrontMatter::try_from(content.header()).unwrap();
         assert_eq!(front_matter.get("title"), Some(&json!("My day")));
         assert_eq!(front_matter.get("subtitle"), Some(&json!("Note")));
+
+        // TOML front matter.
+        let raw = "\u{feff}+++\ntitle = \"My day\"\nsubtitle = \"Note\"\n\
+                   date = 2020-04-21T10:00:00Z\ntags = [\"a\", \"b\"]\n\
+                   [extra]\nflag = true\n+++\nBody";
+        let content = ContentString::from_string(raw.to_string(), "doc".to_string());
+        let front_matter = FrontMatter::try_from_content(&content).unwrap();
+        assert_eq!(front_matter.get("title"), Some(&json!("My day")));
+        assert_eq!(front_matter.get("subtitle"), Some(&json!("Note")));
+        assert_eq!(
+            front_matter.get("date"),
+            Some(&json!("2020-04-21T10:00:00Z"))
+        );
+        assert_eq!(front_matter.get("tags"), Some(&json!(["a", "b"])));
+        assert_eq!(front_matter.get("extra"), Some(&json!({"flag": true})));
+
+        // Invalid TOML front matter.
+        let raw = "+++\ntitle: My day\n+++\nBody";
+        let content = ContentString::from_string(raw.to_string(), "doc".to_string());
+        assert!(matches!(
+            FrontMatter::try_from_content(&content),
+            Err(NoteError::InvalidFrontMatterToml { .. })
+        ));
+
+        // Org-mode front matter.
+        let raw = "\u{feff}#+TITLE: My day\n#+subtitle:   Note\n#+AUTHOR: Me\n\
+                   #+AUTHOR: You\n:PROPERTIES:\n:SORT_TAG: 20200421-\n:END:\n\
+                   #+BEGIN_SRC rust\n#+END_SRC\n#+DATE: Body";
+        let content = ContentString::from_org_string(raw.to_string(), "doc".to_string());
+        let front_matter = FrontMatter::try_from_content(&content).unwrap();
+        assert_eq!(front_matter.get("title"), Some(&json!("My day")));
+        assert_eq!(front_matter.get("subtitle"), Some(&json!("Note")));
+        assert_eq!(front_matter.get("author"), Some(&json!(["Me", "You"])));
+        assert_eq!(front_matter.get("sort_tag"), Some(&json!("20200421-")));
+        assert_eq!(front_matter.get("date"), None);
+        assert_eq!(front_matter.len(), 4);
+    }
+
+    #[test]
+    fn test_yaml_to_toml() {
+        use super::yaml_to_toml;
+
+        let header = "title:        My day\nsubtitle:     Note\nauthor:       ~\n\
+                      date:         2020-04-21\ntags:\n- a\n- b\nextra:\n  flag: true";
+        assert_eq!(
+            yaml_to_toml(header).unwrap(),
+            "title = \"My day\"\nsubtitle = \"Note\"\ndate = \"2020-04-21\"\n\
+             tags = [\"a\", \"b\"]\n\n[extra]\nflag = true\n"
+        );
+
+        // TOML keys must be strings.
+        assert_eq!(yaml_to_toml("[1, 2]: a"), None);
+        assert_eq!(yaml_to_toml("- a\n- b"), None);
     }
 
     #[test]
@@ -1,34 +1,226 @@
 //! Syntax highlighting for (inline) source code blocks in Markdown input.
-
-use pulldown_cmark::{CodeBlockKind, Event, Tag, TagEnd};
+//! Also renders GitHub-style alerts and `:::` containers as admonitions and
+//! diagram code blocks as inline SVG.
+
+use crate::config::LIB_CFG;
+use crate::error::LibCfgError;
+use crate::error::NoteError;
+use crate::external::run_external;
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use parking_lot::{Mutex, RwLock};
+use pulldown_cmark::{BlockQuoteKind, CodeBlockKind, Event, Tag, TagEnd};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::time::Duration;
 use syntect::highlighting::ThemeSet;
 use syntect::html::css_for_theme_with_class_style;
 use syntect::html::{ClassStyle, ClassedHTMLGenerator};
 use syntect::parsing::SyntaxSet;
 use syntect::util::LinesWithEndings;
 
-/// Get the viewer syntax highlighting CSS configuration.
-pub(crate) fn get_highlighting_css(theme_name: &str) -> String {
-    let ts = ThemeSet::load_defaults();
+/// The built-in syntax definitions and themes shared by all renditions.
+/// Loading them is expensive, so this is done only once, on first use.
+pub static HIGHLIGHT_REGISTRY: LazyLock<Arc<HighlightRegistry>> =
+    LazyLock::new(|| Arc::new(HighlightRegistry::new()));
+
+/// Registries extended with the syntax definitions and themes of a user
+/// directory, see `HighlightRegistry::get()`.
+static USER_HIGHLIGHT_REGISTRIES: LazyLock<RwLock<HashMap<PathBuf, Arc<HighlightRegistry>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Maximum number of diagrams kept in `DIAGRAM_CACHE`.
+const DIAGRAM_CACHE_CAPACITY: usize = 64;
+
+/// SVG renditions of diagrams, the most recently used first, see
+/// `render_diagram()`. When full, the least recently used diagram is
+/// dropped.
+static DIAGRAM_CACHE: LazyLock<Mutex<VecDeque<CachedDiagram>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(DIAGRAM_CACHE_CAPACITY)));
+
+/// A `DIAGRAM_CACHE` entry: the parameters of `render_diagram()` and its
+/// result.
+struct CachedDiagram {
+    lang: String,
+    command: Vec<String>,
+    code: String,
+    html: String,
+}
+
+/// A registry of syntax definitions and highlighting themes.
+#[derive(Debug)]
+pub struct HighlightRegistry {
+    syntax_set: SyntaxSet,
+    theme_set: ThemeSet,
+}
+
+impl HighlightRegistry {
+    /// Constructor loading _syntect_'s default syntax definitions and themes.
+    /// Prefer the shared instance `HIGHLIGHT_REGISTRY`.
+    pub fn new() -> Self {
+        Self {
+            syntax_set: SyntaxSet::load_defaults_newlines(),
+            theme_set: ThemeSet::load_defaults(),
+        }
+    }
+
+    /// Constructor loading _syntect_'s default syntax definitions and themes
+    /// and, in addition, all `.sublime-syntax` and `.tmTheme` files found in
+    /// `dir` and its subdirectories. A theme's name is its filename without
+    /// extension. User definitions take precedence over the defaults.
+    /// Prefer the cached instances returned by `HighlightRegistry::get()`.
+    pub fn from_dir(dir: &Path) -> Result<Self, LibCfgError> {
+        let err = |e: syntect::LoadingError| LibCfgError::HighlightingDir {
+            path: dir.to_string_lossy().into_owned(),
+            error: e.to_string(),
+        };
+        if !dir.is_dir() {
+            return Err(LibCfgError::HighlightingDir {
+                path: dir.to_string_lossy().into_owned(),
+                error: "not a directory".to_string(),
+            });
+        }
+
+        let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+        builder.add_from_folder(dir, true).map_err(err)?;
+        let mut theme_set = ThemeSet::load_defaults();
+        theme_set.add_from_folder(dir).map_err(err)?;
+        log::debug!("Loaded syntax definitions and themes from {:?}", dir);
 
-    ts.themes
-        .get(theme_name)
-        .and_then(|theme| {
-            css_for_theme_with_class_style(theme, syntect::html::ClassStyle::Spaced).ok()
+        Ok(Self {
+            syntax_set: builder.build(),
+            theme_set,
         })
-        .unwrap_or_default()
+    }
+
+    /// Returns the shared registry with the syntax definitions and themes
+    /// of the directory `dir` (cf. `HighlightRegistry::from_dir()`). The
+    /// result is cached. If `dir` is empty, `HIGHLIGHT_REGISTRY` with the
+    /// built-in defaults is returned.
+    pub fn get(dir: &Path) -> Result<Arc<Self>, LibCfgError> {
+        if dir.as_os_str().is_empty() {
+            return Ok(HIGHLIGHT_REGISTRY.clone());
+        }
+        if let Some(registry) = USER_HIGHLIGHT_REGISTRIES.read().get(dir) {
+            return Ok(registry.clone());
+        }
+        let registry = Arc::new(Self::from_dir(dir)?);
+        USER_HIGHLIGHT_REGISTRIES
+            .write()
+            .insert(dir.to_path_buf(), registry.clone());
+        Ok(registry)
+    }
+
+    /// Returns the registry for the directory configured in
+    /// `tmpl_html.highlighting_dir`. Falls back to the built-in defaults,
+    /// when this directory can not be loaded.
+    pub fn configured() -> Arc<Self> {
+        let dir = PathBuf::from(&LIB_CFG.read_recursive().tmpl_html.highlighting_dir);
+        Self::get(&dir).unwrap_or_else(|e| {
+            log::warn!("{}", e);
+            HIGHLIGHT_REGISTRY.clone()
+        })
+    }
+
+    /// The available syntax definitions.
+    pub fn syntax_set(&self) -> &SyntaxSet {
+        &self.syntax_set
+    }
+
+    /// The available highlighting themes.
+    pub fn theme_set(&self) -> &ThemeSet {
+        &self.theme_set
+    }
+
+    /// Returns the highlighting CSS of the theme `theme_name`, or the empty
+    /// string if there is no such theme.
+    pub fn highlighting_css(&self, theme_name: &str) -> String {
+        self.theme_set
+            .themes
+            .get(theme_name)
+            .and_then(|theme| css_for_theme_with_class_style(theme, ClassStyle::Spaced).ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns the HTML rendition of the source code block `code` written in
+    /// the language `lang`. Unknown languages are rendered as plain text.
+    ///
+    /// ```rust
+    /// use tpnote_lib::highlight::HIGHLIGHT_REGISTRY;
+    ///
+    /// let html = HIGHLIGHT_REGISTRY.highlight_code("rust", "fn main() {}\n");
+    /// assert!(html.starts_with(
+    ///     "<pre><code class=\"language-rust\"><span class=\"source rust\">"));
+    /// ```
+    pub fn highlight_code(&self, lang: &str, code: &str) -> String {
+        let mut html = String::with_capacity(code.len() + code.len() * 3 / 2 + 20);
+
+        let sr = match self.syntax_set.find_syntax_by_token(lang) {
+            Some(sr) => {
+                html.push_str("<pre><code class=\"language-");
+                html.push_str(lang);
+                html.push_str("\">");
+                sr
+            }
+            None => {
+                log::debug!("renderer: no syntax definition found for: `{}`", lang);
+                html.push_str("<pre><code>");
+                self.syntax_set.find_syntax_plain_text()
+            }
+        };
+        let mut html_generator =
+            ClassedHTMLGenerator::new_with_class_style(sr, &self.syntax_set, ClassStyle::Spaced);
+        for line in LinesWithEndings::from(code) {
+            html_generator
+                .parse_html_for_line_which_includes_newline(line)
+                .unwrap_or_default();
+        }
+        html.push_str(html_generator.finalize().as_str());
+
+        html.push_str("</code></pre>");
+        html
+    }
+}
+
+impl Default for HighlightRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// A wrapper for a `pulldown_cmark` event iterator.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct SyntaxPreprocessor<'a, I: Iterator<Item = Event<'a>>> {
     parent: I,
+    /// Syntax definitions used to highlight code blocks.
+    registry: Arc<HighlightRegistry>,
+    /// Commands rendering code blocks into SVG, indexed by fence language
+    /// (cf. `tmpl_html.diagram_renderers`).
+    diagram_renderers: Vec<(String, Vec<String>)>,
+    /// Time after which a diagram renderer is killed.
+    diagram_timeout: Duration,
 }
 
 /// Constructor.
 impl<'a, I: Iterator<Item = Event<'a>>> SyntaxPreprocessor<'a, I> {
+    /// Highlights with the syntax definitions configured in
+    /// `tmpl_html.highlighting_dir` (cf. `HighlightRegistry::configured()`).
     pub fn new(parent: I) -> Self {
-        Self { parent }
+        Self::with_registry(parent, HighlightRegistry::configured())
+    }
+
+    /// Highlights with the syntax definitions in `registry`.
+    pub fn with_registry(parent: I, registry: Arc<HighlightRegistry>) -> Self {
+        let lib_cfg = LIB_CFG.read_recursive();
+        Self {
+            parent,
+            registry,
+            diagram_renderers: lib_cfg.tmpl_html.diagram_renderers.clone(),
+            diagram_timeout: Duration::from_secs(lib_cfg.tmpl_html.diagram_renderer_timeout),
+        }
     }
 }
 
@@ -86,45 +278,444 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for SyntaxPreprocessor<'a, I> {
             ));
         }
 
-        let mut html = String::with_capacity(code.len() + code.len() * 3 / 2 + 20);
+        if let Some((_, command)) = self
+            .diagram_renderers
+            .iter()
+            .find(|(l, _)| l == lang.as_ref())
+        {
+            return Some(Event::Html(
+                render_diagram(lang.as_ref(), command, self.diagram_timeout, &code).into(),
+            ));
+        }
 
-        // Use default syntax styling.
-        let ss = SyntaxSet::load_defaults_newlines();
-        let sr = match ss.find_syntax_by_token(lang.as_ref()) {
-            Some(sr) => {
-                html.push_str("<pre><code class=\"language-");
-                html.push_str(lang.as_ref());
-                html.push_str("\">");
-                sr
+        let html = self.registry.highlight_code(lang.as_ref(), &code);
+
+        Some(Event::Html(html.into()))
+    }
+}
+
+/// Pipes the diagram source `code` written in the fence language `lang` into
+/// `command` and returns the resulting SVG image, wrapped in
+/// `<div class="diagram diagram-{lang}">`. Successful renditions are cached,
+/// so the command runs only once per diagram, as long as the diagram is
+/// among the `DIAGRAM_CACHE_CAPACITY` most recently used ones. When the
+/// command fails, an admonition box with the error message is returned
+/// instead.
+pub fn render_diagram(lang: &str, command: &[String], timeout: Duration, code: &str) -> String {
+    {
+        let mut cache = DIAGRAM_CACHE.lock();
+        if let Some(i) = cache
+            .iter()
+            .position(|d| d.lang == lang && d.command == command && d.code == code)
+        {
+            // Move the hit to the front.
+            let diagram = cache.remove(i).unwrap();
+            let html = diagram.html.clone();
+            cache.push_front(diagram);
+            return html;
+        }
+    }
+
+    let svg = run_external(command, timeout, code).and_then(|output| {
+        // Drop the XML declaration and doctype preceding the image.
+        output
+            .find("<svg")
+            .map(|start| output[start..].trim_end().to_string())
+            .ok_or_else(|| NoteError::RenderError {
+                renderer: command.join(" "),
+                msg: "the output contains no SVG image".to_string(),
+            })
+    });
+
+    match svg {
+        Ok(svg) => {
+            let html = format!(
+                "<div class=\"diagram diagram-{}\">\n{}\n</div>\n",
+                encode_double_quoted_attribute(lang),
+                svg
+            );
+            let mut cache = DIAGRAM_CACHE.lock();
+            cache.push_front(CachedDiagram {
+                lang: lang.to_string(),
+                command: command.to_vec(),
+                code: code.to_string(),
+                html: html.clone(),
+            });
+            cache.truncate(DIAGRAM_CACHE_CAPACITY);
+            html
+        }
+        Err(e) => {
+            log::warn!("{}", e);
+            format!(
+                "{}<pre>{}</pre>\n</div>\n",
+                admonition_start("error", "Diagram error"),
+                encode_text(&e.to_string())
+            )
+        }
+    }
+}
+
+/// Prefix of the HTML comments `mark_containers()` replaces `:::` container
+/// marker lines with.
+const CONTAINER_COMMENT: &str = "<!--tpnote-container";
+
+/// A wrapper for a `pulldown_cmark` event iterator, rendering admonitions.
+///
+/// GitHub-style alerts, e.g. `> [!NOTE]` or `> [!WARNING]`, and fenced
+/// containers, e.g. `:::note` ... `:::`, become
+/// `<div class="admonition note">` blocks with a
+/// `<p class="admonition-title">` heading. A container's title defaults to
+/// its capitalized name and can be given after the name:
+/// `:::warning Mind the gap`. The container markers must have been replaced
+/// with `mark_containers()` before parsing.
+#[derive(Debug)]
+pub struct AdmonitionPreprocessor<'a, I: Iterator<Item = Event<'a>>> {
+    parent: I,
+    /// Events waiting to be returned before the next `parent` event.
+    buffer: VecDeque<Event<'a>>,
+    /// Number of `:::` containers not closed yet.
+    open_containers: usize,
+}
+
+/// Constructor.
+impl<'a, I: Iterator<Item = Event<'a>>> AdmonitionPreprocessor<'a, I> {
+    pub fn new(parent: I) -> Self {
+        Self {
+            parent,
+            buffer: VecDeque::new(),
+            open_containers: 0,
+        }
+    }
+}
+
+/// Implement `Iterator` for wrapper `AdmonitionPreprocessor`.
+impl<'a, I: Iterator<Item = Event<'a>>> Iterator for AdmonitionPreprocessor<'a, I> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.buffer.pop_front() {
+            return Some(event);
+        }
+
+        let Some(event) = self.parent.next() else {
+            // Close containers left open at the end of the document.
+            if self.open_containers > 0 {
+                self.open_containers -= 1;
+                return Some(Event::Html("</div>\n".into()));
             }
-            None => {
-                log::debug!(
-                    "renderer: no syntax definition found for: `{}`",
-                    lang.as_ref()
-                );
-                html.push_str("<pre><code>");
-                ss.find_syntax_plain_text()
+            return None;
+        };
+
+        match event {
+            Event::Start(Tag::BlockQuote(Some(kind))) => {
+                let (name, title) = match kind {
+                    BlockQuoteKind::Note => ("note", "Note"),
+                    BlockQuoteKind::Tip => ("tip", "Tip"),
+                    BlockQuoteKind::Important => ("important", "Important"),
+                    BlockQuoteKind::Warning => ("warning", "Warning"),
+                    BlockQuoteKind::Caution => ("caution", "Caution"),
+                };
+                Some(Event::Html(admonition_start(name, title).into()))
             }
+            Event::End(TagEnd::BlockQuote(Some(_))) => Some(Event::Html("</div>\n".into())),
+            Event::Start(Tag::HtmlBlock) => {
+                // A marker comment is an HTML block of its own.
+                let html = self.parent.next();
+                let end = self.parent.next();
+                let marker = match (&html, &end) {
+                    (Some(Event::Html(html)), Some(Event::End(TagEnd::HtmlBlock))) => html
+                        .trim_end()
+                        .strip_prefix(CONTAINER_COMMENT)
+                        .and_then(|html| html.strip_suffix("-->"))
+                        .and_then(ContainerMarker::parse),
+                    _ => None,
+                };
+                match marker {
+                    Some(ContainerMarker::Open { name, title }) => {
+                        self.open_containers += 1;
+                        let title = title.unwrap_or_else(|| capitalize(&name));
+                        Some(Event::Html(admonition_start(&name, &title).into()))
+                    }
+                    Some(ContainerMarker::Close) if self.open_containers > 0 => {
+                        self.open_containers -= 1;
+                        Some(Event::Html("</div>\n".into()))
+                    }
+                    _ => {
+                        self.buffer.extend(html.into_iter().chain(end));
+                        Some(Event::Start(Tag::HtmlBlock))
+                    }
+                }
+            }
+            other => Some(other),
+        }
+    }
+}
+
+/// Replaces the `:::` container marker lines outside of fenced code blocks
+/// with HTML comments, which `AdmonitionPreprocessor` renders as admonitions.
+/// Only markers starting in the first column are replaced. As HTML blocks,
+/// the comments end any preceding paragraph, list or blockquote, so the
+/// Markdown parser leaves the structure of the container content intact.
+/// Closing markers without an open container and indented markers stay
+/// text: their first colon is escaped, as otherwise the Markdown parser takes
+/// a marker following a paragraph for a definition list item. Apply this
+/// before parsing input for `AdmonitionPreprocessor`.
+pub fn mark_containers(input: &str) -> Cow<'_, str> {
+    let mut output = String::new();
+    let mut copied = 0;
+    // The character and length of the opening code fence, if any.
+    let mut fence: Option<(char, usize)> = None;
+    let mut open_containers = 0;
+    let mut pos = 0;
+    for line in input.split_inclusive('\n') {
+        let start = pos;
+        pos += line.len();
+        let trimmed = line.trim_start_matches(' ');
+        if line.len() - trimmed.len() > 3 {
+            continue;
+        }
+        let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        if let Some(c) = fence_char {
+            let len = trimmed.chars().take_while(|&x| x == c).count();
+            match fence {
+                None if len >= 3 => fence = Some((c, len)),
+                Some((open_c, open_len))
+                    if c == open_c && len >= open_len && trimmed[len..].trim().is_empty() =>
+                {
+                    fence = None
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if fence.is_some() || !trimmed.starts_with(":::") {
+            continue;
+        }
+        let marker = match ContainerMarker::parse(trimmed) {
+            Some(marker) => marker,
+            None => continue,
         };
-        let mut html_generator =
-            ClassedHTMLGenerator::new_with_class_style(sr, &ss, ClassStyle::Spaced);
-        for line in LinesWithEndings::from(&code) {
-            html_generator
-                .parse_html_for_line_which_includes_newline(line)
-                .unwrap_or_default();
+        let replace = trimmed.len() == line.len()
+            && match marker {
+                ContainerMarker::Open { .. } => {
+                    open_containers += 1;
+                    true
+                }
+                ContainerMarker::Close if open_containers > 0 => {
+                    open_containers -= 1;
+                    true
+                }
+                ContainerMarker::Close => false,
+            };
+        output.push_str(&input[copied..start]);
+        if replace {
+            let marker_line = line.trim_end();
+            output.push_str(CONTAINER_COMMENT);
+            output.push_str(marker_line);
+            output.push_str("-->");
+            output.push_str(&line[marker_line.len()..]);
+            copied = pos;
+        } else {
+            let colon = start + line.len() - trimmed.len();
+            output.push_str(&input[start..colon]);
+            output.push('\\');
+            copied = colon;
         }
-        html.push_str(html_generator.finalize().as_str());
+    }
+    if copied == 0 && output.is_empty() {
+        return Cow::Borrowed(input);
+    }
+    output.push_str(&input[copied..]);
+    Cow::Owned(output)
+}
 
-        html.push_str("</code></pre>");
+/// A line opening or closing a `:::` container.
+#[derive(Debug, PartialEq)]
+enum ContainerMarker {
+    Open { name: String, title: Option<String> },
+    Close,
+}
 
-        Some(Event::Html(html.into()))
+impl ContainerMarker {
+    /// Parses lines like `:::note`, `::: warning Mind the gap` or `:::`.
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let rest = line.strip_prefix(":::")?.trim_start_matches(':').trim();
+        if rest.is_empty() {
+            return Some(Self::Close);
+        }
+        let (name, title) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return None;
+        }
+        let title = title.trim();
+        Some(Self::Open {
+            name: name.to_ascii_lowercase(),
+            title: (!title.is_empty()).then(|| title.to_string()),
+        })
     }
 }
 
+/// The HTML opening an admonition block.
+fn admonition_start(name: &str, title: &str) -> String {
+    format!(
+        "<div class=\"admonition {}\">\n<p class=\"admonition-title\">{}</p>\n",
+        name,
+        encode_text(title)
+    )
+}
+
+/// Uppercases the first character of `s`.
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map(|c| c.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod test {
-    use crate::highlight::SyntaxPreprocessor;
+    use crate::highlight::{
+        AdmonitionPreprocessor, DIAGRAM_CACHE, DIAGRAM_CACHE_CAPACITY, SyntaxPreprocessor,
+        mark_containers, render_diagram,
+    };
     use pulldown_cmark::{Options, Parser, html};
+    use std::time::Duration;
+
+    #[test]
+    fn test_admonitions() {
+        let render = |input: &str| {
+            let input = mark_containers(input);
+            let parser = Parser::new_ext(&input, Options::all());
+            let mut rendered = String::new();
+            html::push_html(&mut rendered, AdmonitionPreprocessor::new(parser));
+            rendered
+        };
+
+        // GitHub-style alert.
+        assert_eq!(
+            render("> [!WARNING]\n> Be *careful*."),
+            "<div class=\"admonition warning\">\n\
+            <p class=\"admonition-title\">Warning</p>\n\
+            <p>Be <em>careful</em>.</p>\n\
+            </div>\n"
+        );
+
+        // Plain blockquotes are unchanged.
+        assert_eq!(
+            render("> Quote"),
+            "<blockquote>\n<p>Quote</p>\n</blockquote>\n"
+        );
+
+        // Fenced container.
+        assert_eq!(
+            render("Before\n\n:::note\nFirst\n\nSecond\n:::\n\nAfter"),
+            "<p>Before</p>\n\
+            <div class=\"admonition note\">\n\
+            <p class=\"admonition-title\">Note</p>\n\
+            <p>First</p>\n<p>Second</p>\n\
+            </div>\n\
+            <p>After</p>\n"
+        );
+
+        // Custom title, nesting and a container left open.
+        assert_eq!(
+            render("::: tip Mind this & that\n::::warning\nText\n:::"),
+            "<div class=\"admonition tip\">\n\
+            <p class=\"admonition-title\">Mind this &amp; that</p>\n\
+            <div class=\"admonition warning\">\n\
+            <p class=\"admonition-title\">Warning</p>\n\
+            <p>Text</p>\n\
+            </div>\n</div>\n"
+        );
+
+        // Definition lists still work.
+        assert_eq!(
+            render("Term\n: Definition"),
+            "<dl>\n<dt>Term</dt>\n<dd>Definition</dd>\n</dl>\n"
+        );
+
+        // Unmatched closing markers and code blocks are left alone.
+        assert_eq!(render(":::"), "<p>:::</p>\n");
+        assert_eq!(
+            render("```\n:::note\n```"),
+            "<pre><code>:::note\n</code></pre>\n"
+        );
+
+        // Lists, quotes and code inside containers.
+        assert_eq!(
+            render(":::note\n- a\n:::\n\nafter"),
+            "<div class=\"admonition note\">\n\
+            <p class=\"admonition-title\">Note</p>\n\
+            <ul>\n<li>a</li>\n</ul>\n\
+            </div>\n\
+            <p>after</p>\n"
+        );
+        assert_eq!(
+            render(":::tip\n> quote\n:::\nafter"),
+            "<div class=\"admonition tip\">\n\
+            <p class=\"admonition-title\">Tip</p>\n\
+            <blockquote>\n<p>quote</p>\n</blockquote>\n\
+            </div>\n\
+            <p>after</p>\n"
+        );
+        assert_eq!(
+            render(":::note\n```\n:::\n```\n:::"),
+            "<div class=\"admonition note\">\n\
+            <p class=\"admonition-title\">Note</p>\n\
+            <pre><code>:::\n</code></pre>\n\
+            </div>\n"
+        );
+
+        // Indented markers stay text.
+        assert_eq!(render("  :::note"), "<p>:::note</p>\n");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_diagrams() {
+        let sh = |script: &str| vec!["sh".to_string(), "-c".to_string(), script.to_string()];
+        let timeout = Duration::from_secs(10);
+
+        // The XML declaration is dropped.
+        let parser = Parser::new("```dot\ndigraph { a -> b }\n```");
+        let mut processed = SyntaxPreprocessor::new(parser);
+        processed.diagram_renderers = vec![(
+            "dot".to_string(),
+            sh("cat >/dev/null; echo '<?xml version=\"1.0\"?>'; echo '<svg>a</svg>'"),
+        )];
+        let mut rendered = String::new();
+        html::push_html(&mut rendered, processed);
+        assert_eq!(
+            rendered,
+            "<div class=\"diagram diagram-dot\">\n<svg>a</svg>\n</div>\n"
+        );
+
+        // Renditions are cached: the process ID in the output does not change.
+        let command = sh("cat >/dev/null; echo \"<svg>$$</svg>\"");
+        let html = render_diagram("pikchr", &command, timeout, "box");
+        assert_eq!(render_diagram("pikchr", &command, timeout, "box"), html);
+        assert_ne!(render_diagram("pikchr", &command, timeout, "circle"), html);
+        assert!(render_diagram("dot", &command, timeout, "box").contains("diagram-dot"));
+
+        // The least recently used diagram is dropped when the cache is full.
+        for i in 0..DIAGRAM_CACHE_CAPACITY {
+            render_diagram("pikchr", &command, timeout, &i.to_string());
+        }
+        assert!(DIAGRAM_CACHE.lock().len() <= DIAGRAM_CACHE_CAPACITY);
+        assert_ne!(render_diagram("pikchr", &command, timeout, "box"), html);
+
+        // Failures are rendered as error box.
+        let html = render_diagram("dot", &sh("echo oops >&2; exit 1"), timeout, "");
+        assert!(html.starts_with("<div class=\"admonition error\">"));
+        assert!(html.contains("oops"));
+        let html = render_diagram("dot", &["cat".to_string()], timeout, "<p>No image</p>");
+        assert!(html.contains("no SVG image"));
+    }
 
     #[test]
     fn test_latex_math() {
@@ -294,4 +885,83 @@ mod test {
         html::push_html(&mut html_output, parser);
         assert!(html_output.starts_with(expected));
     }
+
+    #[test]
+    fn test_highlight_registry_from_dir() {
+        use crate::highlight::HighlightRegistry;
+        use std::env::temp_dir;
+        use std::fs;
+        use std::sync::Arc;
+
+        let dir = temp_dir().join("tpnote_test_highlighting");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("themes")).unwrap();
+        fs::write(
+            dir.join("Acme.sublime-syntax"),
+            "%YAML 1.2\n---\nname: Acme\nfile_extensions: [acme]\n\
+            scope: source.acme\ncontexts:\n  main:\n\
+            \x20   - match: '\\bfrob\\b'\n      scope: keyword.control.acme\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("themes/Acme Light.tmTheme"),
+            r##"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>name</key><string>Acme Light</string>
+  <key>settings</key>
+  <array>
+    <dict><key>settings</key><dict>
+      <key>background</key><string>#FFFFFF</string>
+      <key>foreground</key><string>#000000</string>
+    </dict></dict>
+    <dict><key>scope</key><string>keyword</string><key>settings</key><dict>
+      <key>foreground</key><string>#FF0000</string>
+    </dict></dict>
+  </array>
+</dict>
+</plist>
+"##,
+        )
+        .unwrap();
+
+        let registry = HighlightRegistry::get(&dir).unwrap();
+        // The result is cached.
+        assert!(Arc::ptr_eq(
+            &registry,
+            &HighlightRegistry::get(&dir).unwrap()
+        ));
+
+        // User syntax definition.
+        let html = registry.highlight_code("acme", "frob it\n");
+        assert_eq!(
+            html,
+            "<pre><code class=\"language-acme\"><span class=\"source acme\">\
+            <span class=\"keyword control acme\">frob</span> it\n</span></code></pre>"
+        );
+        // Built-in syntax definitions are still available.
+        assert!(
+            registry
+                .highlight_code("rust", "fn main() {}\n")
+                .contains("source rust")
+        );
+
+        // User theme and built-in themes.
+        assert!(registry.highlighting_css("Acme Light").contains(".keyword"));
+        assert!(!registry.highlighting_css("InspiredGitHub").is_empty());
+        assert!(registry.highlighting_css("No Such Theme").is_empty());
+
+        // Render with the user registry.
+        let parser = Parser::new("```acme\nfrob\n```");
+        let processed = SyntaxPreprocessor::with_registry(parser, registry);
+        let mut rendered = String::new();
+        html::push_html(&mut rendered, processed);
+        assert!(rendered.contains("<span class=\"keyword control acme\">frob</span>"));
+
+        // A missing directory is an error.
+        assert!(HighlightRegistry::get(&dir.join("missing")).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
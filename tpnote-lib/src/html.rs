@@ -1,19 +1,28 @@
 //! Helper functions dealing with HTML conversion.
 use crate::clone_ext::CloneExt;
+use crate::config::FILENAME_ROOT_PATH_MARKER;
+use crate::content::{Content, ContentString};
+use crate::context::Context;
 use crate::error::InputStreamError;
 use crate::filename::{NotePath, NotePathStr};
+use crate::markup_language::MarkupLanguage;
 use crate::{config::LocalLinkKind, error::NoteError};
 use html_escape;
+use parking_lot::Mutex;
 use parking_lot::RwLock;
 use parse_hyperlinks::parser::Link;
 use parse_hyperlinks_extras::iterator_html::HtmlLinkInlineImage;
 use percent_encoding::percent_decode_str;
+use sanitize_filename_reader_friendly::sanitize;
+use serde::Serialize;
 use std::path::MAIN_SEPARATOR_STR;
 use std::{
     borrow::Cow,
-    collections::HashSet,
+    cell::OnceCell,
+    collections::{BTreeSet, HashMap, HashSet},
     path::{Component, Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, LazyLock},
+    time::SystemTime,
 };
 
 pub(crate) const HTML_EXT: &str = ".html";
@@ -35,6 +44,70 @@ const FORMAT_COMPLETE_FILENAME: &str = "?";
 /// the _from_ and _to_ pattern.
 const FORMAT_FROM_TO_SEPARATOR: char = ':';
 
+/// The Markdown renderer prepends this marker to the destination of wiki
+/// links, e.g. `[[My note]]`. `rewrite_links()` later resolves the marked
+/// destination against the note titles and sort-tags under `root_path`.
+pub(crate) const WIKI_LINK_MARKER: &str = "tpnote-wiki:";
+
+/// In a note's filename stem, this separates the title from the subtitle.
+const TITLE_SUBTITLE_SEPARATOR: &str = "--";
+
+/// Index of Tp-Note files searched for wiki link targets, see
+/// `WikiIndex::find()`. The filenames are disassembled only once, when the
+/// index is built.
+#[derive(Debug, Default)]
+struct WikiIndex {
+    /// Maps every sort-tag to the first file with this sort-tag.
+    sort_tags: HashMap<String, PathBuf>,
+    /// Maps every lowercase filename stem and its title part to the first
+    /// file with this stem or title.
+    titles: HashMap<String, PathBuf>,
+}
+
+impl WikiIndex {
+    /// Indexes `note_files`. Earlier files take precedence over later ones.
+    fn new(note_files: &[PathBuf]) -> Self {
+        let mut index = Self::default();
+        for p in note_files {
+            let (sort_tag, _, stem, _, _) = p.disassemble();
+            if !sort_tag.is_empty() {
+                index
+                    .sort_tags
+                    .entry(sort_tag.to_string())
+                    .or_insert_with(|| p.to_owned());
+            }
+            let stem = stem.to_lowercase();
+            if let Some(title) = stem.split(TITLE_SUBTITLE_SEPARATOR).next()
+                && title != stem
+            {
+                index
+                    .titles
+                    .entry(title.to_string())
+                    .or_insert_with(|| p.to_owned());
+            }
+            index.titles.entry(stem).or_insert_with(|| p.to_owned());
+        }
+        index
+    }
+
+    /// Searches the target of the wiki link `[[target]]`.
+    /// First, when `target` is a valid sort-tag, the first file with this
+    /// sort-tag is returned. Otherwise, the first file is returned whose
+    /// filename stem, or the title part of it, equals the sanitized `target`.
+    /// Title comparison is case insensitive.
+    fn find(&self, target: &str) -> Option<&Path> {
+        if let Some(sort_tag) = target.is_valid_sort_tag()
+            && let Some(p) = self.sort_tags.get(sort_tag)
+        {
+            return Some(p);
+        }
+
+        self.titles
+            .get(&sanitize(target).to_lowercase())
+            .map(|p| p.as_path())
+    }
+}
+
 /// If `rewrite_rel_path` and `dest` is relative, concatenate `docdir` and
 /// `dest`, then strip `root_path` from the left before returning.
 /// If not `rewrite_rel_path` and `dest` is relative, return `dest`.
@@ -44,7 +117,7 @@ const FORMAT_FROM_TO_SEPARATOR: char = ':';
 /// The `dest` portion of the output is always canonicalized.
 /// Return the assembled path, when in `root_path`, or `None` otherwise.
 /// Asserts in debug mode, that `doc_dir` is in `root_path`.
-fn assemble_link(
+pub(crate) fn assemble_link(
     root_path: &Path,
     docdir: &Path,
     dest: &Path,
@@ -191,6 +264,21 @@ trait Hyperlink {
     /// is needed as parameter and prepended.
     fn expand_shorthand_link(&mut self, prepend_path: Option<&Path>) -> Result<(), NoteError>;
 
+    /// If `dest` in `Link::Text2Dest` starts with `WIKI_LINK_MARKER`, search
+    /// all Tp-Note files under `search_path` for a matching sort-tag or title
+    /// (cf. `WikiIndex::find()`). Then replace `dest` with the found
+    /// path relative to `root_path` and prepended with `/`. A fragment
+    /// `#...` is preserved. Otherwise, no action.
+    /// The directory walk is performed only once and its result is cached
+    /// in `wiki_index`.
+    /// Contract: `search_path` is in `root_path`.
+    fn expand_wiki_link(
+        &mut self,
+        root_path: &Path,
+        search_path: &Path,
+        wiki_index: &OnceCell<WikiIndex>,
+    ) -> Result<(), NoteError>;
+
     /// This removes a possible scheme in `text`.
     /// Call this method only when you sure that this
     /// is an autolink by testing with `is_autolink()`.
@@ -428,6 +516,70 @@ impl Hyperlink for Link<'_> {
         Ok(())
     }
 
+    //
+    fn expand_wiki_link(
+        &mut self,
+        root_path: &Path,
+        search_path: &Path,
+        wiki_index: &OnceCell<WikiIndex>,
+    ) -> Result<(), NoteError> {
+        let dest = match self {
+            Link::Text2Dest(_, dest, _) => dest,
+            _ => return Ok(()),
+        };
+
+        let Some(target) = dest.strip_prefix(WIKI_LINK_MARKER) else {
+            return Ok(());
+        };
+
+        let (target, fragment) = match target.split_once('#') {
+            Some((target, fragment)) => (target.trim(), Some(fragment)),
+            None => (target.trim(), None),
+        };
+
+        let wiki_index = wiki_index.get_or_init(|| {
+            let note_files = search_path
+                .find_tpnote_files_recursively()
+                .unwrap_or_else(|e| {
+                    log::warn!(
+                        "Can not search `{}` for wiki links: {}",
+                        search_path.display(),
+                        e
+                    );
+                    Vec::new()
+                });
+            WikiIndex::new(&note_files)
+        });
+
+        let Some(path) = wiki_index.find(target) else {
+            return Err(NoteError::CanNotResolveWikiLink {
+                link: target.to_string(),
+            });
+        };
+        log::debug!(
+            "File `{}` referenced by wiki link `[[{}]]`.",
+            path.display(),
+            target
+        );
+
+        // All files were found under `root_path`, we can safely unwrap.
+        let found_link = path.strip_prefix(root_path).unwrap();
+        // Prepend `/`.
+        let mut found_link = Path::new(MAIN_SEPARATOR_STR)
+            .join(found_link)
+            .to_str()
+            .unwrap_or_default()
+            .to_string();
+        if let Some(fragment) = fragment {
+            found_link.push('#');
+            found_link.push_str(fragment);
+        }
+
+        // Store result.
+        let _ = std::mem::replace(dest, Cow::Owned(found_link));
+        Ok(())
+    }
+
     //
     fn rewrite_autolink(&mut self) {
         let text = match self {
@@ -551,10 +703,15 @@ impl Hyperlink for Link<'_> {
             _ => return,
         };
         if <Link as Hyperlink>::is_local_fn(dest) {
-            let path = dest.as_ref();
+            // Keep a fragment, e.g. `note.md#section`.
+            let (path, fragment) = match dest.rfind('#') {
+                Some(n) if !dest[n..].contains(['/', '\\']) => dest.split_at(n),
+                _ => (dest.as_ref(), ""),
+            };
             if path.has_tpnote_ext() {
                 let mut newpath = path.to_string();
                 newpath.push_str(HTML_EXT);
+                newpath.push_str(fragment);
 
                 let _ = std::mem::replace(dest, Cow::Owned(newpath));
             }
@@ -667,6 +824,12 @@ impl Hyperlink for Link<'_> {
 /// as URL and only the stem of the filename is displayed, e.g.
 /// `<a ...>http:dir/my file.md</a>` is replaced with `<a ...>my file</a>`.
 ///
+/// Wiki links marked by the Markdown renderer with `WIKI_LINK_MARKER`
+/// are resolved against the titles and sort-tags of all Tp-Note files under
+/// `root_path` first (cf. `WikiIndex::find()`). When `root_path`
+/// has no `FILENAME_ROOT_PATH_MARKER`, e.g. when it is `/`, only the files
+/// under `docdir` are searched.
+///
 /// Finally, before a converted local link is reinserted in the output HTML, a
 /// copy of that link is kept in `allowed_local_links` for further bookkeeping.
 ///
@@ -683,6 +846,37 @@ pub fn rewrite_links(
     rewrite_ext: bool,
     allowed_local_links: Arc<RwLock<HashSet<PathBuf>>>,
 ) -> String {
+    rewrite_links_with_wiki_index(
+        html_input,
+        root_path,
+        docdir,
+        local_link_kind,
+        rewrite_ext,
+        allowed_local_links,
+        &OnceCell::new(),
+        None,
+    )
+}
+
+/// Same as `rewrite_links()`, but the index of Tp-Note files searched for
+/// wiki link targets is taken from `wiki_index`, if already initialized.
+/// If `broken_links` is given, every local link that can not be resolved or
+/// whose target does not exist, is pushed together with its destination as
+/// written in the note (cf. `written_dest_and_src()`).
+/// Contract: with `broken_links`, `local_link_kind` is `LocalLinkKind::Short`.
+#[allow(clippy::too_many_arguments)]
+fn rewrite_links_with_wiki_index(
+    html_input: String,
+    root_path: &Path,
+    docdir: &Path,
+    local_link_kind: LocalLinkKind,
+    rewrite_ext: bool,
+    allowed_local_links: Arc<RwLock<HashSet<PathBuf>>>,
+    wiki_index: &OnceCell<WikiIndex>,
+    mut broken_links: Option<&mut Vec<(String, NoteError)>>,
+) -> String {
+    debug_assert!(broken_links.is_none() || matches!(local_link_kind, LocalLinkKind::Short));
+
     let (rewrite_rel_paths, rewrite_abs_paths) = match local_link_kind {
         LocalLinkKind::Off => (false, false),
         LocalLinkKind::Short => (true, false),
@@ -693,6 +887,14 @@ pub fn rewrite_links(
     // of this note.
     let mut rest = &*html_input;
     let mut html_out = String::new();
+    // Tp-Note files under `wiki_search_path` are collected on the first wiki
+    // link. Without marker file, `root_path` is `/`: do not walk the whole
+    // filesystem.
+    let wiki_search_path = if root_path.join(FILENAME_ROOT_PATH_MARKER).is_file() {
+        root_path
+    } else {
+        docdir
+    };
     for ((skipped, _consumed, remaining), mut link) in HtmlLinkInlineImage::new(&html_input) {
         html_out.push_str(skipped);
         rest = remaining;
@@ -708,9 +910,18 @@ pub fn rewrite_links(
 
         link.strip_local_scheme();
 
+        let (written_dest, written_src) = if broken_links.is_some() {
+            written_dest_and_src(&link)
+        } else {
+            (None, None)
+        };
+
         // Rewrite the local link.
         match link
-            .rebase_local_link(root_path, docdir, rewrite_rel_paths, rewrite_abs_paths)
+            .expand_wiki_link(root_path, wiki_search_path, wiki_index)
+            .and_then(|_| {
+                link.rebase_local_link(root_path, docdir, rewrite_rel_paths, rewrite_abs_paths)
+            })
             .and_then(|_| {
                 link.expand_shorthand_link(
                     (matches!(local_link_kind, LocalLinkKind::Short)).then_some(root_path),
@@ -718,9 +929,13 @@ pub fn rewrite_links(
             }) {
             Ok(()) => {}
             Err(e) => {
-                let e = e.to_string();
-                let e = html_escape::encode_text(&e);
-                html_out.push_str(&format!("<i>{}</i>", e));
+                let e_str = e.to_string();
+                let e_str = html_escape::encode_text(&e_str);
+                html_out.push_str(&format!("<i>{}</i>", e_str));
+                if let Some(broken_links) = broken_links.as_mut() {
+                    let written = written_dest.or(written_src).unwrap_or_default();
+                    broken_links.push((written, e));
+                }
                 continue;
             }
         };
@@ -738,6 +953,27 @@ pub fn rewrite_links(
             allowed_local_links.write().insert(src_path.to_path_buf());
         };
 
+        if let Some(broken_links) = broken_links.as_mut() {
+            for (path, written) in [
+                (link.get_local_link_dest_path(), written_dest),
+                (link.get_local_link_src_path(), written_src),
+            ] {
+                let Some(path) = path else { continue };
+                // `LocalLinkKind::Short`: local links are absolute with
+                // `root_path` as base.
+                let full_path =
+                    root_path.join(path.strip_prefix(MAIN_SEPARATOR_STR).unwrap_or(path));
+                if !full_path.exists() {
+                    broken_links.push((
+                        written.unwrap_or_default(),
+                        NoteError::LocalLinkTargetNotFound {
+                            path: path.to_string_lossy().into_owned(),
+                        },
+                    ));
+                }
+            }
+        }
+
         if rewrite_ext {
             link.append_html_ext();
         }
@@ -763,6 +999,558 @@ pub fn rewrite_links(
     // The `RwLockWriteGuard` is released here.
 }
 
+/// Returns the local link destination `dest` and the local image source `src`
+/// of `link` as written in the note, i.e. before the link is rebased or
+/// expanded. Wiki links are returned as `[[target]]`.
+fn written_dest_and_src(link: &Link) -> (Option<String>, Option<String>) {
+    let local = |s: &Cow<str>| {
+        if let Some(target) = s.strip_prefix(WIKI_LINK_MARKER) {
+            Some(format!("[[{}]]", target))
+        } else {
+            <Link as Hyperlink>::is_local_fn(s).then(|| s.to_string())
+        }
+    };
+    match link {
+        Link::Text2Dest(_, dest, _) => (local(dest), None),
+        Link::Image2Dest(_, _, src, _, dest, _) => (local(dest), local(src)),
+        Link::Image(_, src) => (None, local(src)),
+        _ => (None, None),
+    }
+}
+
+/// A broken local link found by `check_links()`.
+#[derive(Debug, Serialize)]
+pub struct BrokenLink {
+    /// The note containing the link.
+    pub path: PathBuf,
+    /// The line in the note's source text, where the link was found.
+    /// `None`, if it could not be located unambiguously.
+    pub line: Option<usize>,
+    /// The link destination as written in the note (percent decoded).
+    /// Wiki links are displayed as `[[target]]`.
+    pub link: String,
+    /// Why the link is broken.
+    pub error: NoteError,
+}
+
+/// Returns the line number of the link destination `link` in the note's
+/// `content`. The destination is searched in the body only. As it is
+/// percent decoded, also the encoded variant with `%20` is searched. Wiki
+/// links are searched without the trailing `]]`, because of a possible
+/// `|alias`. The HTML rendition does not tell where a link comes from:
+/// when the destination occurs more than once, e.g. in a code span or in a
+/// second link, the line is ambiguous and `None` is returned.
+fn find_link_line(content: &impl Content, link: &str) -> Option<usize> {
+    let link = link.strip_suffix("]]").unwrap_or(link);
+    if link.is_empty() {
+        return None;
+    }
+    let body = content.body();
+    let encoded_link = link.replace(' ', "%20");
+    let mut positions = body.match_indices(link).map(|(pos, _)| pos);
+    let mut encoded_positions = body
+        .match_indices(&encoded_link)
+        .map(|(pos, _)| pos)
+        .filter(|_| encoded_link != link);
+    let pos = match (
+        positions.next(),
+        positions.next(),
+        encoded_positions.next(),
+        encoded_positions.next(),
+    ) {
+        (Some(pos), None, None, None) | (None, None, Some(pos), None) => pos,
+        _ => return None,
+    };
+
+    // The body is a suffix of `content`.
+    let header_len = content.as_str().len() - body.len();
+    Some(content.as_str()[..header_len + pos].matches('\n').count() + 1)
+}
+
+/// Result of `check_links()`.
+#[derive(Debug, Default)]
+pub struct LinkCheckSummary {
+    /// The broken local links.
+    pub broken: Vec<BrokenLink>,
+    /// Note files that could not be read or rendered and the reason why.
+    pub failed: Vec<(PathBuf, NoteError)>,
+}
+
+/// Renders all Tp-Note files under `path` and reports their broken local
+/// links. If `path` is a file, only this note is checked. A local link is
+/// broken, when:
+/// * its path leaves the root directory (`NoteError::InvalidLocalPath`),
+/// * a sort-tag shorthand link can not be expanded
+///   (`NoteError::CanNotExpandShorthandLink`),
+/// * a wiki link can not be resolved (`NoteError::CanNotResolveWikiLink`),
+/// * its target does not exist (`NoteError::LocalLinkTargetNotFound`).
+///
+/// Relative and absolute local links are checked the same way the viewer
+/// resolves them: absolute links have the root directory of the note as base.
+/// Unlike a broken link, a note that can not be read or rendered does not
+/// abort the check. Instead, it is recorded in the returned
+/// `LinkCheckSummary`. Only when the directory `path` can not be read, an
+/// error is returned.
+pub fn check_links(path: &Path) -> Result<LinkCheckSummary, NoteError> {
+    let note_files = if path.is_file() {
+        vec![path.to_path_buf()]
+    } else {
+        path.find_tpnote_files_recursively()?
+    };
+
+    let mut summary = LinkCheckSummary::default();
+    // Index of Tp-Note files searched for wiki link targets, per root
+    // directory.
+    let mut wiki_indexes: HashMap<PathBuf, OnceCell<WikiIndex>> = HashMap::new();
+
+    for note_file in note_files {
+        let context = match Context::from(&note_file) {
+            Ok(context) => context,
+            Err(e) => {
+                log::debug!("Can not check `{}`:\n{}", note_file.display(), e);
+                summary.failed.push((note_file, e.into()));
+                continue;
+            }
+        };
+        let root_path = context.get_root_path();
+        let docdir = context.get_dir_path();
+
+        let content = match ContentString::open(&note_file) {
+            Ok(content) => content,
+            Err(e) => {
+                log::debug!("Can not read `{}`:\n{}", note_file.display(), e);
+                summary.failed.push((note_file, e.into()));
+                continue;
+            }
+        };
+        let html = match render_note_body(&note_file, &content) {
+            Ok(html) => html,
+            Err(e) => {
+                log::debug!("Can not render `{}`:\n{}", note_file.display(), e);
+                summary.failed.push((note_file, e));
+                continue;
+            }
+        };
+
+        // Without marker file, wiki links are searched under `docdir`.
+        let fresh_wiki_index = OnceCell::new();
+        let wiki_index = if root_path.join(FILENAME_ROOT_PATH_MARKER).is_file() {
+            wiki_indexes.entry(root_path.to_path_buf()).or_default()
+        } else {
+            &fresh_wiki_index
+        };
+
+        let mut found = Vec::new();
+        let _ = rewrite_links_with_wiki_index(
+            html,
+            root_path,
+            docdir,
+            LocalLinkKind::Short,
+            false,
+            Arc::new(RwLock::new(HashSet::new())),
+            wiki_index,
+            Some(&mut found),
+        );
+
+        summary
+            .broken
+            .extend(found.into_iter().map(|(link, error)| BrokenLink {
+                path: note_file.clone(),
+                line: find_link_line(&content, &link),
+                link,
+                error,
+            }));
+    }
+    Ok(summary)
+}
+
+/// Helper function rendering the body of the note `note_file` into HTML.
+/// The markup language is derived from the file extension. A panicking
+/// renderer is reported as error too.
+fn render_note_body(note_file: &Path, content: &ContentString) -> Result<String, NoteError> {
+    let ext = note_file
+        .extension()
+        .unwrap_or_default()
+        .to_str()
+        .unwrap_or_default();
+    MarkupLanguage::from(ext).render_for_catch_unwind(ext, content.body())
+}
+
+/// Renders all Tp-Note files under `root_path` and collects their local
+/// links to other Tp-Note files. The result maps every link destination to
+/// the set of notes linking to it. Destinations and sources are absolute
+/// paths with `root_path` as base, e.g. `/dir/my note.md`. Self-references
+/// are omitted.
+/// When `root_path` has no `FILENAME_ROOT_PATH_MARKER`, e.g. when it is
+/// `/`, only the files under `search_path` are considered.
+/// Notes that can not be read or rendered are skipped silently.
+/// Contract: `search_path` is in `root_path`.
+pub fn backlink_index(root_path: &Path, search_path: &Path) -> HashMap<PathBuf, BTreeSet<PathBuf>> {
+    let (note_files, has_marker) = backlink_note_files(root_path, search_path);
+    backlink_index_of(root_path, &note_files, has_marker)
+}
+
+/// Maximum number of note trees kept in `BACKLINK_CACHE`.
+const BACKLINK_CACHE_CAPACITY: usize = 8;
+
+/// Cached link indexes per `root_path` and search path, see `backlinks()`.
+static BACKLINK_CACHE: LazyLock<Mutex<HashMap<(PathBuf, PathBuf), BacklinkCache>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The links of all Tp-Note files of a note tree and the backlink index
+/// built from them, see `backlinks()`.
+#[derive(Debug, Default)]
+struct BacklinkCache {
+    /// The links of every note, indexed by the note's path.
+    notes: HashMap<PathBuf, NoteLinks>,
+    /// Maps every link destination to the set of notes linking to it, see
+    /// `backlink_index()`.
+    index: HashMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+/// The outgoing links of a note, see `note_links()`.
+#[derive(Debug)]
+struct NoteLinks {
+    /// The modification time and length of the note file.
+    stamp: Option<(SystemTime, u64)>,
+    /// The note's local links to other Tp-Note files.
+    links: BTreeSet<PathBuf>,
+    /// The note has wiki links or broken links, which may resolve
+    /// differently when notes are added or removed.
+    resolves_by_name: bool,
+}
+
+/// Returns the set of notes linking to `doc_path`, an absolute path with
+/// `root_path` as base, e.g. `/dir/my note.md`. See `backlink_index()` for
+/// details. Unlike the latter, the links of every note are cached. Only the
+/// notes modified since the last call are rendered again. When notes were
+/// added or removed, so are the notes with wiki links, broken links or
+/// links to removed notes.
+/// Contract: `search_path` is in `root_path`.
+pub fn backlinks(root_path: &Path, search_path: &Path, doc_path: &Path) -> BTreeSet<PathBuf> {
+    let (note_files, has_marker) = backlink_note_files(root_path, search_path);
+    let key = (root_path.to_path_buf(), search_path.to_path_buf());
+
+    let mut cache = BACKLINK_CACHE.lock();
+    if !cache.contains_key(&key) && cache.len() >= BACKLINK_CACHE_CAPACITY {
+        cache.clear();
+    }
+    let cache = cache.entry(key).or_default();
+
+    // Forget the removed notes.
+    let current: HashSet<&PathBuf> = note_files.iter().collect();
+    let removed: HashSet<PathBuf> = cache
+        .notes
+        .keys()
+        .filter(|p| !current.contains(p))
+        .map(|p| note_source(root_path, p))
+        .collect();
+    cache.notes.retain(|p, _| current.contains(p));
+    let set_changed = !removed.is_empty() || cache.notes.len() != note_files.len();
+
+    // With marker file, wiki links are resolved against the same files.
+    // We walk the directory only once.
+    let wiki_index = if has_marker {
+        OnceCell::from(WikiIndex::new(&note_files))
+    } else {
+        OnceCell::new()
+    };
+
+    let mut changed = set_changed;
+    for note_file in &note_files {
+        let stamp = note_file
+            .metadata()
+            .and_then(|m| Ok((m.modified()?, m.len())))
+            .ok();
+        let stale = cache.notes.get(note_file).is_none_or(|n| {
+            n.stamp != stamp
+                || (set_changed
+                    && (n.resolves_by_name || n.links.iter().any(|l| removed.contains(l))))
+        });
+        if !stale {
+            continue;
+        }
+        // Notes that can not be read or rendered have no links.
+        let (links, resolves_by_name) =
+            note_links(root_path, note_file, &wiki_index).unwrap_or_default();
+        cache.notes.insert(
+            note_file.to_owned(),
+            NoteLinks {
+                stamp,
+                links,
+                resolves_by_name,
+            },
+        );
+        changed = true;
+    }
+
+    if changed {
+        let mut index: HashMap<PathBuf, BTreeSet<PathBuf>> = HashMap::new();
+        for (note_file, note) in &cache.notes {
+            let source = note_source(root_path, note_file);
+            for dest in &note.links {
+                index
+                    .entry(dest.to_owned())
+                    .or_default()
+                    .insert(source.clone());
+            }
+        }
+        cache.index = index;
+    }
+    cache.index.get(doc_path).cloned().unwrap_or_default()
+}
+
+/// Helper function collecting the Tp-Note files considered by
+/// `backlink_index()`. Returns them together with the information whether
+/// `root_path` has a `FILENAME_ROOT_PATH_MARKER`.
+fn backlink_note_files(root_path: &Path, search_path: &Path) -> (Vec<PathBuf>, bool) {
+    let has_marker = root_path.join(FILENAME_ROOT_PATH_MARKER).is_file();
+    let search_path = if has_marker { root_path } else { search_path };
+    let note_files = search_path
+        .find_tpnote_files_recursively()
+        .unwrap_or_else(|e| {
+            log::warn!(
+                "Can not search `{}` for backlinks: {}",
+                search_path.display(),
+                e
+            );
+            Vec::new()
+        });
+    (note_files, has_marker)
+}
+
+/// Helper function building the backlink index of `note_files`, see
+/// `backlink_index()`.
+fn backlink_index_of(
+    root_path: &Path,
+    note_files: &[PathBuf],
+    has_marker: bool,
+) -> HashMap<PathBuf, BTreeSet<PathBuf>> {
+    let mut index: HashMap<PathBuf, BTreeSet<PathBuf>> = HashMap::new();
+    for (note_file, (links, _)) in note_links_of(root_path, note_files, note_files, has_marker) {
+        let source = note_source(root_path, &note_file);
+        for dest in links {
+            index.entry(dest).or_default().insert(source.clone());
+        }
+    }
+    index
+}
+
+/// Renders the Tp-Note files `note_files` and collects their local links to
+/// other Tp-Note files, see `note_links()`. With `has_marker`, wiki links
+/// are resolved against `all_note_files`. Notes that can not be read or
+/// rendered are skipped silently.
+pub(crate) fn note_links_of<'a>(
+    root_path: &Path,
+    all_note_files: &[PathBuf],
+    note_files: impl IntoIterator<Item = &'a PathBuf>,
+    has_marker: bool,
+) -> HashMap<PathBuf, (BTreeSet<PathBuf>, bool)> {
+    // With marker file, wiki links are resolved against the same files.
+    // We walk the directory only once.
+    let wiki_index = if has_marker {
+        OnceCell::from(WikiIndex::new(all_note_files))
+    } else {
+        OnceCell::new()
+    };
+
+    note_files
+        .into_iter()
+        .filter_map(|note_file| {
+            note_links(root_path, note_file, &wiki_index).map(|l| (note_file.to_owned(), l))
+        })
+        .collect()
+}
+
+/// Helper function rendering `note_file` and collecting its local links to
+/// other Tp-Note files, without self-references. The second value is true,
+/// when the note has wiki links or broken links.
+/// Returns `None` if the note can not be read or rendered.
+fn note_links(
+    root_path: &Path,
+    note_file: &Path,
+    wiki_index: &OnceCell<WikiIndex>,
+) -> Option<(BTreeSet<PathBuf>, bool)> {
+    let content = ContentString::open(note_file).ok()?;
+    let html = render_note_body(note_file, &content).ok()?;
+    let has_wiki_links = html.contains(WIKI_LINK_MARKER);
+    let docdir = note_file.parent().unwrap_or(root_path);
+    let links = Arc::new(RwLock::new(HashSet::new()));
+    let mut broken_links = Vec::new();
+    let _ = rewrite_links_with_wiki_index(
+        html,
+        root_path,
+        docdir,
+        LocalLinkKind::Short,
+        false,
+        links.clone(),
+        wiki_index,
+        Some(&mut broken_links),
+    );
+
+    let source = note_source(root_path, note_file);
+    let links = links
+        .read_recursive()
+        .iter()
+        .filter(|dest| dest.has_tpnote_ext() && **dest != source)
+        .cloned()
+        .collect();
+    Some((links, has_wiki_links || !broken_links.is_empty()))
+}
+
+/// The path of `note_file` with `root_path` as base, e.g. `/dir/my note.md`.
+/// All files were found under `root_path`, we can safely unwrap.
+pub(crate) fn note_source(root_path: &Path, note_file: &Path) -> PathBuf {
+    Path::new(MAIN_SEPARATOR_STR).join(note_file.strip_prefix(root_path).unwrap())
+}
+
+/// An entry of a note's table of contents, see `toc()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TocEntry {
+    /// The heading level `1` to `6`.
+    pub level: u8,
+    /// The heading's text without markup.
+    pub text: String,
+    /// The heading's id. Link to the heading with `#{anchor}`.
+    pub anchor: String,
+}
+
+/// Derives a heading id from the heading's plain `text`, similar to GitHub:
+/// letters and digits are lowercased, whitespace becomes `-`, `-` and `_`
+/// are kept, everything else is dropped, e.g. `My 1. Heading!` becomes
+/// `my-1-heading`. The result is unique in `ids` and registered there, see
+/// `unique_id()`.
+///
+/// ```rust
+/// use std::collections::HashSet;
+/// use tpnote_lib::html::slugify;
+///
+/// let mut ids = HashSet::new();
+/// assert_eq!(slugify("My 1. Heading!", &mut ids), "my-1-heading");
+/// assert_eq!(slugify("My 1. Heading!", &mut ids), "my-1-heading-1");
+/// assert_eq!(slugify("", &mut ids), "section");
+/// ```
+pub fn slugify(text: &str, ids: &mut HashSet<String>) -> String {
+    unique_id(ids, &slug(text))
+}
+
+/// Like `slugify()`, but the result is not made unique. Use this to link to
+/// a heading by its text.
+pub fn slug(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() || c == '-' || c == '_' {
+            slug.extend(c.to_lowercase());
+        } else if c.is_whitespace() {
+            slug.push('-');
+        }
+    }
+    if slug.is_empty() {
+        slug.push_str("section");
+    }
+    slug
+}
+
+/// Returns `id`, or `id` with the suffix `-1`, `-2` ... when `id` is
+/// already in `ids`. The result is registered in `ids`.
+///
+/// ```rust
+/// use std::collections::HashSet;
+/// use tpnote_lib::html::unique_id;
+///
+/// let mut ids = HashSet::new();
+/// assert_eq!(unique_id(&mut ids, "_intro"), "_intro");
+/// assert_eq!(unique_id(&mut ids, "_intro"), "_intro-1");
+/// ```
+pub fn unique_id(ids: &mut HashSet<String>, id: &str) -> String {
+    let mut unique = id.to_string();
+    let mut n = 0;
+    while ids.contains(&unique) {
+        n += 1;
+        unique = format!("{id}-{n}");
+    }
+    ids.insert(unique.clone());
+    unique
+}
+
+/// Collects the headings `<h1 id="...">` ... `<h6>` of the HTML rendition
+/// `html` into a table of contents. Headings without `id` attribute are
+/// skipped, unless they are the first element of a `<section id="...">`, as
+/// rendered by the ReStructuredText renderer. `html` is parsed like a web
+/// browser does: nested or unclosed tags are tolerated.
+///
+/// ```rust
+/// use tpnote_lib::html::{toc, TocEntry};
+///
+/// let html = "<h1 id=\"intro\">Intro</h1><p>Text</p>\
+///     <h2 id=\"a-b\">A &amp; <em>B</em></h2><h3>No id</h3>";
+/// assert_eq!(toc(html), vec![
+///     TocEntry { level: 1, text: "Intro".to_string(), anchor: "intro".to_string() },
+///     TocEntry { level: 2, text: "A & B".to_string(), anchor: "a-b".to_string() },
+/// ]);
+/// ```
+#[cfg(feature = "renderer")]
+pub fn toc(html: &str) -> Vec<TocEntry> {
+    use html2md::common::get_tag_attr;
+    use html2md::{Handle, NodeData, RcDom};
+    use html5ever::driver::ParseOpts;
+    use html5ever::parse_document;
+    use html5ever::tendril::TendrilSink;
+
+    /// Appends the text of `node` and its descendants to `text`.
+    fn text_of(node: &Handle, text: &mut String) {
+        if let NodeData::Text { contents } = &node.data {
+            text.push_str(&contents.borrow());
+        }
+        for child in node.children.borrow().iter() {
+            text_of(child, text);
+        }
+    }
+
+    /// Walks the children of `node`. `section_id` is the id of `node`, if it
+    /// is a `<section>`.
+    fn walk(node: &Handle, section_id: Option<String>, entries: &mut Vec<TocEntry>) {
+        let mut section_id = section_id;
+        for child in node.children.borrow().iter() {
+            let NodeData::Element { name, .. } = &child.data else {
+                continue;
+            };
+            // Only the first element of a section inherits its id.
+            let inherited_id = section_id.take();
+            let tag = name.local.as_ref();
+            let level = match tag.as_bytes() {
+                [b'h', d @ b'1'..=b'6'] => d - b'0',
+                _ => {
+                    let id = (tag == "section")
+                        .then(|| get_tag_attr(child, "id"))
+                        .flatten();
+                    walk(child, id, entries);
+                    continue;
+                }
+            };
+            let Some(anchor) = get_tag_attr(child, "id").or(inherited_id) else {
+                continue;
+            };
+            let mut text = String::new();
+            text_of(child, &mut text);
+            entries.push(TocEntry {
+                level,
+                text: text.trim().to_string(),
+                anchor,
+            });
+        }
+    }
+
+    let dom = parse_document(RcDom::default(), ParseOpts::default())
+        .from_utf8()
+        .read_from(&mut html.as_bytes());
+    let mut entries = Vec::new();
+    if let Ok(dom) = dom {
+        walk(&dom.document, None, &mut entries);
+    }
+    entries
+}
+
 /// This trait deals with tagged HTML `&str` data.
 pub trait HtmlStr {
     /// Lowercase pattern to check if this is a Doctype tag.
@@ -1754,6 +2542,346 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_rewrite_links_fragment() {
+        use crate::config::LocalLinkKind;
+
+        let allowed_urls = Arc::new(RwLock::new(HashSet::new()));
+        let input = "<a href=\"sub/my note.md#my-section\">Section</a>".to_string();
+        let expected = "<a href=\"/abs/note path/sub/my note.md.html#my-section\">Section</a>";
+        let root_path = Path::new("/my/");
+        let docdir = Path::new("/my/abs/note path/");
+        let output = rewrite_links(
+            input,
+            root_path,
+            docdir,
+            LocalLinkKind::Short,
+            true,
+            allowed_urls.clone(),
+        );
+        let url = allowed_urls.read_recursive();
+        assert!(url.contains(&PathBuf::from("/abs/note path/sub/my note.md")));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_rewrite_links_wiki() {
+        use crate::config::LocalLinkKind;
+        use std::env::temp_dir;
+        use std::fs;
+
+        // Prepare test: a small note tree.
+        let root_path = temp_dir().join("rewrite_links_wiki");
+        let _ = fs::remove_dir_all(&root_path);
+        fs::create_dir_all(root_path.join("sub")).unwrap();
+        fs::write(root_path.join("tpnote.toml"), "").unwrap();
+        fs::write(root_path.join("20221030-My day--Note.md"), "").unwrap();
+        fs::write(root_path.join("sub/03b-Other note.md"), "").unwrap();
+
+        let allowed_urls = Arc::new(RwLock::new(HashSet::new()));
+        let input = "abc<a href=\"tpnote-wiki:my%20day\">my day</a>\
+            def<a href=\"tpnote-wiki:03b\">03b</a>\
+            ghi<a href=\"tpnote-wiki:Other%20note#part\">alias</a>\
+            jkl<a href=\"tpnote-wiki:Missing\">Missing</a>"
+            .to_string();
+        let expected = "abc<a href=\"/20221030-My day--Note.md\">my day</a>\
+            def<a href=\"/sub/03b-Other note.md\">03b</a>\
+            ghi<a href=\"/sub/03b-Other note.md#part\">alias</a>\
+            jkl<i>&lt;NONE FOUND: [[Missing]]&gt;</i>"
+            .to_string();
+
+        let output = rewrite_links(
+            input,
+            &root_path,
+            &root_path.join("sub"),
+            LocalLinkKind::Short,
+            false,
+            allowed_urls.clone(),
+        );
+        let url = allowed_urls.read_recursive();
+        assert!(url.contains(&PathBuf::from("/20221030-My day--Note.md")));
+        assert!(url.contains(&PathBuf::from("/sub/03b-Other note.md")));
+        assert_eq!(output, expected);
+        drop(url);
+
+        // Without marker file, only `docdir` is searched.
+        fs::remove_file(root_path.join("tpnote.toml")).unwrap();
+        let input = "abc<a href=\"tpnote-wiki:my%20day\">my day</a>\
+            def<a href=\"tpnote-wiki:03b\">03b</a>"
+            .to_string();
+        let expected = "abc<i>&lt;NONE FOUND: [[my day]]&gt;</i>\
+            def<a href=\"/sub/03b-Other note.md\">03b</a>"
+            .to_string();
+        let output = rewrite_links(
+            input,
+            &root_path,
+            &root_path.join("sub"),
+            LocalLinkKind::Short,
+            false,
+            allowed_urls.clone(),
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_wiki_index() {
+        use crate::html::WikiIndex;
+
+        let note_files = [
+            PathBuf::from("/dir/20221030-My day--Note.md"),
+            PathBuf::from("/dir/20221030-My day--Other.md"),
+            PathBuf::from("/dir/sub/03b-Other note.md"),
+            PathBuf::from("/dir/sub/my day.md"),
+        ];
+        let index = WikiIndex::new(&note_files);
+
+        // The first file with the sort-tag or title wins.
+        assert_eq!(
+            index.find("20221030"),
+            Some(Path::new("/dir/20221030-My day--Note.md"))
+        );
+        assert_eq!(
+            index.find("my DAY"),
+            Some(Path::new("/dir/20221030-My day--Note.md"))
+        );
+        assert_eq!(
+            index.find("My day--Other"),
+            Some(Path::new("/dir/20221030-My day--Other.md"))
+        );
+        assert_eq!(
+            index.find("03b"),
+            Some(Path::new("/dir/sub/03b-Other note.md"))
+        );
+        assert_eq!(
+            index.find("other note"),
+            Some(Path::new("/dir/sub/03b-Other note.md"))
+        );
+        assert_eq!(index.find("Missing"), None);
+    }
+
+    #[test]
+    fn test_backlink_index() {
+        use crate::html::backlink_index;
+        use std::collections::BTreeSet;
+        use std::env::temp_dir;
+        use std::fs;
+
+        // Prepare test: a small note tree.
+        let root_path = temp_dir().join("backlink_index");
+        let _ = fs::remove_dir_all(&root_path);
+        fs::create_dir_all(root_path.join("sub")).unwrap();
+        fs::write(root_path.join("tpnote.toml"), "").unwrap();
+        fs::write(
+            root_path.join("01-first.md"),
+            "---\ntitle: first\n---\n[second](<sub/02-second.md>) [[first]]\n",
+        )
+        .unwrap();
+        fs::write(
+            root_path.join("sub/02-second.md"),
+            "---\ntitle: second\n---\n[[first]] ![img](<image.png>)\n",
+        )
+        .unwrap();
+        fs::write(
+            root_path.join("sub/03-third.md"),
+            "---\ntitle: third\n---\n[second](<02>)\n",
+        )
+        .unwrap();
+
+        let index = backlink_index(&root_path, &root_path.join("sub"));
+        // Self-references and links to images are omitted.
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index[Path::new("/01-first.md")],
+            BTreeSet::from([PathBuf::from("/sub/02-second.md")])
+        );
+        assert_eq!(
+            index[Path::new("/sub/02-second.md")],
+            BTreeSet::from([
+                PathBuf::from("/01-first.md"),
+                PathBuf::from("/sub/03-third.md")
+            ])
+        );
+
+        // Without marker file, only `search_path` is searched.
+        fs::remove_file(root_path.join("tpnote.toml")).unwrap();
+        let index = backlink_index(&root_path, &root_path.join("sub"));
+        assert_eq!(index.len(), 1);
+        assert_eq!(
+            index[Path::new("/sub/02-second.md")],
+            BTreeSet::from([PathBuf::from("/sub/03-third.md")])
+        );
+    }
+
+    #[test]
+    fn test_backlinks() {
+        use crate::html::backlinks;
+        use std::collections::BTreeSet;
+        use std::env::temp_dir;
+        use std::fs;
+
+        // Prepare test: a small note tree.
+        let root_path = temp_dir().join("backlinks");
+        let _ = fs::remove_dir_all(&root_path);
+        fs::create_dir_all(&root_path).unwrap();
+        fs::write(root_path.join("tpnote.toml"), "").unwrap();
+        fs::write(root_path.join("01-first.md"), "---\ntitle: first\n---\n").unwrap();
+        fs::write(
+            root_path.join("02-second.md"),
+            "---\ntitle: second\n---\n[[first]]\n",
+        )
+        .unwrap();
+
+        let first = Path::new("/01-first.md");
+        assert_eq!(
+            backlinks(&root_path, &root_path, first),
+            BTreeSet::from([PathBuf::from("/02-second.md")])
+        );
+
+        // A new note invalidates the cached index.
+        fs::write(
+            root_path.join("03-third.md"),
+            "---\ntitle: third\n---\n[first](<01-first.md>)\n",
+        )
+        .unwrap();
+        assert_eq!(
+            backlinks(&root_path, &root_path, first),
+            BTreeSet::from([
+                PathBuf::from("/02-second.md"),
+                PathBuf::from("/03-third.md")
+            ])
+        );
+
+        // So does a modified note.
+        fs::write(root_path.join("02-second.md"), "---\ntitle: second\n---\n").unwrap();
+        assert_eq!(
+            backlinks(&root_path, &root_path, first),
+            BTreeSet::from([PathBuf::from("/03-third.md")])
+        );
+
+        // A new note resolves a wiki link of an unmodified note.
+        fs::write(
+            root_path.join("04-fourth.md"),
+            "---\ntitle: fourth\n---\n[[fifth]]\n",
+        )
+        .unwrap();
+        let fifth = Path::new("/05-fifth.md");
+        assert_eq!(backlinks(&root_path, &root_path, fifth), BTreeSet::new());
+        fs::write(root_path.join("05-fifth.md"), "---\ntitle: fifth\n---\n").unwrap();
+        assert_eq!(
+            backlinks(&root_path, &root_path, fifth),
+            BTreeSet::from([PathBuf::from("/04-fourth.md")])
+        );
+
+        // A removed note has no links anymore.
+        fs::remove_file(root_path.join("03-third.md")).unwrap();
+        assert_eq!(backlinks(&root_path, &root_path, first), BTreeSet::new());
+    }
+
+    #[test]
+    fn test_check_links() {
+        use crate::html::check_links;
+        use std::env::temp_dir;
+        use std::fs;
+
+        // Prepare test: a small note tree.
+        let root_path = temp_dir().join("check_links");
+        let _ = fs::remove_dir_all(&root_path);
+        fs::create_dir_all(root_path.join("sub")).unwrap();
+        fs::write(root_path.join("tpnote.toml"), "").unwrap();
+        fs::write(root_path.join("sub/image.png"), "").unwrap();
+        fs::write(
+            root_path.join("01-first.md"),
+            "---\ntitle: first\n---\n\
+            [ok](<sub/02-second.md>) ![ok](</sub/image.png>)\n\
+            [missing](<sub/my note.md>)\n\
+            [shorthand](<sub/99>) [[Nobody]]\n\
+            [underflow](<../../../../../../x.md>)\n",
+        )
+        .unwrap();
+        fs::write(
+            root_path.join("sub/02-second.md"),
+            "---\ntitle: second\n---\n[[first]] [ok](<../01>)\n![missing](<img.png>)\n",
+        )
+        .unwrap();
+        // The link destination occurs more than once: no line number.
+        fs::write(
+            root_path.join("sub/03-third.md"),
+            "---\ntitle: third\nsubtitle: gone.md\n---\n`[x](gone.md)`\n[x](gone.md)\n\
+            [y](<new one.md>)\n",
+        )
+        .unwrap();
+        // This note can not be read.
+        fs::write(root_path.join("sub/04-fourth.md"), b"\xff\xfe\xfd").unwrap();
+
+        let summary = check_links(&root_path).unwrap();
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].0, root_path.join("sub/04-fourth.md"));
+        let broken: Vec<_> = summary
+            .broken
+            .iter()
+            .map(|b| {
+                (
+                    b.path.strip_prefix(&root_path).unwrap().to_str().unwrap(),
+                    b.line,
+                    b.link.as_str(),
+                    b.error.to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            broken,
+            vec![
+                (
+                    "01-first.md",
+                    Some(5),
+                    "sub/my note.md",
+                    "<NOT FOUND: /sub/my note.md>".to_string()
+                ),
+                (
+                    "01-first.md",
+                    Some(6),
+                    "sub/99",
+                    format!("<NONE FOUND: {}...>", root_path.join("sub/99").display())
+                ),
+                (
+                    "01-first.md",
+                    Some(6),
+                    "[[Nobody]]",
+                    "<NONE FOUND: [[Nobody]]>".to_string()
+                ),
+                (
+                    "01-first.md",
+                    Some(7),
+                    "../../../../../../x.md",
+                    "<INVALID: ../../../../../../x.md>".to_string()
+                ),
+                (
+                    "sub/02-second.md",
+                    Some(5),
+                    "img.png",
+                    "<NOT FOUND: /sub/img.png>".to_string()
+                ),
+                (
+                    "sub/03-third.md",
+                    None,
+                    "gone.md",
+                    "<NOT FOUND: /sub/gone.md>".to_string()
+                ),
+                (
+                    "sub/03-third.md",
+                    Some(7),
+                    "new one.md",
+                    "<NOT FOUND: /sub/new one.md>".to_string()
+                ),
+            ]
+        );
+
+        // A single note.
+        let summary = check_links(&root_path.join("sub/02-second.md")).unwrap();
+        assert_eq!(summary.broken.len(), 1);
+        assert!(summary.failed.is_empty());
+    }
+
     #[test]
     fn test_is_empty_html() {
         // Bring new methods into scope.
@@ -1914,4 +3042,35 @@ mod tests {
             Ok(String::from("<!DOCTYPE html>"))
         );
     }
+
+    #[test]
+    #[cfg(feature = "renderer")]
+    fn test_toc() {
+        use crate::html::{TocEntry, toc};
+        let entry = |level, text: &str, anchor: &str| TocEntry {
+            level,
+            text: text.to_string(),
+            anchor: anchor.to_string(),
+        };
+
+        // Nested markup and unclosed tags.
+        let html = "<h1 id=\"a\">A <span>x</span></h1><div><h2 id=\"b\">B\
+            <h3 id=\"c\">C</h3><p>No </h2> heading</p>";
+        assert_eq!(
+            toc(html),
+            vec![entry(1, "A x", "a"), entry(2, "B", "b"), entry(3, "C", "c")]
+        );
+
+        // Headings inside a `<section id="...">`.
+        let html = "<section id=\"s1\"><h1>S 1</h1><p>Text</p><h2>Other</h2>\
+            <section id=\"s2\"><h2>S 2</h2></section></section>";
+        assert_eq!(
+            toc(html),
+            vec![entry(1, "S 1", "s1"), entry(2, "S 2", "s2")]
+        );
+
+        // Headings in code are not headings.
+        let html = "<pre><code>&lt;h1 id=\"x\"&gt;X&lt;/h1&gt;</code></pre>";
+        assert_eq!(toc(html), vec![]);
+    }
 }
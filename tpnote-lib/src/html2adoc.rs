@@ -0,0 +1,953 @@
+//! This module implements the HTML to AsciiDoc filter.
+//! It reuses `html2md`'s DOM walker. The tag handlers below replace the
+//! handlers of the constructs AsciiDoc writes differently than Markdown.
+use crate::error::NoteError;
+use html2md::common::{element_name, get_tag_attr, text_content};
+use html2md::dummy::DummyHandler;
+use html2md::math::math_latex;
+use html2md::{
+    Handle, NodeData, StructuredPrinter, TagHandler, TagHandlerFactory, parse_html_custom, walk,
+};
+use percent_encoding::percent_decode_str;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Replaces backslashes in code and link targets while the Markdown escapes
+/// of the text are removed.
+const BACKSLASH_PLACEHOLDER: char = '\u{E000}';
+
+/// Characters `html2md` escapes with a backslash in Markdown text.
+const MARKDOWN_ESCAPED: [char; 11] = ['<', '>', '*', '\\', '_', '~', '=', '+', '-', '#', '`'];
+
+/// Escaped characters opening AsciiDoc inline markup, e.g. `*strong*`.
+const MARKUP_CHARS: [char; 4] = ['*', '_', '`', '~'];
+
+/// Escaped characters starting list items or section titles at the
+/// beginning of a line, e.g. `* item`.
+const LINE_START_CHARS: [char; 3] = ['*', '-', '='];
+
+/// Delimiters of AsciiDoc blocks.
+const BLOCK_DELIMITERS: [&str; 4] = ["----", "....", "____", "|==="];
+
+/// URL schemes AsciiDoc recognizes without the `link:` macro.
+const URL_SCHEMES: [&str; 4] = ["https://", "http://", "ftp://", "mailto:"];
+
+/// Abstracts the HTML to AsciiDoc conversion.
+pub(crate) fn convert_html_to_adoc(html: &str) -> Result<String, NoteError> {
+    let adoc = parse_html_custom(html, &adoc_handlers());
+    let adoc = convert_footnotes(&convert_markdown_escapes(&adoc));
+    Ok(adoc.replace(BACKSLASH_PLACEHOLDER, "\\"))
+}
+
+/// The tag handlers overriding `html2md`'s Markdown handlers.
+fn adoc_handlers() -> HashMap<String, Box<dyn TagHandlerFactory>> {
+    let mut handlers: HashMap<String, Box<dyn TagHandlerFactory>> = HashMap::new();
+    for tag in ["h1", "h2", "h3", "h4", "h5", "h6"] {
+        handlers.insert(tag.to_string(), Box::new(AdocTitleHandler::default));
+    }
+    for tag in [
+        "b", "strong", "i", "em", "cite", "q", "code", "kbd", "mark", "sub", "sup",
+    ] {
+        handlers.insert(tag.to_string(), Box::new(AdocStyleHandler::default));
+    }
+    // Not supported by Tp-Note's AsciiDoc renderer, only the text is kept.
+    for tag in ["s", "del", "strike", "details", "summary"] {
+        handlers.insert(tag.to_string(), Box::new(DummyHandler::default));
+    }
+    handlers.insert("li".to_string(), Box::new(AdocListItemHandler::default));
+    handlers.insert("input".to_string(), Box::new(AdocCheckboxHandler::default));
+    handlers.insert("dt".to_string(), Box::new(AdocTermHandler::default));
+    handlers.insert("dd".to_string(), Box::new(AdocDefinitionHandler::default));
+    handlers.insert("a".to_string(), Box::new(AdocAnchorHandler::default));
+    handlers.insert("img".to_string(), Box::new(AdocImageHandler::default));
+    handlers.insert("pre".to_string(), Box::new(AdocListingHandler::default));
+    handlers.insert(
+        "blockquote".to_string(),
+        Box::new(AdocQuoteHandler::default),
+    );
+    handlers.insert("hr".to_string(), Box::new(AdocBreakHandler::default));
+    handlers.insert("br".to_string(), Box::new(AdocLineBreakHandler::default));
+    handlers.insert("table".to_string(), Box::new(AdocTableHandler::default));
+    handlers.insert("math".to_string(), Box::new(AdocMathHandler::default));
+    handlers
+}
+
+/// Section titles start with one `=` per level.
+#[derive(Default)]
+struct AdocTitleHandler {
+    start_pos: usize,
+    level: usize,
+}
+
+impl TagHandler for AdocTitleHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        printer.append_str("\n\n");
+        self.start_pos = printer.data.len();
+        self.level = match tag.data {
+            NodeData::Element { ref name, .. } => name.local[1..].parse().unwrap_or(1),
+            _ => 1,
+        };
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let title = printer.data.split_off(self.start_pos);
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        if title.is_empty() {
+            return;
+        }
+        printer.append_str(&"=".repeat(self.level.clamp(1, 6)));
+        printer.append_str(" ");
+        printer.append_str(&title);
+        printer.append_str("\n\n");
+    }
+}
+
+/// Writes inline markup, e.g. `*strong*` or `` `code` ``. The unconstrained
+/// form `**strong**` is used within words.
+#[derive(Default)]
+struct AdocStyleHandler {
+    start_pos: usize,
+    style_type: String,
+    /// A word character follows the element.
+    followed_by_word: bool,
+}
+
+impl TagHandler for AdocStyleHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        self.start_pos = printer.data.len();
+        self.style_type = element_name(tag);
+        self.followed_by_word = following_char(tag).is_some_and(char::is_alphanumeric);
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let delimiter = match self.style_type.as_str() {
+            "b" | "strong" => "*",
+            "i" | "em" | "cite" => "_",
+            "q" => "\"",
+            "code" | "kbd" => "`",
+            "mark" => "#",
+            "sub" => "~",
+            "sup" => "^",
+            _ => return,
+        };
+        if delimiter == "`" {
+            let code = printer.data.split_off(self.start_pos);
+            printer.append_str(&code.replace('\\', &BACKSLASH_PLACEHOLDER.to_string()));
+        }
+        // The delimiters enclose the text without its surrounding white space.
+        let Some(offset) = printer.data[self.start_pos..].find(|c: char| !c.is_whitespace()) else {
+            return;
+        };
+        let start = self.start_pos + offset;
+        let end = printer.data.trim_end().len();
+        let within_word = self.followed_by_word
+            || printer.data[..start]
+                .chars()
+                .next_back()
+                .is_some_and(char::is_alphanumeric);
+        let delimiter = if within_word && ["*", "_", "`", "#"].contains(&delimiter) {
+            delimiter.repeat(2)
+        } else {
+            delimiter.to_string()
+        };
+        printer.data.insert_str(end, &delimiter);
+        printer.data.insert_str(start, &delimiter);
+    }
+}
+
+/// List items start with `*` (unordered) or `.` (ordered), repeated once per
+/// nesting level. Further blocks of the item are attached with `+`.
+#[derive(Default)]
+struct AdocListItemHandler {
+    start_pos: usize,
+}
+
+impl TagHandler for AdocListItemHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        self.start_pos = printer.data.len();
+        let lists = printer
+            .parent_chain
+            .iter()
+            .filter(|tag| ["ul", "ol", "menu"].contains(&tag.as_str()))
+            .collect::<Vec<_>>();
+        let Some(list) = lists.last() else {
+            return;
+        };
+        let marker = if list.as_str() == "ol" { "." } else { "*" };
+        let marker = marker.repeat(lists.len().min(5));
+        // Items of the same list are not separated by blank lines.
+        let depth = printer.parent_chain.len();
+        if printer.siblings[&depth]
+            .last()
+            .is_some_and(|tag| tag == "li")
+        {
+            while printer.data.ends_with("\n\n") {
+                printer.data.pop();
+            }
+        }
+        if !printer.data.is_empty() && !printer.data.ends_with('\n') {
+            printer.append_str("\n");
+        }
+        printer.append_str(&marker);
+        printer.append_str(" ");
+        self.start_pos = printer.data.len();
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let item = printer.data.split_off(self.start_pos);
+        printer.append_str(&attach_blocks(&item));
+        printer.append_str("\n");
+    }
+}
+
+/// Writes a check list marker `[ ]` or `[x]` for a checkbox at the beginning
+/// of a list item. Other form elements are dropped.
+#[derive(Default)]
+struct AdocCheckboxHandler;
+
+impl TagHandler for AdocCheckboxHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        if get_tag_attr(tag, "type").is_none_or(|t| !t.eq_ignore_ascii_case("checkbox")) {
+            return;
+        }
+        // The list item marker must be the only thing on the line so far.
+        let line = printer
+            .data
+            .trim_end()
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default();
+        if line.is_empty() || !line.chars().all(|c| c == '*') {
+            return;
+        }
+        if get_tag_attr(tag, "checked").is_some() {
+            printer.append_str("[x] ");
+        } else {
+            printer.append_str("[ ] ");
+        }
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+}
+
+/// The term of a description list ends with `::`.
+#[derive(Default)]
+struct AdocTermHandler {
+    start_pos: usize,
+}
+
+impl TagHandler for AdocTermHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        if !printer.data.ends_with('\n') {
+            printer.append_str("\n");
+        }
+        self.start_pos = printer.data.len();
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let term = printer.data.split_off(self.start_pos);
+        let term = term.split_whitespace().collect::<Vec<_>>().join(" ");
+        printer.append_str(&term);
+        printer.append_str("::");
+    }
+}
+
+/// The definition follows its term on the same line. Further blocks and
+/// definitions are attached with `+`.
+#[derive(Default)]
+struct AdocDefinitionHandler {
+    start_pos: usize,
+}
+
+impl TagHandler for AdocDefinitionHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        if printer.data.ends_with("::") {
+            printer.append_str(" ");
+        } else {
+            // A blank line would end the description list.
+            while printer.data.ends_with('\n') {
+                printer.data.pop();
+            }
+            printer.append_str("\n+\n");
+        }
+        self.start_pos = printer.data.len();
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let definition = printer.data.split_off(self.start_pos);
+        printer.append_str(&attach_blocks(&definition));
+        printer.append_str("\n");
+    }
+}
+
+/// Writes links `https://example.com[text]`, `link:path[text]` and cross
+/// references `<<id,text>>`. Linked images get a `link` attribute instead.
+#[derive(Default)]
+struct AdocAnchorHandler {
+    start_pos: usize,
+    url: Option<String>,
+}
+
+impl TagHandler for AdocAnchorHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        self.start_pos = printer.data.len();
+        self.url = get_tag_attr(tag, "href").map(|href| {
+            percent_decode_str(&href)
+                .decode_utf8()
+                .map(|url| url.replace(' ', "%20"))
+                .unwrap_or(href)
+                .replace('\\', &BACKSLASH_PLACEHOLDER.to_string())
+        });
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let Some(url) = &self.url else {
+            return;
+        };
+        let text = printer.data.split_off(self.start_pos);
+        let leading = &text[..text.len() - text.trim_start().len()];
+        printer.append_str(leading);
+        let text = text.trim();
+        if text.starts_with("image:") && text.ends_with(']') && !text.contains('\n') {
+            let separator = if text.ends_with("[]") { "" } else { "," };
+            printer.append_str(&text[..text.len() - 1]);
+            printer.append_str(&format!("{separator}link={url}]"));
+            return;
+        }
+        let text = text.replace(']', "\\]");
+        if let Some(id) = url.strip_prefix('#') {
+            if text.is_empty() {
+                printer.append_str(&format!("<<{id}>>"));
+            } else {
+                printer.append_str(&format!("<<{id},{text}>>"));
+            }
+        } else if !URL_SCHEMES.iter().any(|scheme| url.starts_with(scheme)) {
+            printer.append_str(&format!("link:{url}[{text}]"));
+        } else if text.is_empty() || unescape_markdown(&text) == *url {
+            printer.append_str(url);
+        } else {
+            printer.append_str(&format!("{url}[{text}]"));
+        }
+    }
+}
+
+/// Images become inline image macros `image:src[alt,width,height]`.
+#[derive(Default)]
+struct AdocImageHandler;
+
+impl TagHandler for AdocImageHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        let Some(src) = get_tag_attr(tag, "src") else {
+            return;
+        };
+        let alt = get_tag_attr(tag, "alt")
+            .unwrap_or_default()
+            .replace(']', "");
+        let alt = if alt.contains(',') {
+            format!("\"{alt}\"")
+        } else {
+            alt
+        };
+        let mut attributes = vec![
+            alt,
+            get_tag_attr(tag, "width").unwrap_or_default(),
+            get_tag_attr(tag, "height").unwrap_or_default(),
+        ];
+        while attributes.last().is_some_and(String::is_empty) {
+            attributes.pop();
+        }
+        printer.append_str(&format!(
+            "image:{}[{}]",
+            src.replace(' ', "%20")
+                .replace('\\', &BACKSLASH_PLACEHOLDER.to_string()),
+            attributes.join(",")
+        ));
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+}
+
+/// Preformatted text becomes a literal block or, if the language is known,
+/// a source block.
+#[derive(Default)]
+struct AdocListingHandler;
+
+impl TagHandler for AdocListingHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        let language = tag
+            .children
+            .borrow()
+            .iter()
+            .chain(std::iter::once(tag))
+            .filter_map(|node| get_tag_attr(node, "class"))
+            .flat_map(|class| {
+                class
+                    .split_whitespace()
+                    .filter_map(|c| c.strip_prefix("language-"))
+                    .map(|c| c.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .next();
+        let delimiter = match language {
+            Some(language) => {
+                printer.append_str(&format!("\n\n[source,{language}]\n"));
+                "----"
+            }
+            None => {
+                printer.append_str("\n\n");
+                "...."
+            }
+        };
+        let code = text_content(tag).replace('\\', &BACKSLASH_PLACEHOLDER.to_string());
+        printer.append_str(&format!(
+            "{delimiter}\n{}\n{delimiter}\n\n",
+            code.trim_matches('\n')
+        ));
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+
+    fn skip_descendants(&self) -> bool {
+        true
+    }
+}
+
+/// Block quotes are enclosed in `____` delimiters.
+#[derive(Default)]
+struct AdocQuoteHandler {
+    start_pos: usize,
+}
+
+impl TagHandler for AdocQuoteHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        self.start_pos = printer.data.len();
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let quote = printer.data.split_off(self.start_pos);
+        printer.append_str("\n\n____\n");
+        printer.append_str(quote.trim());
+        printer.append_str("\n____\n\n");
+    }
+}
+
+/// A horizontal ruler becomes a thematic break.
+#[derive(Default)]
+struct AdocBreakHandler;
+
+impl TagHandler for AdocBreakHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        printer.append_str("\n\n'''\n\n");
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+}
+
+/// A hard line break is written as ` +` at the end of the line.
+#[derive(Default)]
+struct AdocLineBreakHandler;
+
+impl TagHandler for AdocLineBreakHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        printer.append_str(" +\n");
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+}
+
+/// Writes tables with one row per line. The first row is the header, when it
+/// consists of `<th>` cells only. Cells spanning several columns are padded
+/// with empty cells. A caption becomes the table's title.
+#[derive(Default)]
+struct AdocTableHandler;
+
+impl TagHandler for AdocTableHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut header = false;
+        for (i, row) in own_descendants(tag, &["tr"]).iter().enumerate() {
+            let cells = own_descendants(row, &["td", "th"]);
+            if i == 0 {
+                header = !cells.is_empty() && cells.iter().all(|cell| element_name(cell) == "th");
+            }
+            let mut texts = Vec::new();
+            for cell in cells {
+                texts.push(cell_text(&cell).replace('|', "\\|"));
+                let colspan = get_tag_attr(&cell, "colspan")
+                    .and_then(|n| n.trim().parse::<usize>().ok())
+                    .unwrap_or(1)
+                    .clamp(1, 1000);
+                texts.extend(std::iter::repeat_n(String::new(), colspan - 1));
+            }
+            rows.push(texts);
+        }
+        let column_count = rows.iter().map(|row| row.len()).max().unwrap_or_default();
+        if column_count == 0 {
+            return;
+        }
+        header = header && rows.len() > 1;
+
+        printer.append_str("\n\n");
+        if let Some(caption) = own_descendants(tag, &["caption"]).first() {
+            let caption = cell_text(caption);
+            if !caption.is_empty() {
+                printer.append_str(&format!(".{caption}\n"));
+            }
+        }
+        printer.append_str("|===\n");
+        for (i, row) in rows.iter().enumerate() {
+            let cells = (0..column_count)
+                .map(|index| {
+                    format!(
+                        "|{}",
+                        row.get(index).map(String::as_str).unwrap_or_default()
+                    )
+                })
+                .collect::<Vec<_>>();
+            printer.append_str(cells.join(" ").trim_end());
+            printer.append_str("\n");
+            // An implicit header row is followed by a blank line.
+            if header && i == 0 {
+                printer.append_str("\n");
+            }
+        }
+        printer.append_str("|===\n\n");
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+
+    fn skip_descendants(&self) -> bool {
+        true
+    }
+}
+
+/// Writes formulas as `latexmath:[...]` inline macro or as `latexmath`
+/// passthrough block.
+#[derive(Default)]
+struct AdocMathHandler;
+
+impl TagHandler for AdocMathHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        let Some((latex, display)) = math_latex(tag) else {
+            return;
+        };
+        let latex = latex.replace('\\', &BACKSLASH_PLACEHOLDER.to_string());
+        if display {
+            printer.append_str(&format!("\n\n[latexmath]\n++++\n{latex}\n++++\n\n"));
+        } else {
+            printer.append_str(&format!("latexmath:[{}]", latex.replace(']', "\\]")));
+        }
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+
+    fn skip_descendants(&self) -> bool {
+        true
+    }
+}
+
+/// Joins the blocks of a list item or definition with list continuation
+/// lines `+`. Nested list items and the content of delimited blocks are
+/// kept as they are.
+fn attach_blocks(text: &str) -> String {
+    let mut result = String::new();
+    let mut blank = false;
+    let mut delimiter: Option<&str> = None;
+    for line in text.trim().lines() {
+        if delimiter.is_none() && line.trim().is_empty() {
+            blank = !result.is_empty();
+            continue;
+        }
+        if !result.is_empty() {
+            result.push('\n');
+            if blank && !is_list_item(line) {
+                result.push_str("+\n");
+            }
+        }
+        result.push_str(line);
+        blank = false;
+        if let Some(d) = delimiter {
+            if line == d {
+                delimiter = None;
+            }
+        } else {
+            delimiter = BLOCK_DELIMITERS.into_iter().find(|d| line == *d);
+        }
+    }
+    result
+}
+
+/// Returns true if `line` starts with a list item marker, e.g. `** ` or `. `.
+fn is_list_item(line: &str) -> bool {
+    let marker_len = line
+        .chars()
+        .take_while(|c| *c == '*')
+        .count()
+        .max(line.chars().take_while(|c| *c == '.').count());
+    marker_len > 0 && line[marker_len..].starts_with(' ')
+}
+
+/// Converts the content of a table cell into one line of AsciiDoc.
+fn cell_text(cell: &Handle) -> String {
+    let mut printer = StructuredPrinter::default();
+    let handlers = adoc_handlers();
+    for child in cell.children.borrow().iter() {
+        walk(child, &mut printer, &handlers);
+    }
+    printer
+        .data
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Finds the descendants named `names`, without those of nested tables.
+fn own_descendants(tag: &Handle, names: &[&str]) -> Vec<Handle> {
+    let mut result = Vec::new();
+    for child in tag.children.borrow().iter() {
+        let name = element_name(child);
+        if names.contains(&name.as_str()) {
+            result.push(child.clone());
+        } else if name != "table" {
+            result.append(&mut own_descendants(child, names));
+        }
+    }
+    result
+}
+
+/// Returns the first character of the text following `tag`.
+fn following_char(tag: &Handle) -> Option<char> {
+    let parent = tag.parent.take()?;
+    tag.parent.set(Some(parent.clone()));
+    let parent = parent.upgrade()?;
+    let siblings = parent.children.borrow();
+    let position = siblings
+        .iter()
+        .position(|sibling| Rc::ptr_eq(sibling, tag))?;
+    siblings[position + 1..]
+        .iter()
+        .find_map(|sibling| text_content(sibling).chars().next())
+}
+
+/// Removes the backslashes `html2md` inserts to escape Markdown syntax.
+/// AsciiDoc interprets these characters only in pairs at word boundaries or
+/// at the beginning of a line.
+fn unescape_markdown(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\'
+            && let Some(&next) = chars.peek()
+            && MARKDOWN_ESCAPED.contains(&next)
+        {
+            result.push(next);
+            chars.next();
+            continue;
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Removes the backslashes `html2md` inserts to escape Markdown syntax, like
+/// `unescape_markdown()`. The escaped characters AsciiDoc would interpret
+/// are written as passthrough `++*++` instead: the `MARKUP_CHARS` where they
+/// could open inline markup and the `LINE_START_CHARS` where they would
+/// start a list item or a section title. Runs of the same character share
+/// one passthrough, e.g. `++**++`.
+fn convert_markdown_escapes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\'
+            && let Some(&escaped) = chars.peek()
+            && MARKDOWN_ESCAPED.contains(&escaped)
+        {
+            chars.next();
+            let prev = result.chars().next_back();
+            let next = chars.peek().copied();
+            let opens_markup = MARKUP_CHARS.contains(&escaped)
+                && prev.is_none_or(|p| !p.is_alphanumeric() && p != escaped)
+                && next.is_some_and(|n| !n.is_whitespace());
+            let starts_block = LINE_START_CHARS.contains(&escaped)
+                && prev.is_none_or(|p| p == '\n')
+                && next == Some(' ');
+            if !(opens_markup || starts_block) {
+                result.push(escaped);
+                continue;
+            }
+            let mut run = escaped.to_string();
+            loop {
+                let mut ahead = chars.clone();
+                if ahead.next() != Some('\\') || ahead.next() != Some(escaped) {
+                    break;
+                }
+                run.push(escaped);
+                chars = ahead;
+            }
+            result.push_str(&format!("++{run}++"));
+            continue;
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Rewrites `html2md`'s Markdown footnotes: the footnote bodies `[^label]:
+/// ...` at the end of the document are moved into the footnote macros
+/// `footnote:[...]` replacing their references `[^label]`. Footnotes
+/// referenced several times get an id: `footnote:label[...]` and
+/// `footnote:label[]`.
+fn convert_footnotes(text: &str) -> String {
+    // Collect the footnote bodies.
+    let mut bodies: HashMap<String, String> = HashMap::new();
+    let mut lines: Vec<&str> = Vec::new();
+    let mut current: Option<String> = None;
+    for line in text.lines() {
+        if let Some((label, body)) = footnote_body(line) {
+            current = Some(label.to_string());
+            bodies.insert(label.to_string(), body.trim().to_string());
+            continue;
+        }
+        if let Some(label) = &current
+            && (line.starts_with("    ") || line.trim().is_empty())
+        {
+            let body = bodies.entry(label.clone()).or_default();
+            if !line.trim().is_empty() {
+                body.push(' ');
+                body.push_str(line.trim());
+            }
+            continue;
+        }
+        current = None;
+        lines.push(line);
+    }
+    if bodies.is_empty() {
+        return text.to_string();
+    }
+    let text = lines.join("\n");
+    let text = text.trim_end();
+
+    // Replace the references.
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut used: HashMap<&str, usize> = HashMap::new();
+    while let Some(pos) = rest.find("[^") {
+        let after = &rest[pos + 2..];
+        let Some(end) = after.find(']') else {
+            break;
+        };
+        let label = &after[..end];
+        let Some(body) = bodies.get(label) else {
+            result.push_str(&rest[..pos + 2]);
+            rest = after;
+            continue;
+        };
+        result.push_str(&rest[..pos]);
+        let count = text.matches(&format!("[^{label}]")).count();
+        let uses = used.entry(label).or_default();
+        *uses += 1;
+        let body = body.replace(']', "\\]");
+        match (count, *uses) {
+            (1, _) => result.push_str(&format!("footnote:[{body}]")),
+            (_, 1) => result.push_str(&format!("footnote:{label}[{body}]")),
+            _ => result.push_str(&format!("footnote:{label}[]")),
+        }
+        rest = &after[end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Splits the first line of a footnote body `[^label]: text`.
+fn footnote_body(line: &str) -> Option<(&str, &str)> {
+    let (label, body) = line.strip_prefix("[^")?.split_once("]: ")?;
+    (!label.is_empty()
+        && label
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_'))
+    .then_some((label, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html2adoc::convert_html_to_adoc;
+
+    #[test]
+    fn test_convert_html_to_adoc() {
+        let input: &str = "<h1>My Title</h1>\
+            <p><strong>Bold</strong>, <em>italic</em>, <code>x = a\\*b</code> and \
+            H<sub>2</sub>O, see <a href=\"https://example.com/my%20uri\">the docs</a>, \
+            <a href=\"https://example.com\">https://example.com</a>, \
+            <a href=\"notes/other.md\">other</a> or <a href=\"#intro\">intro</a>.<br>\
+            snake_case * 2</p>\
+            <h2>Lists</h2>\
+            <ul><li>one<ul><li>nested</li></ul></li><li><p>two</p><p>more</p></li></ul>\
+            <ol><li>first</li><li>second</li></ol>\
+            <ul><li><input type=\"checkbox\" checked>done</li></ul>\
+            <dl><dt>Term</dt><dd>Definition</dd></dl>";
+        let expected: &str = "= My Title\n\n\
+            *Bold*, _italic_, `x = a\\*b` and H~2~O, see \
+            https://example.com/my%20uri[the docs], https://example.com, \
+            link:notes/other.md[other] or <<intro,intro>>. +\n\
+            snake_case * 2\n\n\
+            == Lists\n\n\
+            * one\n** nested\n* two\n+\nmore\n\n\
+            . first\n. second\n\n\
+            * [x] done\n\n\
+            Term:: Definition";
+
+        let result = convert_html_to_adoc(input);
+        assert_eq!(result.unwrap(), expected);
+
+        //
+        let input: &str = "<pre><code class=\"language-rust\">fn main() {\n\n}\n</code></pre>\
+            <pre>C:\\dir</pre>\
+            <blockquote><p>Quote</p></blockquote>\
+            <hr>\
+            <a href=\"/big.png\"><img src=\"/small.png\" alt=\"Small\" width=\"10\"></a>";
+        let expected: &str = "[source,rust]\n----\nfn main() {\n\n}\n----\n\n\
+            ....\nC:\\dir\n....\n\n____\nQuote\n____\n\n'''\n\n\
+            image:/small.png[Small,10,link=/big.png]";
+
+        let result = convert_html_to_adoc(input);
+        assert_eq!(result.unwrap(), expected);
+
+        //
+        let input: &str = "<table><caption>Prices</caption>\
+            <tr><th>Item</th><th>Price</th></tr>\
+            <tr><td>Apple</td><td>1</td></tr>\
+            <tr><td colspan=\"2\">Sold | out</td></tr></table>\
+            <p>Text<sup><a href=\"#fn1\">1</a></sup>.</p>\
+            <section class=\"footnotes\"><ol><li id=\"fn1\">The [note].</li></ol></section>";
+        let expected: &str = ".Prices\n|===\n|Item |Price\n\n|Apple |1\n|Sold \\| out |\n|===\n\n\
+            Textfootnote:[The [note\\].].";
+
+        let result = convert_html_to_adoc(input);
+        assert_eq!(result.unwrap(), expected);
+
+        //
+        let input: &str = "<p>Mass <math><mi>m</mi><annotation encoding=\"application/x-tex\">\
+            m_{[0]}</annotation></math>:</p>\
+            <math display=\"block\"><mfrac><mi>a</mi><mi>b</mi></mfrac></math>";
+        let expected: &str = "Mass latexmath:[m_{[0\\]}]:\n\n\
+            [latexmath]\n++++\n\\frac{a}{b}\n++++";
+
+        let result = convert_html_to_adoc(input);
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_convert_html_to_adoc_titles() {
+        let input: &str = "<h1>A</h1><h2>B</h2><h3>C</h3><h4>D</h4><h5>E</h5><h6>F</h6>";
+        let expected: &str = "= A\n\n== B\n\n=== C\n\n==== D\n\n===== E\n\n====== F";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+
+        // White space is collapsed, empty titles are dropped.
+        let input: &str = "<h2>Long   <em>title</em>\n text</h2><h1> </h1><p>x</p>";
+        let expected: &str = "== Long _title_ text\n\nx";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_convert_html_to_adoc_lists() {
+        let input: &str = "<ol><li>one</li><li>two<ol><li>a</li></ol></li></ol>\
+            <ul><li>x<ol><li>y</li></ol></li></ul>";
+        let expected: &str = ". one\n. two\n.. a\n\n* x\n.. y";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+
+        // Further blocks are attached.
+        let input: &str = "<ul><li><p>para</p><pre>code</pre></li><li>b</li></ul>";
+        let expected: &str = "* para\n+\n....\ncode\n....\n* b";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+
+        // Check lists.
+        let input: &str = "<ul><li><input type=\"checkbox\">open</li>\
+            <li><input type=\"checkbox\" checked>done</li>\
+            <li>text <input type=\"checkbox\"></li></ul>";
+        let expected: &str = "* [ ] open\n* [x] done\n* text";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+
+        // Description lists.
+        let input: &str = "<dl><dt>T1</dt><dd><p>D1</p><p>D2</p></dd><dd>E</dd>\
+            <dt>T2 <em>x</em></dt><dd>F</dd></dl>";
+        let expected: &str = "T1:: D1\n+\nD2\n+\nE\nT2 _x_:: F";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_convert_html_to_adoc_tables() {
+        // Header in `<thead>`, inline markup and cell separators.
+        let input: &str = "<table><thead><tr><th>A</th><th>B</th></tr></thead>\
+            <tbody><tr><td><strong>x</strong></td><td>a|b</td></tr></tbody></table>";
+        let expected: &str = "|===\n|A |B\n\n|*x* |a\\|b\n|===";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+
+        // No header, missing cells are filled. A single header row is no
+        // header.
+        let input: &str = "<table><tr><td>a</td><td>b</td></tr><tr><td>c</td></tr></table>\
+            <table><tr><th>h</th></tr></table>";
+        let expected: &str = "|===\n|a |b\n|c |\n|===\n\n|===\n|h\n|===";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+
+        // Empty tables are dropped.
+        let input: &str = "<p>x</p><table><tr></tr></table>";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), "x");
+    }
+
+    #[test]
+    fn test_convert_html_to_adoc_links() {
+        let input: &str = "<p><a href=\"https://a.invalid/c d\">x</a> \
+            <a href=\"https://a.invalid/%C3%A4\">y</a> <a href=\"#s\"></a> <a>no href</a> \
+            <a href=\"https://a.invalid\">[b]</a> <a href=\"C:\\x\">w</a> \
+            <a href=\"https://a.invalid/a_b\">https://a.invalid/a_b</a></p>";
+        let expected: &str = "https://a.invalid/c%20d[x] https://a.invalid/ä[y] <<s>> no href \
+            https://a.invalid[[b\\]] link:C:\\x[w] https://a.invalid/a_b";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+
+        // Images.
+        let input: &str = "<p><img src=\"/i j.png\" alt=\"a, b\" height=\"5\"> \
+            <img src=\"/k.png\"></p>";
+        let expected: &str = "image:/i%20j.png[\"a, b\",,5] image:/k.png[]";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_convert_html_to_adoc_code() {
+        let input: &str = "<p>Use <code>*a*</code>, <code>C:\\x</code> and <kbd>Ctrl</kbd>.</p>";
+        let expected: &str = "Use `*a*`, `C:\\x` and `Ctrl`.";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+
+        // Indentation, markup characters and backslashes are kept.
+        let input: &str = "<pre><code>a\n  *b* \\n</code></pre>\
+            <pre><code class=\"language-python\">print(1)</code></pre>";
+        let expected: &str = "....\na\n  *b* \\n\n....\n\n\
+            [source,python]\n----\nprint(1)\n----";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_convert_html_to_adoc_inline_markup() {
+        // Text that would be read as inline markup is passed through.
+        let input: &str = "<p>1 * 2, *a*, _b_, `c`, ~d~, a\\b, **e**, snake_case</p>";
+        let expected: &str = "1 * 2, ++*++a*, ++_++b_, ++`++c`, ++~++d~, a\\b, ++**++e**, \
+            snake_case";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+
+        // So is text that would start a list item or a section title.
+        let input: &str = "<p>* not a list</p><p>- dash</p><p>= not a title</p>";
+        let expected: &str = "++*++ not a list\n\n++-++ dash\n\n++=++ not a title";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+
+        // Unconstrained markup within words.
+        let input: &str = "<p>word<strong>bold</strong>word <em>a</em>, \
+            <mark>m</mark>k <code>c</code>s x<sup>2</sup>y <q>q</q> <s>gone</s></p>";
+        let expected: &str = "word**bold**word _a_, ##m##k ``c``s x^2^y \"q\" gone";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+
+        // Line breaks and thematic breaks.
+        let input: &str = "<p>a<br>b</p><hr><p>c</p>";
+        let expected: &str = "a +\nb\n\n'''\n\nc";
+        assert_eq!(convert_html_to_adoc(input).unwrap(), expected);
+    }
+}
@@ -1,6 +1,13 @@
-//! This module abstracts the HTML to Markdown filter.
+//! This module abstracts the HTML to Markdown and the HTML to Djot filter.
 use crate::error::NoteError;
-use html2md::parse_html;
+use html2md::common::get_tag_attr;
+use html2md::math::math_latex;
+use html2md::{
+    Handle, NodeData, StructuredPrinter, TagHandler, TagHandlerFactory, parse_html,
+    parse_html_custom,
+};
+use percent_encoding::percent_decode_str;
+use std::collections::HashMap;
 
 /*
 // Alternative implementation:
@@ -25,10 +32,177 @@ pub(crate) fn convert_html_to_md(html: &str) -> Result<String, NoteError> {
     Ok(parse_html(html))
 }
 
+/// Abstracts the HTML to Djot conversion.
+/// Most of `html2md`'s Markdown output is valid Djot. The tag handlers below
+/// replace the constructs Djot writes differently.
+pub(crate) fn convert_html_to_djot(html: &str) -> Result<String, NoteError> {
+    let mut handlers: HashMap<String, Box<dyn TagHandlerFactory>> = HashMap::new();
+    for tag in [
+        "b", "strong", "i", "em", "s", "del", "strike", "u", "ins", "mark", "sub", "sup",
+    ] {
+        handlers.insert(tag.to_string(), Box::new(DjotStyleHandler::default));
+    }
+    for tag in ["ol", "ul", "menu"] {
+        handlers.insert(tag.to_string(), Box::new(DjotListHandler::default));
+    }
+    handlers.insert("a".to_string(), Box::new(DjotAnchorHandler::default));
+    handlers.insert("br".to_string(), Box::new(DjotLineBreakHandler::default));
+    handlers.insert("dt".to_string(), Box::new(DjotTermHandler::default));
+    handlers.insert("dd".to_string(), Box::new(DjotDefinitionHandler::default));
+    handlers.insert("math".to_string(), Box::new(DjotMathHandler::default));
+    Ok(parse_html_custom(html, &handlers))
+}
+
+/// Writes Djot's inline containers, e.g. `_emphasis_` or `{=highlight=}`.
+#[derive(Default)]
+struct DjotStyleHandler {
+    start_pos: usize,
+    style_type: String,
+}
+
+impl TagHandler for DjotStyleHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        self.start_pos = printer.data.len();
+        self.style_type = match tag.data {
+            NodeData::Element { ref name, .. } => name.local.to_string(),
+            _ => String::new(),
+        };
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let (open, close) = match self.style_type.as_str() {
+            "b" | "strong" => ("*", "*"),
+            "i" | "em" => ("_", "_"),
+            "s" | "del" | "strike" => ("{-", "-}"),
+            "u" | "ins" => ("{+", "+}"),
+            "mark" => ("{=", "=}"),
+            "sub" => ("~", "~"),
+            "sup" => ("^", "^"),
+            _ => return,
+        };
+        // The delimiters enclose the text without its surrounding white space.
+        let Some(offset) = printer.data[self.start_pos..].find(|c: char| !c.is_whitespace()) else {
+            return;
+        };
+        let start = self.start_pos + offset;
+        let end = printer.data.trim_end().len();
+        printer.data.insert_str(end, close);
+        printer.data.insert_str(start, open);
+    }
+}
+
+/// Djot requires a blank line before a nested list.
+#[derive(Default)]
+struct DjotListHandler;
+
+impl TagHandler for DjotListHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        printer.insert_newline();
+        printer.insert_newline();
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        printer.insert_newline();
+        printer.insert_newline();
+    }
+}
+
+/// Djot link destinations are not enclosed in `<...>`. White space is
+/// percent encoded instead.
+#[derive(Default)]
+struct DjotAnchorHandler {
+    start_pos: usize,
+    url: Option<String>,
+}
+
+impl TagHandler for DjotAnchorHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        self.start_pos = printer.data.len();
+        self.url = get_tag_attr(tag, "href").map(|href| {
+            percent_decode_str(&href)
+                .decode_utf8()
+                .map(|url| url.replace(' ', "%20"))
+                .unwrap_or(href)
+        });
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        if let Some(url) = &self.url {
+            printer.insert_str(self.start_pos, "[");
+            printer.append_str(&format!("]({url})"));
+        }
+    }
+}
+
+/// A Djot hard line break is a backslash at the end of the line.
+#[derive(Default)]
+struct DjotLineBreakHandler;
+
+impl TagHandler for DjotLineBreakHandler {
+    fn handle(&mut self, _tag: &Handle, _printer: &mut StructuredPrinter) {}
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        printer.append_str("\\\n");
+    }
+}
+
+/// A Djot definition list item starts with `: term`.
+#[derive(Default)]
+struct DjotTermHandler;
+
+impl TagHandler for DjotTermHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        printer.append_str("\n\n: ");
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+}
+
+/// The definition follows the term after a blank line, indented.
+#[derive(Default)]
+struct DjotDefinitionHandler {
+    start_pos: usize,
+}
+
+impl TagHandler for DjotDefinitionHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        printer.append_str("\n\n");
+        self.start_pos = printer.data.len();
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let definition = printer.data.split_off(self.start_pos);
+        let definition = definition.trim().replace('\n', "\n  ");
+        printer.append_str("  ");
+        printer.append_str(&definition);
+        printer.append_str("\n\n");
+    }
+}
+
+/// Writes Djot math: `` $`...` `` inline and `` $$`...` `` displayed.
+#[derive(Default)]
+struct DjotMathHandler;
+
+impl TagHandler for DjotMathHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        match math_latex(tag) {
+            Some((latex, true)) => printer.append_str(&format!("\n\n$$`{latex}`\n\n")),
+            Some((latex, false)) => printer.append_str(&format!("$`{latex}`")),
+            None => {}
+        }
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+
+    fn skip_descendants(&self) -> bool {
+        true
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
-    use crate::html2md::convert_html_to_md;
+    use crate::html2md::{convert_html_to_djot, convert_html_to_md};
 
     #[test]
     fn test_convert_html_to_md() {
@@ -70,4 +244,47 @@ mod tests {
         let result = convert_html_to_md(input);
         assert_eq!(result.unwrap(), expected);
     }
+
+    #[test]
+    fn test_convert_html_to_djot() {
+        let input: &str = "<p><strong>Bold</strong>, <i>italic</i>, <del>deleted</del>, \
+            <mark>marked</mark> and H<sub>2</sub>O</p>";
+        let expected: &str = "*Bold*, _italic_, {-deleted-}, {=marked=} and H~2~O";
+
+        let result = convert_html_to_djot(input);
+        assert_eq!(result.unwrap(), expected);
+
+        //
+        let input: &str = r#"<p><a href="/my%20uri">link</a><br>next</p>"#;
+        let expected: &str = "[link](/my%20uri)\\\nnext";
+
+        let result = convert_html_to_djot(input);
+        assert_eq!(result.unwrap(), expected);
+
+        //
+        // Djot requires a blank line before a nested list.
+        let input: &str = "<ul><li>one<ul><li>nested</li></ul></li><li>two</li></ul>";
+        let expected: &str = "* one\n\n  * nested\n\n* two";
+
+        let result = convert_html_to_djot(input);
+        assert_eq!(result.unwrap(), expected);
+
+        //
+        let input: &str = "<dl><dt>Term</dt><dd><p>One</p><p>Two</p></dd></dl>\
+            <ul><li><input type=\"checkbox\" checked> done</li></ul>\
+            <p>Note<sup><a href=\"#fn1\">1</a></sup></p>\
+            <section class=\"footnotes\"><ol><li id=\"fn1\">Body</li></ol></section>";
+        let expected: &str = ": Term\n\n  One\n\n  Two\n\n* [x] done\n\nNote[^1]\n\n[^1]: Body";
+
+        let result = convert_html_to_djot(input);
+        assert_eq!(result.unwrap(), expected);
+
+        //
+        let input: &str = "<p>Mass <math><mi>m</mi><annotation encoding=\"application/x-tex\">m</annotation></math>:</p>\
+            <math display=\"block\"><msup><mi>c</mi><mn>2</mn></msup></math>";
+        let expected: &str = "Mass $`m`:\n\n$$`c^2`";
+
+        let result = convert_html_to_djot(input);
+        assert_eq!(result.unwrap(), expected);
+    }
 }
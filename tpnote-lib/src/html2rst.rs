@@ -0,0 +1,756 @@
+//! This module implements the HTML to reStructuredText filter.
+//! It reuses `html2md`'s DOM walker. The tag handlers below replace the
+//! handlers of the constructs reStructuredText writes differently than
+//! Markdown.
+use crate::error::NoteError;
+use html2md::common::{element_name, get_tag_attr, text_content};
+use html2md::dummy::DummyHandler;
+use html2md::footnotes::footnote_label;
+use html2md::math::math_latex;
+use html2md::{
+    Handle, NodeData, StructuredPrinter, TagHandler, TagHandlerFactory, parse_html_custom, walk,
+};
+use percent_encoding::percent_decode_str;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Underline characters of the section titles `<h1>` to `<h6>`.
+const TITLE_UNDERLINES: [char; 6] = ['=', '-', '~', '^', '"', '\''];
+
+/// Marks the end of inline markup, see `resolve_markup_ends()`.
+const MARKUP_END_MARKER: char = '\u{E000}';
+
+/// Characters that may follow the end of inline markup.
+const MARKUP_END_FOLLOWERS: &str = "-.,:;!?\\/'\")]}>";
+
+/// Abstracts the HTML to reStructuredText conversion.
+pub(crate) fn convert_html_to_rst(html: &str) -> Result<String, NoteError> {
+    let rst = parse_html_custom(html, &rst_handlers());
+    Ok(convert_footnotes(&resolve_markup_ends(&rst)))
+}
+
+/// The tag handlers overriding `html2md`'s Markdown handlers.
+fn rst_handlers() -> HashMap<String, Box<dyn TagHandlerFactory>> {
+    let mut handlers: HashMap<String, Box<dyn TagHandlerFactory>> = HashMap::new();
+    for tag in ["h1", "h2", "h3", "h4", "h5", "h6"] {
+        handlers.insert(tag.to_string(), Box::new(RstTitleHandler::default));
+    }
+    for tag in [
+        "b", "strong", "i", "em", "cite", "q", "code", "kbd", "sub", "sup",
+    ] {
+        handlers.insert(tag.to_string(), Box::new(RstStyleHandler::default));
+    }
+    // No equivalent in reStructuredText, only the text is kept.
+    for tag in ["s", "del", "strike", "details", "summary"] {
+        handlers.insert(tag.to_string(), Box::new(DummyHandler::default));
+    }
+    for tag in ["ol", "ul", "menu"] {
+        handlers.insert(tag.to_string(), Box::new(RstListHandler::default));
+    }
+    handlers.insert("a".to_string(), Box::new(RstAnchorHandler::default));
+    handlers.insert("img".to_string(), Box::new(RstImageHandler::default));
+    handlers.insert("pre".to_string(), Box::new(RstLiteralBlockHandler::default));
+    handlers.insert("blockquote".to_string(), Box::new(RstQuoteHandler::default));
+    handlers.insert("dd".to_string(), Box::new(RstDefinitionHandler::default));
+    handlers.insert("hr".to_string(), Box::new(RstTransitionHandler::default));
+    handlers.insert("br".to_string(), Box::new(RstLineBreakHandler::default));
+    handlers.insert("table".to_string(), Box::new(RstTableHandler::default));
+    handlers.insert("math".to_string(), Box::new(RstMathHandler::default));
+    handlers.insert(
+        "footnote".to_string(),
+        Box::new(RstFootnoteHandler::default),
+    );
+    handlers
+}
+
+/// Section titles are underlined, the underline character determines the
+/// level.
+#[derive(Default)]
+struct RstTitleHandler {
+    start_pos: usize,
+    level: usize,
+}
+
+impl TagHandler for RstTitleHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        printer.append_str("\n\n");
+        self.start_pos = printer.data.len();
+        self.level = match tag.data {
+            NodeData::Element { ref name, .. } => name.local[1..].parse().unwrap_or(1),
+            _ => 1,
+        };
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let title = resolve_markup_ends(&printer.data.split_off(self.start_pos));
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        if title.is_empty() {
+            return;
+        }
+        let underline = TITLE_UNDERLINES[self.level.clamp(1, 6) - 1];
+        printer.append_str(&title);
+        printer.append_str("\n");
+        printer.append_str(&underline.to_string().repeat(title.chars().count()));
+        printer.append_str("\n\n");
+    }
+}
+
+/// Writes inline markup, e.g. `**strong**` or ``` ``code`` ```.
+#[derive(Default)]
+struct RstStyleHandler {
+    start_pos: usize,
+    style_type: String,
+}
+
+impl TagHandler for RstStyleHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        self.start_pos = printer.data.len();
+        self.style_type = match tag.data {
+            NodeData::Element { ref name, .. } => name.local.to_string(),
+            _ => String::new(),
+        };
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let (open, close) = match self.style_type.as_str() {
+            "b" | "strong" => ("**", "**"),
+            "i" | "em" | "cite" => ("*", "*"),
+            "q" => ("\"", "\""),
+            "code" | "kbd" => ("``", "``"),
+            "sub" => (":sub:`", "`"),
+            "sup" => (":sup:`", "`"),
+            _ => return,
+        };
+        // The delimiters enclose the text without its surrounding white space.
+        let Some(offset) = printer.data[self.start_pos..].find(|c: char| !c.is_whitespace()) else {
+            return;
+        };
+        let start = self.start_pos + offset;
+        let end = printer.data.trim_end().len();
+        printer.data.insert(end, MARKUP_END_MARKER);
+        printer.data.insert_str(end, close);
+        printer.data.insert_str(start, open);
+        // Inline markup must not follow a word character directly.
+        if printer.data[..start]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphanumeric)
+        {
+            printer.data.insert_str(start, "\\ ");
+        }
+    }
+}
+
+/// Nested lists are separated by blank lines.
+#[derive(Default)]
+struct RstListHandler;
+
+impl TagHandler for RstListHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        printer.append_str("\n\n");
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        printer.append_str("\n\n");
+    }
+}
+
+/// Writes anonymous hyperlinks ``` `text <url>`__ ```. Linked images get a
+/// `:target:` option instead.
+#[derive(Default)]
+struct RstAnchorHandler {
+    start_pos: usize,
+    url: Option<String>,
+}
+
+impl TagHandler for RstAnchorHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        self.start_pos = printer.data.len();
+        self.url = get_tag_attr(tag, "href").map(|href| {
+            percent_decode_str(&href)
+                .decode_utf8()
+                .map(|url| url.replace(' ', "%20"))
+                .unwrap_or(href)
+        });
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let Some(url) = &self.url else {
+            return;
+        };
+        let text = printer.data.split_off(self.start_pos);
+        if text.contains(".. image::") {
+            printer.append_str(text.trim_end());
+            printer.append_str(&format!("\n   :target: {url}\n\n"));
+            return;
+        }
+        let leading = &text[..text.len() - text.trim_start().len()];
+        printer.append_str(leading);
+        let text = text.trim();
+        if text.is_empty() || text == url {
+            printer.append_str(url);
+        } else {
+            printer.append_str(&format!("`{text} <{url}>`__{MARKUP_END_MARKER}"));
+        }
+    }
+}
+
+/// Images become `image` directives.
+#[derive(Default)]
+struct RstImageHandler;
+
+impl TagHandler for RstImageHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        let Some(src) = get_tag_attr(tag, "src") else {
+            return;
+        };
+        printer.append_str(&format!("\n\n.. image:: {src}\n"));
+        if let Some(alt) = get_tag_attr(tag, "alt").filter(|alt| !alt.is_empty()) {
+            printer.append_str(&format!("   :alt: {alt}\n"));
+        }
+        for option in ["width", "height"] {
+            if let Some(value) = get_tag_attr(tag, option) {
+                printer.append_str(&format!("   :{option}: {value}\n"));
+            }
+        }
+        printer.append_str("\n");
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+}
+
+/// Preformatted text becomes a literal block or, if the language is known,
+/// a `code-block` directive.
+#[derive(Default)]
+struct RstLiteralBlockHandler;
+
+impl TagHandler for RstLiteralBlockHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        let language = tag
+            .children
+            .borrow()
+            .iter()
+            .chain(std::iter::once(tag))
+            .filter_map(|node| get_tag_attr(node, "class"))
+            .flat_map(|class| {
+                class
+                    .split_whitespace()
+                    .filter_map(|c| c.strip_prefix("language-"))
+                    .map(|c| c.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .next();
+        match language {
+            Some(language) => printer.append_str(&format!("\n\n.. code-block:: {language}\n\n")),
+            None => printer.append_str("\n\n::\n\n"),
+        }
+        let code = text_content(tag);
+        printer.append_str(&indent(code.trim_matches('\n'), "    "));
+        printer.append_str("\n\n");
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+
+    fn skip_descendants(&self) -> bool {
+        true
+    }
+}
+
+/// Block quotes are indented.
+#[derive(Default)]
+struct RstQuoteHandler {
+    start_pos: usize,
+}
+
+impl TagHandler for RstQuoteHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        self.start_pos = printer.data.len();
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let quote = printer.data.split_off(self.start_pos);
+        printer.append_str("\n\n");
+        printer.append_str(&indent(quote.trim(), "    "));
+        printer.append_str("\n\n");
+    }
+}
+
+/// The definition is indented below its term.
+#[derive(Default)]
+struct RstDefinitionHandler {
+    start_pos: usize,
+}
+
+impl TagHandler for RstDefinitionHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        if !printer.data.ends_with('\n') {
+            printer.append_str("\n");
+        }
+        self.start_pos = printer.data.len();
+    }
+
+    fn after_handle(&mut self, printer: &mut StructuredPrinter) {
+        let definition = printer.data.split_off(self.start_pos);
+        printer.append_str(&indent(definition.trim(), "    "));
+        printer.append_str("\n");
+    }
+}
+
+/// A horizontal ruler becomes a transition.
+#[derive(Default)]
+struct RstTransitionHandler;
+
+impl TagHandler for RstTransitionHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        printer.append_str("\n\n----------\n\n");
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+}
+
+/// reStructuredText has no line breaks within paragraphs.
+#[derive(Default)]
+struct RstLineBreakHandler;
+
+impl TagHandler for RstLineBreakHandler {
+    fn handle(&mut self, _tag: &Handle, printer: &mut StructuredPrinter) {
+        printer.append_str("\n");
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+}
+
+/// Writes grid tables. The first row is the header, when it consists of
+/// `<th>` cells only. Cells spanning several columns are padded with empty
+/// cells. A caption precedes the table as paragraph.
+#[derive(Default)]
+struct RstTableHandler;
+
+impl TagHandler for RstTableHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut header = false;
+        for (i, row) in own_descendants(tag, &["tr"]).iter().enumerate() {
+            let cells = own_descendants(row, &["td", "th"]);
+            if i == 0 {
+                header = !cells.is_empty() && cells.iter().all(|cell| element_name(cell) == "th");
+            }
+            let mut texts = Vec::new();
+            for cell in cells {
+                texts.push(cell_text(&cell));
+                let colspan = get_tag_attr(&cell, "colspan")
+                    .and_then(|n| n.trim().parse::<usize>().ok())
+                    .unwrap_or(1)
+                    .clamp(1, 1000);
+                texts.extend(std::iter::repeat_n(String::new(), colspan - 1));
+            }
+            rows.push(texts);
+        }
+        let column_count = rows.iter().map(|row| row.len()).max().unwrap_or_default();
+        if column_count == 0 {
+            return;
+        }
+        header = header && rows.len() > 1;
+
+        let mut widths = vec![1; column_count];
+        for row in &rows {
+            for (i, text) in row.iter().enumerate() {
+                widths[i] = widths[i].max(text.chars().count());
+            }
+        }
+        let border = |c: char| {
+            let mut line = String::from("+");
+            for width in &widths {
+                line.push_str(&c.to_string().repeat(width + 2));
+                line.push('+');
+            }
+            line.push('\n');
+            line
+        };
+
+        printer.append_str("\n\n");
+        if let Some(caption) = own_descendants(tag, &["caption"]).first() {
+            let caption = cell_text(caption);
+            if !caption.is_empty() {
+                printer.append_str(&caption);
+                printer.append_str("\n\n");
+            }
+        }
+        let mut table = border('-');
+        for (i, row) in rows.iter().enumerate() {
+            table.push('|');
+            for (index, width) in widths.iter().enumerate() {
+                let text = row.get(index).map(String::as_str).unwrap_or_default();
+                table.push(' ');
+                table.push_str(text);
+                table.push_str(&" ".repeat(width - text.chars().count() + 1));
+                table.push('|');
+            }
+            table.push('\n');
+            table.push_str(&border(if header && i == 0 { '=' } else { '-' }));
+        }
+        printer.append_str(&table);
+        printer.append_str("\n");
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+
+    fn skip_descendants(&self) -> bool {
+        true
+    }
+}
+
+/// Writes footnote references `[label]_`.
+#[derive(Default)]
+struct RstFootnoteHandler;
+
+impl TagHandler for RstFootnoteHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        let Some(label) = footnote_label(tag, printer) else {
+            return;
+        };
+        // Inline markup must not follow a word character directly.
+        if printer
+            .data
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphanumeric)
+        {
+            printer.append_str("\\ ");
+        }
+        printer.append_str(&format!(
+            "[{}]_{MARKUP_END_MARKER}",
+            rst_footnote_label(&label)
+        ));
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+
+    fn skip_descendants(&self) -> bool {
+        true
+    }
+}
+
+/// Writes the `math` role inline and the `math` directive for displayed
+/// formulas.
+#[derive(Default)]
+struct RstMathHandler;
+
+impl TagHandler for RstMathHandler {
+    fn handle(&mut self, tag: &Handle, printer: &mut StructuredPrinter) {
+        match math_latex(tag) {
+            Some((latex, true)) => {
+                printer.append_str(&format!("\n\n.. math::\n\n    {latex}\n\n"));
+            }
+            Some((latex, false)) => {
+                // Inline markup must not follow a word character directly.
+                if printer
+                    .data
+                    .chars()
+                    .next_back()
+                    .is_some_and(char::is_alphanumeric)
+                {
+                    printer.append_str("\\ ");
+                }
+                printer.append_str(&format!(":math:`{latex}`{MARKUP_END_MARKER}"));
+            }
+            None => {}
+        }
+    }
+
+    fn after_handle(&mut self, _printer: &mut StructuredPrinter) {}
+
+    fn skip_descendants(&self) -> bool {
+        true
+    }
+}
+
+/// Converts the content of a table cell into one line of reStructuredText.
+fn cell_text(cell: &Handle) -> String {
+    let mut printer = StructuredPrinter::default();
+    let handlers = rst_handlers();
+    for child in cell.children.borrow().iter() {
+        walk(child, &mut printer, &handlers);
+    }
+    resolve_markup_ends(&printer.data)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Finds the descendants named `names`, without those of nested tables.
+fn own_descendants(tag: &Handle, names: &[&str]) -> Vec<Handle> {
+    let mut result = Vec::new();
+    for child in tag.children.borrow().iter() {
+        let name = element_name(child);
+        if names.contains(&name.as_str()) {
+            result.push(child.clone());
+        } else if name != "table" {
+            result.append(&mut own_descendants(child, names));
+        }
+    }
+    result
+}
+
+/// Inline markup must be followed by white space or punctuation. Escapes the
+/// white space between the markup end `MARKUP_END_MARKER` and a following
+/// word character, e.g. ``` **bold**\ word ```, and removes the markers.
+fn resolve_markup_ends(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != MARKUP_END_MARKER {
+            result.push(c);
+        } else if chars.peek().is_some_and(|&c| {
+            !(c.is_whitespace() || c == MARKUP_END_MARKER || MARKUP_END_FOLLOWERS.contains(c))
+        }) {
+            result.push_str("\\ ");
+        }
+    }
+    result
+}
+
+/// Prepends `prefix` to all non-empty lines of `text`.
+fn indent(text: &str, prefix: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                String::new()
+            } else {
+                format!("{prefix}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the reStructuredText label of the footnote `label`. Labels that
+/// are not numbers are auto-numbered: `#label`.
+fn rst_footnote_label(label: &str) -> String {
+    if label.chars().all(|c| c.is_ascii_digit()) {
+        label.to_string()
+    } else {
+        format!("#{label}")
+    }
+}
+
+/// Rewrites the footnote bodies `[^label]: ...`, which `html2md` appends at
+/// the end of the document, to `.. [label] ...`. `RstFootnoteHandler` writes
+/// the references.
+fn convert_footnotes(text: &str) -> String {
+    text.split_inclusive('\n')
+        .map(|line| {
+            let body = line.strip_prefix("[^").and_then(|rest| {
+                let (label, body) = rest.split_once("]: ")?;
+                (!label.is_empty()
+                    && label
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '-' || c == '_'))
+                .then(|| format!(".. [{}] {body}", rst_footnote_label(label)))
+            });
+            body.map_or(Cow::Borrowed(line), Cow::Owned)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html2rst::convert_html_to_rst;
+
+    #[test]
+    fn test_convert_html_to_rst() {
+        let input: &str = "<h1>My Title</h1>\
+            <p><strong>Bold</strong>, <em>italic</em>, <code>x = 1</code> and \
+            H<sub>2</sub>O, see <a href=\"https://example.com/my%20uri\">the docs</a> \
+            or <a href=\"https://example.com\">https://example.com</a>.</p>\
+            <h2>Lists</h2>\
+            <ul><li>one<ul><li>nested</li></ul></li><li>two</li></ul>\
+            <dl><dt>Term</dt><dd>Definition</dd></dl>";
+        let expected: &str = "My Title\n========\n\n\
+            **Bold**, *italic*, ``x = 1`` and H\\ :sub:`2`\\ O, see \
+            `the docs <https://example.com/my%20uri>`__ or https://example.com.\n\n\
+            Lists\n-----\n\n\
+            * one\n\n  * nested\n\n* two\n\n\
+            Term\n    Definition";
+
+        let result = convert_html_to_rst(input);
+        assert_eq!(result.unwrap(), expected);
+
+        //
+        let input: &str = "<pre><code class=\"language-rust\">fn main() {\n\n}\n</code></pre>\
+            <pre>plain</pre>\
+            <blockquote><p>Quote</p></blockquote>\
+            <a href=\"/big.png\"><img src=\"/small.png\" alt=\"Small\"></a>";
+        let expected: &str = ".. code-block:: rust\n\n    fn main() {\n\n    }\n\n\
+            ::\n\n    plain\n\n    Quote\n\n\
+            .. image:: /small.png\n   :alt: Small\n   :target: /big.png";
+
+        let result = convert_html_to_rst(input);
+        assert_eq!(result.unwrap(), expected);
+
+        //
+        let input: &str = "<table><caption>Prices</caption>\
+            <tr><th>Item</th><th>Price</th></tr>\
+            <tr><td>Apple</td><td>1</td></tr>\
+            <tr><td colspan=\"2\">Sold out</td></tr></table>\
+            <p>Text<sup><a href=\"#fn1\">1</a></sup>.</p>\
+            <section class=\"footnotes\"><ol><li id=\"fn1\">The note.</li></ol></section>";
+        let expected: &str = "Prices\n\n\
+            +----------+-------+\n\
+            | Item     | Price |\n\
+            +==========+=======+\n\
+            | Apple    | 1     |\n\
+            +----------+-------+\n\
+            | Sold out |       |\n\
+            +----------+-------+\n\n\
+            Text\\ [1]_.\n\n\
+            .. [1] The note.";
+
+        let result = convert_html_to_rst(input);
+        assert_eq!(result.unwrap(), expected);
+
+        //
+        let input: &str = "<p>Mass<math><mi>m</mi></math>:</p>\
+            <math display=\"block\"><msup><mi>c</mi><mn>2</mn></msup></math>";
+        let expected: &str = "Mass\\ :math:`m`:\n\n.. math::\n\n    c^2";
+
+        let result = convert_html_to_rst(input);
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_convert_html_to_rst_titles() {
+        let input: &str = "<h1>A</h1><h2>B</h2><h3>C</h3><h4>D</h4><h5>E</h5><h6>F</h6>";
+        let expected: &str = "A\n=\n\nB\n-\n\nC\n~\n\nD\n^\n\nE\n\"\n\nF\n'";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        // The underline is as long as the title with collapsed white space.
+        let input: &str = "<h2>Long   <em>title</em>\n text</h2><p>x</p>";
+        let expected: &str = "Long *title* text\n-----------------\n\nx";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        // The escaped white space counts.
+        let input: &str = "<h1><code>a</code>b</h1>";
+        let expected: &str = "``a``\\ b\n========";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        // Empty titles are dropped.
+        let input: &str = "<h1> </h1><p>x</p>";
+        assert_eq!(convert_html_to_rst(input).unwrap(), "x");
+    }
+
+    #[test]
+    fn test_convert_html_to_rst_lists() {
+        let input: &str = "<ol><li>one</li><li>two<ol><li>a</li></ol></li></ol>";
+        let expected: &str = "1. one\n2. two\n\n   1. a";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        let input: &str = "<ul><li><p>para one</p><p>para two</p></li><li>b</li></ul>";
+        let expected: &str = "* para one\n\n  para two\n\n* b";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        let input: &str = "<p>Before</p><dl><dt>Term</dt><dd><p>Line 1</p><p>Line 2</p></dd></dl>";
+        let expected: &str = "Before\n\nTerm\n    Line 1\n\n    Line 2";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_convert_html_to_rst_tables() {
+        // Header in `<thead>`, inline markup and multi-byte characters.
+        let input: &str = "<table><thead><tr><th>A</th><th>B</th></tr></thead>\
+            <tbody><tr><td><strong>x</strong></td><td>äöü</td></tr></tbody></table>";
+        let expected: &str = "+-------+-----+\n\
+            | A     | B   |\n\
+            +=======+=====+\n\
+            | **x** | äöü |\n\
+            +-------+-----+";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        // No header, missing cells are filled.
+        let input: &str = "<table><tr><td>a</td><td>b</td></tr><tr><td>c</td></tr></table>";
+        let expected: &str = "+---+---+\n| a | b |\n+---+---+\n| c |   |\n+---+---+";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        // A single header row is no header.
+        let input: &str = "<table><tr><th>a</th></tr></table>";
+        let expected: &str = "+---+\n| a |\n+---+";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        // Empty tables are dropped.
+        let input: &str = "<p>x</p><table><tr></tr></table>";
+        assert_eq!(convert_html_to_rst(input).unwrap(), "x");
+    }
+
+    #[test]
+    fn test_convert_html_to_rst_links() {
+        let input: &str = "<p><a href=\"https://a.invalid/c d\">x</a>, \
+            <a href=\"https://a.invalid/%C3%A4\">y</a>, <a href=\"#sec\">sec</a>, \
+            <a>no href</a>, <a href=\"https://a.invalid\"> spaced </a>.</p>";
+        let expected: &str = "`x <https://a.invalid/c%20d>`__, `y <https://a.invalid/ä>`__, \
+            `sec <#sec>`__, no href,  `spaced <https://a.invalid>`__.";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        // A link followed by a word character.
+        let input: &str = "<p><a href=\"https://a.invalid\">x</a>s</p>";
+        let expected: &str = "`x <https://a.invalid>`__\\ s";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        // Images.
+        let input: &str = "<p><img src=\"/i.png\" width=\"10\" height=\"20\" alt=\"\"></p>";
+        let expected: &str = ".. image:: /i.png\n   :width: 10\n   :height: 20";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_convert_html_to_rst_code() {
+        let input: &str = "<p>Use <code>a*b</code> and <kbd>Ctrl</kbd>.</p>";
+        let expected: &str = "Use ``a*b`` and ``Ctrl``.";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        // Indentation and markup characters are kept.
+        let input: &str = "<pre><code>a\n  *b*</code></pre>";
+        let expected: &str = "::\n\n    a\n      *b*";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        let input: &str = "<pre><code class=\"language-python\">print(1)</code></pre>";
+        let expected: &str = ".. code-block:: python\n\n    print(1)";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_convert_html_to_rst_footnotes() {
+        // Only the footnote references are rewritten, not literal text.
+        let input: &str = "<p>A<sup><a href=\"#n\">[a]</a></sup>b, [^x] and <code>[^x]</code>.</p>\
+            <pre>[^x]: y</pre>\
+            <section class=\"footnotes\"><ol><li id=\"n\">Note.</li></ol></section>";
+        let expected: &str = "A\\ [#a]_\\ b, [^x] and ``[^x]``.\n\n\
+            ::\n\n    [^x]: y\n\n\
+            .. [#a] Note.";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_convert_html_to_rst_inline_markup() {
+        // Characters with meaning in reStructuredText are escaped.
+        let input: &str = "<p>1 * 2 * 3, _a_, `tick`, a\\b, [x]_</p>";
+        let expected: &str = "1 \\* 2 \\* 3, \\_a\\_, \\`tick\\`, a\\\\b, [x]\\_";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        // Inline markup within words is separated by escaped white space.
+        let input: &str = "<p>word<strong>bold</strong>word <em>a</em><em>b</em> \
+            x<sup>2</sup>y x<sub>i</sub>. <q>quoted</q> <s>gone</s></p>";
+        let expected: &str = "word\\ **bold**\\ word *a*\\ *b* \
+            x\\ :sup:`2`\\ y x\\ :sub:`i`. \"quoted\" gone";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        // Surrounding white space is moved out of the markup.
+        let input: &str = "<p>a<em> b </em>c</p>";
+        let expected: &str = "a *b* c";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+
+        // Line breaks and transitions.
+        let input: &str = "<p>a<br>b</p><hr><p>c</p>";
+        let expected: &str = "a\nb\n\n----------\n\nc";
+        assert_eq!(convert_html_to_rst(input).unwrap(), expected);
+    }
+}
@@ -1,35 +1,232 @@
 //! Tp-Note's high level HTML rendering API.
 //!
 //! A set of functions that take a `Context` type and a `Content` type (or raw
-//! text) and return the HTML rendition of the content. The API is completely
-//! stateless. All functions read the `LIB_CFG` global variable to read the
+//! text) and return the HTML rendition of the content. The API is stateless,
+//! except for `HtmlRenderer::save_exporter_site()`, which keeps the state of
+//! the last export in the file `EXPORT_CACHE_FILENAME` in the export
+//! directory. All functions read the `LIB_CFG` global variable to read the
 //! configuration stored in `LibCfg.tmpl_html`.
 
+use crate::config::FILENAME_ROOT_PATH_MARKER;
 use crate::config::LIB_CFG;
 use crate::config::LocalLinkKind;
 use crate::content::Content;
 use crate::context::Context;
 use crate::context::HasSettings;
 use crate::error::NoteError;
-#[cfg(feature = "viewer")]
+use crate::filename::NotePath;
 use crate::filter::TERA;
 use crate::html::HTML_EXT;
+use crate::html::assemble_link;
+use crate::html::note_links_of;
 use crate::html::rewrite_links;
 use crate::note::Note;
-#[cfg(feature = "viewer")]
 use crate::note_error_tera_template;
 use crate::template::TemplateKind;
 use parking_lot::RwLock;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashSet;
+use std::fs;
 use std::fs::OpenOptions;
 use std::io;
 use std::io::Write;
+use std::path::MAIN_SEPARATOR_STR;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
-#[cfg(feature = "viewer")]
+use std::time::UNIX_EPOCH;
 use tera::Tera;
 
+/// The filename of the index pages generated by
+/// `HtmlRenderer::save_exporter_site()` in every exported directory.
+pub const INDEX_HTML_FILENAME: &str = "index.html";
+
+/// The filename of the file in the export directory, where
+/// `HtmlRenderer::save_exporter_site()` remembers the state of the last export.
+/// Delete this file to force a full rebuild.
+pub const EXPORT_CACHE_FILENAME: &str = ".tpnote-export-cache.toml";
+
+/// Result of `HtmlRenderer::save_exporter_site()`.
+#[derive(Debug, Default)]
+pub struct SiteSummary {
+    /// Note files that have been rendered: `(note_path, html_path)`.
+    pub exported: Vec<(PathBuf, PathBuf)>,
+    /// Note files whose HTML rendition was up to date.
+    pub unchanged: Vec<PathBuf>,
+    /// Referenced asset files copied into the export directory
+    /// (destination paths).
+    pub copied: Vec<PathBuf>,
+    /// Generated directory index pages.
+    pub indexes: Vec<PathBuf>,
+    /// Outdated files deleted from the export directory, e.g. the HTML
+    /// rendition of a removed note.
+    pub removed: Vec<PathBuf>,
+    /// Note files, assets or directories that could not be exported and the
+    /// reason why.
+    pub failed: Vec<(PathBuf, NoteError)>,
+}
+
+/// Returns the 64-bit FNV-1a hash of the byte strings in `data` as hex
+/// string. Every string is terminated by `0xff`. Unlike `DefaultHasher`, the
+/// result does not change between Rust releases, which matters for the
+/// hashes persisted in `EXPORT_CACHE_FILENAME`.
+fn stable_hash<'a>(data: impl IntoIterator<Item = &'a [u8]>) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = OFFSET_BASIS;
+    for bytes in data {
+        for b in bytes.iter().chain([0xff].iter()) {
+            hash ^= u64::from(*b);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+/// What `HtmlRenderer::save_exporter_site()` remembers about an exported
+/// note.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ExportCacheEntry {
+    /// Hash of the note file's content.
+    content_hash: String,
+    /// The note's `Context::get_doc_file_date()` in seconds since the Unix
+    /// epoch.
+    doc_file_date: Option<u64>,
+    /// The note's local links, relative to the source directory.
+    links: BTreeSet<PathBuf>,
+    /// The links of the note's body to other notes, with the source
+    /// directory as root, e.g. `/dir/my note.md`. The backlinks are built
+    /// from them.
+    note_links: BTreeSet<PathBuf>,
+    /// The note has wiki links or broken links, which may resolve
+    /// differently when notes are added or removed.
+    resolves_by_name: bool,
+}
+
+impl ExportCacheEntry {
+    /// Reads the note file `doc_path` and records its content hash and
+    /// `doc_file_date`. The links are left empty.
+    fn from(doc_path: &Path) -> Result<Self, NoteError> {
+        let content_hash = stable_hash([fs::read(doc_path)?.as_slice()]);
+        let doc_file_date = Context::from(doc_path)?
+            .get_doc_file_date()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        Ok(Self {
+            content_hash,
+            doc_file_date,
+            ..Default::default()
+        })
+    }
+}
+
+/// The state of the last `HtmlRenderer::save_exporter_site()` run, stored in
+/// `EXPORT_CACHE_FILENAME`. All paths are relative to the source or export
+/// directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ExportCache {
+    /// Hash of Tp-Note's version, the `LIB_CFG` configuration and the export
+    /// parameters. When it changes, all notes are rendered again.
+    config_fingerprint: String,
+    /// Hash of the paths of all notes. When a note is added or removed, the
+    /// notes with wiki links, broken links or links to removed notes are
+    /// rendered again.
+    note_set_hash: String,
+    /// Copied asset files.
+    assets: BTreeSet<PathBuf>,
+    /// Directories with a generated index page.
+    indexes: BTreeSet<PathBuf>,
+    /// Exported notes.
+    notes: BTreeMap<PathBuf, ExportCacheEntry>,
+}
+
+impl ExportCache {
+    /// Reads the cache file `path`. Returns `None` if the file is missing or
+    /// can not be parsed.
+    fn load(path: &Path) -> Option<Self> {
+        let cache = fs::read_to_string(path).ok()?;
+        toml::from_str(&cache)
+            .inspect_err(|e| log::info!("Ignoring export cache {:?}: {}", path, e))
+            .ok()
+    }
+
+    /// Writes the cache file `path`.
+    fn save(&self, path: &Path) -> Result<(), NoteError> {
+        let cache = toml::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, cache)?;
+        Ok(())
+    }
+
+    /// Hashes all settings the HTML renditions depend on, including the
+    /// files in `tmpl_html.highlighting_dir`.
+    fn config_fingerprint(local_link_kind: LocalLinkKind, asset_extensions: &[&str]) -> String {
+        let lib_cfg = LIB_CFG.read_recursive();
+        // The order of the schemes is not guaranteed.
+        let mut schemes: Vec<String> = lib_cfg
+            .scheme
+            .iter()
+            .map(|s| serde_json::to_string(s).unwrap_or_default())
+            .collect();
+        schemes.sort();
+
+        let tmpl_html = serde_json::to_string(&lib_cfg.tmpl_html).unwrap_or_default();
+        let local_link_kind = serde_json::to_string(&local_link_kind).unwrap_or_default();
+        let mut highlighting_files = Vec::new();
+        if !lib_cfg.tmpl_html.highlighting_dir.is_empty() {
+            Self::collect_files(
+                Path::new(&lib_cfg.tmpl_html.highlighting_dir),
+                &mut highlighting_files,
+            );
+        }
+        highlighting_files.sort();
+        let highlighting: Vec<Vec<u8>> = highlighting_files
+            .iter()
+            .flat_map(|path| {
+                [
+                    path.as_os_str().as_encoded_bytes().to_vec(),
+                    fs::read(path).unwrap_or_default(),
+                ]
+            })
+            .collect();
+        stable_hash(
+            [
+                env!("CARGO_PKG_VERSION"),
+                &lib_cfg.scheme_sync_default,
+                &tmpl_html,
+                &local_link_kind,
+            ]
+            .into_iter()
+            .chain(schemes.iter().map(String::as_str))
+            .chain(asset_extensions.iter().copied())
+            .map(str::as_bytes)
+            .chain(highlighting.iter().map(Vec::as_slice)),
+        )
+    }
+
+    /// Helper function for `config_fingerprint()`: appends the paths of all
+    /// files in `dir` and its subdirectories to `files`.
+    fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.is_dir() {
+                Self::collect_files(&path, files);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    /// Hashes the note paths `rel_paths`.
+    fn note_set_hash(rel_paths: &BTreeSet<PathBuf>) -> String {
+        stable_hash(rel_paths.iter().map(|p| p.as_os_str().as_encoded_bytes()))
+    }
+}
+
 /// High level API to render a note providing its `content` and some `context`.
 pub struct HtmlRenderer;
 
@@ -43,7 +240,7 @@ impl HtmlRenderer {
     /// The string `viewer_doc_js` contains JavaScript live update code that
     /// will be injected into the HTML page via the
     /// `TMPL_HTML_VAR_DOC_VIEWER_JS` template variable.
-    /// This function is stateless.
+    /// Unlike `save_exporter_site()`, this function keeps no state.
     ///
     /// ```rust
     /// use tpnote_lib::content::Content;
@@ -122,7 +319,7 @@ impl HtmlRenderer {
     /// `context` is expected to have at least all `HasSettings` keys
     /// and the additional key `TMPL_HTML_VAR_VIEWER_DOC_JS` set and valid.
     /// All other keys are ignored.
-    /// This function is stateless.
+    /// Unlike `save_exporter_site()`, this function keeps no state.
     ///
     /// ```rust
     /// use tpnote_lib::config::TMPL_HTML_VAR_VIEWER_DOC_JS;
@@ -180,7 +377,7 @@ impl HtmlRenderer {
     /// The string `viewer_doc_js` contains JavaScript live update code that
     /// will be injected into the HTML page via the
     /// `TMPL_HTML_VAR_DOC_VIEWER_JS` template variable.
-    /// This function is stateless.
+    /// Unlike `save_exporter_site()`, this function keeps no state.
     ///
     /// ```rust
     /// use tpnote_lib::config::LIB_CFG;
@@ -242,6 +439,48 @@ impl HtmlRenderer {
         Ok(html)
     }
 
+    /// Returns the HTML index page of the directory `context.path` rendered
+    /// with the `tmpl_html.exporter_index` template. `index_dirs` lists
+    /// the names of the subdirectories and `index_notes` the filenames of the
+    /// Tp-Note files to be linked from the index page.
+    /// Unlike `save_exporter_site()`, this function keeps no state.
+    ///
+    /// ```rust
+    /// use tpnote_lib::context::Context;
+    /// use tpnote_lib::html_renderer::HtmlRenderer;
+    /// use std::path::Path;
+    ///
+    /// let context = Context::from(Path::new("/path/to/notes")).unwrap();
+    /// let html = HtmlRenderer::exporter_index_page(
+    ///     context,
+    ///     &["sub".to_string()],
+    ///     &["01-My day#1--Note.md".to_string()],
+    /// )
+    /// .unwrap();
+    /// assert!(html.starts_with("<!DOCTYPE html>\n<html"));
+    /// assert!(html.contains(r#"<a href="sub/index.html">sub/</a>"#));
+    /// assert!(html.contains(
+    ///     r#"<a href="01-My%20day%231--Note.md.html">My day#1--Note</a>"#));
+    /// ```
+    pub fn exporter_index_page(
+        context: Context<HasSettings>,
+        index_dirs: &[String],
+        index_notes: &[String],
+    ) -> Result<String, NoteError> {
+        //
+        let context = context.insert_dir_index(index_dirs, index_notes);
+
+        let tmpl_html = &LIB_CFG.read_recursive().tmpl_html.exporter_index;
+
+        // Apply template.
+        let mut tera = Tera::default();
+        tera.register_from(&TERA);
+        let html = tera
+            .render_str(tmpl_html, &context, true)
+            .map_err(|e| note_error_tera_template!(e, "[html_tmpl] exporter_index".to_string()))?;
+        Ok(html)
+    }
+
     /// Renders `doc_path` with `content` into HTML and saves the result in
     /// `export_dir` in case `export_dir` is an absolute directory. Otherwise
     /// the parent directory of `doc_path` is concatenated with `export_dir`
@@ -343,4 +582,622 @@ impl HtmlRenderer {
         }
         Ok(())
     }
+
+    /// Renders all Tp-Note files in `src_dir` and its subdirectories into
+    /// HTML and saves the result in `export_dir`, mirroring the directory
+    /// structure of `src_dir`. The filename of each HTML rendition is the
+    /// note's filename with `.html` appended. `src_dir` and `export_dir` are
+    /// expected to be absolute paths.
+    ///
+    /// Local links are rewritten according to `local_link_kind`, with
+    /// `src_dir` as root directory: `LocalLinkKind::Off` keeps relative
+    /// links, which is what you want when browsing the result with
+    /// `file://`. `LocalLinkKind::Short` results in links absolute to the
+    /// site's root, suitable for a web server serving `export_dir`.
+    /// Links to Tp-Note files get `.html` appended.
+    ///
+    /// Files referenced by some note are copied into `export_dir` if they
+    /// are located under `src_dir` and if their extension is listed in
+    /// `asset_extensions` (case insensitive), e.g. the extensions of
+    /// `viewer.served_mime_types`.
+    /// Finally, every exported directory gets an `index.html` page listing
+    /// its subdirectories and notes (cf. `exporter_index_page()`), unless a
+    /// copied asset has this name already.
+    ///
+    /// The export is incremental: the state of the last run is kept in
+    /// `export_dir` in the file `EXPORT_CACHE_FILENAME`. A note is rendered
+    /// again only when its content hash or its `doc_file_date` changed, when
+    /// a note it links to or its backlinks changed, when its wiki links or
+    /// broken links may resolve differently because a note was added or
+    /// removed, or when the configuration in `LIB_CFG` changed.
+    /// The HTML renditions of removed
+    /// notes, assets no longer referenced and empty index pages are deleted.
+    /// Assets are copied only when the copy is outdated.
+    ///
+    /// An error concerning a single note, asset or index page does not abort
+    /// the export: it is recorded in `SiteSummary::failed`. Notes located
+    /// in `export_dir` are skipped.
+    ///
+    /// ```rust
+    /// use tpnote_lib::config::LocalLinkKind;
+    /// use tpnote_lib::content::ContentString;
+    /// use tpnote_lib::html_renderer::HtmlRenderer;
+    /// use std::env::temp_dir;
+    /// use std::fs;
+    ///
+    /// // Prepare test: create a note referencing an image.
+    /// let src_dir = temp_dir().join("tpnote_doc_site_src");
+    /// let export_dir = temp_dir().join("tpnote_doc_site_out");
+    /// let _ = fs::remove_dir_all(&src_dir);
+    /// let _ = fs::remove_dir_all(&export_dir);
+    /// fs::create_dir_all(src_dir.join("sub")).unwrap();
+    /// fs::write(
+    ///     src_dir.join("sub/01-Note--A.md"),
+    ///     "---\ntitle: Note\nsubtitle: A\n---\n![pic](img.png)\n",
+    /// )
+    /// .unwrap();
+    /// fs::write(src_dir.join("sub/img.png"), b"png").unwrap();
+    ///
+    /// // Start test
+    /// let summary = HtmlRenderer::save_exporter_site::<ContentString>(
+    ///     &src_dir, &export_dir, LocalLinkKind::Off, &["png"]).unwrap();
+    /// assert!(summary.failed.is_empty());
+    /// assert!(export_dir.join("sub/01-Note--A.md.html").is_file());
+    /// assert!(export_dir.join("sub/img.png").is_file());
+    /// assert!(export_dir.join("index.html").is_file());
+    /// assert!(export_dir.join("sub/index.html").is_file());
+    ///
+    /// // Nothing changed: nothing is rendered again.
+    /// let summary = HtmlRenderer::save_exporter_site::<ContentString>(
+    ///     &src_dir, &export_dir, LocalLinkKind::Off, &["png"]).unwrap();
+    /// assert!(summary.exported.is_empty());
+    /// assert_eq!(summary.unchanged.len(), 1);
+    /// ```
+    pub fn save_exporter_site<T: Content>(
+        src_dir: &Path,
+        export_dir: &Path,
+        local_link_kind: LocalLinkKind,
+        asset_extensions: &[&str],
+    ) -> Result<SiteSummary, NoteError> {
+        let mut summary = SiteSummary::default();
+        let note_files = src_dir.find_tpnote_files_recursively()?;
+        fs::create_dir_all(export_dir)?;
+
+        let cache_path = export_dir.join(EXPORT_CACHE_FILENAME);
+        let config_fingerprint = ExportCache::config_fingerprint(local_link_kind, asset_extensions);
+        let mut old_cache = ExportCache::load(&cache_path)
+            .filter(|c| c.config_fingerprint == config_fingerprint)
+            .unwrap_or_default();
+        let mut cache = ExportCache {
+            config_fingerprint,
+            ..Default::default()
+        };
+
+        // Maps the note's path relative to `src_dir` to its absolute path,
+        // its HTML rendition's path and its new cache entry.
+        let mut notes = BTreeMap::new();
+        let mut rel_paths = BTreeSet::new();
+        for doc_path in note_files {
+            if doc_path.starts_with(export_dir) {
+                continue;
+            }
+            let Ok(rel_path) = doc_path.strip_prefix(src_dir) else {
+                continue;
+            };
+            let rel_path = rel_path.to_path_buf();
+            rel_paths.insert(rel_path.clone());
+            let html_path = Self::site_html_path(export_dir, &rel_path);
+            match ExportCacheEntry::from(&doc_path) {
+                Ok(entry) => {
+                    notes.insert(rel_path, (doc_path, html_path, entry));
+                }
+                Err(e) => summary.failed.push((doc_path, e)),
+            }
+        }
+
+        // A note was added or removed: wiki links and broken links may
+        // resolve differently now.
+        cache.note_set_hash = ExportCache::note_set_hash(&rel_paths);
+        let note_set_changed = cache.note_set_hash != old_cache.note_set_hash;
+        let removed: BTreeSet<PathBuf> = old_cache
+            .notes
+            .keys()
+            .filter(|rel_path| !notes.contains_key(*rel_path))
+            .map(|rel_path| Path::new(MAIN_SEPARATOR_STR).join(rel_path))
+            .collect();
+
+        // Notes that are new, modified or removed since the last export, and
+        // notes whose links may resolve differently.
+        let mut touched: BTreeSet<PathBuf> = notes
+            .iter()
+            .filter(|(rel_path, (_, html_path, entry))| {
+                !html_path.is_file()
+                    || old_cache.notes.get(*rel_path).is_none_or(|old| {
+                        old.content_hash != entry.content_hash
+                            || old.doc_file_date != entry.doc_file_date
+                            || (note_set_changed
+                                && (old.resolves_by_name || !old.note_links.is_disjoint(&removed)))
+                    })
+            })
+            .map(|(rel_path, _)| rel_path.clone())
+            .collect();
+        for rel_path in old_cache.notes.keys() {
+            if notes.contains_key(rel_path) {
+                continue;
+            }
+            touched.insert(rel_path.clone());
+            let html_path = Self::site_html_path(export_dir, rel_path);
+            match Self::remove_site_file(export_dir, &html_path) {
+                Ok(true) => summary.removed.push(html_path),
+                Ok(false) => {}
+                Err(e) => summary.failed.push((html_path, e)),
+            }
+        }
+
+        // Collect the links of the touched notes to other notes. Those of the
+        // other notes are known from the last export.
+        let has_marker = src_dir.join(FILENAME_ROOT_PATH_MARKER).is_file();
+        let all_note_files: Vec<PathBuf> = notes.values().map(|(p, _, _)| p.clone()).collect();
+        let mut note_links = note_links_of(
+            src_dir,
+            &all_note_files,
+            touched
+                .iter()
+                .filter_map(|rel_path| notes.get(rel_path).map(|(p, _, _)| p)),
+            has_marker,
+        );
+        for (rel_path, (doc_path, _, entry)) in notes.iter_mut() {
+            if touched.contains(rel_path) {
+                (entry.note_links, entry.resolves_by_name) =
+                    note_links.remove(doc_path).unwrap_or_default();
+            } else if let Some(old) = old_cache.notes.get(rel_path) {
+                entry.note_links = old.note_links.clone();
+                entry.resolves_by_name = old.resolves_by_name;
+            }
+        }
+
+        // Maps every note to the notes linking to it, with `src_dir` as root.
+        let old_backlinks = Self::site_backlinks(
+            old_cache
+                .notes
+                .iter()
+                .map(|(rel_path, old)| (rel_path, &old.note_links)),
+        );
+        let site_backlinks = Self::site_backlinks(
+            notes
+                .iter()
+                .map(|(rel_path, (_, _, entry))| (rel_path, &entry.note_links)),
+        );
+
+        // Besides the touched notes, the notes whose backlinks changed and
+        // the notes linking to touched notes must be rendered again: the
+        // latter show link texts and wiki links.
+        let mut todo: BTreeSet<PathBuf> = touched.clone();
+        for (rel_path, old) in &old_cache.notes {
+            if !old.links.is_disjoint(&touched) {
+                todo.insert(rel_path.clone());
+            }
+        }
+        for rel_path in notes.keys() {
+            let rel_path_abs = Path::new(MAIN_SEPARATOR_STR).join(rel_path);
+            if old_backlinks.get(&rel_path_abs) != site_backlinks.get(&rel_path_abs) {
+                todo.insert(rel_path.clone());
+            }
+        }
+
+        let mut failed_notes = BTreeSet::new();
+        for rel_path in todo {
+            let Some((doc_path, html_path, entry)) = notes.get_mut(&rel_path) else {
+                continue;
+            };
+            let backlinks = site_backlinks
+                .get(&Path::new(MAIN_SEPARATOR_STR).join(&rel_path))
+                .cloned()
+                .unwrap_or_default();
+            match Self::save_site_page::<T>(
+                src_dir,
+                doc_path,
+                html_path,
+                local_link_kind,
+                &backlinks,
+            ) {
+                Ok(links) => {
+                    let mut entry = std::mem::take(entry);
+                    entry.links = links;
+                    cache.notes.insert(rel_path, entry);
+                    summary.exported.push((doc_path.clone(), html_path.clone()));
+                }
+                Err(e) => {
+                    failed_notes.insert(rel_path);
+                    summary.failed.push((doc_path.clone(), e));
+                }
+            }
+        }
+
+        // Maps a directory relative to `src_dir` to the names of its
+        // subdirectories and notes.
+        let mut index: BTreeMap<PathBuf, (BTreeSet<String>, BTreeSet<String>)> = BTreeMap::new();
+        index.entry(PathBuf::new()).or_default();
+        for (rel_path, (doc_path, _, _)) in notes {
+            if failed_notes.contains(&rel_path) {
+                continue;
+            }
+            if !cache.notes.contains_key(&rel_path) {
+                // Up to date: keep what we know from the last export.
+                let Some(old) = old_cache.notes.remove(&rel_path) else {
+                    continue;
+                };
+                cache.notes.insert(rel_path.clone(), old);
+                summary.unchanged.push(doc_path);
+            }
+
+            // Register the note and all its parent directories.
+            let mut dir = rel_path.parent().unwrap_or(Path::new("")).to_path_buf();
+            let note_name = rel_path.file_name().unwrap_or_default();
+            index
+                .entry(dir.clone())
+                .or_default()
+                .1
+                .insert(note_name.to_string_lossy().into_owned());
+            while let Some(dir_name) = dir.file_name().map(|n| n.to_string_lossy().into_owned()) {
+                dir.pop();
+                index.entry(dir.clone()).or_default().0.insert(dir_name);
+            }
+        }
+
+        // Copy the referenced assets.
+        let referenced: BTreeSet<PathBuf> = cache
+            .notes
+            .values()
+            .flat_map(|entry| entry.links.iter().cloned())
+            .collect();
+        for rel_path in referenced {
+            let asset_path = src_dir.join(&rel_path);
+            let is_asset = asset_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| asset_extensions.iter().any(|a| a.eq_ignore_ascii_case(ext)));
+            if !is_asset
+                || asset_path.has_tpnote_ext()
+                || asset_path.starts_with(export_dir)
+                || !asset_path.is_file()
+            {
+                continue;
+            }
+            let dest_path = export_dir.join(&rel_path);
+            let is_up_to_date = fs::metadata(&asset_path)
+                .and_then(|src| {
+                    let dest = fs::metadata(&dest_path)?;
+                    Ok(src.len() == dest.len() && src.modified()? <= dest.modified()?)
+                })
+                .unwrap_or(false);
+            if is_up_to_date {
+                cache.assets.insert(rel_path);
+                continue;
+            }
+            log::debug!("Copying asset {:?} to {:?}", asset_path, dest_path);
+            match dest_path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::copy(&asset_path, &dest_path))
+            {
+                Ok(_) => {
+                    cache.assets.insert(rel_path);
+                    summary.copied.push(dest_path);
+                }
+                Err(e) => summary.failed.push((asset_path, e.into())),
+            }
+        }
+        for rel_path in old_cache.assets.difference(&cache.assets) {
+            let dest_path = export_dir.join(rel_path);
+            match Self::remove_site_file(export_dir, &dest_path) {
+                Ok(true) => summary.removed.push(dest_path),
+                Ok(false) => {}
+                Err(e) => summary.failed.push((dest_path, e)),
+            }
+        }
+
+        // Generate the directory index pages.
+        for (rel_dir, (dirs, notes)) in index {
+            let index_path = export_dir.join(&rel_dir).join(INDEX_HTML_FILENAME);
+            if cache.assets.contains(&rel_dir.join(INDEX_HTML_FILENAME)) {
+                log::info!("Skipping index page {:?}: file exists", index_path);
+                continue;
+            }
+            let dir_path = src_dir.join(&rel_dir);
+            let dirs: Vec<String> = dirs.into_iter().collect();
+            let notes: Vec<String> = notes.into_iter().collect();
+            match Context::from(&dir_path)
+                .map_err(NoteError::from)
+                .and_then(|context| Self::exporter_index_page(context, &dirs, &notes))
+                .and_then(|html| {
+                    fs::create_dir_all(export_dir.join(&rel_dir))?;
+                    fs::write(&index_path, html)?;
+                    Ok(())
+                }) {
+                Ok(()) => {
+                    cache.indexes.insert(rel_dir);
+                    summary.indexes.push(index_path);
+                }
+                Err(e) => summary.failed.push((dir_path, e)),
+            }
+        }
+        for rel_dir in old_cache.indexes.difference(&cache.indexes) {
+            let index_path = export_dir.join(rel_dir).join(INDEX_HTML_FILENAME);
+            if cache.assets.contains(&rel_dir.join(INDEX_HTML_FILENAME)) {
+                continue;
+            }
+            match Self::remove_site_file(export_dir, &index_path) {
+                Ok(true) => summary.removed.push(index_path),
+                Ok(false) => {}
+                Err(e) => summary.failed.push((index_path, e)),
+            }
+        }
+
+        if let Err(e) = cache.save(&cache_path) {
+            summary.failed.push((cache_path, e));
+        }
+
+        Ok(summary)
+    }
+
+    /// Helper function for `save_exporter_site()`: renders the note
+    /// `doc_path` showing `backlinks` and writes the result to `html_path`.
+    /// Returns the note's local links relative to `src_dir`.
+    fn save_site_page<T: Content>(
+        src_dir: &Path,
+        doc_path: &Path,
+        html_path: &Path,
+        local_link_kind: LocalLinkKind,
+        backlinks: &BTreeSet<PathBuf>,
+    ) -> Result<BTreeSet<PathBuf>, NoteError> {
+        log::debug!("Rendering HTML into: {:?}", html_path);
+        let context = Context::from(doc_path)?.insert_backlinks(backlinks);
+        let doc_dir = context.get_dir_path().to_owned();
+        let content = T::open(doc_path)?;
+        let html = Self::exporter_page(context, content)?;
+
+        let links = Arc::new(RwLock::new(HashSet::new()));
+        let html = rewrite_links(
+            html,
+            src_dir,
+            &doc_dir,
+            local_link_kind,
+            // Do append `.html` to `.md` in links.
+            true,
+            links.clone(),
+        );
+
+        if let Some(parent) = html_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(html_path, html)?;
+
+        // The collected links are written as `local_link_kind` says.
+        // Rebase them on `src_dir`.
+        let links = links
+            .read_recursive()
+            .iter()
+            .filter_map(|l| {
+                let l = match local_link_kind {
+                    LocalLinkKind::Long => {
+                        Path::new(MAIN_SEPARATOR_STR).join(l.strip_prefix(src_dir).unwrap_or(l))
+                    }
+                    _ => l.to_owned(),
+                };
+                assemble_link(src_dir, &doc_dir, &l, true, false)
+            })
+            .map(|l| {
+                l.strip_prefix(MAIN_SEPARATOR_STR)
+                    .unwrap_or(&l)
+                    .to_path_buf()
+            })
+            .collect();
+        Ok(links)
+    }
+
+    /// Helper function for `save_exporter_site()`: maps every note to the
+    /// notes linking to it. `notes` yields the path of a note relative to
+    /// `src_dir` and its `ExportCacheEntry::note_links`.
+    fn site_backlinks<'a>(
+        notes: impl IntoIterator<Item = (&'a PathBuf, &'a BTreeSet<PathBuf>)>,
+    ) -> BTreeMap<PathBuf, BTreeSet<PathBuf>> {
+        let mut index: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+        for (rel_path, note_links) in notes {
+            let source = Path::new(MAIN_SEPARATOR_STR).join(rel_path);
+            for dest in note_links {
+                index
+                    .entry(dest.to_owned())
+                    .or_default()
+                    .insert(source.clone());
+            }
+        }
+        index
+    }
+
+    /// Helper function for `save_exporter_site()`: the path of the HTML
+    /// rendition of the note `rel_path`.
+    fn site_html_path(export_dir: &Path, rel_path: &Path) -> PathBuf {
+        let mut html_path = export_dir.join(rel_path).into_os_string();
+        html_path.push(HTML_EXT);
+        PathBuf::from(html_path)
+    }
+
+    /// Helper function for `save_exporter_site()`: deletes `path` and its
+    /// parent directories below `export_dir`, as long as they are empty.
+    /// Returns `false`, if `path` did not exist.
+    fn remove_site_file(export_dir: &Path, path: &Path) -> Result<bool, NoteError> {
+        match fs::remove_file(path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        }
+        log::debug!("Removed outdated file {:?}", path);
+        for dir in path.ancestors().skip(1) {
+            if dir == export_dir || !dir.starts_with(export_dir) || fs::remove_dir(dir).is_err() {
+                break;
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EXPORT_CACHE_FILENAME;
+    use super::HtmlRenderer;
+    use super::stable_hash;
+    use crate::config::LocalLinkKind;
+    use crate::content::ContentString;
+    use std::env::temp_dir;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_save_exporter_site_incremental() {
+        let src_dir = temp_dir().join("tpnote_test_site_src");
+        let export_dir = temp_dir().join("tpnote_test_site_out");
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&export_dir);
+        fs::create_dir_all(src_dir.join("sub")).unwrap();
+        let note_a = src_dir.join("01-A.md");
+        let note_b = src_dir.join("sub/02-B.md");
+        let note_c = src_dir.join("sub/03-C.md");
+        fs::write(&note_a, "---\ntitle: A\n---\n[b](sub/02-B.md)\n").unwrap();
+        fs::write(&note_b, "---\ntitle: B\n---\n![i](i.png)\n").unwrap();
+        fs::write(&note_c, "---\ntitle: C\n---\nC\n").unwrap();
+        fs::write(src_dir.join("sub/i.png"), b"png").unwrap();
+
+        let export = || {
+            HtmlRenderer::save_exporter_site::<ContentString>(
+                &src_dir,
+                &export_dir,
+                LocalLinkKind::Off,
+                &["png"],
+            )
+            .unwrap()
+        };
+        let exported = |s: &super::SiteSummary| {
+            let mut v: Vec<PathBuf> = s.exported.iter().map(|(p, _)| p.clone()).collect();
+            v.sort();
+            v
+        };
+
+        // First run: all notes are exported.
+        let summary = export();
+        assert!(summary.failed.is_empty());
+        assert_eq!(summary.exported.len(), 3);
+        assert_eq!(summary.copied, vec![export_dir.join("sub/i.png")]);
+        assert!(export_dir.join(EXPORT_CACHE_FILENAME).is_file());
+        let html = fs::read_to_string(export_dir.join("01-A.md.html")).unwrap();
+        assert!(html.contains(r#"href="sub/02-B.md.html""#));
+
+        // Second run: nothing to do.
+        let summary = export();
+        assert!(summary.exported.is_empty());
+        assert!(summary.copied.is_empty());
+        assert_eq!(summary.unchanged.len(), 3);
+
+        // Modify `B`: `A` links to `B` and is rendered again.
+        fs::write(&note_b, "---\ntitle: B\n---\n![i](i.png)\nmore\n").unwrap();
+        let summary = export();
+        assert_eq!(exported(&summary), vec![note_a.clone(), note_b.clone()]);
+        assert_eq!(summary.unchanged, vec![note_c.clone()]);
+
+        // `C` links to `A` now: `A` shows a new backlink.
+        fs::write(&note_c, "---\ntitle: C\n---\n[a](../01-A.md)\n").unwrap();
+        let summary = export();
+        assert_eq!(exported(&summary), vec![note_a.clone(), note_c.clone()]);
+        let html = fs::read_to_string(export_dir.join("01-A.md.html")).unwrap();
+        assert!(html.contains("sub/03-C.md.html"));
+
+        // Remove `B`: its rendition and its image are deleted, the other
+        // notes are rendered again.
+        fs::remove_file(&note_b).unwrap();
+        let summary = export();
+        assert_eq!(exported(&summary), vec![note_a.clone(), note_c.clone()]);
+        assert!(!export_dir.join("sub/02-B.md.html").exists());
+        assert!(!export_dir.join("sub/i.png").exists());
+        assert_eq!(summary.removed.len(), 2);
+
+        // A corrupt cache results in a full rebuild.
+        fs::write(export_dir.join(EXPORT_CACHE_FILENAME), "garbage").unwrap();
+        let summary = export();
+        assert_eq!(summary.exported.len(), 2);
+
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&export_dir);
+    }
+
+    #[test]
+    fn test_stable_hash() {
+        // These values must never change: they are persisted in
+        // `EXPORT_CACHE_FILENAME`.
+        assert_eq!(stable_hash([]), "cbf29ce484222325");
+        assert_eq!(stable_hash([b"a".as_slice()]), "089bc907b544c769");
+        assert_ne!(
+            stable_hash([b"ab".as_slice(), b"c".as_slice()]),
+            stable_hash([b"a".as_slice(), b"bc".as_slice()])
+        );
+    }
+
+    #[test]
+    fn test_save_exporter_site_new_note() {
+        let src_dir = temp_dir().join("tpnote_test_site_new_src");
+        let export_dir = temp_dir().join("tpnote_test_site_new_out");
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&export_dir);
+        fs::create_dir_all(&src_dir).unwrap();
+        let note_a = src_dir.join("01-A.md");
+        let note_b = src_dir.join("02-B.md");
+        let note_c = src_dir.join("03-C.md");
+        fs::write(&note_a, "---\ntitle: A\n---\n[[C]]\n").unwrap();
+        fs::write(&note_b, "---\ntitle: B\n---\nB\n").unwrap();
+
+        let export = || {
+            HtmlRenderer::save_exporter_site::<ContentString>(
+                &src_dir,
+                &export_dir,
+                LocalLinkKind::Off,
+                &[],
+            )
+            .unwrap()
+        };
+
+        // `A`'s wiki link can not be resolved yet.
+        let summary = export();
+        assert_eq!(summary.exported.len(), 2);
+        assert_eq!(summary.failed.len(), 0);
+        let html = fs::read_to_string(export_dir.join("01-A.md.html")).unwrap();
+        assert!(!html.contains("03-C.md.html"));
+
+        // The new note `C` links to `B`. `A`'s wiki link resolves to `C`
+        // and `B` shows `C` as backlink.
+        fs::write(&note_c, "---\ntitle: C\n---\n[b](02-B.md)\n").unwrap();
+        let summary = export();
+        assert_eq!(summary.exported.len(), 3);
+        let html = fs::read_to_string(export_dir.join("01-A.md.html")).unwrap();
+        assert!(html.contains("03-C.md.html"));
+        let html = fs::read_to_string(export_dir.join("02-B.md.html")).unwrap();
+        assert!(html.contains("03-C.md.html"));
+
+        // The note set is unchanged: nothing to do.
+        let summary = export();
+        assert!(summary.exported.is_empty());
+        assert_eq!(summary.unchanged.len(), 3);
+
+        // A new unrelated note: `A`, whose wiki link may resolve
+        // differently now, and `C`, showing `A` as backlink, are rendered
+        // again. `B` is not.
+        let note_d = src_dir.join("04-D.md");
+        fs::write(&note_d, "---\ntitle: D\n---\nD\n").unwrap();
+        let summary = export();
+        let mut exported: Vec<PathBuf> = summary.exported.iter().map(|(p, _)| p.clone()).collect();
+        exported.sort();
+        assert_eq!(exported, vec![note_a.clone(), note_c.clone(), note_d]);
+        assert_eq!(summary.unchanged, vec![note_b.clone()]);
+
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&export_dir);
+    }
 }
@@ -16,25 +16,41 @@
 //! consumer of `tpnote-lib`'s high-level API is the module `workflow` and
 //! `html_renderer` in the `tpnote` crate.
 //!
+#[cfg(feature = "renderer")]
+pub mod asciidoc;
 pub mod clone_ext;
 pub mod config;
 pub mod config_value;
 pub mod content;
 pub mod context;
+#[cfg(feature = "renderer")]
+pub mod djot;
 pub mod error;
+#[cfg(feature = "renderer")]
+mod external;
 pub mod filename;
 mod filter;
-mod front_matter;
+pub mod front_matter;
 #[cfg(feature = "renderer")]
 pub mod highlight;
 pub mod html;
 #[cfg(feature = "renderer")]
+pub mod html2adoc;
+#[cfg(feature = "renderer")]
 pub mod html2md;
+#[cfg(feature = "renderer")]
+pub mod html2rst;
 pub mod html_renderer;
 #[cfg(feature = "lang-detection")]
 pub mod lingua;
 pub mod markup_language;
+#[cfg(feature = "renderer")]
+pub mod mediawiki;
 mod note;
+#[cfg(feature = "renderer")]
+pub mod org;
+#[cfg(feature = "renderer")]
+pub mod rst;
 pub mod settings;
 pub mod template;
 pub mod text_reader;
@@ -1,23 +1,39 @@
 //! Helper functions dealing with markup languages.
+#[cfg(feature = "renderer")]
+use crate::asciidoc::asciidoc_to_html;
 use crate::config::LIB_CFG;
+#[cfg(feature = "renderer")]
+use crate::djot::djot_to_html;
 use crate::error::NoteError;
 #[cfg(feature = "renderer")]
-use crate::highlight::SyntaxPreprocessor;
+use crate::external::run_external;
+#[cfg(feature = "renderer")]
+use crate::highlight::{AdmonitionPreprocessor, SyntaxPreprocessor, mark_containers};
+#[cfg(feature = "renderer")]
+use crate::html::{WIKI_LINK_MARKER, slugify};
+#[cfg(feature = "renderer")]
+use crate::html2adoc::convert_html_to_adoc;
+#[cfg(feature = "renderer")]
+use crate::html2md::{convert_html_to_djot, convert_html_to_md};
+#[cfg(feature = "renderer")]
+use crate::html2rst::convert_html_to_rst;
 #[cfg(feature = "renderer")]
-use crate::html2md::convert_html_to_md;
+use crate::mediawiki::mediawiki_to_html;
+#[cfg(feature = "renderer")]
+use crate::org::org_to_html;
+#[cfg(feature = "renderer")]
+use crate::rst::rst_to_html;
 use crate::settings::SETTINGS;
 use parse_hyperlinks::renderer::text_links2html;
 use parse_hyperlinks::renderer::text_rawlinks2html;
 #[cfg(feature = "renderer")]
-use pulldown_cmark::{Options, Parser, html};
-#[cfg(feature = "renderer")]
-use rst_parser;
-#[cfg(feature = "renderer")]
-use rst_renderer;
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd, html};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "renderer")]
+use std::collections::HashSet;
 use std::path::Path;
 #[cfg(feature = "renderer")]
-use std::str::from_utf8;
+use std::time::Duration;
 
 /// The filter `filter_tags()` omits HTML `<span....>` after converting to
 /// Markdown.
@@ -32,6 +48,12 @@ const FILTERED_TAGS: &[&str; 4] = &["<span", "</span>", "<div", "</div>"];
 pub enum InputConverter {
     /// Convert from HTML to Markdown.
     ToMarkdown,
+    /// Convert from HTML to Djot.
+    ToDjot,
+    /// Convert from HTML to ReStructuredText.
+    ToReStructuredText,
+    /// Convert from HTML to AsciiDoc.
+    ToAsciiDoc,
     /// Do not convert, return an error instead.
     #[default]
     Disabled,
@@ -61,6 +83,15 @@ impl InputConverter {
             #[cfg(feature = "renderer")]
             InputConverter::ToMarkdown => |s| convert_html_to_md(&s),
 
+            #[cfg(feature = "renderer")]
+            InputConverter::ToDjot => |s| convert_html_to_djot(&s),
+
+            #[cfg(feature = "renderer")]
+            InputConverter::ToReStructuredText => |s| convert_html_to_rst(&s),
+
+            #[cfg(feature = "renderer")]
+            InputConverter::ToAsciiDoc => |s| convert_html_to_adoc(&s),
+
             InputConverter::Disabled => {
                 |_: String| -> Result<String, NoteError> { Err(NoteError::HtmlToMarkupDisabled) }
             }
@@ -121,6 +152,15 @@ impl InputConverter {
 pub enum MarkupLanguage {
     Markdown,
     ReStructuredText,
+    AsciiDoc,
+    /// MediaWiki's WikiText.
+    MediaWiki,
+    /// Emacs' Org-mode.
+    OrgMode,
+    Djot,
+    /// Rendered by the external command configured for the file extension
+    /// in `filename.external_renderers`.
+    External,
     Html,
     PlainText,
     /// The markup language is known, but the renderer is disabled.
@@ -148,6 +188,11 @@ impl MarkupLanguage {
         match self {
             Self::Markdown => Some("text/markodwn"),
             Self::ReStructuredText => Some("x-rst"),
+            Self::AsciiDoc => Some("text/asciidoc"),
+            Self::MediaWiki => Some("text/x-wiki"),
+            Self::OrgMode => Some("text/org"),
+            Self::Djot => Some("text/x-djot"),
+            Self::External => Some("text/plain"),
             Self::Html => Some("text/html"),
             Self::PlainText => Some("text/plain"),
             Self::RendererDisabled => Some("text/plain"),
@@ -172,11 +217,31 @@ impl MarkupLanguage {
 
     /// Every `MarkupLanguage` variant has an own internal HTML renderer:
     /// * `Markdown` is rendered according the "CommonMark" standard.
+    ///   In addition, wiki links `[[Title]]`, `[[sort-tag]]` and
+    ///   `[[Title|alias]]` are marked with `WIKI_LINK_MARKER`. They are
+    ///   resolved later by `html::rewrite_links()`. Headings without
+    ///   explicit `{#id}` get an id derived from their text, see
+    ///   `html::slugify()`.
     /// * Currently only as small subset of ReStructuredText is rendered for
-    ///   `ReStructuredText`. This feature is experimental.
+    ///   `ReStructuredText`. Code blocks are highlighted like in Markdown,
+    ///   the `math` directive and role, image, figure and admonition
+    ///   directives are rendered by `rst::rst_to_html()`.
+    /// * `AsciiDoc` is rendered by an internal renderer supporting the subset
+    ///   of AsciiDoc commonly found in notes: sections, lists, tables,
+    ///   admonitions, source blocks and cross references, see
+    ///   `asciidoc::asciidoc_to_html()`.
+    /// * `MediaWiki` renders WikiText, see `mediawiki::mediawiki_to_html()`.
+    ///   Like in Markdown, internal links `[[Page]]` are marked with
+    ///   `WIKI_LINK_MARKER` and resolved later against the note titles.
+    /// * `OrgMode` renders headlines with TODO keywords, lists, tables,
+    ///   links and highlighted source blocks, see `org::org_to_html()`.
+    /// * `Djot` is rendered by an internal renderer implementing Djot's
+    ///   block and inline syntax, see `djot::djot_to_html()`.
+    /// * `External` requires the file extension to find the external
+    ///   command, use `render_for()` instead. Here, it returns an error.
     /// * The `Html` renderer simply forwards the input without modification.
     /// * `PlainText` is rendered as raw text. Hyperlinks in Markdown,
-    ///   ReStructuredText, AsciiDoc and WikiText syntax are detected and
+    ///   ReStructuredText, AsciiDoc, WikiText and Org syntax are detected and
     ///   are displayed in the rendition with their link text. All hyperlinks
     ///   are clickable.
     /// * `Unknown` is rendered like `PlainText`, hyperlinks are also
@@ -186,37 +251,38 @@ impl MarkupLanguage {
     pub fn render(&self, input: &str) -> Result<String, NoteError> {
         match self {
             #[cfg(feature = "renderer")]
-            Self::Markdown => {
-                // Set up options and parser. Besides the CommonMark standard
-                // we enable some useful extras.
-
-                let options = Options::all();
-                let parser = Parser::new_ext(input, options);
-                let parser = SyntaxPreprocessor::new(parser);
-
-                // Write to String buffer.
-                let mut html_output: String = String::with_capacity(input.len() * 3 / 2);
-                html::push_html(&mut html_output, parser);
-                Ok(html_output)
-            }
+            Self::Markdown => Ok(markdown_to_html(input)),
 
             #[cfg(feature = "renderer")]
-            Self::ReStructuredText => {
-                // Note, that the current ReStructuredText renderer requires
-                // files to end with no new line.
-                let rest_input = input.trim();
-                // Write to String buffer.
-                let mut html_output: Vec<u8> = Vec::with_capacity(rest_input.len() * 3 / 2);
-                const STANDALONE: bool = false; // Don't wrap in `<!doctype html><html></html>`.
-                let doc = rst_parser::parse(rest_input.trim_start())
-                    .map_err(|e| NoteError::RstParse { msg: e.to_string() })?;
-                rst_renderer::render_html(&doc, &mut html_output, STANDALONE)
-                    .map_err(|e| NoteError::RstParse { msg: e.to_string() })?;
-                Ok(from_utf8(&html_output).unwrap_or_default().to_string())
-            }
+            Self::ReStructuredText => rst_to_html(input),
+
+            #[cfg(feature = "renderer")]
+            Self::AsciiDoc => Ok(asciidoc_to_html(input)),
+
+            #[cfg(feature = "renderer")]
+            Self::MediaWiki => Ok(mediawiki_to_html(input)),
+
+            #[cfg(feature = "renderer")]
+            Self::OrgMode => Ok(org_to_html(input)),
+
+            #[cfg(feature = "renderer")]
+            Self::Djot => Ok(djot_to_html(input)),
+
+            #[cfg(feature = "renderer")]
+            Self::External => Err(NoteError::RenderError {
+                renderer: "External".to_string(),
+                msg: "no file extension given to look up the external command".to_string(),
+            }),
 
             Self::Html => Ok(input.to_string()),
 
+            // Without the `renderer` feature, AsciiDoc, WikiText, Org, Djot
+            // and externally rendered markup are rendered as plain text.
+            #[cfg(not(feature = "renderer"))]
+            Self::AsciiDoc | Self::MediaWiki | Self::OrgMode | Self::Djot | Self::External => {
+                Ok(text_links2html(input))
+            }
+
             Self::PlainText | Self::RendererDisabled => Ok(text_links2html(input)),
 
             Self::Unkown => Ok(text_rawlinks2html(input)),
@@ -224,6 +290,146 @@ impl MarkupLanguage {
             _ => Ok(String::new()),
         }
     }
+
+    /// Like `render_for()`, but a renderer panicking, e.g. on unsupported
+    /// markup elements, is reported as `NoteError::RenderPanic`.
+    pub(crate) fn render_for_catch_unwind(
+        &self,
+        extension: &str,
+        input: &str,
+    ) -> Result<String, NoteError> {
+        self.catch_unwind(|| self.render_for(extension, input))
+    }
+
+    /// Runs the renderer `render`. A panicking renderer, e.g. on unsupported
+    /// markup elements, is reported as `NoteError::RenderPanic`.
+    fn catch_unwind<R>(
+        &self,
+        render: impl FnOnce() -> Result<R, NoteError>,
+    ) -> Result<R, NoteError> {
+        #[cfg(feature = "renderer")]
+        {
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(render)).unwrap_or_else(
+                |payload| {
+                    let msg = payload
+                        .downcast_ref::<&str>()
+                        .map(|s| s.to_string())
+                        .or_else(|| payload.downcast_ref::<String>().cloned())
+                        .unwrap_or_else(|| "unknown".to_string());
+                    Err(NoteError::RenderPanic {
+                        renderer: format!("{:?}", self),
+                        msg,
+                    })
+                },
+            )
+        }
+        #[cfg(not(feature = "renderer"))]
+        render()
+    }
+
+    /// Like `render()`, but `MarkupLanguage::External` is rendered by the
+    /// command configured for `extension` in `filename.external_renderers`.
+    /// The `input` is piped into the command and its standard output is
+    /// returned. When the command fails or does not terminate within
+    /// `filename.external_renderer_timeout` seconds, a
+    /// `NoteError::RenderError` is returned.
+    #[cfg_attr(not(feature = "renderer"), allow(unused_variables))]
+    pub fn render_for(&self, extension: &str, input: &str) -> Result<String, NoteError> {
+        match self {
+            #[cfg(feature = "renderer")]
+            Self::External => {
+                let (command, timeout) = {
+                    let scheme =
+                        &LIB_CFG.read_recursive().scheme[SETTINGS.read_recursive().current_scheme];
+                    let command = scheme
+                        .filename
+                        .external_renderers
+                        .iter()
+                        .find(|(ext, _)| ext == extension)
+                        .map(|(_, command)| command.clone())
+                        .ok_or_else(|| NoteError::RenderError {
+                            renderer: "External".to_string(),
+                            msg: format!(
+                                "no command for extension `{extension}` \
+                                in `filename.external_renderers`"
+                            ),
+                        })?;
+                    (
+                        command,
+                        Duration::from_secs(scheme.filename.external_renderer_timeout),
+                    )
+                };
+                run_external(&command, timeout, input)
+            }
+            _ => self.render(input),
+        }
+    }
+}
+
+/// Renders the Markdown `input` to HTML.
+#[cfg(feature = "renderer")]
+fn markdown_to_html(input: &str) -> String {
+    // Set up options and parser. Besides the CommonMark standard
+    // we enable some useful extras.
+
+    let options = Options::all();
+    let input = mark_containers(input);
+    let parser = Parser::new_ext(&input, options).map(|event| match event {
+        // Mark wiki links. Their destination is resolved later
+        // against the note titles and sort-tags under `root_path`.
+        Event::Start(Tag::Link {
+            link_type: link_type @ LinkType::WikiLink { .. },
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: format!("{WIKI_LINK_MARKER}{dest_url}").into(),
+            title,
+            id,
+        }),
+        event => event,
+    });
+    let mut events: Vec<Event> = parser.collect();
+    add_heading_ids(&mut events);
+    let parser = SyntaxPreprocessor::new(AdmonitionPreprocessor::new(events.into_iter()));
+
+    // Write to String buffer.
+    let mut html_output: String = String::with_capacity(input.len() * 3 / 2);
+    html::push_html(&mut html_output, parser);
+    html_output
+}
+
+/// Sets the `id` of all headings in `events` without explicit `{#id}`
+/// attribute to the slug of the heading's text.
+#[cfg(feature = "renderer")]
+fn add_heading_ids(events: &mut [Event]) {
+    // Explicit ids are reserved first.
+    let mut ids: HashSet<String> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Start(Tag::Heading { id: Some(id), .. }) => Some(id.to_string()),
+            _ => None,
+        })
+        .collect();
+
+    for i in 0..events.len() {
+        let Event::Start(Tag::Heading { id: None, .. }) = &events[i] else {
+            continue;
+        };
+        let text: String = events[i + 1..]
+            .iter()
+            .take_while(|event| !matches!(event, Event::End(TagEnd::Heading(_))))
+            .filter_map(|event| match event {
+                Event::Text(t) | Event::Code(t) | Event::InlineMath(t) => Some(t.as_ref()),
+                _ => None,
+            })
+            .collect();
+        let id = slugify(&text, &mut ids);
+        if let Event::Start(Tag::Heading { id: heading_id, .. }) = &mut events[i] {
+            *heading_id = Some(id.into());
+        }
+    }
 }
 
 impl From<&Path> for MarkupLanguage {
@@ -286,6 +492,22 @@ mod tests {
         //
         let ext = "rst";
         assert_eq!(MarkupLanguage::from(ext), MarkupLanguage::ReStructuredText);
+
+        //
+        let ext = "adoc";
+        assert_eq!(MarkupLanguage::from(ext), MarkupLanguage::AsciiDoc);
+
+        //
+        let ext = "mw";
+        assert_eq!(MarkupLanguage::from(ext), MarkupLanguage::MediaWiki);
+
+        //
+        let ext = "org";
+        assert_eq!(MarkupLanguage::from(ext), MarkupLanguage::OrgMode);
+
+        //
+        let ext = "dj";
+        assert_eq!(MarkupLanguage::from(ext), MarkupLanguage::Djot);
     }
 
     #[test]
@@ -297,12 +519,91 @@ mod tests {
         let result = MarkupLanguage::Markdown.render(input).unwrap();
         assert_eq!(result, expected);
 
+        // Markdown wiki links
+        let input = "[[My note]] [[20221030]] [[My note|alias]]";
+        let expected: &str = "<p><a href=\"tpnote-wiki:My%20note\">My note</a> \
+            <a href=\"tpnote-wiki:20221030\">20221030</a> \
+            <a href=\"tpnote-wiki:My%20note\">alias</a></p>\n";
+
+        let result = MarkupLanguage::Markdown.render(input).unwrap();
+        assert_eq!(result, expected);
+
         // ReStructuredText
         let input = "`Link text <https://domain.invalid/>`_";
         let expected: &str = "<p><a href=\"https://domain.invalid/\">Link text</a></p>";
 
         let result = MarkupLanguage::ReStructuredText.render(input).unwrap();
         assert_eq!(result, expected);
+
+        // AsciiDoc
+        let input = "https://domain.invalid/[Link text]";
+        let expected: &str = "<p><a href=\"https://domain.invalid/\">Link text</a></p>\n";
+
+        let result = MarkupLanguage::AsciiDoc.render(input).unwrap();
+        assert_eq!(result, expected);
+
+        // MediaWiki
+        let input = "[https://domain.invalid/ Link text] [[My note]]";
+        let expected: &str = "<p><a href=\"https://domain.invalid/\">Link text</a> \
+            <a href=\"tpnote-wiki:My%20note\">My note</a></p>\n";
+
+        let result = MarkupLanguage::MediaWiki.render(input).unwrap();
+        assert_eq!(result, expected);
+
+        // Org
+        let input = "[[https://domain.invalid/][Link text]] [[My note]]";
+        let expected: &str = "<p><a href=\"https://domain.invalid/\">Link text</a> \
+            <a href=\"tpnote-wiki:My%20note\">My note</a></p>\n";
+
+        let result = MarkupLanguage::OrgMode.render(input).unwrap();
+        assert_eq!(result, expected);
+
+        // Djot
+        let input = "[Link text](https://domain.invalid/)";
+        let expected: &str = "<p><a href=\"https://domain.invalid/\">Link text</a></p>\n";
+
+        let result = MarkupLanguage::Djot.render(input).unwrap();
+        assert_eq!(result, expected);
+
+        // External, no command is configured by default.
+        #[cfg(feature = "renderer")]
+        assert!(MarkupLanguage::External.render_for("t2t", input).is_err());
+        assert_eq!(
+            MarkupLanguage::Djot.render_for("dj", input).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "renderer")]
+    fn test_markuplanguage_render_heading_ids() {
+        use crate::html::{TocEntry, toc};
+
+        let input = "# Intro\n\nText\n\n## A `b` c {#abc}\n\n## Intro\n\n\
+            ```\n# No heading\n```\n";
+        let html = MarkupLanguage::Markdown.render_for("md", input).unwrap();
+        assert!(html.contains("<h1 id=\"intro\">Intro</h1>"));
+        assert!(html.contains("<h2 id=\"intro-1\">Intro</h2>"));
+        assert_eq!(
+            toc(&html),
+            vec![
+                TocEntry {
+                    level: 1,
+                    text: "Intro".to_string(),
+                    anchor: "intro".to_string()
+                },
+                TocEntry {
+                    level: 2,
+                    text: "A b c".to_string(),
+                    anchor: "abc".to_string()
+                },
+                TocEntry {
+                    level: 2,
+                    text: "Intro".to_string(),
+                    anchor: "intro-1".to_string()
+                },
+            ]
+        );
     }
 
     #[test]
@@ -347,6 +648,47 @@ mod tests {
         assert_eq!(result.unwrap(), expected);
     }
 
+    #[test]
+    fn test_input_converter_md_math() {
+        // Math survives a render, HTML to Markdown, render round trip.
+        let ic = InputConverter::build("md");
+        let input: &str = "Energy $E=mc^2$ and\n\n$$\\frac{a+1}{\\sqrt{b}}\\leq\\sum_{i=1}^n x_i$$";
+        let html = MarkupLanguage::Markdown.render(input).unwrap();
+        let markdown = ic(html.clone()).unwrap();
+        assert_eq!(markdown, input);
+        assert_eq!(MarkupLanguage::Markdown.render(&markdown).unwrap(), html);
+    }
+
+    #[test]
+    fn test_input_converter_djot() {
+        let ic = InputConverter::build("dj");
+        let input: &str = "<p><b>Bold</b> and <em>emphasized</em> <a href=\"/my uri\">link</a></p>";
+        let expected: &str = "*Bold* and _emphasized_ [link](/my%20uri)";
+
+        let result = ic(input.to_string());
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_input_converter_rst() {
+        let ic = InputConverter::build("rst");
+        let input: &str = "<h2>Title</h2><p><b>Bold</b> <a href=\"/my uri\">link</a></p>";
+        let expected: &str = "Title\n-----\n\n**Bold** `link </my%20uri>`__";
+
+        let result = ic(input.to_string());
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_input_converter_adoc() {
+        let ic = InputConverter::build("adoc");
+        let input: &str = "<h2>Title</h2><p><b>Bold</b> <a href=\"/my uri\">link</a></p>";
+        let expected: &str = "== Title\n\n*Bold* link:/my%20uri[link]";
+
+        let result = ic(input.to_string());
+        assert_eq!(result.unwrap(), expected);
+    }
+
     #[test]
     fn test_filter_tags() {
         let input: &str =
@@ -0,0 +1,1030 @@
+//! A renderer for MediaWiki's markup language WikiText.
+//!
+//! Supported are headings, bold and italic text, ordered, unordered and
+//! definition lists, internal and external links, tables, preformatted text,
+//! `<nowiki>`, `<pre>`, `<syntaxhighlight>` (highlighted with the configured
+//! `HighlightRegistry`), `<math>` and `<ref>` footnotes. Templates `{{...}}`,
+//! comments, categories and magic words like `__TOC__` are dropped, as
+//! there is no wiki to resolve them against.
+//!
+//! Internal links `[[Page]]` are marked with `WIKI_LINK_MARKER`, like
+//! Markdown wiki links. `html::rewrite_links()` resolves them later against
+//! the titles and sort-tags of the Tp-Note files under the root path.
+use crate::highlight::HighlightRegistry;
+use crate::html::WIKI_LINK_MARKER;
+use crate::html::unique_id;
+use html_escape::{decode_html_entities, encode_double_quoted_attribute, encode_text};
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Characters percent encoded in the destination of internal links.
+const WIKI_LINK_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`');
+
+/// URL schemes recognized in external links.
+const URL_SCHEMES: [&str; 5] = ["https://", "http://", "ftp://", "mailto:", "//"];
+
+/// HTML tags allowed in WikiText. Their attributes are dropped.
+const ALLOWED_TAGS: [&str; 19] = [
+    "b",
+    "big",
+    "blockquote",
+    "cite",
+    "code",
+    "del",
+    "em",
+    "i",
+    "ins",
+    "kbd",
+    "mark",
+    "s",
+    "small",
+    "strike",
+    "strong",
+    "sub",
+    "sup",
+    "tt",
+    "u",
+];
+
+/// HTML tags removed from WikiText while their content is kept.
+const REMOVED_TAGS: [&str; 4] = ["center", "div", "font", "span"];
+
+/// Namespaces of internal links rendered as images.
+const IMAGE_NAMESPACES: [&str; 2] = ["File:", "Image:"];
+
+/// The HTML writer for WikiText input.
+struct Renderer {
+    /// Footnotes `<ref>` not yet written by `<references />`.
+    refs: Vec<String>,
+    /// Number of the last footnote.
+    ref_count: usize,
+    /// Named footnotes `<ref name="...">` mapped to their number.
+    ref_names: HashMap<String, usize>,
+    /// Number of the last external link without link text.
+    autonumber: usize,
+    /// Ids already in use.
+    ids: HashSet<String>,
+    registry: Arc<HighlightRegistry>,
+}
+
+/// Renders WikiText `input` to HTML.
+///
+/// ```rust
+/// use tpnote_lib::mediawiki::mediawiki_to_html;
+///
+/// let input = "== Intro ==\n'''Bold''' and [[My note|a note]].";
+/// let expected = "<h2 id=\"Intro\">Intro</h2>\n\
+///     <p><b>Bold</b> and <a href=\"tpnote-wiki:My%20note\">a note</a>.</p>\n";
+/// assert_eq!(mediawiki_to_html(input), expected);
+/// ```
+pub fn mediawiki_to_html(input: &str) -> String {
+    let mut renderer = Renderer {
+        refs: Vec::new(),
+        ref_count: 0,
+        ref_names: HashMap::new(),
+        autonumber: 0,
+        ids: HashSet::new(),
+        registry: HighlightRegistry::configured(),
+    };
+    let input = strip_comments_and_templates(input);
+    let mut html = renderer.blocks(&input);
+    if !renderer.refs.is_empty() {
+        html.push_str(&renderer.references());
+    }
+    html
+}
+
+impl Renderer {
+    /// Renders the block level elements of `input`.
+    fn blocks(&mut self, input: &str) -> String {
+        let lines: Vec<&str> = input.lines().collect();
+        let mut html = String::with_capacity(input.len() * 3 / 2);
+        let mut paragraph: Vec<&str> = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            let trimmed = line.trim();
+
+            // Elements ending a paragraph.
+            let is_block = trimmed.is_empty()
+                || heading(trimmed).is_some()
+                || trimmed.starts_with("----")
+                || trimmed.starts_with("{|")
+                || line.starts_with(['*', '#', ':', ';', ' '])
+                || starts_with_tag(trimmed, "pre")
+                || starts_with_tag(trimmed, "syntaxhighlight")
+                || starts_with_tag(trimmed, "source")
+                || starts_with_tag(trimmed, "references")
+                || is_magic_word(trimmed);
+            if !is_block {
+                paragraph.push(line);
+                i += 1;
+                continue;
+            }
+            if !paragraph.is_empty() {
+                html.push_str(&format!("<p>{}</p>\n", self.inline_lines(&paragraph)));
+                paragraph.clear();
+            }
+
+            if trimmed.is_empty() || is_magic_word(trimmed) {
+                i += 1;
+            } else if let Some((level, title)) = heading(trimmed) {
+                let content = self.inline(title);
+                let id = unique_id(&mut self.ids, &section_id(&content));
+                html.push_str(&format!(
+                    "<h{level} id=\"{}\">{content}</h{level}>\n",
+                    encode_double_quoted_attribute(&id)
+                ));
+                i += 1;
+            } else if trimmed.starts_with("----") {
+                html.push_str("<hr>\n");
+                let rest = trimmed.trim_start_matches('-').trim();
+                if !rest.is_empty() {
+                    html.push_str(&format!("<p>{}</p>\n", self.inline(rest)));
+                }
+                i += 1;
+            } else if trimmed.starts_with("{|") {
+                let end = table_end(&lines, i);
+                html.push_str(&self.table(&lines[i..end]));
+                i = end;
+            } else if starts_with_tag(trimmed, "references") {
+                html.push_str(&self.references());
+                // Skip a possible closing tag.
+                if !trimmed.ends_with("/>") {
+                    while i < lines.len() && !lines[i].contains("</references>") {
+                        i += 1;
+                    }
+                }
+                i += 1;
+            } else if let Some(tag) = ["pre", "syntaxhighlight", "source"]
+                .into_iter()
+                .find(|t| starts_with_tag(trimmed, t))
+            {
+                // Collect the lines up to the closing tag.
+                let closing = format!("</{tag}>");
+                let start = i;
+                while i < lines.len() && !lines[i].contains(&closing) {
+                    i += 1;
+                }
+                i = (i + 1).min(lines.len());
+                let block = lines[start..i].join("\n");
+                let block = block.trim();
+                let open_end = block.find('>').map_or(block.len(), |e| e + 1);
+                let content_end = block.rfind(&closing).unwrap_or(block.len());
+                let attrs = &block[..open_end];
+                let content = block
+                    .get(open_end..content_end.max(open_end))
+                    .unwrap_or_default();
+                let content = content.strip_prefix('\n').unwrap_or(content);
+                if tag == "pre" {
+                    html.push_str(&format!("<pre>{}</pre>\n", encode_text(content)));
+                } else {
+                    let lang = tag_attribute(attrs, "lang").unwrap_or_default();
+                    let code = format!("{}\n", content.trim_end_matches('\n'));
+                    html.push_str(&self.registry.highlight_code(&lang, &code));
+                    html.push('\n');
+                }
+            } else if line.starts_with(' ') {
+                // Preformatted text.
+                let start = i;
+                while i < lines.len() && lines[i].starts_with(' ') && !lines[i].trim().is_empty() {
+                    i += 1;
+                }
+                let content: Vec<_> = lines[start..i].iter().map(|l| &l[1..]).collect();
+                html.push_str(&format!("<pre>{}</pre>\n", self.inline_lines(&content)));
+            } else {
+                let start = i;
+                while i < lines.len() && lines[i].starts_with(['*', '#', ':', ';']) {
+                    i += 1;
+                }
+                html.push_str(&self.list(&lines[start..i]));
+            }
+        }
+        if !paragraph.is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", self.inline_lines(&paragraph)));
+        }
+        html
+    }
+
+    /// Renders the collected footnotes and clears them.
+    fn references(&mut self) -> String {
+        if self.refs.is_empty() {
+            return String::new();
+        }
+        let first = self.ref_count - self.refs.len() + 1;
+        let mut html = format!("<ol class=\"references\" start=\"{first}\">\n");
+        for (n, note) in std::mem::take(&mut self.refs).into_iter().enumerate() {
+            html.push_str(&format!("<li id=\"cite_note-{}\">{note}</li>\n", first + n));
+        }
+        html.push_str("</ol>\n");
+        html
+    }
+
+    /// Renders consecutive list lines, e.g. `*`, `#*`, `;` or `:`.
+    fn list(&mut self, lines: &[&str]) -> String {
+        let mut html = String::new();
+        // The list markers of the open (nested) lists.
+        let mut open: Vec<char> = Vec::new();
+        for line in lines {
+            let prefix: Vec<char> = line
+                .chars()
+                .take_while(|c| matches!(c, '*' | '#' | ':' | ';'))
+                .collect();
+            let text = line[prefix.len()..].trim();
+            let common = open
+                .iter()
+                .zip(&prefix)
+                .take_while(|(a, b)| a == b || (is_dl(**a) && is_dl(**b)))
+                .count();
+            // Close the lists not continued.
+            while open.len() > common {
+                let marker = open.pop().unwrap_or_default();
+                html.push_str(item_close(marker));
+                html.push_str(list_close(marker));
+            }
+            if prefix.len() == common && common > 0 {
+                // A sibling item.
+                let marker = open.pop().unwrap_or_default();
+                html.push_str(item_close(marker));
+                // `;` and `:` items share one `<dl>`.
+                let new_marker = prefix[common - 1];
+                open.push(new_marker);
+                html.push_str(item_open(new_marker));
+            } else {
+                for (n, marker) in prefix[common..].iter().enumerate() {
+                    if n > 0 || common > 0 {
+                        html.push('\n');
+                    }
+                    html.push_str(list_open(*marker));
+                    html.push_str(item_open(*marker));
+                    open.push(*marker);
+                }
+            }
+            // `; term : definition` on one line.
+            if open.last() == Some(&';')
+                && let Some((term, definition)) = split_definition(text)
+            {
+                html.push_str(&self.inline(term));
+                html.push_str(item_close(';'));
+                html.push_str(item_open(':'));
+                html.push_str(&self.inline(definition));
+                open.pop();
+                open.push(':');
+            } else {
+                html.push_str(&self.inline(text));
+            }
+        }
+        while let Some(marker) = open.pop() {
+            html.push_str(item_close(marker));
+            html.push_str(list_close(marker));
+        }
+        html
+    }
+
+    /// Renders a table. `lines` start with `{|` and end with `|}`.
+    fn table(&mut self, lines: &[&str]) -> String {
+        let mut html = String::from("<table>\n");
+        let mut caption: Option<String> = None;
+        // Rows of cells: (is header, attributes, content lines).
+        let mut rows: Vec<Vec<(bool, String, Vec<String>)>> = Vec::new();
+        let mut depth = 0;
+        for line in lines.iter().skip(1) {
+            let trimmed = line.trim();
+            if depth > 0 {
+                // Nested table content belongs to the current cell.
+                if trimmed.starts_with("{|") {
+                    depth += 1;
+                } else if trimmed.starts_with("|}") {
+                    depth -= 1;
+                }
+                if let Some(cell) = rows.last_mut().and_then(|r| r.last_mut()) {
+                    cell.2.push(line.to_string());
+                }
+                continue;
+            }
+            if trimmed.starts_with("|}") {
+                break;
+            } else if let Some(c) = trimmed.strip_prefix("|+") {
+                caption = Some(cell_content(c).1.to_string());
+            } else if trimmed.starts_with("|-") {
+                rows.push(Vec::new());
+            } else if let Some(cells) = trimmed.strip_prefix(['|', '!']) {
+                let header = trimmed.starts_with('!');
+                if rows.is_empty() {
+                    rows.push(Vec::new());
+                }
+                let Some(row) = rows.last_mut() else {
+                    continue;
+                };
+                let separator = if header { "!!" } else { "||" };
+                for cell in split_outside_links(cells, separator)
+                    .into_iter()
+                    .flat_map(|c| {
+                        if header {
+                            split_outside_links(c, "||")
+                        } else {
+                            vec![c]
+                        }
+                    })
+                {
+                    let (attrs, content) = cell_content(cell);
+                    row.push((header, attrs.to_string(), vec![content.to_string()]));
+                }
+            } else if let Some(cell) = rows.last_mut().and_then(|r| r.last_mut()) {
+                if trimmed.starts_with("{|") {
+                    depth += 1;
+                }
+                cell.2.push(line.to_string());
+            }
+        }
+        if let Some(caption) = caption {
+            html.push_str(&format!("<caption>{}</caption>\n", self.inline(&caption)));
+        }
+        for row in rows.into_iter().filter(|r| !r.is_empty()) {
+            html.push_str("<tr>");
+            for (header, attrs, content) in row {
+                let tag = if header { "th" } else { "td" };
+                html.push_str(&format!("<{tag}"));
+                for name in ["colspan", "rowspan"] {
+                    if let Some(val) = tag_attribute(&attrs, name)
+                        .filter(|v| v.chars().all(|c| c.is_ascii_digit()))
+                    {
+                        html.push_str(&format!(" {name}=\"{val}\""));
+                    }
+                }
+                html.push('>');
+                let content = if content.len() == 1 {
+                    self.inline(content[0].trim())
+                } else {
+                    let content = self.blocks(&content.join("\n"));
+                    content.trim_end().to_string()
+                };
+                html.push_str(&format!("{content}</{tag}>"));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+        html
+    }
+
+    /// Renders the inline markup of each line in `lines`.
+    fn inline_lines(&mut self, lines: &[&str]) -> String {
+        lines
+            .iter()
+            .map(|l| self.inline(l.trim_end()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the inline markup of one line of text.
+    fn inline(&mut self, text: &str) -> String {
+        let mut html = String::with_capacity(text.len() * 3 / 2);
+        // Open `<b>` and `<i>` tags.
+        let mut quotes: Vec<&str> = Vec::new();
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            if rest.starts_with("''") {
+                let n = rest.chars().take_while(|c| *c == '\'').count();
+                // Apostrophes beyond five are literal.
+                let (literal, n) = match n {
+                    2 | 3 | 5 => (0, n),
+                    4 => (1, 3),
+                    n => (n - 5, 5),
+                };
+                html.push_str(&"'".repeat(literal));
+                match n {
+                    2 => toggle_quote(&mut html, &mut quotes, "i"),
+                    3 => toggle_quote(&mut html, &mut quotes, "b"),
+                    _ => {
+                        if quotes.last() == Some(&"i") {
+                            toggle_quote(&mut html, &mut quotes, "i");
+                            toggle_quote(&mut html, &mut quotes, "b");
+                        } else {
+                            toggle_quote(&mut html, &mut quotes, "b");
+                            toggle_quote(&mut html, &mut quotes, "i");
+                        }
+                    }
+                }
+                rest = &rest[literal + n..];
+                continue;
+            }
+            if let Some((consumed, out)) = self.inline_element(rest) {
+                html.push_str(&out);
+                rest = &rest[consumed..];
+                continue;
+            }
+            match c {
+                '&' if entity_len(rest).is_some() => {
+                    let len = entity_len(rest).unwrap_or_default();
+                    html.push_str(&rest[..len]);
+                    rest = &rest[len..];
+                    continue;
+                }
+                _ => html.push_str(&encode_text(&rest[..c.len_utf8()])),
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+        while let Some(tag) = quotes.pop() {
+            html.push_str(&format!("</{tag}>"));
+        }
+        html
+    }
+
+    /// Tries to render an inline element at the start of `s`. Returns the
+    /// number of consumed bytes and the HTML rendition.
+    fn inline_element(&mut self, s: &str) -> Option<(usize, String)> {
+        if s.starts_with("[[") {
+            let end = matching_brackets(s)?;
+            let inner = &s[2..end];
+            // Link trail, e.g. `[[Note]]s`.
+            let trail = s[end + 2..]
+                .chars()
+                .take_while(|c| c.is_alphabetic())
+                .map(char::len_utf8)
+                .sum::<usize>();
+            if IMAGE_NAMESPACES.iter().any(|ns| inner.starts_with(ns)) {
+                return Some((end + 2, self.image(inner)));
+            }
+            let html = self.internal_link(inner, &s[end + 2..end + 2 + trail])?;
+            return Some((end + 2 + trail, html));
+        }
+
+        if let Some(inner) = s.strip_prefix('[')
+            && URL_SCHEMES.iter().any(|scheme| inner.starts_with(scheme))
+            && let Some(end) = inner.find(']')
+        {
+            let (url, text) = match inner[..end].split_once(' ') {
+                Some((url, text)) => (url, self.inline(text.trim())),
+                None => {
+                    self.autonumber += 1;
+                    (&inner[..end], format!("[{}]", self.autonumber))
+                }
+            };
+            return Some((end + 2, external_link(url, &text)));
+        }
+
+        if ["https://", "http://", "ftp://", "mailto:"]
+            .iter()
+            .any(|scheme| s.starts_with(scheme))
+        {
+            let end = s
+                .find(|c: char| c.is_whitespace() || matches!(c, '[' | ']' | '<' | '>' | '"'))
+                .unwrap_or(s.len());
+            let url = s[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '\'']);
+            return Some((url.len(), external_link(url, &encode_text(url))));
+        }
+
+        if !s.starts_with('<') {
+            return None;
+        }
+        let tag_end = s.find('>')?;
+        let tag = &s[1..tag_end];
+        let (name, closing, self_closing) = match tag.strip_prefix('/') {
+            Some(name) => (name.trim(), true, false),
+            None => (
+                tag.split(|c: char| c.is_whitespace() || c == '/')
+                    .next()
+                    .unwrap_or_default(),
+                false,
+                tag.ends_with('/'),
+            ),
+        };
+        let name = name.to_ascii_lowercase();
+        let consumed = tag_end + 1;
+
+        match name.as_str() {
+            "br" => Some((consumed, "<br>".to_string())),
+            "nowiki" | "math" | "ref" | "code" if !closing && !self_closing => {
+                let closing_tag = format!("</{name}>");
+                let content_end = s[consumed..].find(&closing_tag)? + consumed;
+                let content = &s[consumed..content_end];
+                let html = match name.as_str() {
+                    "nowiki" => encode_text(content).into_owned(),
+                    "math" => {
+                        let style = if tag_attribute(tag, "display").as_deref() == Some("block") {
+                            latex2mathml::DisplayStyle::Block
+                        } else {
+                            latex2mathml::DisplayStyle::Inline
+                        };
+                        latex2mathml::latex_to_mathml(content, style)
+                            .unwrap_or_else(|e| e.to_string())
+                    }
+                    "ref" => self.footnote(content, tag_attribute(tag, "name")),
+                    _ => format!("<code>{}</code>", self.inline(content)),
+                };
+                Some((content_end + closing_tag.len(), html))
+            }
+            "nowiki" => Some((consumed, String::new())),
+            "ref" if self_closing => {
+                let name = tag_attribute(tag, "name")?;
+                let n = *self.ref_names.get(&name)?;
+                Some((consumed, footnote_mark(n)))
+            }
+            name if ALLOWED_TAGS.contains(&name) => Some((
+                consumed,
+                if closing {
+                    format!("</{name}>")
+                } else {
+                    format!("<{name}>")
+                },
+            )),
+            name if REMOVED_TAGS.contains(&name) => Some((consumed, String::new())),
+            _ => None,
+        }
+    }
+
+    /// Renders `[[target|text]]`. Links to categories are dropped.
+    fn internal_link(&mut self, inner: &str, trail: &str) -> Option<String> {
+        let (target, text) = match inner.split_once('|') {
+            Some((target, text)) => (target.trim(), Some(text.trim())),
+            None => (inner.trim(), None),
+        };
+        if target.starts_with("Category:") {
+            return Some(String::new());
+        }
+        // `[[:Category:Name]]` links to the category instead.
+        let target = target.strip_prefix(':').unwrap_or(target);
+        if target.is_empty() || target.contains(['[', ']', '{', '}', '<', '>']) {
+            return None;
+        }
+        let text = match text {
+            // The "pipe trick" `[[Page (disambiguation)|]]`.
+            Some("") => target
+                .split_once(" (")
+                .map_or(target, |(t, _)| t)
+                .to_string(),
+            Some(text) => self.inline(text),
+            None => encode_text(target).into_owned(),
+        };
+        let href = match target.split_once('#') {
+            Some(("", fragment)) => format!("#{}", section_id(fragment)),
+            Some((page, fragment)) => format!(
+                "{WIKI_LINK_MARKER}{}#{}",
+                utf8_percent_encode(page.trim(), WIKI_LINK_ENCODE_SET),
+                section_id(fragment)
+            ),
+            None => format!(
+                "{WIKI_LINK_MARKER}{}",
+                utf8_percent_encode(target, WIKI_LINK_ENCODE_SET)
+            ),
+        };
+        Some(format!(
+            "<a href=\"{}\">{text}{}</a>",
+            encode_double_quoted_attribute(&href),
+            encode_text(trail)
+        ))
+    }
+
+    /// Renders `[[File:name.png|options|caption]]` as local image.
+    fn image(&mut self, inner: &str) -> String {
+        let mut parts = split_outside_links(inner, "|").into_iter();
+        let target = parts.next().unwrap_or_default();
+        let src = target
+            .split_once(':')
+            .map_or(target, |(_, name)| name)
+            .trim();
+        let mut alt = None;
+        let mut caption = None;
+        let mut width = None;
+        for part in parts {
+            let part = part.trim();
+            if let Some(a) = part.strip_prefix("alt=") {
+                alt = Some(a.to_string());
+            } else if let Some(w) = part.strip_suffix("px") {
+                width = w.split('x').next().filter(|w| w.parse::<u32>().is_ok());
+            } else if !matches!(
+                part,
+                "thumb"
+                    | "thumbnail"
+                    | "frame"
+                    | "framed"
+                    | "frameless"
+                    | "border"
+                    | "left"
+                    | "right"
+                    | "center"
+                    | "none"
+                    | "upright"
+            ) && !part.starts_with("link=")
+                && !part.starts_with("upright=")
+            {
+                caption = Some(part);
+            }
+        }
+        let alt = alt.or_else(|| caption.map(|c| plain_text(&self.inline(c))));
+        let mut html = format!(
+            "<img src=\"{}\" alt=\"{}\"",
+            utf8_percent_encode(src, WIKI_LINK_ENCODE_SET),
+            encode_double_quoted_attribute(&alt.unwrap_or_default())
+        );
+        if let Some(width) = width {
+            html.push_str(&format!(" width=\"{width}\""));
+        }
+        html.push('>');
+        html
+    }
+
+    /// Registers a footnote and returns its mark.
+    fn footnote(&mut self, content: &str, name: Option<String>) -> String {
+        if let Some(n) = name.as_ref().and_then(|n| self.ref_names.get(n)) {
+            return footnote_mark(*n);
+        }
+        self.ref_count += 1;
+        let note = self.inline(content.trim());
+        self.refs.push(note);
+        if let Some(name) = name {
+            self.ref_names.insert(name, self.ref_count);
+        }
+        footnote_mark(self.ref_count)
+    }
+}
+
+fn toggle_quote<'a>(html: &mut String, quotes: &mut Vec<&'a str>, tag: &'a str) {
+    match quotes.iter().rposition(|t| *t == tag) {
+        Some(pos) => {
+            // Close the tags opened later, then reopen them.
+            let reopen: Vec<_> = quotes.drain(pos..).skip(1).collect();
+            for t in reopen.iter().rev() {
+                html.push_str(&format!("</{t}>"));
+            }
+            html.push_str(&format!("</{tag}>"));
+            for t in reopen {
+                html.push_str(&format!("<{t}>"));
+                quotes.push(t);
+            }
+        }
+        None => {
+            html.push_str(&format!("<{tag}>"));
+            quotes.push(tag);
+        }
+    }
+}
+
+fn footnote_mark(n: usize) -> String {
+    format!("<sup class=\"reference\"><a href=\"#cite_note-{n}\">[{n}]</a></sup>")
+}
+
+fn external_link(url: &str, text: &str) -> String {
+    let url = if url.starts_with("//") {
+        format!("https:{url}")
+    } else {
+        url.to_string()
+    };
+    format!(
+        "<a href=\"{}\">{text}</a>",
+        encode_double_quoted_attribute(&url)
+    )
+}
+
+fn is_dl(marker: char) -> bool {
+    matches!(marker, ';' | ':')
+}
+
+fn list_open(marker: char) -> &'static str {
+    match marker {
+        '*' => "<ul>\n",
+        '#' => "<ol>\n",
+        _ => "<dl>\n",
+    }
+}
+
+fn list_close(marker: char) -> &'static str {
+    match marker {
+        '*' => "</ul>\n",
+        '#' => "</ol>\n",
+        _ => "</dl>\n",
+    }
+}
+
+fn item_open(marker: char) -> &'static str {
+    match marker {
+        ';' => "<dt>",
+        ':' => "<dd>",
+        _ => "<li>",
+    }
+}
+
+fn item_close(marker: char) -> &'static str {
+    match marker {
+        ';' => "</dt>\n",
+        ':' => "</dd>\n",
+        _ => "</li>\n",
+    }
+}
+
+/// Splits `term : definition` at the first colon outside of links.
+fn split_definition(text: &str) -> Option<(&str, &str)> {
+    let parts = split_outside_links(text, ":");
+    let first = parts.first()?;
+    // Do not split URLs.
+    if parts.len() < 2 || text[first.len()..].starts_with("://") {
+        return None;
+    }
+    Some((first.trim(), text[first.len() + 1..].trim()))
+}
+
+/// Returns the level and the title of the heading `== title ==`.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '=').count();
+    let trailing = line.chars().rev().take_while(|c| *c == '=').count();
+    let level = level.min(trailing).min(6);
+    if level == 0 || line.len() <= 2 * level {
+        return None;
+    }
+    let title = line[level..line.len() - level].trim();
+    (!title.is_empty()).then_some((level, title))
+}
+
+/// Derives a MediaWiki compatible anchor from the rendered heading `html`.
+fn section_id(html: &str) -> String {
+    plain_text(html).trim().replace(char::is_whitespace, "_")
+}
+
+/// Removes tags and decodes entities in `html`.
+fn plain_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    decode_html_entities(&text).into_owned()
+}
+
+/// `__TOC__`, `__NOTOC__` and similar.
+fn is_magic_word(line: &str) -> bool {
+    line.len() > 4
+        && line.starts_with("__")
+        && line.ends_with("__")
+        && line[2..line.len() - 2]
+            .chars()
+            .all(|c| c.is_ascii_uppercase())
+}
+
+/// Does `line` start with the HTML tag `<name ...>`?
+fn starts_with_tag(line: &str, name: &str) -> bool {
+    line.strip_prefix('<')
+        .and_then(|l| {
+            l.get(..name.len())
+                .filter(|n| n.eq_ignore_ascii_case(name))
+                .map(|_| &l[name.len()..])
+        })
+        .is_some_and(|rest| rest.starts_with(['>', ' ', '/']))
+}
+
+/// Returns the value of the attribute `name` in the tag `tag`.
+fn tag_attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    while let Some(pos) = rest.find(name) {
+        let before = rest[..pos].chars().next_back();
+        let after = rest[pos + name.len()..].trim_start();
+        rest = &rest[pos + name.len()..];
+        if before.is_some_and(|c| c.is_alphanumeric()) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let value = match value.chars().next() {
+            Some(q @ ('"' | '\'')) => value[1..].split(q).next().unwrap_or_default(),
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .next()
+                .unwrap_or_default(),
+        };
+        return Some(value.to_string());
+    }
+    None
+}
+
+/// Returns the length of the HTML entity at the start of `s`.
+fn entity_len(s: &str) -> Option<usize> {
+    let end = s.find(';')?;
+    let name = &s[1..end];
+    let valid = match name.strip_prefix('#') {
+        Some(num) => num.strip_prefix(['x', 'X']).map_or(
+            !num.is_empty() && num.chars().all(|c| c.is_ascii_digit()),
+            |hex| !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        ),
+        None => !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric()),
+    };
+    (valid && end < 12).then_some(end + 1)
+}
+
+/// Returns the position of the `]]` matching the `[[` at the start of `s`.
+fn matching_brackets(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = 0;
+    while i + 1 < s.len() {
+        match &s.as_bytes()[i..i + 2] {
+            b"[[" => {
+                depth += 1;
+                i += 2;
+            }
+            b"]]" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Splits `s` at `separator`, but not inside `[[...]]`.
+fn split_outside_links<'a>(s: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut i = 0;
+    let bytes = s.as_bytes();
+    while i < s.len() {
+        if bytes[i..].starts_with(b"[[") {
+            depth += 1;
+            i += 2;
+        } else if bytes[i..].starts_with(b"]]") {
+            depth = depth.saturating_sub(1);
+            i += 2;
+        } else if depth == 0 && bytes[i..].starts_with(separator.as_bytes()) {
+            parts.push(&s[start..i]);
+            i += separator.len();
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Splits a table cell `attributes | content` into its attributes and its
+/// content.
+fn cell_content(cell: &str) -> (&str, &str) {
+    let parts = split_outside_links(cell, "|");
+    match parts.as_slice() {
+        [attrs, ..] if parts.len() > 1 && attrs.contains('=') && !attrs.contains("[") => {
+            (attrs.trim(), cell[attrs.len() + 1..].trim())
+        }
+        _ => ("", cell.trim()),
+    }
+}
+
+/// Returns the index of the line after the table starting at `lines[start]`.
+fn table_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        let line = line.trim();
+        if line.starts_with("{|") {
+            depth += 1;
+        } else if line.starts_with("|}") {
+            depth -= 1;
+            if depth == 0 {
+                return i + 1;
+            }
+        }
+    }
+    lines.len()
+}
+
+/// Removes `<!-- comments -->` and `{{templates}}`. The content of
+/// `<nowiki>`, `<pre>`, `<syntaxhighlight>`, `<source>` and `<math>` is kept
+/// verbatim.
+fn strip_comments_and_templates(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    let mut depth = 0;
+    while let Some(c) = rest.chars().next() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if rest.starts_with("{{") {
+            depth += 1;
+            rest = &rest[2..];
+            continue;
+        }
+        if depth > 0 && rest.starts_with("}}") {
+            depth -= 1;
+            rest = &rest[2..];
+            continue;
+        }
+        if c == '<'
+            && let Some(tag) = ["nowiki", "pre", "syntaxhighlight", "source", "math"]
+                .into_iter()
+                .find(|t| starts_with_tag(rest, t))
+        {
+            let closing = format!("</{tag}>");
+            let end = rest
+                .find(&closing)
+                .map_or(rest.len(), |e| e + closing.len());
+            if depth == 0 {
+                output.push_str(&rest[..end]);
+            }
+            rest = &rest[end..];
+            continue;
+        }
+        if depth == 0 {
+            output.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mediawiki_to_html;
+
+    #[test]
+    fn test_mediawiki_headings_and_quotes() {
+        let input = "{{Infobox\n| name = {{PAGENAME}}\n}}\n__TOC__\n\
+            == First section ==\n<!-- comment -->\nSome ''italic'', '''bold''' and \
+            '''''both''''' text.\nNext line with a<br/>break.\n\n=== Sub ===\n\
+            == First section ==\n";
+        let expected = "<h2 id=\"First_section\">First section</h2>\n\
+            <p>Some <i>italic</i>, <b>bold</b> and <b><i>both</i></b> text.\n\
+            Next line with a<br>break.</p>\n\
+            <h3 id=\"Sub\">Sub</h3>\n\
+            <h2 id=\"First_section-1\">First section</h2>\n";
+        assert_eq!(mediawiki_to_html(input), expected);
+
+        // Escaping and allowed tags.
+        let input = "a < b & c &amp; <sup>2</sup> <script>x</script> \
+            <nowiki>''raw''</nowiki>";
+        let expected = "<p>a &lt; b &amp; c &amp; <sup>2</sup> \
+            &lt;script&gt;x&lt;/script&gt; ''raw''</p>\n";
+        assert_eq!(mediawiki_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_mediawiki_lists() {
+        let input = "* one\n** nested\n* two\n# first\n#* sub\n# second\n\
+            ; Term : Definition\n; Other\n: More\n";
+        let expected = "<ul>\n<li>one\n<ul>\n<li>nested</li>\n</ul>\n</li>\n\
+            <li>two</li>\n</ul>\n\
+            <ol>\n<li>first\n<ul>\n<li>sub</li>\n</ul>\n</li>\n<li>second</li>\n</ol>\n\
+            <dl>\n<dt>Term</dt>\n<dd>Definition</dd>\n<dt>Other</dt>\n<dd>More</dd>\n</dl>\n";
+        assert_eq!(mediawiki_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_mediawiki_links() {
+        let input = "[[Main Page]], [[20221030]], [[Other note#Some part|part]], \
+            [[#Local]], [[Note]]s, [[Category:Ignored]]\n\
+            [https://example.com Example], [https://example.com], \
+            https://example.org. [[File:My image.png|thumb|A caption]]";
+        let expected = "<p><a href=\"tpnote-wiki:Main%20Page\">Main Page</a>, \
+            <a href=\"tpnote-wiki:20221030\">20221030</a>, \
+            <a href=\"tpnote-wiki:Other%20note#Some_part\">part</a>, \
+            <a href=\"#Local\">#Local</a>, <a href=\"tpnote-wiki:Note\">Notes</a>, \n\
+            <a href=\"https://example.com\">Example</a>, \
+            <a href=\"https://example.com\">[1]</a>, \
+            <a href=\"https://example.org\">https://example.org</a>. \
+            <img src=\"My%20image.png\" alt=\"A caption\"></p>\n";
+        assert_eq!(mediawiki_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_mediawiki_tables() {
+        let input = "{| class=\"wikitable\"\n|+ Prices\n! Name !! Price\n|-\n\
+            | Apple || 1\n|-\n| colspan=\"2\" style=\"color:red\" | [[Pear|pears]]\n|}\n";
+        let expected = "<table>\n<caption>Prices</caption>\n\
+            <tr><th>Name</th><th>Price</th></tr>\n\
+            <tr><td>Apple</td><td>1</td></tr>\n\
+            <tr><td colspan=\"2\"><a href=\"tpnote-wiki:Pear\">pears</a></td></tr>\n\
+            </table>\n";
+        assert_eq!(mediawiki_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_mediawiki_code_and_refs() {
+        let input = "Fact.<ref name=\"a\">Source</ref> Again.<ref name=\"a\" />\n\
+            <syntaxhighlight lang=\"rust\">\nfn main() {}\n</syntaxhighlight>\n \
+            pre ''formatted''\n\n<references />";
+        let result = mediawiki_to_html(input);
+        assert!(result.starts_with(
+            "<p>Fact.<sup class=\"reference\"><a href=\"#cite_note-1\">[1]</a></sup> \
+            Again.<sup class=\"reference\"><a href=\"#cite_note-1\">[1]</a></sup></p>\n\
+            <pre><code class=\"language-rust\">"
+        ));
+        assert!(result.ends_with(
+            "</code></pre>\n<pre>pre <i>formatted</i></pre>\n\
+            <ol class=\"references\" start=\"1\">\n<li id=\"cite_note-1\">Source</li>\n</ol>\n"
+        ));
+    }
+}
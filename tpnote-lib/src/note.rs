@@ -5,6 +5,7 @@
 //! parsing its front matter.
 //! NB: The high level API is in the module `tpnote_lib::workflow`.
 
+use crate::config::LIB_CFG;
 use crate::config::TMPL_VAR_DOC;
 use crate::content::Content;
 use crate::context::Context;
@@ -16,7 +17,10 @@ use crate::filename::NotePath;
 use crate::filename::NotePathBuf;
 use crate::filter::TERA;
 use crate::front_matter::FrontMatter;
+use crate::front_matter::FrontMatterFormat;
+use crate::front_matter::yaml_to_toml;
 use crate::note_error_tera_template;
+use crate::settings::SETTINGS;
 use crate::template::TemplateKind;
 use std::default::Default;
 use std::fs;
@@ -37,6 +41,8 @@ pub struct Note<T: Content> {
     /// The full text content of the note, including
     /// its front matter.
     pub content: T,
+    /// The front matter as deserialized from `content`'s header.
+    pub front_matter: FrontMatter,
     /// This field equals to `PathBuf::new()` until `self.render_filename()`
     /// is called.
     pub rendered_filename: PathBuf,
@@ -74,7 +80,7 @@ impl<T: Content> Note<T> {
 
         // Deserialize the note's header read from disk.
         // Store the front matter in the context for later use in templates.
-        let fm = FrontMatter::try_from(content.header())?;
+        let fm = FrontMatter::try_from_content(&content)?;
         let context = context.insert_front_matter(&fm);
 
         // This data comes from outside. We need additional checks here.
@@ -90,6 +96,7 @@ impl<T: Content> Note<T> {
         Ok(Note {
             context,
             content,
+            front_matter: fm,
             rendered_filename: PathBuf::new(),
         })
     }
@@ -146,8 +153,30 @@ impl<T: Content> Note<T> {
             new_content.body()
         );
 
+        // Convert the YAML front matter, if the scheme asks for TOML.
+        let fm_format = LIB_CFG.read_recursive().scheme[SETTINGS.read_recursive().current_scheme]
+            .tmpl
+            .fm_format;
+        let new_content = if fm_format == FrontMatterFormat::Toml
+            && new_content.header_format() == FrontMatterFormat::Yaml
+            && !new_content.header().is_empty()
+        {
+            match yaml_to_toml(new_content.header()) {
+                Some(toml) => T::from_string(
+                    format!("+++\n{}+++\n{}", toml, new_content.body()),
+                    TMPL_VAR_DOC.to_string(),
+                ),
+                None => {
+                    log::warn!("Can not convert the front matter into TOML, keeping YAML.");
+                    new_content
+                }
+            }
+        } else {
+            new_content
+        };
+
         // Deserialize the rendered template
-        let fm = FrontMatter::try_from(new_content.header())?;
+        let fm = FrontMatter::try_from_content(&new_content)?;
 
         let new_context = Context::from_context_path(&context).insert_front_matter(&fm);
 
@@ -155,6 +184,7 @@ impl<T: Content> Note<T> {
         Ok(Note {
             context: new_context,
             content: new_content,
+            front_matter: fm,
             rendered_filename: PathBuf::new(),
         })
     }
@@ -955,11 +985,38 @@ Body text
         let tmpl = "{{ doc.body | markup_to_html(extension='md') | safe }}";
         let html = n.render_content_to_html(tmpl, "").unwrap();
         assert!(
-            html.contains("<h1>Hello</h1>"),
+            html.contains("<h1 id=\"hello\">Hello</h1>"),
             "unexpected Markdown output: {html}"
         );
     }
 
+    /// The HTML template variable `doc_toc` lists the headings of the note
+    /// with their anchors.
+    #[test]
+    #[cfg(feature = "renderer")]
+    fn test_render_content_to_html_toc() {
+        use crate::content::Content;
+        use crate::content::ContentString;
+        use crate::context::Context;
+        use crate::note::Note;
+        use crate::template::TemplateKind;
+        use std::env::temp_dir;
+        use std::fs;
+
+        let raw = "---\ntitle: TOC test\n---\n# Hello\nWorld\n## Sub *section*\n# Hello";
+        let notefile = temp_dir().join("20221030-toc-test.md");
+        fs::write(&notefile, raw.as_bytes()).unwrap();
+
+        let context = Context::from(&notefile).unwrap();
+        let content = <ContentString as Content>::open(&notefile).unwrap();
+        let n = Note::<ContentString>::from_existing_content(context, content, TemplateKind::None)
+            .unwrap();
+
+        let tmpl = "{% for h in doc_toc %}{{ h.level }}:{{ h.text }}:{{ h.anchor }} {% endfor %}";
+        let html = n.render_content_to_html(tmpl, "").unwrap();
+        assert_eq!(html, "1:Hello:hello 2:Sub section:sub-section 1:Hello:hello-1 ");
+    }
+
     /// Valid RST in the note body renders successfully through a custom
     /// `markup_to_html` template call.
     #[test]
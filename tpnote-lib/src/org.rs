@@ -0,0 +1,1000 @@
+//! A renderer for Emacs' Org-mode markup.
+//!
+//! Supported are headlines with TODO keywords, priorities and tags,
+//! paragraphs, (nested) ordered, unordered, check and description lists,
+//! tables, fixed-width areas, source blocks (highlighted with the configured
+//! `HighlightRegistry`), example, quote, verse, center and export blocks,
+//! links and the emphasis markers `*bold*`, `/italic/`, `_underline_`,
+//! `+strike-through+`, `=verbatim=` and `~code~`. Keywords `#+...:`,
+//! comments and drawers are not rendered.
+//!
+//! Links to headlines `[[*Headline]]`, to custom ids `[[#id]]` and to
+//! dedicated targets `<<target>>` are resolved in the document. All other
+//! internal links, e.g. `[[My note]]`, are marked with `WIKI_LINK_MARKER`,
+//! like Markdown wiki links. `html::rewrite_links()` resolves them later
+//! against the titles and sort-tags of the Tp-Note files under the root path.
+use crate::front_matter::org_keyword as keyword;
+use crate::highlight::HighlightRegistry;
+use crate::html::WIKI_LINK_MARKER;
+use crate::html::slug;
+use crate::html::unique_id;
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Characters percent encoded in the destination of internal links.
+const WIKI_LINK_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`');
+
+/// TODO keywords known without `#+TODO:` line. The keywords after `|` mark
+/// done states.
+const DEFAULT_TODO_KEYWORDS: &str = "TODO NEXT WAIT WAITING HOLD | DONE CANCELED CANCELLED";
+
+/// Special blocks rendered as admonitions.
+const ADMONITIONS: [&str; 5] = ["note", "tip", "important", "warning", "caution"];
+
+/// File extensions of links rendered as inline images.
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp"];
+
+/// Emphasis markers and their HTML tags.
+const EMPHASIS: [(char, &str); 6] = [
+    ('*', "b"),
+    ('/', "i"),
+    ('_', "u"),
+    ('+', "del"),
+    ('=', "code"),
+    ('~', "code"),
+];
+
+/// The HTML writer for Org input.
+struct Renderer {
+    /// TODO keywords mapped to `true` for done states.
+    todo_keywords: HashMap<String, bool>,
+    /// Headline titles and dedicated targets mapped to their ids.
+    targets: HashMap<String, String>,
+    /// Ids already in use.
+    ids: HashSet<String>,
+    /// Ids of the headlines in document order.
+    headline_ids: Vec<String>,
+    /// Index of the next headline in `headline_ids`.
+    headline_count: usize,
+    registry: Arc<HighlightRegistry>,
+}
+
+/// Renders Org `input` to HTML.
+///
+/// ```rust
+/// use tpnote_lib::org::org_to_html;
+///
+/// let input = "* TODO Intro :work:\nSee [[*Intro][here]] and *bold* text.";
+/// let expected = "<h1 id=\"intro\"><span class=\"todo TODO\">TODO</span> Intro \
+///     <span class=\"tags\"><span class=\"tag\">work</span></span></h1>\n\
+///     <p>See <a href=\"#intro\">here</a> and <b>bold</b> text.</p>\n";
+/// assert_eq!(org_to_html(input), expected);
+/// ```
+pub fn org_to_html(input: &str) -> String {
+    let mut renderer = Renderer {
+        todo_keywords: HashMap::new(),
+        targets: HashMap::new(),
+        ids: HashSet::new(),
+        headline_ids: Vec::new(),
+        headline_count: 0,
+        registry: HighlightRegistry::configured(),
+    };
+    renderer.collect_targets(input);
+    let lines: Vec<&str> = input.lines().collect();
+    renderer.blocks(&lines)
+}
+
+impl Renderer {
+    /// First pass: registers the TODO keywords, the ids of all headlines and
+    /// the dedicated targets `<<target>>`.
+    fn collect_targets(&mut self, input: &str) {
+        self.parse_todo_keywords(DEFAULT_TODO_KEYWORDS);
+        for line in input.lines() {
+            if let Some((key, value)) = keyword(line)
+                && matches!(key.as_str(), "todo" | "seq_todo" | "typ_todo")
+            {
+                self.parse_todo_keywords(value);
+            }
+        }
+
+        let lines: Vec<&str> = input.lines().collect();
+        let mut in_block = false;
+        for (i, line) in lines.iter().enumerate() {
+            let trimmed = line.trim();
+            if block_begin(trimmed).is_some() {
+                in_block = true;
+            } else if in_block {
+                in_block = !trimmed.to_lowercase().starts_with("#+end_");
+            } else if let Some((_, title)) = self.headline_parts(line).map(|h| (h.level, h.title)) {
+                let id = match custom_id(&lines[i + 1..]) {
+                    Some(id) => id,
+                    None => unique_id(&mut self.ids, &slug(title)),
+                };
+                self.targets
+                    .entry(title.to_string())
+                    .or_insert_with(|| id.clone());
+                self.headline_ids.push(id);
+            } else {
+                let mut rest = *line;
+                while let Some(start) = rest.find("<<") {
+                    let after = &rest[start + 2..];
+                    let Some(end) = after.find(">>") else {
+                        break;
+                    };
+                    let target = after[..end].trim_matches(['<', '>']);
+                    if !target.is_empty() {
+                        let id = unique_id(&mut self.ids, &slug(target));
+                        self.targets.insert(target.to_string(), id);
+                    }
+                    rest = &after[end + 2..];
+                }
+            }
+        }
+    }
+
+    /// Parses the TODO keyword sequence `TODO NEXT | DONE`. Without `|`, the
+    /// last keyword is the only done state.
+    fn parse_todo_keywords(&mut self, sequence: &str) {
+        let words: Vec<&str> = sequence.split_whitespace().collect();
+        let separator = words.iter().position(|w| *w == "|");
+        for (n, word) in words.iter().enumerate() {
+            if *word == "|" {
+                continue;
+            }
+            let done = match separator {
+                Some(separator) => n > separator,
+                None => n + 1 == words.len(),
+            };
+            // A keyword may carry a fast access key: `TODO(t)`.
+            let word = word.split('(').next().unwrap_or(word);
+            self.todo_keywords.insert(word.to_string(), done);
+        }
+    }
+
+    /// Splits a headline into its parts.
+    fn headline_parts<'a>(&self, line: &'a str) -> Option<Headline<'a>> {
+        let level = line.chars().take_while(|c| *c == '*').count();
+        let mut rest = line.get(level..)?;
+        if level == 0 || !(rest.is_empty() || rest.starts_with(' ')) {
+            return None;
+        }
+        rest = rest.trim();
+        let mut todo = None;
+        if let Some((word, tail)) = rest.split_once(' ').or(Some((rest, "")))
+            && let Some(done) = self.todo_keywords.get(word)
+        {
+            todo = Some((word, *done));
+            rest = tail.trim_start();
+        }
+        let mut priority = None;
+        if rest.starts_with("[#") && rest.get(3..4) == Some("]") {
+            priority = rest.get(2..3);
+            rest = rest[4..].trim_start();
+        }
+        let mut tags = Vec::new();
+        if rest.ends_with(':')
+            && let Some(start) = rest.trim_end_matches(':').rfind([' ', '\t'])
+        {
+            let candidate = &rest[start + 1..];
+            if candidate.starts_with(':')
+                && candidate.len() > 2
+                && candidate[1..candidate.len() - 1].split(':').all(|t| {
+                    !t.is_empty() && t.chars().all(|c| c.is_alphanumeric() || "_@#%".contains(c))
+                })
+            {
+                tags = candidate[1..candidate.len() - 1].split(':').collect();
+                rest = rest[..start].trim_end();
+            }
+        } else if rest.starts_with(':') && rest.ends_with(':') && rest.len() > 2 {
+            // A headline with tags only.
+            tags = rest[1..rest.len() - 1].split(':').collect();
+            rest = "";
+        }
+        Some(Headline {
+            level,
+            todo,
+            priority,
+            title: rest,
+            tags,
+        })
+    }
+
+    /// Renders the block level elements of `lines`.
+    fn blocks(&mut self, lines: &[&str]) -> String {
+        let mut html = String::new();
+        let mut caption: Option<String> = None;
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            let trimmed = line.trim();
+
+            if trimmed.is_empty() || is_comment(trimmed) {
+                i += 1;
+                continue;
+            }
+
+            if let Some(h) = self.headline_parts(line) {
+                html.push_str(&self.headline(&h));
+                i += 1;
+                // Planning line and property drawer.
+                if let Some(next) = lines.get(i)
+                    && is_planning(next.trim())
+                {
+                    html.push_str(&format!(
+                        "<p class=\"planning\">{}</p>\n",
+                        self.inline(next.trim())
+                    ));
+                    i += 1;
+                }
+                continue;
+            }
+
+            if let Some((key, value)) = keyword(trimmed) {
+                if key == "caption" {
+                    caption = Some(value.to_string());
+                }
+                i += 1;
+                continue;
+            }
+
+            if let Some((name, args)) = block_begin(trimmed) {
+                let start = i + 1;
+                let end_marker = format!("#+end_{name}");
+                i = start;
+                let mut depth = 0;
+                while i < lines.len() {
+                    let l = lines[i].trim().to_lowercase();
+                    if l.starts_with(&format!("#+begin_{name}")) {
+                        depth += 1;
+                    } else if l == end_marker || l.starts_with(&format!("{end_marker} ")) {
+                        if depth == 0 {
+                            break;
+                        }
+                        depth -= 1;
+                    }
+                    i += 1;
+                }
+                let content = &lines[start..i.min(lines.len())];
+                i += 1;
+                html.push_str(&self.block(&name, args, content));
+                continue;
+            }
+
+            if is_drawer_start(trimmed) {
+                while i < lines.len() && !lines[i].trim().eq_ignore_ascii_case(":end:") {
+                    i += 1;
+                }
+                i += 1;
+                continue;
+            }
+
+            if trimmed.len() >= 5 && trimmed.chars().all(|c| c == '-') {
+                html.push_str("<hr>\n");
+                i += 1;
+                continue;
+            }
+
+            if trimmed == ":" || trimmed.starts_with(": ") {
+                let mut text = String::new();
+                while i < lines.len() {
+                    let t = lines[i].trim();
+                    let Some(content) = t.strip_prefix(": ").or((t == ":").then_some("")) else {
+                        break;
+                    };
+                    text.push_str(content);
+                    text.push('\n');
+                    i += 1;
+                }
+                html.push_str(&format!(
+                    "<pre class=\"example\">{}</pre>\n",
+                    encode_text(&text)
+                ));
+                continue;
+            }
+
+            if trimmed.starts_with('|') {
+                let start = i;
+                while i < lines.len() && lines[i].trim().starts_with('|') {
+                    i += 1;
+                }
+                html.push_str(&self.table(&lines[start..i], caption.take()));
+                continue;
+            }
+
+            if list_item(line).is_some() {
+                let start = i;
+                i = list_end(lines, i);
+                html.push_str(&self.list(&lines[start..i]));
+                continue;
+            }
+
+            // Paragraph.
+            let start = i;
+            while i < lines.len() {
+                let l = lines[i];
+                let t = l.trim();
+                if i > start
+                    && (t.is_empty()
+                        || self.headline_parts(l).is_some()
+                        || keyword(t).is_some()
+                        || block_begin(t).is_some()
+                        || t.starts_with('|')
+                        || t.starts_with(": ")
+                        || is_comment(t)
+                        || list_item(l).is_some())
+                {
+                    break;
+                }
+                i += 1;
+            }
+            html.push_str(&format!("<p>{}</p>\n", self.inline_lines(&lines[start..i])));
+        }
+        html
+    }
+
+    fn headline(&mut self, h: &Headline) -> String {
+        let id = self
+            .headline_ids
+            .get(self.headline_count)
+            .cloned()
+            .unwrap_or_else(|| slug(h.title));
+        self.headline_count += 1;
+        let level = h.level.min(6);
+        let mut html = format!("<h{level} id=\"{}\">", encode_double_quoted_attribute(&id));
+        if let Some((keyword, done)) = h.todo {
+            let class = if done { "done" } else { "todo" };
+            html.push_str(&format!(
+                "<span class=\"{class} {keyword}\">{keyword}</span> "
+            ));
+        }
+        if let Some(priority) = h.priority {
+            html.push_str(&format!(
+                "<span class=\"priority\">[{}]</span> ",
+                encode_text(priority)
+            ));
+        }
+        html.push_str(&self.inline(h.title));
+        if !h.tags.is_empty() {
+            html.push_str(" <span class=\"tags\">");
+            for tag in &h.tags {
+                html.push_str(&format!("<span class=\"tag\">{}</span>", encode_text(tag)));
+            }
+            html.push_str("</span>");
+        }
+        html.push_str(&format!("</h{level}>\n"));
+        html
+    }
+
+    /// Renders the block `#+BEGIN_name args` with the `content` lines.
+    fn block(&mut self, name: &str, args: &str, content: &[&str]) -> String {
+        let text = unindent(content);
+        match name {
+            "src" => {
+                let lang = args.split_whitespace().next().unwrap_or_default();
+                let code = format!("{}\n", text.trim_end_matches('\n'));
+                let mut html = self.registry.highlight_code(lang, &code);
+                html.push('\n');
+                html
+            }
+            "example" => format!("<pre class=\"example\">{}\n</pre>\n", encode_text(&text)),
+            "quote" => {
+                let lines: Vec<&str> = text.lines().collect();
+                format!("<blockquote>\n{}</blockquote>\n", self.blocks(&lines))
+            }
+            "verse" => {
+                let lines: Vec<&str> = text.lines().collect();
+                format!(
+                    "<p class=\"verse\">\n{}</p>\n",
+                    lines
+                        .iter()
+                        .map(|l| format!("{}<br>\n", self.inline(l)))
+                        .collect::<String>()
+                )
+            }
+            "export" if args.trim().eq_ignore_ascii_case("html") => format!("{text}\n"),
+            "export" | "comment" => String::new(),
+            name if ADMONITIONS.contains(&name) => {
+                let lines: Vec<&str> = text.lines().collect();
+                let mut title = name.to_string();
+                title[..1].make_ascii_uppercase();
+                format!(
+                    "<div class=\"admonition {name}\">\n\
+                    <p class=\"admonition-title\">{title}</p>\n{}</div>\n",
+                    self.blocks(&lines)
+                )
+            }
+            name => {
+                let lines: Vec<&str> = text.lines().collect();
+                format!(
+                    "<div class=\"{}\">\n{}</div>\n",
+                    encode_double_quoted_attribute(name),
+                    self.blocks(&lines)
+                )
+            }
+        }
+    }
+
+    /// Renders a table. Rows above the first horizontal rule are header rows.
+    fn table(&mut self, lines: &[&str], caption: Option<String>) -> String {
+        let is_rule = |l: &&str| l.trim().starts_with("|-");
+        let header_rows = match lines.iter().position(is_rule) {
+            Some(n) if n > 0 && n < lines.len() - 1 => n,
+            _ => 0,
+        };
+        let mut html = String::from("<table>\n");
+        if let Some(caption) = caption {
+            html.push_str(&format!("<caption>{}</caption>\n", self.inline(&caption)));
+        }
+        let rows: Vec<&str> = lines.iter().copied().filter(|l| !is_rule(l)).collect();
+        for (n, row) in rows.iter().enumerate() {
+            if n == 0 && header_rows > 0 {
+                html.push_str("<thead>\n");
+            }
+            if n == header_rows {
+                html.push_str("<tbody>\n");
+            }
+            let tag = if n < header_rows { "th" } else { "td" };
+            let row = row.trim().trim_start_matches('|');
+            let row = row.strip_suffix('|').unwrap_or(row);
+            html.push_str("<tr>");
+            for cell in row.split('|') {
+                html.push_str(&format!("<{tag}>{}</{tag}>", self.inline(cell.trim())));
+            }
+            html.push_str("</tr>\n");
+            if n + 1 == header_rows {
+                html.push_str("</thead>\n");
+            }
+        }
+        if rows.len() > header_rows {
+            html.push_str("</tbody>\n");
+        }
+        html.push_str("</table>\n");
+        html
+    }
+
+    /// Renders a list. `lines` start with a list item.
+    fn list(&mut self, lines: &[&str]) -> String {
+        let Some((indent, bullet, _)) = list_item(lines[0]) else {
+            return String::new();
+        };
+        let ordered = bullet.ends_with(['.', ')']);
+        let description = !ordered && lines[0].contains(" :: ");
+        let tag = if ordered {
+            "ol"
+        } else if description {
+            "dl"
+        } else {
+            "ul"
+        };
+        let mut html = format!("<{tag}>\n");
+
+        // Split into items.
+        let mut items: Vec<Vec<&str>> = Vec::new();
+        for line in lines {
+            match list_item(line) {
+                Some((i, _, _)) if i == indent => items.push(vec![line]),
+                _ => {
+                    if let Some(item) = items.last_mut() {
+                        item.push(line);
+                    }
+                }
+            }
+        }
+
+        for item in items {
+            let Some((_, bullet, text)) = list_item(item[0]) else {
+                continue;
+            };
+            // Continuation lines are unindented relative to the item text.
+            let content_indent = indent + bullet.len() + 1;
+            let mut content: Vec<&str> = Vec::with_capacity(item.len());
+            content.push(text);
+            for line in &item[1..] {
+                let strip = line
+                    .char_indices()
+                    .take_while(|(i, c)| *i < content_indent && c.is_whitespace())
+                    .map(|(_, c)| c.len_utf8())
+                    .sum::<usize>();
+                content.push(&line[strip..]);
+            }
+            while content.len() > 1 && content.last().is_some_and(|l| l.trim().is_empty()) {
+                content.pop();
+            }
+
+            let (checkbox, first) = checkbox(content[0]);
+            content[0] = first;
+            if description {
+                let term = match content[0].split_once(" :: ") {
+                    Some((term, definition)) => {
+                        content[0] = definition;
+                        term.trim()
+                    }
+                    None => "",
+                };
+                html.push_str(&format!("<dt>{}</dt>\n<dd>", self.inline(term)));
+            } else {
+                html.push_str("<li>");
+            }
+            if let Some(checked) = checkbox {
+                html.push_str(&format!("<input type=\"checkbox\" disabled{checked}> "));
+            }
+
+            // A single paragraph is rendered without `<p>`.
+            let paragraph_len = content
+                .iter()
+                .position(|l| {
+                    l.trim().is_empty() || list_item(l).is_some() || block_begin(l.trim()).is_some()
+                })
+                .unwrap_or(content.len());
+            html.push_str(&self.inline_lines(&content[..paragraph_len]));
+            if paragraph_len < content.len() {
+                html.push('\n');
+                html.push_str(&self.blocks(&content[paragraph_len..]));
+            }
+            html.push_str(if description { "</dd>\n" } else { "</li>\n" });
+        }
+        html.push_str(&format!("</{tag}>\n"));
+        html
+    }
+
+    /// Renders the inline markup of each line in `lines`.
+    fn inline_lines(&self, lines: &[&str]) -> String {
+        lines
+            .iter()
+            .map(|l| {
+                let l = l.trim();
+                match l.strip_suffix("\\\\") {
+                    Some(l) => format!("{}<br>", self.inline(l.trim_end())),
+                    None => self.inline(l),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the inline markup of `text`.
+    fn inline(&self, text: &str) -> String {
+        let mut html = String::with_capacity(text.len() * 3 / 2);
+        let mut rest = text;
+        let mut prev: Option<char> = None;
+        while let Some(c) = rest.chars().next() {
+            if let Some((consumed, out)) = self.inline_element(rest, prev) {
+                html.push_str(&out);
+                prev = rest[..consumed].chars().next_back();
+                rest = &rest[consumed..];
+                continue;
+            }
+            html.push_str(&encode_text(&rest[..c.len_utf8()]));
+            prev = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        html
+    }
+
+    /// Tries to render an inline element at the start of `s`. `prev` is the
+    /// preceding character. Returns the number of consumed bytes and the
+    /// HTML rendition.
+    fn inline_element(&self, s: &str, prev: Option<char>) -> Option<(usize, String)> {
+        // Links `[[target][description]]` and `[[target]]`.
+        if let Some(inner) = s.strip_prefix("[[") {
+            let end = inner.find("]]")?;
+            let link = &inner[..end];
+            let (target, description) = match link.split_once("][") {
+                Some((target, description)) => (target, Some(description)),
+                None => (link, None),
+            };
+            return Some((end + 4, self.link(target.trim(), description)));
+        }
+
+        // Dedicated target `<<target>>`.
+        if let Some(inner) = s.strip_prefix("<<")
+            && !inner.starts_with('<')
+            && let Some(end) = inner.find(">>")
+        {
+            let target = &inner[..end];
+            let id = self
+                .targets
+                .get(target)
+                .cloned()
+                .unwrap_or_else(|| slug(target));
+            return Some((
+                end + 4,
+                format!(
+                    "<span id=\"{}\"></span>",
+                    encode_double_quoted_attribute(&id)
+                ),
+            ));
+        }
+
+        let at_boundary = prev.is_none_or(|p| p.is_whitespace() || "-({'\"".contains(p));
+
+        if at_boundary
+            && ["https://", "http://", "ftp://", "mailto:"]
+                .iter()
+                .any(|scheme| s.starts_with(scheme))
+        {
+            let end = s
+                .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"' | '[' | ']'))
+                .unwrap_or(s.len());
+            let url = s[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '\'']);
+            return Some((
+                url.len(),
+                format!(
+                    "<a href=\"{}\">{}</a>",
+                    encode_double_quoted_attribute(url),
+                    encode_text(url)
+                ),
+            ));
+        }
+
+        // Emphasis.
+        let marker = s.chars().next()?;
+        let (_, tag) = EMPHASIS.iter().find(|(m, _)| *m == marker)?;
+        if !at_boundary {
+            return None;
+        }
+        let inner = &s[1..];
+        if inner.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let mut prev_inner = None;
+        for (i, c) in inner.char_indices() {
+            if c == marker
+                && i > 0
+                && prev_inner.is_some_and(|p: char| !p.is_whitespace())
+                && inner[i + 1..]
+                    .chars()
+                    .next()
+                    .is_none_or(|n| n.is_whitespace() || "-.,;:!?')}[\"\\".contains(n))
+            {
+                let content = &inner[..i];
+                let html = if matches!(marker, '=' | '~') {
+                    format!("<code>{}</code>", encode_text(content))
+                } else {
+                    format!("<{tag}>{}</{tag}>", self.inline(content))
+                };
+                return Some((i + 2, html));
+            }
+            if c == '\n' && prev_inner == Some('\n') {
+                return None;
+            }
+            prev_inner = Some(c);
+        }
+        None
+    }
+
+    /// Renders the link `[[target][description]]`.
+    fn link(&self, target: &str, description: Option<&str>) -> String {
+        let is_url = ["https://", "http://", "ftp://", "mailto:"]
+            .iter()
+            .any(|scheme| target.starts_with(scheme));
+        let path = target.strip_prefix("file:").map(|p| {
+            // Search options: `file:note.org::*Headline`.
+            p.split_once("::").map_or(p, |(p, _)| p)
+        });
+        let is_local = path.is_some() || target.starts_with(['.', '/']);
+        let local_path = path.unwrap_or(target);
+
+        // Images without description are inlined.
+        if description.is_none() && (is_url || is_local) {
+            let ext = local_path
+                .rsplit('.')
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+            if IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+                return format!(
+                    "<img src=\"{}\" alt=\"{}\">",
+                    encode_double_quoted_attribute(local_path),
+                    encode_double_quoted_attribute(
+                        local_path.rsplit('/').next().unwrap_or(local_path)
+                    )
+                );
+            }
+        }
+
+        let href = if is_url {
+            target.to_string()
+        } else if is_local {
+            local_path.to_string()
+        } else if let Some(headline) = target.strip_prefix('*') {
+            let headline = headline.trim();
+            format!(
+                "#{}",
+                self.targets
+                    .get(headline)
+                    .cloned()
+                    .unwrap_or_else(|| slug(headline))
+            )
+        } else if let Some(id) = target.strip_prefix('#') {
+            format!("#{id}")
+        } else if let Some(id) = self.targets.get(target) {
+            format!("#{id}")
+        } else {
+            format!(
+                "{WIKI_LINK_MARKER}{}",
+                utf8_percent_encode(target, WIKI_LINK_ENCODE_SET)
+            )
+        };
+        let text = match description {
+            Some(d) => self.inline(d),
+            None => encode_text(
+                target
+                    .strip_prefix('*')
+                    .or_else(|| target.strip_prefix("file:"))
+                    .unwrap_or(target),
+            )
+            .into_owned(),
+        };
+        format!(
+            "<a href=\"{}\">{text}</a>",
+            encode_double_quoted_attribute(&href)
+        )
+    }
+}
+
+/// The parts of a headline `** TODO [#A] Title :tag1:tag2:`.
+struct Headline<'a> {
+    level: usize,
+    /// The TODO keyword and whether it is a done state.
+    todo: Option<(&'a str, bool)>,
+    priority: Option<&'a str>,
+    title: &'a str,
+    tags: Vec<&'a str>,
+}
+
+/// Returns the lowercase name and the arguments of `#+BEGIN_name args`.
+fn block_begin(line: &str) -> Option<(String, &str)> {
+    let rest = line
+        .get(..8)?
+        .eq_ignore_ascii_case("#+begin_")
+        .then(|| &line[8..])?;
+    let (name, args) = rest.split_once([' ', '\t']).unwrap_or((rest, ""));
+    (!name.is_empty()).then(|| (name.to_lowercase(), args.trim()))
+}
+
+/// Comment lines start with `# ` or are `#` only.
+fn is_comment(line: &str) -> bool {
+    line == "#" || line.starts_with("# ")
+}
+
+/// Drawers like `:PROPERTIES:` or `:LOGBOOK:`.
+fn is_drawer_start(line: &str) -> bool {
+    line.len() > 2
+        && line.starts_with(':')
+        && line.ends_with(':')
+        && line[1..line.len() - 1]
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        && !line.eq_ignore_ascii_case(":end:")
+}
+
+/// Planning lines follow headlines: `SCHEDULED: <2024-01-01 Mon>`.
+fn is_planning(line: &str) -> bool {
+    ["SCHEDULED:", "DEADLINE:", "CLOSED:"]
+        .iter()
+        .any(|p| line.starts_with(p))
+}
+
+/// Returns the `CUSTOM_ID` property of a headline, whose following lines
+/// are `lines`.
+fn custom_id(lines: &[&str]) -> Option<String> {
+    let mut lines = lines.iter().map(|l| l.trim());
+    let mut line = lines.next()?;
+    if is_planning(line) {
+        line = lines.next()?;
+    }
+    if !line.eq_ignore_ascii_case(":properties:") {
+        return None;
+    }
+    for line in lines {
+        if line.eq_ignore_ascii_case(":end:") {
+            break;
+        }
+        if let Some(id) = line
+            .strip_prefix(":CUSTOM_ID:")
+            .or_else(|| line.strip_prefix(":custom_id:"))
+        {
+            return Some(id.trim().to_string());
+        }
+    }
+    None
+}
+
+/// Returns the indentation, the bullet and the text of the list item `line`.
+fn list_item(line: &str) -> Option<(usize, &str, &str)> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    let bullet_len = match rest.chars().next()? {
+        '-' | '+' => 1,
+        // A `*` in the first column starts a headline.
+        '*' if indent > 0 => 1,
+        c if c.is_ascii_alphanumeric() => {
+            let n = rest.chars().take_while(char::is_ascii_digit).count();
+            let n = if n == 0 { 1 } else { n };
+            if !rest[n..].starts_with(['.', ')'])
+                || (n == 1 && !c.is_ascii_digit() && !c.is_ascii_lowercase())
+            {
+                return None;
+            }
+            if !c.is_ascii_digit() && n > 1 {
+                return None;
+            }
+            n + 1
+        }
+        _ => return None,
+    };
+    let text = rest[bullet_len..]
+        .strip_prefix([' ', '\t'])
+        .or_else(|| rest[bullet_len..].is_empty().then_some(""))?;
+    // Alphabetical bullets `a.` are only recognized when followed by text.
+    if !rest.starts_with(|c: char| c.is_ascii_digit() || "-+*".contains(c)) && text.is_empty() {
+        return None;
+    }
+    Some((indent, &rest[..bullet_len], text))
+}
+
+/// Returns the index of the line after the list starting at `lines[start]`.
+/// The list ends with two blank lines or a line indented not more than the
+/// first item that is not an item itself.
+fn list_end(lines: &[&str], start: usize) -> usize {
+    let Some((indent, _, _)) = list_item(lines[start]) else {
+        return start + 1;
+    };
+    let mut i = start + 1;
+    let mut blank = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim().is_empty() {
+            blank += 1;
+            if blank == 2 {
+                break;
+            }
+            i += 1;
+            continue;
+        }
+        let line_indent = line.len() - line.trim_start().len();
+        let same_level_item = list_item(line).is_some_and(|(n, _, _)| n == indent);
+        if line_indent <= indent && !same_level_item {
+            break;
+        }
+        blank = 0;
+        i += 1;
+    }
+    // Trailing blank lines do not belong to the list.
+    while i > start + 1 && lines[i - 1].trim().is_empty() {
+        i -= 1;
+    }
+    i
+}
+
+/// Splits a leading check box `[ ]`, `[X]` or `[-]` from `text`.
+fn checkbox(text: &str) -> (Option<&'static str>, &str) {
+    for (prefix, checked) in [
+        ("[ ]", ""),
+        ("[X]", " checked"),
+        ("[x]", " checked"),
+        ("[-]", ""),
+    ] {
+        if let Some(rest) = text.strip_prefix(prefix)
+            && (rest.is_empty() || rest.starts_with(' '))
+        {
+            return (Some(checked), rest.trim_start());
+        }
+    }
+    (None, text)
+}
+
+/// Joins `lines` and removes their common leading white space.
+fn unindent(lines: &[&str]) -> String {
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|l| l.get(indent..).unwrap_or(l.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::org_to_html;
+
+    #[test]
+    fn test_org_headlines() {
+        let input = "#+TITLE: Ignored\n#+TODO: TODO REVIEW | DONE\n\
+            * DONE [#A] Finished task :home:errand:\nCLOSED: [2024-01-02 Tue]\n\
+            :PROPERTIES:\n:CUSTOM_ID: finished\n:END:\n\
+            ** REVIEW Second\n* Plain /italic/ headline\n# A comment\n* Plain italic headline";
+        let expected = "<h1 id=\"finished\"><span class=\"done DONE\">DONE</span> \
+            <span class=\"priority\">[A]</span> Finished task \
+            <span class=\"tags\"><span class=\"tag\">home</span>\
+            <span class=\"tag\">errand</span></span></h1>\n\
+            <p class=\"planning\">CLOSED: [2024-01-02 Tue]</p>\n\
+            <h2 id=\"second\"><span class=\"todo REVIEW\">REVIEW</span> Second</h2>\n\
+            <h1 id=\"plain-italic-headline\">Plain <i>italic</i> headline</h1>\n\
+            <h1 id=\"plain-italic-headline-1\">Plain italic headline</h1>\n";
+        assert_eq!(org_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_org_inline() {
+        let input = "Some *bold*, /italic/, _under_, +strike+, =verb <x>= and ~code~.\n\
+            Not emphasized: a*b*c, 2 * 3 * 4, path/to/file and snake_case_name.\n\
+            Line break\\\\\nhttps://example.com.";
+        let expected = "<p>Some <b>bold</b>, <i>italic</i>, <u>under</u>, <del>strike</del>, \
+            <code>verb &lt;x&gt;</code> and <code>code</code>.\n\
+            Not emphasized: a*b*c, 2 * 3 * 4, path/to/file and snake_case_name.\n\
+            Line break<br>\n<a href=\"https://example.com\">https://example.com</a>.</p>\n";
+        assert_eq!(org_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_org_links() {
+        let input = "* Setup\n<<anchor>>Text.\n\
+            [[*Setup][setup]], [[anchor]], [[#custom][custom]], [[My note]], \
+            [[https://example.com][Example]], [[file:other.org][Other]], \
+            [[file:other.org::*Part]], [[./img/logo.png]]";
+        let expected = "<h1 id=\"setup\">Setup</h1>\n\
+            <p><span id=\"anchor\"></span>Text.\n\
+            <a href=\"#setup\">setup</a>, <a href=\"#anchor\">anchor</a>, \
+            <a href=\"#custom\">custom</a>, <a href=\"tpnote-wiki:My%20note\">My note</a>, \
+            <a href=\"https://example.com\">Example</a>, <a href=\"other.org\">Other</a>, \
+            <a href=\"other.org\">other.org::*Part</a>, \
+            <img src=\"./img/logo.png\" alt=\"logo.png\"></p>\n";
+        assert_eq!(org_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_org_lists() {
+        let input = "- one\n  continued\n  1. nested\n  2. [X] done\n- [ ] two\n\n\n\
+            - Term :: Definition\n- Other :: More\n\nAfter.";
+        let expected = "<ul>\n<li>one\ncontinued\n<ol>\n<li>nested</li>\n\
+            <li><input type=\"checkbox\" disabled checked> done</li>\n</ol>\n</li>\n\
+            <li><input type=\"checkbox\" disabled> two</li>\n</ul>\n\
+            <dl>\n<dt>Term</dt>\n<dd>Definition</dd>\n<dt>Other</dt>\n<dd>More</dd>\n</dl>\n\
+            <p>After.</p>\n";
+        assert_eq!(org_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_org_tables() {
+        let input = "#+CAPTION: Prices\n| Name  | Price |\n|-------+-------|\n\
+            | Apple | 1     |\n| Pear  | *2*   |\n";
+        let expected = "<table>\n<caption>Prices</caption>\n\
+            <thead>\n<tr><th>Name</th><th>Price</th></tr>\n</thead>\n\
+            <tbody>\n<tr><td>Apple</td><td>1</td></tr>\n\
+            <tr><td>Pear</td><td><b>2</b></td></tr>\n</tbody>\n</table>\n";
+        assert_eq!(org_to_html(input), expected);
+    }
+
+    #[test]
+    fn test_org_blocks() {
+        let input = "#+BEGIN_SRC rust :results output\nfn main() {}\n#+END_SRC\n\
+            #+begin_example\n<b>x</b>\n#+end_example\n: fixed\n\
+            #+BEGIN_QUOTE\nQuoted *text*.\n#+END_QUOTE\n\
+            #+BEGIN_WARNING\nHot.\n#+END_WARNING\n\
+            :LOGBOOK:\n- hidden\n:END:\n-----";
+        let result = org_to_html(input);
+        assert!(result.starts_with("<pre><code class=\"language-rust\">"));
+        assert!(result.ends_with(
+            "</code></pre>\n<pre class=\"example\">&lt;b&gt;x&lt;/b&gt;\n</pre>\n\
+            <pre class=\"example\">fixed\n</pre>\n\
+            <blockquote>\n<p>Quoted <b>text</b>.</p>\n</blockquote>\n\
+            <div class=\"admonition warning\">\n<p class=\"admonition-title\">Warning</p>\n\
+            <p>Hot.</p>\n</div>\n<hr>\n"
+        ));
+    }
+}
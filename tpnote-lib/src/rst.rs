@@ -0,0 +1,483 @@
+//! Extends the ReStructuredText rendition of `rst_parser` and `rst_renderer`.
+//!
+//! Before parsing, the directives `code-block` (`code`, `sourcecode`),
+//! `math`, `image`, `figure`, `admonition` and the specific admonitions
+//! (`note`, `warning`, `tip` ...) are rendered here and replaced by
+//! placeholders. So is the interpreted text role `` :math:`...` ``. After
+//! the rendition of the remaining document, the placeholders are substituted
+//! by their HTML.
+//!
+//! Code blocks are highlighted with the configured `HighlightRegistry`, math
+//! is converted to MathML with `latex2mathml`. Images are rendered as
+//! `<img src="..." alt="...">`, so that `html::rewrite_links()` resolves
+//! their local paths like those of Markdown images. Admonitions produce the
+//! same HTML as AsciiDoc admonitions. The content of admonitions and figure
+//! legends is rendered recursively.
+use crate::error::NoteError;
+use crate::highlight::HighlightRegistry;
+use html_escape::encode_double_quoted_attribute;
+use std::str::from_utf8;
+use std::sync::Arc;
+
+/// Brackets the number of a placeholder. This is a character of Unicode's
+/// private use area, which is not expected in note files.
+const PLACEHOLDER: char = '\u{F8FF}';
+
+/// Directive names of the specific admonitions.
+const ADMONITIONS: [&str; 9] = [
+    "attention",
+    "caution",
+    "danger",
+    "error",
+    "hint",
+    "important",
+    "note",
+    "tip",
+    "warning",
+];
+
+/// Renders ReStructuredText `input` to HTML.
+///
+/// ```rust
+/// use tpnote_lib::rst::rst_to_html;
+///
+/// let input = ".. note::\n\n   Energy is :math:`E`.\n";
+/// let expected = "<div class=\"admonition note\">\n\
+///     <p class=\"admonition-title\">Note</p>\n\
+///     <p>Energy is <math xmlns=\"http://www.w3.org/1998/Math/MathML\" \
+///     display=\"inline\"><mi>E</mi></math>.</p>\n</div>";
+/// assert_eq!(rst_to_html(input).unwrap(), expected);
+/// ```
+pub fn rst_to_html(input: &str) -> Result<String, NoteError> {
+    let mut renderer = Renderer {
+        fragments: Vec::new(),
+        registry: HighlightRegistry::configured(),
+    };
+    let rest_input = renderer.preprocess(input)?;
+
+    // Note, that the ReStructuredText parser requires the input to end with
+    // no new line.
+    let rest_input = rest_input.trim();
+    let mut html = if rest_input.is_empty() {
+        String::new()
+    } else {
+        let mut html_output: Vec<u8> = Vec::with_capacity(rest_input.len() * 3 / 2);
+        const STANDALONE: bool = false; // Don't wrap in `<!doctype html><html></html>`.
+        let doc = rst_parser::parse(rest_input)
+            .map_err(|e| NoteError::RstParse { msg: e.to_string() })?;
+        rst_renderer::render_html(&doc, &mut html_output, STANDALONE)
+            .map_err(|e| NoteError::RstParse { msg: e.to_string() })?;
+        from_utf8(&html_output).unwrap_or_default().to_string()
+    };
+
+    for (n, fragment) in renderer.fragments.iter().enumerate() {
+        let placeholder = placeholder(n);
+        html = html
+            .replace(&format!("<p>{placeholder}</p>"), fragment)
+            .replace(&placeholder, fragment);
+    }
+    html.truncate(html.trim_end().len());
+    Ok(html.trim_start().to_string())
+}
+
+/// The placeholder for the `n`-th rendered fragment.
+fn placeholder(n: usize) -> String {
+    format!("{PLACEHOLDER}{n}{PLACEHOLDER}")
+}
+
+/// Number of leading spaces of `line`.
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Index after the last line of the block starting at `start`, which
+/// comprises all lines indented more than `indent` and blank lines between
+/// them.
+fn block_end(lines: &[&str], start: usize, indent: usize) -> usize {
+    let mut end = start;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if indentation(line) <= indent {
+            break;
+        }
+        end = i + 1;
+    }
+    end
+}
+
+/// Recognizes the explicit markup `.. name:: argument` in the trimmed line.
+/// Returns the lowercase directive name and its argument.
+fn directive(trimmed: &str) -> Option<(String, &str)> {
+    let rest = trimmed.strip_prefix(".. ")?.trim_start();
+    let name_len = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "-_+.".contains(c)))
+        .unwrap_or(rest.len());
+    let argument = rest[name_len..].strip_prefix("::")?;
+    if name_len == 0 || !(argument.is_empty() || argument.starts_with(' ')) {
+        return None;
+    }
+    Some((rest[..name_len].to_ascii_lowercase(), argument.trim()))
+}
+
+/// Splits the body of a directive into its field list of options, e.g.
+/// `:alt: text`, and its content. The content is dedented.
+fn options_and_content(body: &[&str]) -> (Vec<(String, String)>, String) {
+    let mut options = Vec::new();
+    let mut lines = body.iter().peekable();
+    while let Some(line) = lines.peek() {
+        let Some((name, value)) = line
+            .trim()
+            .strip_prefix(':')
+            .and_then(|option| option.split_once(':'))
+        else {
+            break;
+        };
+        options.push((name.to_ascii_lowercase(), value.trim().to_string()));
+        lines.next();
+    }
+
+    let content: Vec<&str> = lines
+        .skip_while(|line| line.trim().is_empty())
+        .copied()
+        .collect();
+    let dedent = content
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indentation(line))
+        .min()
+        .unwrap_or_default();
+    let content = content
+        .iter()
+        .map(|line| line.get(dedent..).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+    (options, content)
+}
+
+/// Looks up the value of the directive option `name`.
+fn option<'a>(options: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    options
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v.as_str())
+}
+
+/// Converts LaTeX into MathML. Conversion errors are displayed instead.
+fn math(latex: &str, style: latex2mathml::DisplayStyle) -> String {
+    latex2mathml::latex_to_mathml(latex, style).unwrap_or_else(|e| e.to_string())
+}
+
+/// Renders ReStructuredText meant to be a single line of inline markup, e.g.
+/// a title or caption, without the enclosing paragraph.
+fn inline(text: &str) -> Result<String, NoteError> {
+    let html = rst_to_html(text)?;
+    let html = html.trim();
+    Ok(html
+        .strip_prefix("<p>")
+        .and_then(|html| html.strip_suffix("</p>"))
+        .unwrap_or(html)
+        .to_string())
+}
+
+struct Renderer {
+    /// HTML of the placeholders inserted so far, indexed by their number.
+    fragments: Vec<String>,
+    registry: Arc<HighlightRegistry>,
+}
+
+impl Renderer {
+    /// Stores `html` and returns its placeholder.
+    fn push(&mut self, html: String) -> String {
+        self.fragments.push(html);
+        placeholder(self.fragments.len() - 1)
+    }
+
+    /// Replaces the supported directives and roles by placeholders. Literal
+    /// blocks, raw blocks and other directives are copied unchanged.
+    fn preprocess(&mut self, input: &str) -> Result<String, NoteError> {
+        let lines: Vec<&str> = input.lines().collect();
+        let mut output = String::with_capacity(input.len());
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            let indent = indentation(line);
+            let trimmed = line.trim();
+
+            if let Some((name, argument)) = directive(trimmed) {
+                let end = block_end(&lines, i + 1, indent);
+                match self.directive(&name, argument, &lines[i + 1..end])? {
+                    Some(html) => {
+                        let placeholder = self.push(html);
+                        output.push_str(&line[..indent]);
+                        output.push_str(&placeholder);
+                        output.push_str("\n\n");
+                    }
+                    None => {
+                        for line in &lines[i..end] {
+                            output.push_str(line);
+                            output.push('\n');
+                        }
+                    }
+                }
+                i = end;
+                continue;
+            }
+
+            output.push_str(&self.roles(line));
+            output.push('\n');
+            i += 1;
+
+            // A paragraph ending with `::` introduces a literal block.
+            if trimmed.ends_with("::") && !trimmed.starts_with("..") {
+                let end = block_end(&lines, i, indent);
+                for line in &lines[i..end] {
+                    output.push_str(line);
+                    output.push('\n');
+                }
+                i = end;
+            }
+        }
+        Ok(output)
+    }
+
+    /// Renders the directive `name` with its `argument` and the following
+    /// indented `body` lines. Returns `None` for directives left to
+    /// `rst_parser`.
+    fn directive(
+        &mut self,
+        name: &str,
+        argument: &str,
+        body: &[&str],
+    ) -> Result<Option<String>, NoteError> {
+        let (options, content) = options_and_content(body);
+        let html = match name {
+            "code" | "code-block" | "sourcecode" if argument == "math" => {
+                math(&content, latex2mathml::DisplayStyle::Block)
+            }
+            "code" | "code-block" | "sourcecode" => {
+                let mut code = content;
+                code.push('\n');
+                self.registry.highlight_code(argument, &code)
+            }
+            "math" => {
+                // Equations are separated by blank lines.
+                let latex = format!("{argument}\n{content}");
+                latex
+                    .split("\n\n")
+                    .filter(|eq| !eq.trim().is_empty())
+                    .map(|eq| math(eq.trim(), latex2mathml::DisplayStyle::Block))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            "image" => image(argument, &options),
+            "figure" => {
+                let mut html = String::from("<figure");
+                let mut classes = vec![];
+                if let Some(class) = option(&options, "figclass") {
+                    classes.push(class.to_string());
+                }
+                if let Some(align) = option(&options, "align") {
+                    classes.push(format!("align-{align}"));
+                }
+                if !classes.is_empty() {
+                    html.push_str(&format!(
+                        " class=\"{}\"",
+                        encode_double_quoted_attribute(&classes.join(" "))
+                    ));
+                }
+                html.push_str(">\n");
+                html.push_str(&image(argument, &options));
+                html.push('\n');
+                // The first paragraph is the caption, the rest the legend.
+                let (caption, legend) = content.split_once("\n\n").unwrap_or((&content, ""));
+                if !caption.trim().is_empty() {
+                    html.push_str(&format!("<figcaption>{}</figcaption>\n", inline(caption)?));
+                }
+                if !legend.trim().is_empty() {
+                    html.push_str(&format!(
+                        "<div class=\"legend\">{}</div>\n",
+                        rst_to_html(legend)?
+                    ));
+                }
+                html.push_str("</figure>");
+                html
+            }
+            "admonition" => admonition("admonition", &inline(argument)?, &content, &options)?,
+            name if ADMONITIONS.contains(&name) => {
+                let mut title = name.to_string();
+                title[..1].make_ascii_uppercase();
+                // The content may start on the directive line.
+                let content = if argument.is_empty() {
+                    content
+                } else {
+                    format!("{argument}\n{content}")
+                };
+                admonition(name, &title, &content, &options)?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(html))
+    }
+
+    /// Replaces the interpreted text role `` :math:`...` `` by a placeholder.
+    /// Inline literals are skipped.
+    fn roles(&mut self, line: &str) -> String {
+        const ROLE: &str = ":math:`";
+        let mut output = String::new();
+        let mut rest = line;
+        while let Some(role) = rest.find(ROLE) {
+            // Copy inline literals ``...`` preceding the role.
+            if let Some(literal) = rest.find("``")
+                && literal < role
+            {
+                let Some(close) = rest[literal + 2..].find("``") else {
+                    break;
+                };
+                let end = literal + 2 + close + 2;
+                output.push_str(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+            let start = role + ROLE.len();
+            let Some(close) = rest[start..].find('`') else {
+                break;
+            };
+            output.push_str(&rest[..role]);
+            let html = math(
+                &rest[start..start + close],
+                latex2mathml::DisplayStyle::Inline,
+            );
+            output.push_str(&self.push(html));
+            rest = &rest[start + close + 1..];
+        }
+        output.push_str(rest);
+        output
+    }
+}
+
+/// Renders the `image` directive, or the image of a `figure` directive.
+fn image(uri: &str, options: &[(String, String)]) -> String {
+    let mut html = format!("<img src=\"{}\"", encode_double_quoted_attribute(uri));
+    html.push_str(&format!(
+        " alt=\"{}\"",
+        encode_double_quoted_attribute(option(options, "alt").unwrap_or_default())
+    ));
+    for name in ["width", "height"] {
+        if let Some(value) = option(options, name) {
+            html.push_str(&format!(
+                " {name}=\"{}\"",
+                encode_double_quoted_attribute(value)
+            ));
+        }
+    }
+    let mut classes = vec![];
+    if let Some(class) = option(options, "class") {
+        classes.push(class.to_string());
+    }
+    if let Some(align) = option(options, "align") {
+        classes.push(format!("align-{align}"));
+    }
+    if !classes.is_empty() {
+        html.push_str(&format!(
+            " class=\"{}\"",
+            encode_double_quoted_attribute(&classes.join(" "))
+        ));
+    }
+    html.push('>');
+    if let Some(target) = option(options, "target") {
+        html = format!(
+            "<a href=\"{}\">{html}</a>",
+            encode_double_quoted_attribute(target)
+        );
+    }
+    html
+}
+
+/// Renders an admonition with the HTML structure of AsciiDoc admonitions.
+fn admonition(
+    class: &str,
+    title: &str,
+    content: &str,
+    options: &[(String, String)],
+) -> Result<String, NoteError> {
+    let mut html = format!("<div class=\"admonition {class}");
+    if let Some(class) = option(options, "class") {
+        html.push(' ');
+        html.push_str(&encode_double_quoted_attribute(class));
+    }
+    html.push_str("\">\n");
+    if !title.is_empty() {
+        html.push_str(&format!("<p class=\"admonition-title\">{title}</p>\n"));
+    }
+    if !content.trim().is_empty() {
+        html.push_str(&rst_to_html(content)?);
+        html.push('\n');
+    }
+    html.push_str("</div>");
+    Ok(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rst_to_html;
+
+    #[test]
+    fn test_rst_code_blocks() {
+        let input = "Text\n\n.. code-block:: rust\n   :linenos:\n\n   fn main() {}\n\nEnd";
+        let result = rst_to_html(input).unwrap();
+        assert!(result.starts_with("<p>Text</p>\n<pre><code class=\"language-rust\">"));
+        assert!(result.contains("<span class=\"source rust\">"));
+        assert!(result.ends_with("</code></pre>\n<p>End</p>"));
+
+        // Directives in literal blocks are not interpreted.
+        let input = "Example::\n\n   .. math:: a^2\n\nEnd";
+        let result = rst_to_html(input).unwrap();
+        assert!(!result.contains("<math"));
+    }
+
+    #[test]
+    fn test_rst_math() {
+        let input = "The role :math:`a^2` and ``x :math:`b` y``.\n\n\
+            .. math::\n\n   x = 1\n\n   y = 2\n";
+        let expected = "<p>The role <math xmlns=\"http://www.w3.org/1998/Math/MathML\" \
+            display=\"inline\"><msup><mi>a</mi><mn>2</mn></msup></math> \
+            and <code>x :math:`b` y</code>.</p>\n\
+            <math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"block\">\
+            <mi>x</mi><mo>=</mo><mn>1</mn></math>\n\
+            <math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"block\">\
+            <mi>y</mi><mo>=</mo><mn>2</mn></math>";
+        assert_eq!(rst_to_html(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_rst_images() {
+        let input = ".. image:: images/t m p.png\n   :alt: A \"test\"\n   :width: 200px\n\n\
+            .. figure:: fig.png\n   :target: https://domain.invalid/\n\n   \
+            The *caption*.\n\n   The legend.\n";
+        let expected = "<img src=\"images/t m p.png\" alt=\"A &quot;test&quot;\" \
+            width=\"200px\">\n\
+            <figure>\n<a href=\"https://domain.invalid/\"><img src=\"fig.png\" alt=\"\"></a>\n\
+            <figcaption>The <em>caption</em>.</figcaption>\n\
+            <div class=\"legend\"><p>The legend.</p></div>\n</figure>";
+        assert_eq!(rst_to_html(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_rst_admonitions() {
+        let input = ".. note::\n\n   Read *this*.\n\n\
+            .. warning:: Beware of the dog.\n\n\
+            .. admonition:: My *title*\n\n   Text.\n";
+        let expected = "<div class=\"admonition note\">\n\
+            <p class=\"admonition-title\">Note</p>\n\
+            <p>Read <em>this</em>.</p>\n</div>\n\
+            <div class=\"admonition warning\">\n\
+            <p class=\"admonition-title\">Warning</p>\n\
+            <p>Beware of the dog.</p>\n</div>\n\
+            <div class=\"admonition admonition\">\n\
+            <p class=\"admonition-title\">My <em>title</em></p>\n\
+            <p>Text.</p>\n</div>";
+        assert_eq!(rst_to_html(input).unwrap(), expected);
+    }
+}
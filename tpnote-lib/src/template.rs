@@ -2,12 +2,13 @@
 use crate::filename::NotePath;
 use crate::settings::SETTINGS;
 use crate::{config::LIB_CFG, content::Content};
+use serde::Serialize;
 use std::path::Path;
 
 /// Each workflow is related to one `TemplateKind`, which relates to one
 /// content template and one filename template.
 #[non_exhaustive]
-#[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy, Serialize)]
 pub enum TemplateKind {
     /// Templates used when Tp-Note is invoked with a directory path.
     /// Clipboard data may be available.
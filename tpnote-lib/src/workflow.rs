@@ -147,6 +147,8 @@ use crate::config::TMPL_VAR_FM_SCHEME;
 use crate::content::Content;
 use crate::context::Context;
 use crate::error::NoteError;
+use crate::filename::NotePath;
+use crate::front_matter::FrontMatter;
 use crate::html_renderer::HtmlRenderer;
 use crate::note::Note;
 use crate::settings::SETTINGS;
@@ -154,6 +156,7 @@ use crate::settings::SchemeSource;
 use crate::settings::Settings;
 use crate::template::TemplateKind;
 use parking_lot::RwLockUpgradableReadGuard;
+use serde::Serialize;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -167,6 +170,15 @@ pub struct WorkflowBuilder<W> {
 #[derive(Debug, Clone)]
 pub struct SyncFilename<'a> {
     path: &'a Path,
+    dry_run: bool,
+}
+
+/// In this state the workflow will synchronize the filenames of all
+/// Tp-Note files in a directory and its subdirectories.
+#[derive(Debug, Clone)]
+pub struct SyncFilenameRecursive<'a> {
+    path: &'a Path,
+    dry_run: bool,
 }
 
 /// In this state the workflow will either synchronize the filename of an
@@ -179,6 +191,37 @@ pub struct SyncFilenameOrCreateNew<'a, T, F> {
     tk_filter: F,
     html_export: Option<(&'a Path, LocalLinkKind)>,
     force_lang: Option<&'a str>,
+    dry_run: bool,
+}
+
+/// The action the workflow performed on disk, or, in dry-run mode, the
+/// action it would have performed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum NoteAction {
+    /// A new note file is written. In case of
+    /// `TemplateKind::FromTextFile`, the new note replaces the original
+    /// text file.
+    Create,
+    /// The existing note file `from` is renamed.
+    Rename { from: PathBuf },
+    /// The note file on disk remains untouched.
+    NoOp,
+}
+
+/// Detailed result of a workflow run, see `run_with_outcome()`.
+#[derive(Debug)]
+pub struct WorkflowOutcome<T> {
+    /// The note's new or existing filename.
+    pub path: PathBuf,
+    /// The note's (rendered) content.
+    pub content: T,
+    /// What has been (or in dry-run mode would have been) done on disk.
+    pub action: NoteAction,
+    /// The `TemplateKind` the workflow applied.
+    pub template_kind: TemplateKind,
+    /// The note's front matter as parsed from `content`.
+    pub front_matter: FrontMatter,
 }
 
 impl<'a> WorkflowBuilder<SyncFilename<'a>> {
@@ -191,10 +234,21 @@ impl<'a> WorkflowBuilder<SyncFilename<'a>> {
     /// `upgrade()` to add additional input data.
     pub fn new(path: &'a Path) -> Self {
         Self {
-            input: SyncFilename { path },
+            input: SyncFilename {
+                path,
+                dry_run: false,
+            },
         }
     }
 
+    /// Set a flag, that the workflow does not create, rename or delete any
+    /// file. Instead, it only reports the rendered filename, the rendered
+    /// content and the planned `NoteAction`, see `run_with_outcome()`.
+    /// This flag is passed on by `upgrade()` and `recursive()`.
+    pub fn dry_run(&mut self) {
+        self.input.dry_run = true;
+    }
+
     /// Upgrade the `WorkflowBuilder` to enable also the creation of new note
     /// files. It requires providing additional input data:
     ///
@@ -230,6 +284,19 @@ impl<'a> WorkflowBuilder<SyncFilename<'a>> {
                 tk_filter,
                 html_export: None,
                 force_lang: None,
+                dry_run: self.input.dry_run,
+            },
+        }
+    }
+
+    /// Upgrade the `WorkflowBuilder` to synchronize the filenames of all
+    /// Tp-Note files (cf. `NotePath::has_tpnote_ext()`) found in the
+    /// directory `path` and in all its subdirectories.
+    pub fn recursive(self) -> WorkflowBuilder<SyncFilenameRecursive<'a>> {
+        WorkflowBuilder {
+            input: SyncFilenameRecursive {
+                path: self.input.path,
+                dry_run: self.input.dry_run,
             },
         }
     }
@@ -240,6 +307,13 @@ impl<'a> WorkflowBuilder<SyncFilename<'a>> {
     }
 }
 
+impl<'a> WorkflowBuilder<SyncFilenameRecursive<'a>> {
+    /// Finalize the build.
+    pub fn build(self) -> Workflow<SyncFilenameRecursive<'a>> {
+        Workflow { input: self.input }
+    }
+}
+
 impl<'a, T: Content, F: Fn(TemplateKind) -> TemplateKind>
     WorkflowBuilder<SyncFilenameOrCreateNew<'a, T, F>>
 {
@@ -263,6 +337,14 @@ impl<'a, T: Content, F: Fn(TemplateKind) -> TemplateKind>
         self.input.force_lang = Some(force_lang);
     }
 
+    /// Set a flag, that the workflow does not create, rename or delete any
+    /// file. Instead, it only reports the rendered filename, the rendered
+    /// content and the planned `NoteAction`, see `run_with_outcome()`.
+    /// In dry-run mode, no HTML rendition is exported.
+    pub fn dry_run(&mut self) {
+        self.input.dry_run = true;
+    }
+
     /// Finalize the build.
     pub fn build(self) -> Workflow<SyncFilenameOrCreateNew<'a, T, F>> {
         Workflow { input: self.input }
@@ -327,21 +409,141 @@ impl Workflow<SyncFilename<'_>> {
     /// assert!(n.is_file());
     /// ```
     pub fn run<T: Content>(self) -> Result<PathBuf, NoteError> {
+        self.run_with_outcome::<T>().map(|outcome| outcome.path)
+    }
+
+    /// Same as `run()`, but returns additionally the note's content and
+    /// the `NoteAction` performed. In dry-run mode (cf.
+    /// `WorkflowBuilder::dry_run()`), the note file is not renamed and
+    /// the returned `NoteAction` describes what would have been done.
+    ///
+    /// ```rust
+    /// use tpnote_lib::content::Content;
+    /// use tpnote_lib::content::ContentString;
+    /// use tpnote_lib::workflow::NoteAction;
+    /// use tpnote_lib::workflow::WorkflowBuilder;
+    /// use std::env::temp_dir;
+    /// use std::fs;
+    ///
+    /// // Prepare test: create existing note.
+    /// let raw = "---\ntitle: Dry run\nsubtitle: Note\n---\nBody text\n";
+    /// let notefile = temp_dir().join("20221030-dry run test.md");
+    /// fs::write(&notefile, raw.as_bytes()).unwrap();
+    ///
+    /// // Build and run workflow.
+    /// let mut builder = WorkflowBuilder::new(&notefile);
+    /// builder.dry_run();
+    /// let outcome = builder
+    ///      .build()
+    ///      .run_with_outcome::<ContentString>()
+    ///      .unwrap();
+    ///
+    /// // Check result.
+    /// let expected = temp_dir().join("20221030-Dry run--Note.md");
+    /// assert_eq!(outcome.path, expected);
+    /// assert_eq!(outcome.action, NoteAction::Rename { from: notefile.clone() });
+    /// assert_eq!(outcome.content.body(), "Body text\n");
+    /// assert_eq!(outcome.front_matter.get("title").unwrap(), "Dry run");
+    /// // Nothing has changed on disk.
+    /// assert!(notefile.is_file());
+    /// assert!(!expected.exists());
+    /// ```
+    pub fn run_with_outcome<T: Content>(self) -> Result<WorkflowOutcome<T>, NoteError> {
         // Prevent the rest to run in parallel, other threads will block when they
         // try to write.
         let mut settings = SETTINGS.upgradable_read();
 
-        // Collect input data for templates.
-        let context = Context::from(self.input.path)?;
+        synchronize_filename_of_file::<T>(&mut settings, self.input.path, self.input.dry_run)
+    }
+}
 
-        let content = <T>::open(self.input.path).unwrap_or_default();
+/// Result of the "synchronize filename recursively" workflow.
+#[derive(Debug, Default)]
+pub struct SyncSummary {
+    /// Note files that have been renamed: `(old_path, new_path)`.
+    pub renamed: Vec<(PathBuf, PathBuf)>,
+    /// Note files whose filename was in sync already, or whose
+    /// filename synchronization is disabled in their front matter.
+    pub unchanged: Vec<PathBuf>,
+    /// Note files that could not be synchronized and the reason why.
+    pub failed: Vec<(PathBuf, NoteError)>,
+}
 
-        // This does not fill any templates,
-        let mut n = Note::from_existing_content(context, content, TemplateKind::SyncFilename)?;
+impl Workflow<SyncFilenameRecursive<'_>> {
+    /// Starts the "synchronize filename recursively" workflow. All Tp-Note
+    /// files in the directory `path` and its subdirectories are collected
+    /// first. Then, the "synchronize filename" workflow is executed for
+    /// each of them. Unlike the single file workflow, an error concerning one
+    /// note file does not abort the workflow. Instead, it is recorded in the
+    /// returned `SyncSummary`. Only when the directory `path` can not be
+    /// read, an error is returned. In dry-run mode (cf.
+    /// `WorkflowBuilder::dry_run()`), the summary lists the planned renames
+    /// without performing them.
+    ///
+    /// Note: this method holds an (upgradeable read) lock on the `SETTINGS`
+    /// object during the whole directory walk.
+    ///
+    /// ```rust
+    /// use tpnote_lib::content::ContentString;
+    /// use tpnote_lib::workflow::WorkflowBuilder;
+    /// use std::env::temp_dir;
+    /// use std::fs;
+    ///
+    /// // Prepare test: create a directory with some notes.
+    /// let notedir = temp_dir().join("sync_filename_recursive");
+    /// let _ = fs::remove_dir_all(&notedir);
+    /// fs::create_dir_all(notedir.join("subdir")).unwrap();
+    /// let raw = "---\ntitle: My day\nsubtitle: Note\n---\nBody text\n";
+    /// fs::write(notedir.join("subdir/20221030-hello.md"), raw).unwrap();
+    /// let raw = "---\ntitle: Other\nsubtitle: Note\n---\nBody text\n";
+    /// fs::write(notedir.join("20221030-Other--Note.md"), raw).unwrap();
+    /// fs::write(notedir.join("20221030-no header.md"), "Body text").unwrap();
+    ///
+    /// // Build and run workflow.
+    /// let summary = WorkflowBuilder::new(&notedir)
+    ///      .recursive()
+    ///      .build()
+    ///      // You can plug in your own type (must impl. `Content`).
+    ///      .run::<ContentString>()
+    ///      .unwrap();
+    ///
+    /// // Check result.
+    /// assert_eq!(summary.renamed, vec![(
+    ///     notedir.join("subdir/20221030-hello.md"),
+    ///     notedir.join("subdir/20221030-My day--Note.md"),
+    /// )]);
+    /// assert_eq!(summary.unchanged, vec![notedir.join("20221030-Other--Note.md")]);
+    /// assert_eq!(summary.failed.len(), 1);
+    /// assert_eq!(summary.failed[0].0, notedir.join("20221030-no header.md"));
+    /// ```
+    pub fn run<T: Content>(self) -> Result<SyncSummary, NoteError> {
+        // Prevent the rest to run in parallel, other threads will block when they
+        // try to write.
+        let mut settings = SETTINGS.upgradable_read();
 
-        synchronize_filename(&mut settings, &mut n)?;
+        // Collect all paths first, as the directory changes while we rename.
+        let paths = self.input.path.find_tpnote_files_recursively()?;
+
+        let mut summary = SyncSummary::default();
+        for path in paths {
+            match synchronize_filename_of_file::<T>(&mut settings, &path, self.input.dry_run) {
+                Ok(WorkflowOutcome {
+                    path: new_path,
+                    action: NoteAction::Rename { .. },
+                    ..
+                }) => {
+                    log::debug!("Renamed {:?} to {:?}", path, new_path);
+                    summary.renamed.push((path, new_path));
+                }
+                Ok(_) => summary.unchanged.push(path),
+                Err(e) => {
+                    log::debug!("Can not synchronize {:?}:\n{}", path, e);
+                    summary.failed.push((path, e));
+                }
+            }
+        }
 
-        Ok(n.rendered_filename)
+        Ok(summary)
     }
 }
 
@@ -413,6 +615,101 @@ impl<T: Content, F: Fn(TemplateKind) -> TemplateKind> Workflow<SyncFilenameOrCre
     ///            "\u{feff}---\r\ntitle:"));
     /// ```
     pub fn run(self) -> Result<PathBuf, NoteError> {
+        self.run_with_outcome().map(|outcome| outcome.path)
+    }
+
+    /// Same as `run()`, but returns additionally the note's rendered content
+    /// and the `NoteAction` performed. In dry-run mode (cf.
+    /// `WorkflowBuilder::dry_run()`), no file is created, renamed or deleted
+    /// and the returned `NoteAction` describes what would have been done.
+    ///
+    /// ```rust
+    /// use tpnote_lib::content::Content;
+    /// use tpnote_lib::content::ContentString;
+    /// use tpnote_lib::workflow::NoteAction;
+    /// use tpnote_lib::workflow::WorkflowBuilder;
+    /// use std::env::temp_dir;
+    /// use std::fs;
+    ///
+    /// // Prepare test.
+    /// let notedir = temp_dir().join("dry_run_create");
+    /// let _ = fs::remove_dir_all(&notedir);
+    /// fs::create_dir_all(&notedir).unwrap();
+    ///
+    /// let html_clipboard = ContentString::from_string(
+    ///     "".to_string(),
+    ///     "html_clipboard".to_string()
+    /// );
+    /// let txt_clipboard = ContentString::from_string(
+    ///     "".to_string(),
+    ///     "txt_clipboard".to_string()
+    /// );
+    /// let stdin = ContentString::from_string(
+    ///     "my dry stdin\n".to_string(),
+    ///     "stdin".to_string()
+    /// );
+    /// let v = vec![&html_clipboard, &txt_clipboard, &stdin];
+    /// let template_kind_filter = |tk|tk;
+    ///
+    /// // Build and run workflow.
+    /// let mut builder = WorkflowBuilder::new(&notedir)
+    ///      .upgrade::<ContentString, _>("default", v, template_kind_filter);
+    /// builder.dry_run();
+    /// let outcome = builder.build().run_with_outcome().unwrap();
+    ///
+    /// // Check result.
+    /// assert_eq!(outcome.action, NoteAction::Create);
+    /// assert!(outcome.path.to_str().unwrap().contains("my dry stdin--Note"));
+    /// assert!(outcome.content.header().contains("my dry stdin"));
+    /// // Nothing was written to disk.
+    /// assert!(!outcome.path.exists());
+    /// assert_eq!(fs::read_dir(&notedir).unwrap().count(), 0);
+    /// ```
+    ///
+    /// The scheme's `tmpl.fm_format` selects the syntax of the new note's
+    /// front matter:
+    ///
+    /// ```rust
+    /// use tpnote_lib::config::LIB_CFG;
+    /// use tpnote_lib::content::Content;
+    /// use tpnote_lib::content::ContentString;
+    /// use tpnote_lib::front_matter::FrontMatterFormat;
+    /// use tpnote_lib::workflow::WorkflowBuilder;
+    /// use std::env::temp_dir;
+    ///
+    /// // Prepare test: write TOML front matter.
+    /// {
+    ///     let mut lib_cfg = LIB_CFG.write();
+    ///     let i = lib_cfg.scheme_idx("default").unwrap();
+    ///     lib_cfg.scheme[i].tmpl.fm_format = FrontMatterFormat::Toml;
+    /// }
+    /// let notedir = temp_dir();
+    /// let html_clipboard = ContentString::from_string(
+    ///     "".to_string(),
+    ///     "html_clipboard".to_string()
+    /// );
+    /// let txt_clipboard = ContentString::from_string(
+    ///     "".to_string(),
+    ///     "txt_clipboard".to_string()
+    /// );
+    /// let stdin = ContentString::from_string(
+    ///     "my toml stdin\n".to_string(),
+    ///     "stdin".to_string()
+    /// );
+    /// let v = vec![&html_clipboard, &txt_clipboard, &stdin];
+    ///
+    /// // Build and run workflow.
+    /// let mut builder = WorkflowBuilder::new(&notedir)
+    ///      .upgrade::<ContentString, _>("default", v, |tk| tk);
+    /// builder.dry_run();
+    /// let outcome = builder.build().run_with_outcome().unwrap();
+    ///
+    /// // Check result.
+    /// assert_eq!(outcome.content.header_format(), FrontMatterFormat::Toml);
+    /// assert!(outcome.content.as_str().starts_with("+++\ntitle = \"my toml stdin\"\n"));
+    /// assert_eq!(outcome.front_matter.get("title").unwrap(), "my toml stdin");
+    /// ```
+    pub fn run_with_outcome(self) -> Result<WorkflowOutcome<T>, NoteError> {
         // Prevent the rest to run in parallel, other threads will block when they
         // try to write.
         let mut settings = SETTINGS.upgradable_read();
@@ -432,7 +729,9 @@ impl<T: Content, F: Fn(TemplateKind) -> TemplateKind> Workflow<SyncFilenameOrCre
         let (template_kind, content) = TemplateKind::from(self.input.path);
         let template_kind = (self.input.tk_filter)(template_kind);
 
-        let n = match template_kind {
+        let dry_run = self.input.dry_run;
+
+        let (n, action) = match template_kind {
             TemplateKind::FromDir | TemplateKind::AnnotateFile => {
                 // CREATE A NEW NOTE WITH THE `TMPL_NEW_CONTENT` TEMPLATE
                 // All these template do not refer to existing front matter,
@@ -445,8 +744,10 @@ impl<T: Content, F: Fn(TemplateKind) -> TemplateKind> Workflow<SyncFilenameOrCre
                 n.render_filename(template_kind)?;
                 // Check if the filename is not taken already
                 n.set_next_unused_rendered_filename()?;
-                n.save()?;
-                n
+                if !dry_run {
+                    n.save()?;
+                }
+                (n, NoteAction::Create)
             }
 
             TemplateKind::FromTextFile => {
@@ -468,8 +769,10 @@ impl<T: Content, F: Fn(TemplateKind) -> TemplateKind> Workflow<SyncFilenameOrCre
                 // Save new note.
                 let context_path = n.context.get_path().to_owned();
                 n.set_next_unused_rendered_filename_or(&context_path)?;
-                n.save_and_delete_from(&context_path)?;
-                n
+                if !dry_run {
+                    n.save_and_delete_from(&context_path)?;
+                }
+                (n, NoteAction::Create)
             }
 
             TemplateKind::SyncFilename => {
@@ -479,13 +782,14 @@ impl<T: Content, F: Fn(TemplateKind) -> TemplateKind> Workflow<SyncFilenameOrCre
                     TemplateKind::SyncFilename,
                 )?;
 
-                synchronize_filename(&mut settings, &mut n)?;
-                n
+                let action = synchronize_filename(&mut settings, &mut n, dry_run)?;
+                (n, action)
             }
 
-            TemplateKind::None => {
-                Note::from_existing_content(context, content.unwrap(), template_kind)?
-            }
+            TemplateKind::None => (
+                Note::from_existing_content(context, content.unwrap(), template_kind)?,
+                NoteAction::NoOp,
+            ),
         };
 
         // If no new filename was rendered, return the old one.
@@ -495,29 +799,76 @@ impl<T: Content, F: Fn(TemplateKind) -> TemplateKind> Workflow<SyncFilenameOrCre
         }
 
         // Export HTML rendition, if wanted.
-        if let Some((export_dir, local_link_kind)) = self.input.html_export {
+        if let Some((export_dir, local_link_kind)) = self.input.html_export
+            && !dry_run
+        {
+            // `Content` is not `Clone`, we copy it for the export.
+            let content =
+                <T>::from_string(n.content.as_str().to_string(), n.content.name().to_string());
             HtmlRenderer::save_exporter_page(
                 &n.rendered_filename,
-                n.content,
+                content,
                 export_dir,
                 local_link_kind,
             )?;
         }
 
-        Ok(n.rendered_filename)
+        Ok(WorkflowOutcome {
+            path: n.rendered_filename,
+            content: n.content,
+            action,
+            template_kind,
+            front_matter: n.front_matter,
+        })
     }
 }
 
+/// Helper function opening the note file `path` and synchronizing its
+/// filename. Returns the note's new or existing filename, its content and
+/// the `NoteAction` performed (or planned if `dry_run`).
+fn synchronize_filename_of_file<T: Content>(
+    settings: &mut RwLockUpgradableReadGuard<Settings>,
+    path: &Path,
+    dry_run: bool,
+) -> Result<WorkflowOutcome<T>, NoteError> {
+    // Collect input data for templates.
+    let context = Context::from(path)?;
+
+    let content = <T>::open(path).unwrap_or_default();
+
+    // This does not fill any templates,
+    let mut n = Note::from_existing_content(context, content, TemplateKind::SyncFilename)?;
+
+    let action = synchronize_filename(settings, &mut n, dry_run)?;
+
+    // If no new filename was rendered, return the old one.
+    if n.rendered_filename == PathBuf::new() {
+        n.rendered_filename = n.context.get_path().to_owned();
+    }
+
+    Ok(WorkflowOutcome {
+        path: n.rendered_filename,
+        content: n.content,
+        action,
+        template_kind: TemplateKind::SyncFilename,
+        front_matter: n.front_matter,
+    })
+}
+
 ///
 /// Helper function. We take `RwLockUpgradableReadGuard<Settings>` as parameter
 /// with a unique `mut` pointer because:
 /// 1. It serves as a lock to prevent several instances of
 ///    `synchronize_filename` from running in parallel.
 /// 2. We need write access to `SETTINGS` in this function.
+///
+/// When `dry_run` is set, the note file is not renamed. The returned
+/// `NoteAction` is either `NoteAction::Rename` or `NoteAction::NoOp`.
 fn synchronize_filename<T: Content>(
     settings: &mut RwLockUpgradableReadGuard<Settings>,
     note: &mut Note<T>,
-) -> Result<(), NoteError> {
+    dry_run: bool,
+) -> Result<NoteAction, NoteError> {
     let no_filename_sync = match (
         note.context
             .get(TMPL_VAR_FM_ALL)
@@ -539,7 +890,7 @@ fn synchronize_filename<T: Content>(
             TMPL_VAR_FM_FILENAME_SYNC.trim_start_matches(TMPL_VAR_FM_),
             !no_filename_sync
         );
-        return Ok(());
+        return Ok(NoteAction::NoOp);
     }
 
     // Shall we switch the `settings.current_theme`?
@@ -577,8 +928,13 @@ fn synchronize_filename<T: Content>(
 
     let path = note.context.get_path().to_owned();
     note.set_next_unused_rendered_filename_or(&path)?;
-    // Silently fails is source and target are identical.
-    note.rename_file_from(note.context.get_path())?;
+    if note.rendered_filename == path {
+        return Ok(NoteAction::NoOp);
+    }
+
+    if !dry_run {
+        note.rename_file_from(&path)?;
+    }
 
-    Ok(())
+    Ok(NoteAction::Rename { from: path })
 }
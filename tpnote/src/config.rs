@@ -231,11 +231,12 @@ impl Cfg {
         // Merge all config files from various locations.
         let cfg_val = config_paths
             .iter()
-            .filter_map(|path| File::open(path).ok())
-            .map(|reader| {
+            .filter_map(|path| File::open(path).ok().map(|reader| (path, reader)))
+            .map(|(path, reader)| {
                 read_as_string_with_crlf_suppression(reader)
                     .map_err(ConfigFileError::from)
                     .and_then(|config| toml::from_str(&config).map_err(ConfigFileError::from))
+                    .and_then(|cfg_val| resolve_highlighting_dir(cfg_val, path))
             })
             .collect::<Result<Vec<_>, _>>()?
             .into_iter()
@@ -382,6 +383,26 @@ pub static CFG: LazyLock<Cfg> = LazyLock::new(|| {
     })
 });
 
+/// Makes the variable `tmpl_html.highlighting_dir` in `cfg_val`, read from
+/// the configuration file `config_path`, relative to the directory of this
+/// file. Absolute and empty paths are left untouched.
+fn resolve_highlighting_dir(
+    cfg_val: CfgVal,
+    config_path: &Path,
+) -> Result<CfgVal, ConfigFileError> {
+    let mut value = cfg_val.to_value();
+    if let Some(Value::String(dir)) = value
+        .get_mut("tmpl_html")
+        .and_then(|tmpl_html| tmpl_html.get_mut("highlighting_dir"))
+        && !dir.is_empty()
+        && Path::new(dir).is_relative()
+        && let Some(config_dir) = config_path.parent()
+    {
+        *dir = config_dir.join(&*dir).to_string_lossy().into_owned();
+    }
+    Ok(value.try_into()?)
+}
+
 /// Variable indicating with `Err` if the loading of the configuration file
 /// went wrong.
 pub static CFG_FILE_LOADING: LazyLock<RwLock<Result<(), ConfigFileError>>> =
@@ -44,19 +44,29 @@ use crate::config::PKG_VERSION;
 use crate::error::WorkflowError;
 use crate::logger::AppLogger;
 use crate::settings::ARGS;
+use crate::settings::DOC_PATH;
 use crate::settings::LAUNCH_EDITOR;
 #[cfg(feature = "message-box")]
 use crate::settings::RUNS_ON_CONSOLE;
+use crate::workflow::RunReport;
 use crate::workflow::run;
+use crate::workflow::run_check_links;
+use crate::workflow::run_export_site;
+use crate::workflow::run_recursive;
 use config::MIN_CONFIG_FILE_VERSION;
 use error::ConfigFileError;
 use semver::Version;
 use serde::Serialize;
 use settings::SYSTEM_CLIPBOARD;
 use std::path::Path;
+use std::path::PathBuf;
 use std::process;
 use std::sync::LazyLock;
 use tpnote_lib::error::NoteError;
+use tpnote_lib::front_matter::FrontMatter;
+use tpnote_lib::html::BrokenLink;
+use tpnote_lib::template::TemplateKind;
+use tpnote_lib::workflow::NoteAction;
 
 #[derive(Debug, PartialEq, Serialize)]
 struct About {
@@ -67,6 +77,119 @@ struct About {
     copyright: String,
 }
 
+/// Result of the `--recursive` filename synchronization, printed as YAML
+/// or as JSON with `--json`.
+#[derive(Debug, Serialize)]
+struct RecursiveSyncReport<'a> {
+    renamed: Vec<RenamedFile>,
+    unchanged: Vec<String>,
+    failed: Vec<FailedFile<'a>>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct RenamedFile {
+    from: String,
+    to: String,
+}
+
+#[derive(Debug, Serialize)]
+struct FailedFile<'a> {
+    path: String,
+    error: &'a NoteError,
+}
+
+/// Result of the `--check-links` run, printed as YAML or as JSON with
+/// `--json`.
+#[derive(Debug, Serialize)]
+struct LinkCheckReport<'a> {
+    broken: &'a [BrokenLink],
+    failed: Vec<FailedFile<'a>>,
+}
+
+/// Result of the `--export-site` run, printed as YAML or as JSON with
+/// `--json`.
+#[derive(Debug, Serialize)]
+struct ExportSiteReport<'a> {
+    exported: Vec<RenamedFile>,
+    unchanged: Vec<String>,
+    copied: Vec<String>,
+    indexes: Vec<String>,
+    removed: Vec<String>,
+    failed: Vec<FailedFile<'a>>,
+}
+
+/// Result of a Tp-Note run printed with `--json`.
+#[derive(Debug, Serialize)]
+struct JsonReport<'a> {
+    path: Option<&'a Path>,
+    template_kind: Option<TemplateKind>,
+    renamed: bool,
+    #[serde(flatten)]
+    action: Option<&'a NoteAction>,
+    front_matter: Option<&'a FrontMatter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<&'a str>,
+    error: Option<serde_json::Value>,
+}
+
+/// Converts `e` into a structured record with an error `code` and its
+/// `fields`. `NoteError`s serialize themselves this way.
+fn workflow_error_to_json(e: &WorkflowError) -> serde_json::Value {
+    let code = match e {
+        WorkflowError::Note(e) => return serde_json::to_value(e).unwrap_or_default(),
+        WorkflowError::Template { tmpl_name, source } => {
+            return serde_json::json!({
+                "code": "Template",
+                "fields": {
+                    "tmpl_name": tmpl_name,
+                    "source": serde_json::to_value(source).unwrap_or_default(),
+                },
+            });
+        }
+        WorkflowError::ExportNeedsNoteFile => "ExportNeedsNoteFile",
+        WorkflowError::ConfigFile(_) => "ConfigFile",
+        WorkflowError::Io(_) | WorkflowError::IoRef(_) => "Io",
+    };
+    serde_json::json!({
+        "code": code,
+        "fields": { "msg": e.to_string() },
+    })
+}
+
+/// A `JsonReport` of the failed run on `path`.
+fn json_error_report<'a>(path: Option<&'a Path>, e: &WorkflowError) -> JsonReport<'a> {
+    JsonReport {
+        path,
+        template_kind: None,
+        renamed: false,
+        action: None,
+        front_matter: None,
+        content: None,
+        error: Some(workflow_error_to_json(e)),
+    }
+}
+
+/// Lists the files that could not be processed.
+fn failed_files(failed: &[(PathBuf, NoteError)]) -> Vec<FailedFile<'_>> {
+    failed
+        .iter()
+        .map(|(p, e)| FailedFile {
+            path: p.display().to_string(),
+            error: e,
+        })
+        .collect()
+}
+
+/// Prints `report` on the console, as JSON with `--json`, as YAML otherwise.
+fn print_report(report: &impl Serialize) {
+    let msg = if ARGS.json {
+        serde_json::to_string(report).unwrap_or_else(|_| "unknown".to_string())
+    } else {
+        serde_yaml::to_string(report).unwrap_or_else(|_| "unknown".to_string())
+    };
+    println!("{}", msg);
+}
+
 /// Print some error message if `run()` does not complete.
 /// Exit prematurely if the configuration file version does
 /// not match the program version.
@@ -226,6 +349,126 @@ fn main() {
         process::exit(0);
     };
 
+    // Process `arg = `--recursive`.
+    // The output is YAML formatted for further automatic processing.
+    if ARGS.recursive {
+        let summary = match run_recursive() {
+            Ok(summary) => summary,
+            Err(e) => {
+                log::error!("{}", e);
+                if ARGS.json {
+                    print_report(&json_error_report(DOC_PATH.as_deref().ok(), &e));
+                }
+                AppLogger::flush();
+                process::exit(1);
+            }
+        };
+
+        let report = RecursiveSyncReport {
+            renamed: summary
+                .renamed
+                .iter()
+                .map(|(from, to)| RenamedFile {
+                    from: from.display().to_string(),
+                    to: to.display().to_string(),
+                })
+                .collect(),
+            unchanged: summary
+                .unchanged
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect(),
+            failed: failed_files(&summary.failed),
+        };
+
+        print_report(&report);
+
+        AppLogger::flush();
+        process::exit(if summary.failed.is_empty() { 0 } else { 1 });
+    };
+
+    // Process `arg = `--check-links`.
+    // The output is YAML formatted for further automatic processing.
+    if ARGS.check_links {
+        let summary = match run_check_links() {
+            Ok(summary) => summary,
+            Err(e) => {
+                log::error!("{}", e);
+                if ARGS.json {
+                    print_report(&json_error_report(DOC_PATH.as_deref().ok(), &e));
+                }
+                AppLogger::flush();
+                process::exit(1);
+            }
+        };
+
+        let report = LinkCheckReport {
+            broken: &summary.broken,
+            failed: failed_files(&summary.failed),
+        };
+        print_report(&report);
+
+        AppLogger::flush();
+        process::exit(if summary.broken.is_empty() && summary.failed.is_empty() {
+            0
+        } else {
+            1
+        });
+    };
+
+    // Process `arg = `--export-site`.
+    // The output is YAML formatted for further automatic processing.
+    if let Some(export_dir) = &ARGS.export_site {
+        let summary = match run_export_site(export_dir) {
+            Ok(summary) => summary,
+            Err(e) => {
+                log::error!("{}", e);
+                if ARGS.json {
+                    print_report(&json_error_report(DOC_PATH.as_deref().ok(), &e));
+                }
+                AppLogger::flush();
+                process::exit(1);
+            }
+        };
+
+        let report = ExportSiteReport {
+            exported: summary
+                .exported
+                .iter()
+                .map(|(from, to)| RenamedFile {
+                    from: from.display().to_string(),
+                    to: to.display().to_string(),
+                })
+                .collect(),
+            unchanged: summary
+                .unchanged
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect(),
+            copied: summary
+                .copied
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect(),
+            indexes: summary
+                .indexes
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect(),
+            removed: summary
+                .removed
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect(),
+            failed: failed_files(&summary.failed),
+        };
+
+        print_report(&report);
+
+        AppLogger::flush();
+        process::exit(if summary.failed.is_empty() { 0 } else { 1 });
+    };
+
     //
     // Run Tp-Note.
     let res = run();
@@ -233,11 +476,30 @@ fn main() {
         Err(ref e) => {
             // Something went wrong. Inform user.
             log::error!("{}", e);
+            if ARGS.json {
+                print_report(&json_error_report(DOC_PATH.as_deref().ok(), e));
+            }
+        }
+
+        // Print the JSON report instead of `path`.
+        Ok(ref report) if ARGS.json => {
+            let report = JsonReport {
+                path: Some(&report.path),
+                template_kind: report.template_kind,
+                renamed: report.renamed,
+                action: report.action.as_ref(),
+                front_matter: report.front_matter.as_ref(),
+                content: report.content.as_deref(),
+                error: None,
+            };
+            print_report(&report);
         }
 
-        // Print `path` unless `--export=-`.
-        Ok(ref path) => {
-            if let Some(p) = &ARGS.export {
+        // Print `path` unless `--export=-` or `--dry-run`.
+        Ok(RunReport { ref path, .. }) => {
+            if ARGS.dry_run {
+                // The dry-run report was printed already.
+            } else if let Some(p) = &ARGS.export {
                 if p.display().to_string() != "-" {
                     println!("{}", path.display());
                 }
@@ -250,12 +512,16 @@ fn main() {
     // Wait if there are still error messages windows open.
     AppLogger::flush();
 
-    // Delete clipboard content.
-    if (*LAUNCH_EDITOR && !ARGS.batch && CFG.clipboard.read_enabled && CFG.clipboard.empty_enabled)
-        || matches!(
-            &res,
-            Err(WorkflowError::Note(NoteError::InvalidInputYaml { .. }))
-        )
+    // Delete clipboard content, unless this is a dry run.
+    if !ARGS.dry_run
+        && ((*LAUNCH_EDITOR
+            && !ARGS.batch
+            && CFG.clipboard.read_enabled
+            && CFG.clipboard.empty_enabled)
+            || matches!(
+                &res,
+                Err(WorkflowError::Note(NoteError::InvalidInputYaml { .. }))
+            ))
     {
         SystemClipboard::empty();
     }
@@ -95,6 +95,19 @@ pub struct Args {
     /// Lets the web server listen to a specific port
     #[arg(long, short = 'p')]
     pub port: Option<u16>,
+    /// Synchronizes the filenames of all notes in the directory `<PATH>` and
+    /// its subdirectories; implies `--batch`
+    #[arg(long, short = 'r')]
+    pub recursive: bool,
+    /// Reports broken local links of all notes in the directory `<PATH>` and
+    /// its subdirectories; implies `--batch`
+    #[arg(long)]
+    pub check_links: bool,
+    /// Renders all notes in the directory `<PATH>` and its subdirectories
+    /// into a static website in the `<EXPORT_SITE>` directory; implies
+    /// `--batch`
+    #[arg(long)]
+    pub export_site: Option<PathBuf>,
     /// Disables filename synchronization
     #[arg(long, short = 'n')]
     pub no_filename_sync: bool,
@@ -118,6 +131,13 @@ pub struct Args {
     /// Exporter local link rewriting: [possible values: off, short, long]
     #[arg(long, value_enum)]
     pub export_link_rewriting: Option<LocalLinkKind>,
+    /// Prints the planned action, filename and content without
+    /// touching any file; implies `--batch`
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Prints the result as one JSON object instead of the path
+    #[arg(long)]
+    pub json: bool,
 }
 
 /// Structure to hold the parsed command line arguments.
@@ -126,6 +146,10 @@ pub static ARGS: LazyLock<Args> = LazyLock::new(Args::parse);
 /// Shall we launch the external text editor?
 pub static LAUNCH_EDITOR: LazyLock<bool> = LazyLock::new(|| {
     !ARGS.batch
+        && !ARGS.recursive
+        && !ARGS.check_links
+        && ARGS.export_site.is_none()
+        && !ARGS.dry_run
         && ARGS.export.is_none()
         && env::var(ENV_VAR_TPNOTE_EDITOR) != Ok(String::new())
         && (ARGS.edit || !ARGS.view)
@@ -135,6 +159,10 @@ pub static LAUNCH_EDITOR: LazyLock<bool> = LazyLock::new(|| {
 /// Shall we launch the internal HTTP server and the external browser?
 pub static LAUNCH_VIEWER: LazyLock<bool> = LazyLock::new(|| {
     !ARGS.batch
+        && !ARGS.recursive
+        && !ARGS.check_links
+        && ARGS.export_site.is_none()
+        && !ARGS.dry_run
         && ARGS.export.is_none()
         && !*RUNS_ON_CONSOLE
         && (ARGS.view
@@ -205,7 +233,12 @@ pub static STDIN: LazyLock<ContentString> = LazyLock::new(|| {
 
 /// Reads the clipboard, if there is any and empties it.
 pub static SYSTEM_CLIPBOARD: LazyLock<SystemClipboard> = LazyLock::new(|| {
-    if CFG.clipboard.read_enabled && !ARGS.batch {
+    if CFG.clipboard.read_enabled
+        && !ARGS.batch
+        && !ARGS.recursive
+        && !ARGS.check_links
+        && ARGS.export_site.is_none()
+    {
         SystemClipboard::new()
     } else {
         SystemClipboard::default()
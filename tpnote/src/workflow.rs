@@ -11,28 +11,79 @@ use crate::settings::SYSTEM_CLIPBOARD;
 use crate::template::template_kind_filter;
 #[cfg(feature = "viewer")]
 use crate::viewer::launch_viewer_thread;
+use serde::Serialize;
+use std::env;
 #[cfg(not(target_family = "windows"))]
 use std::matches;
+use std::path::Path;
 use std::path::PathBuf;
 #[cfg(feature = "viewer")]
 use std::thread;
 #[cfg(feature = "viewer")]
 use std::time::Duration;
+use tpnote_lib::content::Content;
 use tpnote_lib::content::ContentString;
 use tpnote_lib::error::NoteError;
+use tpnote_lib::front_matter::FrontMatter;
+use tpnote_lib::html::LinkCheckSummary;
+use tpnote_lib::html::check_links;
+use tpnote_lib::html_renderer::HtmlRenderer;
+use tpnote_lib::html_renderer::SiteSummary;
+use tpnote_lib::template::TemplateKind;
+use tpnote_lib::workflow::NoteAction;
+use tpnote_lib::workflow::SyncSummary;
 use tpnote_lib::workflow::WorkflowBuilder;
+use tpnote_lib::workflow::WorkflowOutcome;
 
-/// Run Tp-Note and return the (modified) path to the (new) note file.
+/// Information about a Tp-Note run, see `run_workflow()`.
+#[derive(Debug)]
+pub struct RunReport {
+    /// The (modified) path to the (new) note file.
+    pub path: PathBuf,
+    /// The `TemplateKind` the workflow applied. `None`, if the workflow
+    /// continued after a recoverable error.
+    pub template_kind: Option<TemplateKind>,
+    /// True, if the note file was renamed.
+    pub renamed: bool,
+    /// What has been (or in dry-run mode would have been) done on disk.
+    /// `None`, if the workflow continued after a recoverable error.
+    pub action: Option<NoteAction>,
+    /// The note's front matter as parsed after the last filename
+    /// synchronization.
+    pub front_matter: Option<FrontMatter>,
+    /// The rendered note content. Only set in dry-run mode.
+    pub content: Option<String>,
+}
+
+impl From<WorkflowOutcome<ContentString>> for RunReport {
+    fn from(outcome: WorkflowOutcome<ContentString>) -> Self {
+        Self {
+            path: outcome.path,
+            template_kind: Some(outcome.template_kind),
+            renamed: matches!(outcome.action, NoteAction::Rename { .. }),
+            action: Some(outcome.action),
+            front_matter: Some(outcome.front_matter),
+            content: Some(outcome.content.as_str().to_string()),
+        }
+    }
+}
+
+/// Run Tp-Note and return the (modified) path to the (new) note file
+/// together with some information about what happened.
 /// 1. Create a new note by inserting Tp-Note's environment in a template.
 /// 2. If the note to be created exists already, open it, read the YAML front
 ///    matter and synchronize the filename if necessary.
 /// 3. Open the new note in an external editor (configurable).
 /// 4. Read the front matter again and resynchronize the filename if necessary.
 #[inline]
-pub fn run_workflow(mut path: PathBuf) -> Result<PathBuf, WorkflowError> {
+pub fn run_workflow(mut path: PathBuf) -> Result<RunReport, WorkflowError> {
     // Depending on this we might not show the viewer later or
     // log an error as WARN level instead of ERROR level.
     let launch_viewer;
+    let mut template_kind = None;
+    let mut renamed = false;
+    let mut action = None;
+    let mut front_matter = None;
 
     let mut workflow_builder = WorkflowBuilder::new(&path).upgrade::<ContentString, _>(
         &CFG.arg_default.scheme,
@@ -63,12 +114,29 @@ pub fn run_workflow(mut path: PathBuf) -> Result<PathBuf, WorkflowError> {
         );
     }
 
+    if ARGS.dry_run {
+        workflow_builder.dry_run();
+    }
+
     let workflow = workflow_builder.build();
 
-    match workflow.run() {
+    // Print what would be done and quit.
+    if ARGS.dry_run {
+        let outcome = workflow.run_with_outcome()?;
+        if !ARGS.json {
+            print_dry_run_report(&outcome);
+        }
+        return Ok(outcome.into());
+    }
+
+    match workflow.run_with_outcome() {
         // Use the new `path` from now on.
-        Ok(p) => {
-            path = p;
+        Ok(outcome) => {
+            path = outcome.path;
+            template_kind = Some(outcome.template_kind);
+            renamed = matches!(outcome.action, NoteAction::Rename { .. });
+            action = Some(outcome.action);
+            front_matter = Some(outcome.front_matter);
             #[cfg(feature = "viewer")]
             {
                 launch_viewer = *LAUNCH_VIEWER;
@@ -76,6 +144,7 @@ pub fn run_workflow(mut path: PathBuf) -> Result<PathBuf, WorkflowError> {
         }
         Err(e) => {
             if (matches!(e, NoteError::InvalidFrontMatterYaml { .. })
+                || matches!(e, NoteError::InvalidFrontMatterToml { .. })
                 || matches!(e, NoteError::FrontMatterFieldIsCompound { .. })
                 || matches!(e, NoteError::FrontMatterFieldIsDuplicateSortTag { .. })
                 || matches!(e, NoteError::FrontMatterFieldIsEmptyString { .. })
@@ -136,9 +205,18 @@ pub fn run_workflow(mut path: PathBuf) -> Result<PathBuf, WorkflowError> {
 
     if *LAUNCH_EDITOR {
         let workflow = WorkflowBuilder::new(&path).build();
-        match workflow.run::<ContentString>() {
+        match workflow.run_with_outcome::<ContentString>() {
             // `path` has changed!
-            Ok(p) => path = p,
+            Ok(outcome) => {
+                path = outcome.path;
+                renamed |= matches!(outcome.action, NoteAction::Rename { .. });
+                // A note created or renamed before editing keeps this action.
+                action = match action {
+                    None | Some(NoteAction::NoOp) => Some(outcome.action),
+                    a => a,
+                };
+                front_matter = Some(outcome.front_matter);
+            }
             Err(e) => {
                 let missing_header = matches!(e, NoteError::FrontMatterMissing { .. })
                     || matches!(e, NoteError::FrontMatterFieldMissing { .. });
@@ -159,12 +237,86 @@ pub fn run_workflow(mut path: PathBuf) -> Result<PathBuf, WorkflowError> {
         };
     };
 
-    Ok(path)
+    Ok(RunReport {
+        path,
+        template_kind,
+        renamed,
+        action,
+        front_matter,
+        content: None,
+    })
+}
+
+/// Report of the `--dry-run` mode, printed as YAML.
+#[derive(Debug, Serialize)]
+struct DryRunReport<'a> {
+    path: &'a Path,
+    #[serde(flatten)]
+    action: &'a NoteAction,
+    content: &'a str,
+}
+
+/// Prints the planned action, the rendered filename and the rendered content
+/// YAML formatted on standard output.
+fn print_dry_run_report(outcome: &WorkflowOutcome<ContentString>) {
+    let report = DryRunReport {
+        path: &outcome.path,
+        action: &outcome.action,
+        content: outcome.content.as_str(),
+    };
+    let msg = serde_yaml::to_string(&report).unwrap_or_else(|_| "unknown".to_string());
+    println!("{}", msg);
 }
 
 #[inline]
-pub(crate) fn run() -> Result<PathBuf, WorkflowError> {
+pub(crate) fn run() -> Result<RunReport, WorkflowError> {
     // Process arg = <path>
     let doc_path = DOC_PATH.as_deref()?;
     run_workflow(doc_path.to_path_buf())
 }
+
+/// Synchronize the filenames of all notes in the directory `<path>` and
+/// its subdirectories.
+#[inline]
+pub(crate) fn run_recursive() -> Result<SyncSummary, WorkflowError> {
+    // Process arg = <path>
+    let doc_path = DOC_PATH.as_deref()?;
+    let mut workflow_builder = WorkflowBuilder::new(doc_path);
+    if ARGS.dry_run {
+        workflow_builder.dry_run();
+    }
+    let workflow = workflow_builder.recursive().build();
+    Ok(workflow.run::<ContentString>()?)
+}
+
+/// Report the broken local links of all notes in the directory `<path>` and
+/// its subdirectories.
+#[inline]
+pub(crate) fn run_check_links() -> Result<LinkCheckSummary, WorkflowError> {
+    // Process arg = <path>
+    let doc_path = DOC_PATH.as_deref()?;
+    Ok(check_links(doc_path)?)
+}
+
+/// Render all notes in the directory `<path>` and its subdirectories into a
+/// static website in `export_dir`.
+#[inline]
+pub(crate) fn run_export_site(export_dir: &Path) -> Result<SiteSummary, WorkflowError> {
+    // Process arg = <path>
+    let doc_path = DOC_PATH.as_deref()?;
+    let export_dir = env::current_dir()?.join(export_dir);
+    // Copy only files the viewer would serve too.
+    let asset_extensions: Vec<&str> = CFG
+        .viewer
+        .served_mime_types
+        .iter()
+        .map(|(ext, _)| ext.as_str())
+        .collect();
+    Ok(HtmlRenderer::save_exporter_site::<ContentString>(
+        doc_path,
+        &export_dir,
+        ARGS.export_link_rewriting
+            .unwrap_or(CFG.arg_default.export_link_rewriting),
+        &asset_extensions,
+    )?)
+}